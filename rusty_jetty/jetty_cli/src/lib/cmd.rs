@@ -52,6 +52,13 @@ pub(crate) enum JettyCommand {
         /// Connectors to collect for
         #[clap(short, long, use_value_delimiter = true, value_delimiter = ',')]
         connectors: Option<Vec<String>>,
+        /// The maximum number of connectors to fetch at the same time
+        #[clap(long, value_parser, default_value_t = crate::DEFAULT_FETCH_CONCURRENCY)]
+        concurrency: usize,
+        /// Stop as soon as any connector fails instead of building the access graph from the
+        /// connectors that succeeded
+        #[clap(long, value_parser, default_value = "false")]
+        fail_fast: bool,
     },
     /// Watch config files for changes and update the YAML schema as needed to keep validation working properly. It's recommended that you run this while editing configuration files
     Dev,
@@ -78,18 +85,48 @@ pub(crate) enum JettyCommand {
         /// Fetch the current configurations before generating the diff
         #[clap(short, long, value_parser, default_value = "false")]
         fetch: bool,
+        /// The output format
+        #[clap(long, value_enum, default_value = "text")]
+        format: OutputFormat,
+    },
+    /// Fetch and check whether the environment has drifted from the configuration. Exits with
+    /// status 2 if it has
+    Drift {
+        /// Don't fetch the current configurations before checking for drift
+        #[clap(short, long, value_parser, default_value = "false")]
+        no_fetch: bool,
+        /// The output format
+        #[clap(long, value_enum, default_value = "text")]
+        format: OutputFormat,
     },
     /// Plan the changes needed to update the environment based on the diff
     Plan {
         /// Fetch the current configurations before generating the diff
         #[clap(short, long, value_parser, default_value = "false")]
         fetch: bool,
+        /// Save the plan to a file so that it can be applied with `jetty apply <plan file>`
+        #[clap(short, long, value_parser)]
+        out: Option<PathBuf>,
+        /// Write the planned SQL for each connector that supports it to ordered scripts in
+        /// this directory, so that they can be reviewed and run by hand
+        #[clap(long, value_parser)]
+        sql_out: Option<PathBuf>,
+        /// The output format
+        #[clap(long, value_enum, default_value = "text")]
+        format: OutputFormat,
     },
     /// Update the environment with the planned changes
     Apply {
         /// Don't fetch the current configurations before applying the changes
         #[clap(short, long, value_parser, default_value = "false")]
         no_fetch: bool,
+        /// Apply a plan saved with `jetty plan --out`. The plan is rejected if the environment
+        /// or the configuration files have changed since it was created.
+        #[clap(value_parser)]
+        plan_file: Option<PathBuf>,
+        /// Write a JSON report with the outcome of every operation to this file
+        #[clap(short, long, value_parser)]
+        report: Option<PathBuf>,
     },
     /// Launch the exploration web UI
     Explore {
@@ -120,3 +157,14 @@ pub enum RemoveOrModifyNodeType {
     /// Modify a user
     User,
 }
+
+/// The output format for the diff and plan commands
+#[derive(Copy, Clone, PartialEq, Eq, Debug, ValueEnum)]
+pub(crate) enum OutputFormat {
+    /// Human-readable, colored text
+    Text,
+    /// JSON, following the schema documented for each command
+    Json,
+    /// Markdown, suitable for pull request comments
+    Markdown,
+}
@@ -1,12 +1,15 @@
 //! Diff command execution
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 
-use jetty_core::{project, write::diff::get_diffs};
+use jetty_core::{
+    project,
+    write::{diff::get_diffs, output::DiffOutput},
+};
 
-use crate::new_jetty_with_connectors;
+use crate::{cmd::OutputFormat, new_jetty_with_connectors};
 
-pub(super) async fn diff() -> Result<()> {
+pub(super) async fn diff(format: &OutputFormat) -> Result<()> {
     let jetty = &mut new_jetty_with_connectors(".", true).await.map_err(|_| {
         anyhow!(
             "unable to find {} - make sure you are in a \
@@ -17,6 +20,22 @@ pub(super) async fn diff() -> Result<()> {
 
     let diffs = get_diffs(jetty)?;
 
+    match format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&DiffOutput::from(&diffs))
+                    .context("serializing diff")?
+            );
+            return Ok(());
+        }
+        OutputFormat::Markdown => {
+            println!("{}", DiffOutput::from(&diffs).to_markdown());
+            return Ok(());
+        }
+        OutputFormat::Text => (),
+    }
+
     // Now print out the diffs
     println!("\nUSERS\n──────────────────");
     if !diffs.users.is_empty() {
@@ -25,6 +44,16 @@ pub(super) async fn diff() -> Result<()> {
         println!("No changes found");
     };
 
+    println!("\nUSER PROPERTIES\n──────────────────");
+    if !diffs.user_properties.is_empty() {
+        diffs
+            .user_properties
+            .iter()
+            .for_each(|diff| println!("{diff}"));
+    } else {
+        println!("No changes found");
+    };
+
     println!("\nGROUPS\n──────────────────");
     if !diffs.groups.is_empty() {
         diffs.groups.iter().for_each(|diff| println!("{diff}"));
@@ -49,5 +78,31 @@ pub(super) async fn diff() -> Result<()> {
         println!("No changes found");
     };
 
+    println!("\nPROTECTION POLICIES\n──────────────────");
+    if !diffs.protections.is_empty() {
+        diffs.protections.iter().for_each(|diff| println!("{diff}"));
+    } else {
+        println!("No changes found");
+    };
+
+    println!("\nOWNERSHIP\n──────────────────");
+    if !diffs.ownership.is_empty() {
+        diffs.ownership.iter().for_each(|diff| println!("{diff}"));
+    } else {
+        println!("No changes found");
+    };
+
+    println!("\nTAGS\n──────────────────");
+    let tag_changes = diffs
+        .tags
+        .iter()
+        .filter(|state| !state.changes().is_empty())
+        .collect::<Vec<_>>();
+    if !tag_changes.is_empty() {
+        tag_changes.iter().for_each(|state| println!("{state}"));
+    } else {
+        println!("No changes found");
+    };
+
     Ok(())
 }
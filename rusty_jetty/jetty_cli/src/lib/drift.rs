@@ -0,0 +1,63 @@
+//! Drift detection: compare the configuration to the current state of the environment
+
+use anyhow::{anyhow, Context, Result};
+use colored::Colorize;
+
+use jetty_core::{
+    project,
+    write::{diff::get_diffs, output::DriftSummary},
+};
+
+use crate::{cmd::OutputFormat, new_jetty_with_connectors};
+
+/// The exit code used when drift is detected. This is distinct from the exit code used
+/// for errors so that scripts can tell the two apart.
+pub(crate) const DRIFT_EXIT_CODE: i32 = 2;
+
+/// Print a summary of the drift between the configuration and the environment. Returns
+/// whether any drift was found.
+pub(super) async fn drift(format: &OutputFormat) -> Result<bool> {
+    let jetty = &mut new_jetty_with_connectors(".", true).await.map_err(|_| {
+        anyhow!(
+            "unable to find {} - make sure you are in a \
+        Jetty project directory, or create a new project by running `jetty new`",
+            project::jetty_cfg_path_local().display()
+        )
+    })?;
+
+    let diffs = get_diffs(jetty)?;
+    let summary = DriftSummary::from(&diffs);
+
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).context("serializing drift summary")?
+        ),
+        OutputFormat::Markdown => println!("{}", summary.to_markdown()),
+        OutputFormat::Text => {
+            if !summary.drift {
+                println!("{}", "No drift detected".green());
+            } else {
+                println!("{}\n", "Drift detected".red());
+                for (connector, counts) in &summary.connectors {
+                    println!("{connector}:");
+                    for (kind, count) in [
+                        ("identity", counts.identity),
+                        ("membership", counts.membership),
+                        ("group", counts.group),
+                        ("policy", counts.policy),
+                        ("default policy", counts.default_policy),
+                    ] {
+                        if count > 0 {
+                            println!("  {kind}: {count}");
+                        }
+                    }
+                    println!();
+                }
+                println!("Run `jetty diff` to see the details.");
+            }
+        }
+    }
+
+    Ok(summary.drift)
+}
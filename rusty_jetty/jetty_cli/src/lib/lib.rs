@@ -6,6 +6,7 @@
 mod ascii;
 mod cmd;
 mod diff;
+mod drift;
 mod new;
 mod plan;
 mod remove;
@@ -14,7 +15,7 @@ mod tui;
 mod usage_stats;
 
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env, fs,
     path::{Path, PathBuf},
     str::FromStr,
@@ -25,12 +26,13 @@ use std::{
 use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 use colored::Colorize;
+use futures::{stream, StreamExt};
 use human_panic::setup_panic;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
 use jetty_core::{
     access_graph::AccessGraph,
-    connectors::{ConnectorClient, NewConnector},
+    connectors::{file, plugin, registry::ConnectorRegistry, ConnectorClient},
     fetch_credentials,
     jetty::{ConnectorNamespace, CredentialsMap, JettyConfig},
     log_runtime,
@@ -38,17 +40,17 @@ use jetty_core::{
     project::{self, groups_cfg_path_local},
     write::{
         self,
+        apply_report::{ApplyOutcome, ApplyReport},
         assets::bootstrap::{update_asset_files, write_bootstrapped_asset_yaml},
-        config,
-        diff::get_diffs,
-        groups,
+        config, groups,
+        saved_plan::SavedPlan,
         users::bootstrap::{update_user_files, write_bootstrapped_user_yaml},
     },
     Connector, Jetty,
 };
 
 use crate::{
-    cmd::{JettyArgs, JettyCommand},
+    cmd::{JettyArgs, JettyCommand, OutputFormat},
     usage_stats::{record_usage, UsageEvent},
 };
 
@@ -98,9 +100,10 @@ pub async fn cli() -> Result<()> {
                 no_fetch,
                 overwrite,
             },
-            JettyCommand::Diff { fetch } => UsageEvent::InvokedDiff { fetch },
-            JettyCommand::Plan { fetch } => UsageEvent::InvokedPlan { fetch },
-            JettyCommand::Apply { no_fetch } => UsageEvent::InvokedApply { no_fetch },
+            JettyCommand::Diff { fetch, .. } => UsageEvent::InvokedDiff { fetch },
+            JettyCommand::Drift { no_fetch, .. } => UsageEvent::InvokedDrift { no_fetch },
+            JettyCommand::Plan { fetch, .. } => UsageEvent::InvokedPlan { fetch },
+            JettyCommand::Apply { no_fetch, .. } => UsageEvent::InvokedApply { no_fetch },
             JettyCommand::Subgraph { depth, .. } => UsageEvent::InvokedSubgraph { depth },
             JettyCommand::Remove { node_type, .. } => UsageEvent::InvokedRemove { node_type },
             JettyCommand::Rename { node_type, .. } => UsageEvent::InvokedRename { node_type },
@@ -127,8 +130,18 @@ pub async fn cli() -> Result<()> {
         JettyCommand::Fetch {
             visualize,
             connectors,
+            concurrency,
+            fail_fast,
         } => {
-            fetch(connectors, visualize).await?;
+            fetch(
+                connectors,
+                visualize,
+                &FetchOptions {
+                    concurrency: *concurrency,
+                    fail_fast: *fail_fast,
+                },
+            )
+            .await?;
         }
 
         JettyCommand::Explore {
@@ -137,7 +150,7 @@ pub async fn cli() -> Result<()> {
         } => {
             if *fetch_first {
                 info!("Fetching all data first.");
-                fetch(&None, &false).await?;
+                fetch(&None, &false, &FetchOptions::default()).await?;
             }
 
             let jetty = new_jetty_with_connectors(".", true).await?;
@@ -154,34 +167,58 @@ pub async fn cli() -> Result<()> {
         } => {
             if !*no_fetch {
                 info!("Fetching data before bootstrap");
-                fetch(&None, &false).await?;
+                fetch(&None, &false, &FetchOptions::default()).await?;
             };
             bootstrap(*overwrite).await?;
         }
-        JettyCommand::Diff { fetch: fetch_first } => {
+        JettyCommand::Diff {
+            fetch: fetch_first,
+            format,
+        } => {
             if *fetch_first {
                 info!("Fetching data before diff");
-                fetch(&None, &false).await?;
-            } else {
+                fetch(&None, &false, &FetchOptions::default()).await?;
+            } else if *format == OutputFormat::Text {
                 println!("Generating diff based off existing data. Run `jetty diff -f` to fetch before generating the diff.")
             };
-            diff::diff().await?;
+            diff::diff(format).await?;
+        }
+        JettyCommand::Drift { no_fetch, format } => {
+            if !*no_fetch {
+                info!("Fetching data before checking for drift");
+                fetch(&None, &false, &FetchOptions::default()).await?;
+            };
+            if drift::drift(format).await? {
+                std::process::exit(drift::DRIFT_EXIT_CODE);
+            }
         }
-        JettyCommand::Plan { fetch: fetch_first } => {
+        JettyCommand::Plan {
+            fetch: fetch_first,
+            out,
+            sql_out,
+            format,
+        } => {
             if *fetch_first {
                 info!("Fetching data before plan");
-                fetch(&None, &false).await?;
-            } else {
+                fetch(&None, &false, &FetchOptions::default()).await?;
+            } else if *format == OutputFormat::Text {
                 println!("Generating plan based off existing data. Run `jetty plan -f` to fetch before generating the plan.")
             };
-            plan::plan().await?;
+            plan::plan(out, sql_out, format).await?;
         }
-        JettyCommand::Apply { no_fetch } => {
-            if !*no_fetch {
+        JettyCommand::Apply {
+            no_fetch,
+            plan_file,
+            report,
+        } => {
+            if plan_file.is_some() {
+                // Fetching would update the access graph and invalidate the saved plan
+                info!("Applying a saved plan based on the data it was generated from.");
+            } else if !*no_fetch {
                 info!("Fetching data before apply. You can run `jetty apply -n` to run apply based on a previous fetch.");
-                fetch(&None, &false).await?;
+                fetch(&None, &false, &FetchOptions::default()).await?;
             };
-            apply().await?;
+            apply(plan_file, report).await?;
         }
         JettyCommand::Subgraph { id, depth } => {
             let jetty = new_jetty_with_connectors(".", true).await?;
@@ -211,10 +248,36 @@ pub async fn cli() -> Result<()> {
     Ok(())
 }
 
-async fn fetch(connectors: &Option<Vec<String>>, &visualize: &bool) -> Result<()> {
+/// The number of connectors fetched at the same time, unless otherwise specified
+const DEFAULT_FETCH_CONCURRENCY: usize = 4;
+
+/// Options controlling how connector data is fetched
+pub(crate) struct FetchOptions {
+    /// The maximum number of connectors to fetch at once
+    pub(crate) concurrency: usize,
+    /// Abort as soon as any connector fails, rather than building the graph from the
+    /// connectors that succeeded
+    pub(crate) fail_fast: bool,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_FETCH_CONCURRENCY,
+            fail_fast: false,
+        }
+    }
+}
+
+async fn fetch(
+    connectors: &Option<Vec<String>>,
+    &visualize: &bool,
+    options: &FetchOptions,
+) -> Result<()> {
     let jetty = new_jetty_with_connectors(".", false).await?;
 
     let mut data_from_connectors = vec![];
+    let mut failed_connectors = vec![];
 
     // Handle optionally fetching for only a few connectors
     let selected_connectors = if let Some(conns) = connectors {
@@ -227,21 +290,53 @@ async fn fetch(connectors: &Option<Vec<String>>, &visualize: &bool) -> Result<()
         jetty.connectors
     };
 
-    for (namespace, mut conn) in selected_connectors {
-        let pb = basic_progress_bar(format!("Fetching {namespace} data").as_str());
-
-        let now = Instant::now();
-        let data = conn.get_data().await;
-        let pcd = (data, namespace.to_owned());
-
-        data_from_connectors.push(pcd);
-
-        pb.finish_with_message(format!(
-            "Fetching {} data took {:.1} seconds",
-            namespace,
-            now.elapsed().as_secs_f32()
-        ));
+    let progress = MultiProgress::new();
+    let mut fetches = stream::iter(
+        selected_connectors
+            .into_iter()
+            .map(|(namespace, mut conn)| {
+                let pb = progress.add(basic_progress_bar(
+                    format!("Fetching {namespace} data").as_str(),
+                ));
+                async move {
+                    let now = Instant::now();
+                    let data = conn.get_data().await;
+                    match &data {
+                        Ok(_) => pb.finish_with_message(format!(
+                            "Fetching {} data took {:.1} seconds",
+                            namespace,
+                            now.elapsed().as_secs_f32()
+                        )),
+                        Err(_) => pb.abandon_with_message(
+                            format!(
+                                "Fetching {} data failed after {:.1} seconds",
+                                namespace,
+                                now.elapsed().as_secs_f32()
+                            )
+                            .red()
+                            .to_string(),
+                        ),
+                    }
+                    (namespace, data)
+                }
+            }),
+    )
+    .buffer_unordered(options.concurrency.max(1));
+
+    while let Some((namespace, data)) = fetches.next().await {
+        match data {
+            Ok(data) => data_from_connectors.push((data, namespace)),
+            Err(e) if options.fail_fast => {
+                // Dropping the stream cancels any fetches still in progress
+                return Err(e.context(format!("failed to fetch data for {namespace}")));
+            }
+            Err(e) => {
+                error!("failed to fetch data for {namespace}: {e:#}");
+                failed_connectors.push(namespace);
+            }
+        }
     }
+    drop(fetches);
 
     let pb = basic_progress_bar("Creating access graph");
     let now = Instant::now();
@@ -249,10 +344,14 @@ async fn fetch(connectors: &Option<Vec<String>>, &visualize: &bool) -> Result<()
     let jetty = new_jetty_with_connectors(".", false).await?;
 
     let ag = log_runtime!("new graph", AccessGraph::new_from_connector_data(data_from_connectors, &jetty)?;);
-    log_runtime!(
-        "serialize graph",
-        ag.serialize_graph(project::data_dir().join(project::graph_filename()))?;
-    );
+    // A partial graph would make the next diff propose re-granting everything the failed
+    // connectors manage, so the last complete graph is kept instead
+    if failed_connectors.is_empty() {
+        log_runtime!(
+            "serialize graph",
+            ag.serialize_graph(project::data_dir().join(project::graph_filename()))?;
+        );
+    }
 
     pb.finish_with_message(format!(
         "Access graph created in {:.1} seconds",
@@ -272,6 +371,21 @@ async fn fetch(connectors: &Option<Vec<String>>, &visualize: &bool) -> Result<()
         debug!("Skipping visualization.")
     };
 
+    if !failed_connectors.is_empty() {
+        let failed = failed_connectors
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        // The graph is missing the failed connectors, so generating files from it would
+        // remove their assets and users from the configuration
+        warn!(
+            "the access graph doesn't include data from {failed}; keeping the last complete \
+            access graph and skipping config file updates"
+        );
+        bail!("failed to fetch data for {failed}; run `jetty fetch --fail-fast` to stop at the first failure");
+    }
+
     // if config files have already been created, update them, if not, skip
     if project::groups_cfg_path_local().exists()
         || project::assets_cfg_root_path_local().exists()
@@ -359,7 +473,7 @@ async fn bootstrap(overwrite: bool) -> Result<()> {
     Ok(())
 }
 
-async fn apply() -> Result<()> {
+async fn apply(plan_file: &Option<PathBuf>, report_file: &Option<PathBuf>) -> Result<()> {
     let jetty = &mut new_jetty_with_connectors(".", true).await.map_err(|_| {
         anyhow!(
             "unable to find {} - make sure you are in a \
@@ -368,43 +482,82 @@ async fn apply() -> Result<()> {
         )
     })?;
 
-    let diffs = get_diffs(jetty)?;
-
-    // make sure there's an existing access graph
-    let ag = jetty.try_access_graph()?;
-    let connector_specific_diffs = diffs.split_by_connector();
-
-    let tr = ag.translator();
-
-    let local_diffs = connector_specific_diffs
-        .iter()
-        .map(|(k, v)| (k.to_owned(), tr.translate_diffs_to_local(v, k)))
-        .collect::<HashMap<_, _>>();
+    let local_diffs = if let Some(path) = plan_file {
+        let saved_plan = SavedPlan::read_from_file(path)?;
+        saved_plan
+            .validate(jetty)
+            .context(format!("unable to apply {}", path.display()))?;
+        println!(
+            "Applying plan {} (created {})",
+            path.display(),
+            saved_plan.created()
+        );
+        saved_plan
+            .diffs()
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect::<HashMap<_, _>>()
+    } else {
+        plan::get_local_diffs(jetty)?
+    };
 
     let mut results: HashMap<_, _> = HashMap::new();
 
     let pb = basic_progress_bar("Applying changes");
     let now = Instant::now();
     for (conn, diff) in local_diffs {
-        results.insert(
-            conn.to_owned(),
-            jetty.connectors[&conn].apply_changes(&diff).await?,
-        );
+        // A failing connector shouldn't keep the others from applying, or lose their reports
+        let report = match jetty.connectors[&conn].apply_changes(&diff).await {
+            Ok(report) => report,
+            Err(e) => {
+                error!("failed to apply changes to {conn}: {e:#}");
+                ApplyReport::connector_failure(&e)
+            }
+        };
+        results.insert(conn.to_owned(), report);
     }
     pb.finish_with_message(format!(
         "Changes applied in {:.1} seconds",
         now.elapsed().as_secs_f32()
     ));
 
-    for (c, result) in results {
-        println!("{c}:\n{result}\n");
+    let mut failure_count = 0;
+    for (c, report) in &results {
+        println!("{c}:\n{report}\n");
+        let failures = report.failures();
+        if !failures.is_empty() {
+            println!("{}", "Failed operations:".red());
+            for failure in &failures {
+                let error = match &failure.outcome {
+                    ApplyOutcome::Failed { error } => error.as_str(),
+                    ApplyOutcome::Succeeded => "",
+                };
+                println!("  {}", failure.operation.target);
+                println!("{}", textwrap::indent(&failure.operation.request, "    "));
+                println!("    {} {error}\n", "error:".red());
+            }
+        }
+        failure_count += failures.len();
+        if report.error.is_some() {
+            failure_count += 1;
+        }
+    }
+
+    if let Some(path) = report_file {
+        let report_map: BTreeMap<_, _> = results.iter().collect();
+        fs::write(
+            path,
+            serde_json::to_string_pretty(&report_map).context("serializing apply report")?,
+        )
+        .context(format!("writing apply report to {}", path.display()))?;
+        println!("Apply report written to {}\n", path.display());
     }
 
     println!("Fetching updated state");
 
-    match fetch(&None, &false).await {
+    match fetch(&None, &false, &FetchOptions::default()).await {
         Ok(_) => {
-            diff::diff().await?;
+            diff::diff(&OutputFormat::Text).await?;
         }
         Err(_) => {
             error!("unable to perform fetch");
@@ -412,81 +565,22 @@ async fn apply() -> Result<()> {
         }
     };
 
+    if failure_count > 0 {
+        bail!("{failure_count} operations or connectors failed while applying changes");
+    }
+
     Ok(())
 }
 
-async fn get_connectors(
-    creds: &HashMap<String, CredentialsMap>,
-    selected_connectors: &HashMap<ConnectorNamespace, jetty_core::jetty::ConnectorConfig>,
-) -> Result<HashMap<ConnectorNamespace, Box<dyn Connector>>> {
-    let mut connector_map: HashMap<ConnectorNamespace, Box<dyn Connector>> = HashMap::new();
-
-    for (namespace, config) in selected_connectors {
-        connector_map.insert(
-            namespace.to_owned(),
-            match config.connector_type.as_str() {
-                "dbt" => {
-                    jetty_dbt::DbtConnector::new(
-                        &selected_connectors[namespace],
-                        &creds
-                            .get(namespace.to_string().as_str())
-                            .ok_or_else(|| {
-                                anyhow!(
-                                    "unable to find a connector called {} in {}",
-                                    namespace,
-                                    project::connector_cfg_path().display()
-                                )
-                            })?
-                            .to_owned(),
-                        Some(ConnectorClient::Core),
-                        Some(project::data_dir().join(namespace.to_string())),
-                    )
-                    .await?
-                }
-                "snowflake" => {
-                    jetty_snowflake::SnowflakeConnector::new(
-                        &selected_connectors[namespace],
-                        &creds
-                            .get(namespace.to_string().as_str())
-                            .ok_or_else(|| {
-                                anyhow!(
-                                    "unable to find a connector called {} in {}",
-                                    namespace,
-                                    project::connector_cfg_path().display()
-                                )
-                            })?
-                            .to_owned(),
-                        Some(ConnectorClient::Core),
-                        Some(project::data_dir().join(namespace.to_string())),
-                    )
-                    .await?
-                }
-                "tableau" => {
-                    jetty_tableau::TableauConnector::new(
-                        &selected_connectors[namespace],
-                        &creds
-                            .get(namespace.to_string().as_str())
-                            .ok_or_else(|| {
-                                anyhow!(
-                                    "unable to find a connector called {} in {}",
-                                    namespace,
-                                    project::connector_cfg_path().display()
-                                )
-                            })?
-                            .to_owned(),
-                        Some(ConnectorClient::Core),
-                        Some(project::data_dir().join(namespace.to_string())),
-                    )
-                    .await?
-                }
-                _ => panic!(
-                    "unknown connector type: {}",
-                    config.connector_type.to_owned()
-                ),
-            },
-        );
-    }
-    Ok(connector_map)
+/// The connector types that ship with Jetty
+fn connector_registry() -> ConnectorRegistry {
+    let mut registry = ConnectorRegistry::new();
+    file::register(&mut registry);
+    plugin::register(&mut registry);
+    jetty_dbt::register(&mut registry);
+    jetty_snowflake::register(&mut registry);
+    jetty_tableau::register(&mut registry);
+    registry
 }
 
 /// Create a new Jetty struct with all the connectors. Uses default locations for everything
@@ -507,7 +601,14 @@ pub async fn new_jetty_with_connectors<P: AsRef<Path>>(
         )
     })?;
 
-    let connectors = get_connectors(&creds, &config.connectors).await?;
+    let connectors = connector_registry()
+        .new_connectors(
+            &creds,
+            &config.connectors,
+            ConnectorClient::Core,
+            project::data_dir(),
+        )
+        .await?;
 
     Jetty::new_with_config(
         config,
@@ -1,15 +1,13 @@
-use std::collections::HashMap;
-
 use anyhow::{bail, Result};
 use colored::Colorize;
-use inquire::{Confirm, Text};
+use inquire::{Confirm, Password, PasswordDisplayMode, Select, Text};
 use jetty_core::{
     connectors::NewConnector,
     jetty::{ConnectorConfig, ConnectorNamespace, CredentialsMap},
     project::default_keypair_dir_path,
     Connector,
 };
-use jetty_snowflake::SnowflakeConnector;
+use jetty_snowflake::{LoginMethod, SnowflakeConnector, SnowflakeCredentials};
 
 use crate::new::{
     inquiry::{
@@ -21,6 +19,15 @@ use crate::new::{
 
 use super::{validation::filled_validator, SKIP_CMD};
 
+const KEY_PAIR: &str = "Key pair";
+const OAUTH: &str = "OAuth token";
+const PASSWORD: &str = "Username and password";
+const EXTERNAL_BROWSER: &str = "External browser (SSO)";
+
+const OAUTH_COMMAND: &str = "Run a command that prints a token";
+const OAUTH_ENV: &str = "Read an environment variable";
+const OAUTH_TOKEN: &str = "Save a token";
+
 pub(crate) async fn ask_snowflake_connector_setup(
     connector_namespace: ConnectorNamespace,
 ) -> Result<CredentialsMap> {
@@ -66,72 +73,171 @@ pub(crate) async fn ask_snowflake_connector_setup(
             bail!("skipped");
         }
 
-        let keypair_answer = Text::new("Input a path to a pkcs8 private key file (`.p8`) to use for authentication or leave blank to create a new keypair.")
-            .with_validator(FilepathValidator::new(
-                None,
-                PathType::File,
-                "File not found.".to_string(),
-                FilepathValidatorMode::AllowedValues{allowed_values: vec![SKIP_CMD.to_owned(), "".to_owned()]},
-            ))
-            .with_autocomplete(FilepathCompleter::default())
-            .with_help_message(skip_message)
-            .prompt()?;
-        if keypair_answer == SKIP_CMD {
-            bail!("skipped");
-        }
-        let should_create_keypair = keypair_answer.is_empty();
-        let keypair_filepath = if should_create_keypair {
-            default_keypair_dir_path()
-                .join(format!("{connector_namespace}.p8"))
-                .to_string_lossy()
-                .to_string()
-        } else {
-            keypair_answer
-        };
+        // Choose how to authenticate
+        let options = vec![KEY_PAIR, OAUTH, PASSWORD, EXTERNAL_BROWSER];
+        let authentication_type =
+            Select::new("How would you like to authenticate with Snowflake?", options)
+                .with_help_message("Key pair authentication is best for service users. Use OAuth or an external browser if your account requires SSO.")
+                .prompt()?;
 
-        let keypair = if should_create_keypair {
-            println!("Generating keypair...");
-            let keypair = KeyPair::new()?;
-            println!("Creating files...");
-            keypair.save_to_files(keypair_filepath)?;
-            println!("Keypair generated!");
-            keypair
-        } else {
-            println!("Loading keypair...");
-            KeyPair::from_path(keypair_filepath)?
+        let method = match authentication_type {
+            KEY_PAIR => ask_key_pair(&connector_namespace, &admin_username, skip_message)?,
+            OAUTH => ask_oauth()?,
+            PASSWORD => {
+                let password = Password::new("Snowflake password:")
+                    .with_display_toggle_enabled()
+                    .without_confirmation()
+                    .with_display_mode(PasswordDisplayMode::Hidden)
+                    .with_validator(filled_validator)
+                    .with_help_message(
+                        "Your password will only be saved locally. [Ctrl+R] to toggle visibility.",
+                    )
+                    .prompt()?;
+                LoginMethod::Password { password }
+            }
+            EXTERNAL_BROWSER => {
+                println!("Jetty will open a browser to log in to Snowflake whenever it connects.");
+                LoginMethod::ExternalBrowser
+            }
+            _ => {
+                panic!();
+            }
         };
 
-        println!("Authorize Jetty access to your account by running the following SQL statement in Snowflake.");
-        println!(
-            "\n{}\n",
-            format!(
-                "ALTER USER {} SET rsa_public_key='{}';",
-                admin_username,
-                keypair.public_inner()
-            )
-            .italic()
-        );
-
-        let confirmed = Confirm::new("Enter 'y' once the ALTER USER is complete, or 'n' to skip Snowflake setup. You can add connectors later by running 'jetty add'.")
-            .prompt()?;
-
-        if !confirmed {
-            bail!("skipped");
-        }
-
-        let creds = HashMap::from([
-            ("account".to_owned(), snowflake_account_id),
-            ("user".to_owned(), admin_username),
-            ("warehouse".to_owned(), warehouse),
-            ("public_key_fp".to_owned(), keypair.fingerprint()),
-            ("private_key".to_owned(), keypair.private_key()),
-            ("role".to_owned(), user_role),
-        ]);
+        let creds = SnowflakeCredentials::new(
+            method,
+            snowflake_account_id,
+            admin_username,
+            user_role,
+            warehouse,
+        )
+        .to_map();
+        // Logging in with a password or a browser happens when the connection is checked
         let connector =
-            SnowflakeConnector::new(&ConnectorConfig::default(), &creds, None, None).await?;
+            match SnowflakeConnector::new(&ConnectorConfig::default(), &creds, None, None).await {
+                Ok(connector) => connector,
+                Err(e) => {
+                    println!("{}", format!("Could not connect to Snowflake: {e:#}").red());
+                    continue;
+                }
+            };
         if connector.check().await {
             println!("successful connection!");
             return Ok(creds);
         }
     }
 }
+
+/// Set up OAuth authentication. Access tokens expire, so they're best read from a command
+/// or an environment variable that Jetty can read again.
+fn ask_oauth() -> Result<LoginMethod> {
+    let options = vec![OAUTH_COMMAND, OAUTH_ENV, OAUTH_TOKEN];
+    let source = Select::new("How should Jetty get OAuth access tokens?", options)
+        .with_help_message("Access tokens expire. Jetty runs the command or reads the environment variable again whenever Snowflake rejects a token, while a saved token has to be replaced by hand.")
+        .prompt()?;
+
+    let (mut oauth_token, mut oauth_token_command, mut oauth_token_env) = (None, None, None);
+    match source {
+        OAUTH_COMMAND => {
+            oauth_token_command = Some(
+                Text::new("Command:")
+                    .with_validator(filled_validator)
+                    .with_placeholder("idp-cli get-token --audience snowflake")
+                    .with_help_message("A command that prints an access token for the Jetty user from your identity provider or a Snowflake OAuth integration.")
+                    .prompt()?,
+            )
+        }
+        OAUTH_ENV => {
+            oauth_token_env = Some(
+                Text::new("Environment variable:")
+                    .with_validator(filled_validator)
+                    .with_default("SNOWFLAKE_OAUTH_TOKEN")
+                    .with_help_message(
+                        "The environment variable that holds an access token for the Jetty user.",
+                    )
+                    .prompt()?,
+            )
+        }
+        _ => {
+            oauth_token = Some(
+                Password::new("OAuth access token:")
+                    .with_display_toggle_enabled()
+                    .without_confirmation()
+                    .with_display_mode(PasswordDisplayMode::Hidden)
+                    .with_validator(filled_validator)
+                    .with_help_message("An access token for the Jetty user from your identity provider or a Snowflake OAuth integration. It will only be saved locally. [Ctrl+R] to toggle visibility.")
+                    .prompt()?,
+            )
+        }
+    }
+    Ok(LoginMethod::Oauth {
+        oauth_token,
+        oauth_token_command,
+        oauth_token_env,
+    })
+}
+
+/// Set up key pair authentication, with an existing key or a new one
+fn ask_key_pair(
+    connector_namespace: &ConnectorNamespace,
+    admin_username: &str,
+    skip_message: &str,
+) -> Result<LoginMethod> {
+    let keypair_answer = Text::new("Input a path to a pkcs8 private key file (`.p8`) to use for authentication or leave blank to create a new keypair.")
+        .with_validator(FilepathValidator::new(
+            None,
+            PathType::File,
+            "File not found.".to_string(),
+            FilepathValidatorMode::AllowedValues{allowed_values: vec![SKIP_CMD.to_owned(), "".to_owned()]},
+        ))
+        .with_autocomplete(FilepathCompleter::default())
+        .with_help_message(skip_message)
+        .prompt()?;
+    if keypair_answer == SKIP_CMD {
+        bail!("skipped");
+    }
+    let should_create_keypair = keypair_answer.is_empty();
+    let keypair_filepath = if should_create_keypair {
+        default_keypair_dir_path()
+            .join(format!("{connector_namespace}.p8"))
+            .to_string_lossy()
+            .to_string()
+    } else {
+        keypair_answer
+    };
+
+    let keypair = if should_create_keypair {
+        println!("Generating keypair...");
+        let keypair = KeyPair::new()?;
+        println!("Creating files...");
+        keypair.save_to_files(keypair_filepath)?;
+        println!("Keypair generated!");
+        keypair
+    } else {
+        println!("Loading keypair...");
+        KeyPair::from_path(keypair_filepath)?
+    };
+
+    println!("Authorize Jetty access to your account by running the following SQL statement in Snowflake.");
+    println!(
+        "\n{}\n",
+        format!(
+            "ALTER USER {} SET rsa_public_key='{}';",
+            admin_username,
+            keypair.public_inner()
+        )
+        .italic()
+    );
+
+    let confirmed = Confirm::new("Enter 'y' once the ALTER USER is complete, or 'n' to skip Snowflake setup. You can add connectors later by running 'jetty add'.")
+        .prompt()?;
+
+    if !confirmed {
+        bail!("skipped");
+    }
+
+    Ok(LoginMethod::KeyPair {
+        private_key: keypair.private_key(),
+        public_key_fp: keypair.fingerprint(),
+    })
+}
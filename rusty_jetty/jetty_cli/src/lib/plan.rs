@@ -1,13 +1,31 @@
 //! plan the changes for Jetty and each connector
 
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{anyhow, Result};
-use jetty_core::{project, write::diff::get_diffs};
+use anyhow::{anyhow, Context, Result};
+use jetty_core::{
+    access_graph::translate::diffs::LocalConnectorDiffs,
+    jetty::ConnectorNamespace,
+    project,
+    write::{
+        diff::get_diffs,
+        output::PlanOutput,
+        saved_plan::SavedPlan,
+        sql_script::{self, ConfigSources},
+    },
+    Jetty,
+};
 
-use crate::new_jetty_with_connectors;
+use crate::{cmd::OutputFormat, new_jetty_with_connectors};
 
-pub(super) async fn plan() -> Result<()> {
+pub(super) async fn plan(
+    out: &Option<PathBuf>,
+    sql_out: &Option<PathBuf>,
+    format: &OutputFormat,
+) -> Result<()> {
     let jetty = &mut new_jetty_with_connectors(".", true).await.map_err(|_| {
         anyhow!(
             "unable to find {} - make sure you are in a \
@@ -16,19 +34,50 @@ pub(super) async fn plan() -> Result<()> {
         )
     })?;
 
-    let diffs = get_diffs(jetty)?;
-
-    // make sure there's an existing access graph
-    let ag = jetty.try_access_graph()?;
+    let local_diffs = get_local_diffs(jetty)?;
 
-    let connector_specific_diffs = diffs.split_by_connector();
+    if let Some(path) = out {
+        SavedPlan::new(jetty, local_diffs.to_owned())?.write_to_file(path)?;
+        // Keep stdout clean for machine-readable output
+        let message = format!(
+            "Plan saved to {}. Run `jetty apply {}` to apply exactly these changes.\n",
+            path.display(),
+            path.display()
+        );
+        match format {
+            OutputFormat::Text => println!("{message}"),
+            _ => eprintln!("{message}"),
+        }
+    }
 
-    let tr = ag.translator();
+    if let Some(dir) = sql_out {
+        let message = write_sql_scripts(jetty, &local_diffs, dir)?;
+        match format {
+            OutputFormat::Text => println!("{message}"),
+            _ => eprintln!("{message}"),
+        }
+    }
 
-    let local_diffs = connector_specific_diffs
+    let plans: HashMap<_, _> = local_diffs
         .iter()
-        .map(|(k, v)| (k.to_owned(), tr.translate_diffs_to_local(v, k)))
-        .collect::<HashMap<_, _>>();
+        .map(|(k, v)| (k.to_owned(), jetty.connectors[k].plan_changes(v)))
+        .collect();
+
+    match format {
+        OutputFormat::Json => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&PlanOutput::new(&plans))
+                    .context("serializing plan")?
+            );
+            return Ok(());
+        }
+        OutputFormat::Markdown => {
+            println!("{}", PlanOutput::new(&plans).to_markdown());
+            return Ok(());
+        }
+        OutputFormat::Text => (),
+    }
 
     // Exit early if there haven't been any changes
     if local_diffs.is_empty() {
@@ -36,11 +85,6 @@ pub(super) async fn plan() -> Result<()> {
         return Ok(());
     }
 
-    let plans: HashMap<_, _> = local_diffs
-        .iter()
-        .map(|(k, v)| (k.to_owned(), jetty.connectors[k].plan_changes(v)))
-        .collect();
-
     for (c, plan) in plans {
         println!("{c}:");
         if !plan.is_empty() {
@@ -53,3 +97,66 @@ pub(super) async fn plan() -> Result<()> {
     }
     Ok(())
 }
+
+/// Write SQL scripts for the connectors that support them and describe what was written
+fn write_sql_scripts(
+    jetty: &Jetty,
+    local_diffs: &HashMap<ConnectorNamespace, LocalConnectorDiffs>,
+    dir: &Path,
+) -> Result<String> {
+    // Connectors without changes get no new scripts, so their old ones are cleared too
+    for (connector, manifest) in jetty.connector_manifests() {
+        if !manifest.capabilities.write.is_empty() {
+            sql_script::clear_sql_scripts(dir, &connector)?;
+        }
+    }
+
+    let sources = ConfigSources::new(jetty)?;
+    let mut written = vec![];
+    for (connector, diffs) in local_diffs {
+        if let Some(batches) = jetty.connectors[connector].plan_batches(diffs) {
+            let paths = sql_script::write_sql_scripts(dir, connector, &batches, &sources)?;
+            written.push(format!(
+                "{connector}: {}",
+                paths
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+    }
+    written.sort();
+
+    Ok(if written.is_empty() {
+        format!(
+            "No SQL scripts written to {}. There are no changes for connectors that support them.",
+            dir.display()
+        )
+    } else {
+        format!(
+            "SQL scripts written. Run each connector's scripts in order:\n{}\n",
+            written.join("\n")
+        )
+    })
+}
+
+/// Get the diffs for the current configuration and translate them into the namespace
+/// of each connector
+pub(crate) fn get_local_diffs(
+    jetty: &mut Jetty,
+) -> Result<HashMap<ConnectorNamespace, LocalConnectorDiffs>> {
+    let diffs = get_diffs(jetty)?;
+
+    // make sure there's an existing access graph
+    let ag = jetty.try_access_graph()?;
+
+    let connector_specific_diffs = diffs.split_by_connector();
+
+    let tr = ag.translator();
+
+    Ok(connector_specific_diffs
+        .iter()
+        .map(|(k, v)| (k.to_owned(), tr.translate_diffs_to_local(v, k)))
+        .collect())
+}
@@ -189,6 +189,9 @@ pub enum UsageEvent {
     /// `jetty diff`
     #[serde(rename = "invoked_diff")]
     InvokedDiff { fetch: bool },
+    /// `jetty drift`
+    #[serde(rename = "invoked_drift")]
+    InvokedDrift { no_fetch: bool },
     /// `jetty plan`
     #[serde(rename = "invoked_plan")]
     InvokedPlan { fetch: bool },
@@ -32,6 +32,12 @@ use bimap;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+/// Get the key for a connector's user metadata in the graph. Users are merged across
+/// connectors, so their metadata keys are namespaced by connector.
+pub(crate) fn user_metadata_key(connector: &ConnectorNamespace, key: &str) -> String {
+    format!("{connector}::{key}")
+}
+
 /// Struct to translate local data to global data and back again
 /// Eventually, this will need to be persisted with the graph to enable the write path
 #[derive(Default, Serialize, Deserialize)]
@@ -286,7 +292,11 @@ impl Translator {
         ProcessedUser {
             name: self.local_to_global.users[&connector][&user.name].to_owned(),
             identifiers: user.identifiers,
-            metadata: user.metadata,
+            metadata: user
+                .metadata
+                .into_iter()
+                .map(|(k, v)| (user_metadata_key(&connector, &k), v))
+                .collect(),
             member_of: user
                 .member_of
                 .iter()
@@ -4,16 +4,27 @@
 pub mod default_policies;
 /// Group-specific diff functionality
 pub mod groups;
+/// ownership-specific diff functionality
+pub mod ownership;
 /// policy-specific diff functionality
 pub mod policies;
+/// masking and row access policy-specific diff functionality
+pub mod protections;
+/// tag-specific functionality
+pub mod tags;
+/// user property-specific diff functionality
+pub mod user_properties;
 /// User-specific diff functionality
 pub mod users;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{jetty::ConnectorNamespace, write::GlobalDiffs};
 
 use super::Translator;
 
 /// Diffs in the namespace of the connectors
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalConnectorDiffs {
     /// The group-specific diffs
     pub groups: Vec<groups::LocalDiff>,
@@ -23,6 +34,18 @@ pub struct LocalConnectorDiffs {
     pub default_policies: Vec<default_policies::LocalDiff>,
     /// The policies-specific diffs
     pub policies: Vec<policies::LocalDiff>,
+    /// The configured state of tags, for connectors that can write tags
+    #[serde(default)]
+    pub tags: Vec<tags::LocalDiff>,
+    /// The masking and row access policy diffs
+    #[serde(default)]
+    pub protections: Vec<protections::LocalDiff>,
+    /// The ownership transfers
+    #[serde(default)]
+    pub ownership: Vec<ownership::LocalDiff>,
+    /// The user property changes
+    #[serde(default)]
+    pub user_properties: Vec<user_properties::LocalDiff>,
 }
 
 impl Translator {
@@ -55,6 +78,26 @@ impl Translator {
                 .iter()
                 .map(|g| self.translate_policy_diff_to_local(g))
                 .collect(),
+            tags: diffs
+                .tags
+                .iter()
+                .map(|t| self.translate_tag_state_to_local(t))
+                .collect(),
+            protections: diffs
+                .protections
+                .iter()
+                .map(|p| self.translate_protection_diff_to_local(p))
+                .collect(),
+            ownership: diffs
+                .ownership
+                .iter()
+                .map(|o| self.translate_ownership_diff_to_local(o))
+                .collect(),
+            user_properties: diffs
+                .user_properties
+                .iter()
+                .map(|u| self.translate_user_properties_diff_to_local(u))
+                .collect(),
         }
     }
 }
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{
     access_graph::translate::Translator,
     cual::Cual,
@@ -9,7 +11,7 @@ use crate::{
     },
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A group-specific local diff
 pub struct LocalDiff {
     /// the asset being diffed
@@ -6,7 +6,9 @@ use crate::{
 
 use std::collections::{BTreeSet, HashSet};
 
-#[derive(Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A group-specific local diff
 pub struct LocalDiff {
     /// the group being diffed
@@ -15,7 +17,7 @@ pub struct LocalDiff {
     pub details: LocalDiffDetails,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Outlines the diff type needed
 pub enum LocalDiffDetails {
     /// Add a group
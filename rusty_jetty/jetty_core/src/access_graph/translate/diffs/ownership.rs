@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access_graph::translate::Translator, connectors::ownership::CurrentGrants, cual::Cual, write,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A transfer of the ownership of an asset, in the namespace of a connector
+pub struct LocalDiff {
+    /// the asset being transferred
+    pub asset: Cual,
+    /// the group that should own the asset
+    pub owner: String,
+    /// the group that currently owns the asset, if it's known
+    pub previous_owner: Option<String>,
+    /// what happens to the existing grants on the asset
+    pub current_grants: CurrentGrants,
+}
+
+impl Translator {
+    pub(super) fn translate_ownership_diff_to_local(
+        &self,
+        global_diff: &write::assets::diff::ownership::OwnershipDiff,
+    ) -> LocalDiff {
+        let connector = &global_diff.connector;
+        LocalDiff {
+            asset: self.asset_name_to_cual(&global_diff.asset).unwrap(),
+            owner: self.translate_node_name_to_local(&global_diff.owner, connector),
+            previous_owner: global_diff
+                .previous_owner
+                .as_ref()
+                .map(|o| self.translate_node_name_to_local(o, connector)),
+            current_grants: global_diff.current_grants,
+        }
+    }
+}
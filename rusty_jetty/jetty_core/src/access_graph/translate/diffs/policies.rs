@@ -1,8 +1,10 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{access_graph::translate::Translator, cual::Cual, write};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A group-specific local diff
 pub struct LocalDiff {
     /// the asset being diffed
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access_graph::translate::Translator,
+    connectors::protections::RowAccessPolicy,
+    cual::Cual,
+    write::{self, assets::diff::protections::ProtectionChange},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A change to the policies protecting an asset, in the namespace of a connector
+pub struct LocalDiff {
+    /// the asset being diffed
+    pub asset: Cual,
+    /// the change to the row access policy, if it's changing
+    pub row_access_policy: Option<ProtectionChange<RowAccessPolicy>>,
+    /// the changing masking policies, by column
+    pub masking_policies: BTreeMap<String, ProtectionChange<String>>,
+    /// the change to the account's network policy, if it's changing
+    #[serde(default)]
+    pub network_policy: Option<ProtectionChange<String>>,
+    /// the change to the account's session policy, if it's changing
+    #[serde(default)]
+    pub session_policy: Option<ProtectionChange<String>>,
+}
+
+impl Translator {
+    pub(super) fn translate_protection_diff_to_local(
+        &self,
+        global_diff: &write::assets::diff::protections::ProtectionDiff,
+    ) -> LocalDiff {
+        LocalDiff {
+            asset: self.asset_name_to_cual(&global_diff.asset).unwrap(),
+            row_access_policy: global_diff.row_access_policy.to_owned(),
+            masking_policies: global_diff.masking_policies.to_owned(),
+            network_policy: global_diff.network_policy.to_owned(),
+            session_policy: global_diff.session_policy.to_owned(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{access_graph::translate::Translator, cual::Cual, write};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The configured state of a tag, in the namespace of a connector
+pub struct LocalDiff {
+    /// the name of the tag
+    pub name: String,
+    /// the configured value of the tag
+    pub value: Option<String>,
+    /// the configured description of the tag
+    pub description: Option<String>,
+    /// the assets the tag is applied to directly
+    pub applied_to: BTreeSet<Cual>,
+}
+
+impl Translator {
+    pub(super) fn translate_tag_state_to_local(
+        &self,
+        global_state: &write::tags::TagState,
+    ) -> LocalDiff {
+        LocalDiff {
+            name: global_state.tag.to_string(),
+            value: global_state.value.to_owned(),
+            description: global_state.description.to_owned(),
+            applied_to: global_state
+                .applied_to
+                .iter()
+                .map(|a| self.asset_name_to_cual(a).unwrap())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access_graph::translate::Translator, connectors::user_properties::UserProperties, write,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A change to a user's properties, in the namespace of a connector
+pub struct LocalDiff {
+    /// the user being changed
+    pub user: String,
+    /// the properties to set. Only the properties that are changing are included.
+    pub set: UserProperties,
+    /// the current values of the properties. RSA public keys are given by their fingerprint.
+    #[serde(default)]
+    pub current: UserProperties,
+}
+
+impl Translator {
+    pub(super) fn translate_user_properties_diff_to_local(
+        &self,
+        global_diff: &write::users::diff::UserPropertiesDiff,
+    ) -> LocalDiff {
+        LocalDiff {
+            user: self.translate_node_name_to_local(&global_diff.user, &global_diff.connector),
+            set: global_diff.set.to_owned(),
+            current: global_diff.current.to_owned(),
+        }
+    }
+}
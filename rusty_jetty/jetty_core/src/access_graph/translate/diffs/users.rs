@@ -1,8 +1,10 @@
 use std::collections::HashSet;
 
+use serde::{Deserialize, Serialize};
+
 use crate::{access_graph::translate::Translator, jetty::ConnectorNamespace, write};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A group-specific local diff
 pub struct LocalDiff {
     /// the group being diffed
@@ -11,7 +13,7 @@ pub struct LocalDiff {
     pub group_membership: LocalDiffDetails,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Outlines the diff type needed
 pub struct LocalDiffDetails {
     /// the groups that the user should be added as a member of
@@ -26,7 +28,10 @@ impl Translator {
         global_diff: &write::users::CombinedUserDiff,
         connector: &ConnectorNamespace,
     ) -> Option<LocalDiff> {
-        global_diff.group_membership.as_ref().map(|group_membership| LocalDiff {
+        global_diff
+            .group_membership
+            .as_ref()
+            .map(|group_membership| LocalDiff {
                 user: self.translate_node_name_to_local(&global_diff.user, connector),
                 group_membership: LocalDiffDetails {
                     add: group_membership
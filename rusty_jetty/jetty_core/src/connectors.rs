@@ -1,8 +1,16 @@
 //! Connectors module.
 //!
 
+pub mod file;
+pub mod group_membership;
+pub mod managed_tags;
 pub mod nodes;
+pub mod ownership;
+pub mod plugin;
 pub mod processed_nodes;
+pub mod protections;
+pub mod registry;
+pub mod user_properties;
 
 use std::{collections::HashSet, path::PathBuf};
 
@@ -13,11 +21,13 @@ use serde::{Deserialize, Serialize};
 use crate::{
     access_graph::translate::diffs::LocalConnectorDiffs,
     connectors::nodes::ConnectorData,
+    cual::Cual,
     jetty::{ConnectorConfig, ConnectorManifest, CredentialsMap},
+    write::apply_report::{ApplyReport, PlannedOperation},
 };
 
 /// Client using the connector
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone)]
 pub enum ConnectorClient {
     /// Automated tests
     Test,
@@ -34,15 +44,40 @@ pub trait Connector {
     /// status (true for connected, false for not).
     async fn check(&self) -> bool;
     /// Get all data in one container for the connector to supply to the graph.
-    async fn get_data(&mut self) -> ConnectorData;
+    async fn get_data(&mut self) -> Result<ConnectorData>;
     /// Get the capabilities of a given connector. These can include
     fn get_manifest(&self) -> ConnectorManifest;
     /// Plan changes, based on a set of diffs. Can have a todo!() implementation if a connector doesn't have
     /// write capabilities
     fn plan_changes(&self, diffs: &LocalConnectorDiffs) -> Vec<String>;
-    /// Apply changes, based on a set of diffs. Can have a todo!() implementation if a connector doesn't have
+    /// Plan changes as ordered batches of operations that can be exported as scripts and run
+    /// by hand. Each batch has to finish before the next one starts. Requests may include
+    /// comment lines with notes for the person running them. Returns `None` for connectors
+    /// that don't support exporting their changes.
+    fn plan_batches(&self, _diffs: &LocalConnectorDiffs) -> Option<Vec<Vec<PlannedOperation>>> {
+        None
+    }
+    /// Apply changes, based on a set of diffs. Returns a report with the outcome of every operation
+    /// that was attempted. Can have a todo!() implementation if a connector doesn't have
     /// write capabilities
-    async fn apply_changes(&self, diffs: &LocalConnectorDiffs) -> Result<String>;
+    async fn apply_changes(&self, diffs: &LocalConnectorDiffs) -> Result<ApplyReport>;
+    /// Whether an object is managed by the connector. Connectors with filters don't fetch
+    /// the objects they exclude, so changes that involve them are left out of diffs.
+    /// Everything is in scope by default.
+    fn in_scope(&self, _object: ScopedObject) -> bool {
+        true
+    }
+}
+
+/// An object, by its connector-local name, whose scope can be checked
+#[derive(Debug, Clone, Copy)]
+pub enum ScopedObject<'a> {
+    /// An asset
+    Asset(&'a Cual),
+    /// A group
+    Group(&'a str),
+    /// A user
+    User(&'a str),
 }
 
 /// The trait all connectors are expected to implement.
@@ -58,18 +93,19 @@ pub trait NewConnector {
     ) -> Result<Box<Self>>;
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 /// The capabilities of a connector
 pub struct ConnectorCapabilities {
     /// The write capabilities of the connector. Right now these can include:
-    /// groups, policies
+    /// groups, policies, users, tags, protections, ownership, user_properties
     pub write: HashSet<WriteCapabilities>,
     /// The read capabilities of the connector. These could include:
     /// asset_lineage, assets, groups, users, policies
     pub read: HashSet<ReadCapabilities>,
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+#[derive(Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 /// Available read capabilities for connectors
 pub enum ReadCapabilities {
     /// Read asset lineage
@@ -87,7 +123,8 @@ pub enum ReadCapabilities {
     },
 }
 
-#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+#[derive(Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 /// Available write capabilities for connectors
 pub enum WriteCapabilities {
     /// Write groups, and whether groups can be nested inside groups
@@ -102,6 +139,15 @@ pub enum WriteCapabilities {
     },
     /// Add Users
     Users,
+    /// Write the tags from the tags configuration as native tags
+    Tags,
+    /// Write the masking, row access, network, and session policies declared in the asset
+    /// configuration
+    Protections,
+    /// Transfer the ownership of assets to the owners declared in the asset configuration
+    Ownership,
+    /// Set the user properties declared in the user configuration
+    UserProperties,
 }
 
 /// Enum of identifiers used to resolve user identities
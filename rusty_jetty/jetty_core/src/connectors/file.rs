@@ -0,0 +1,361 @@
+//! A read-only connector that loads connector data from YAML or JSON files. This is
+//! useful for systems without an API (their access can be exported by hand) and for
+//! working with Jetty completely offline.
+//!
+//! ```yaml
+//! connectors:
+//!   legacy_hr:
+//!     type: file
+//!     path: legacy/hr.yaml
+//! ```
+//!
+//! `path` can point to a single `.yaml`, `.yml`, or `.json` file, or to a directory of
+//! them, in which case their contents are combined. Each file uses the same shape as
+//! [`ConnectorData`], with an optional `manifest`. If no manifest is provided, one is
+//! inferred from the data.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::{
+    access_graph::translate::diffs::LocalConnectorDiffs,
+    cual::Cual,
+    jetty::{ConnectorConfig, ConnectorManifest, CredentialsMap},
+    write::apply_report::ApplyReport,
+};
+
+use super::{
+    nodes::ConnectorData, registry::ConnectorRegistry, Connector, ConnectorCapabilities,
+    ConnectorClient, NewConnector, ReadCapabilities,
+};
+
+/// The connector type that file connectors are registered under
+pub const FILE_CONNECTOR_TYPE: &str = "file";
+
+/// Register the file connector under the `file` connector type
+pub fn register(registry: &mut ConnectorRegistry) {
+    registry.register::<FileConnector>(FILE_CONNECTOR_TYPE);
+}
+
+/// The contents of a single data file
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+struct FileContents {
+    /// The manifest for the connector. Inferred from the data when not provided.
+    manifest: Option<ConnectorManifest>,
+    #[serde(flatten)]
+    data: ConnectorData,
+}
+
+impl FileContents {
+    /// Parse the contents of a file, using the extension to determine the format
+    fn parse(contents: &str, path: &Path) -> Result<Self> {
+        let value: serde_json::Value = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(contents)?,
+            Some("yaml" | "yml") => yaml_peg::serde::from_str::<serde_json::Value>(contents)?
+                .pop()
+                .unwrap_or_default(),
+            _ => bail!("{} isn't a yaml or json file", path.display()),
+        };
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Combine the contents of another file into this one
+    fn extend(&mut self, other: Self) -> Result<()> {
+        if let Some(manifest) = other.manifest {
+            let own = self.manifest.get_or_insert_with(Default::default);
+            own.capabilities
+                .read
+                .extend(manifest.capabilities.read.into_iter());
+            own.capabilities
+                .write
+                .extend(manifest.capabilities.write.into_iter());
+            merge_privileges(&mut own.asset_privileges, manifest.asset_privileges);
+        }
+
+        let data = other.data;
+        match (&self.data.cual_prefix, data.cual_prefix) {
+            (Some(own), Some(other)) if own != &other => {
+                bail!(
+                    "files for a single connector have different cual prefixes: {own} and {other}"
+                )
+            }
+            (None, Some(other)) => self.data.cual_prefix = Some(other),
+            _ => (),
+        }
+        self.data.groups.extend(data.groups);
+        self.data.users.extend(data.users);
+        self.data.assets.extend(data.assets);
+        self.data.tags.extend(data.tags);
+        self.data.policies.extend(data.policies);
+        self.data.default_policies.extend(data.default_policies);
+        self.data.asset_references.extend(data.asset_references);
+        for (user, assets) in data.effective_permissions {
+            self.data
+                .effective_permissions
+                .entry(user)
+                .or_default()
+                .extend(assets);
+        }
+        Ok(())
+    }
+}
+
+/// A connector that reads its data from files on disk
+pub struct FileConnector {
+    path: PathBuf,
+    /// Used as a fallback cual prefix when neither the file nor its assets provide one
+    namespace: Option<String>,
+    manifest: ConnectorManifest,
+}
+
+#[async_trait]
+impl NewConnector for FileConnector {
+    /// Read the data file(s) once to build the manifest
+    async fn new(
+        config: &ConnectorConfig,
+        _credentials: &CredentialsMap,
+        _client: Option<ConnectorClient>,
+        data_dir: Option<PathBuf>,
+    ) -> Result<Box<Self>> {
+        let path = config
+            .config
+            .get("path")
+            .and_then(|p| p.as_str())
+            .ok_or_else(|| anyhow!("file connectors require a `path` field"))?;
+        let mut connector = FileConnector {
+            path: PathBuf::from(path),
+            namespace: data_dir
+                .as_ref()
+                .and_then(|d| d.file_name())
+                .map(|n| n.to_string_lossy().to_string()),
+            manifest: Default::default(),
+        };
+        let contents = connector.read()?;
+        connector.manifest = contents
+            .manifest
+            .unwrap_or_else(|| infer_manifest(&contents.data));
+        Ok(Box::new(connector))
+    }
+}
+
+impl FileConnector {
+    /// Read and combine all of the data files
+    fn read(&self) -> Result<FileContents> {
+        let mut paths = if self.path.is_dir() {
+            fs::read_dir(&self.path)
+                .context(format!("reading {}", self.path.display()))?
+                .map(|entry| entry.map(|e| e.path()))
+                .collect::<Result<Vec<_>, _>>()?
+                .into_iter()
+                .filter(|p| {
+                    matches!(
+                        p.extension().and_then(|e| e.to_str()),
+                        Some("json" | "yaml" | "yml")
+                    )
+                })
+                .collect()
+        } else {
+            vec![self.path.to_owned()]
+        };
+        paths.sort();
+
+        let mut contents = FileContents::default();
+        for path in paths {
+            let raw = fs::read_to_string(&path).context(format!("reading {}", path.display()))?;
+            let file_contents =
+                FileContents::parse(&raw, &path).context(format!("parsing {}", path.display()))?;
+            contents
+                .extend(file_contents)
+                .context(format!("combining {}", path.display()))?;
+        }
+        Ok(contents)
+    }
+}
+
+#[async_trait]
+impl Connector for FileConnector {
+    async fn check(&self) -> bool {
+        self.read().is_ok()
+    }
+
+    async fn get_data(&mut self) -> Result<ConnectorData> {
+        let mut data = self.read()?.data;
+        if data.cual_prefix.is_none() {
+            // Every connector needs a distinct prefix so the graph can tell them apart
+            data.cual_prefix = data
+                .assets
+                .iter()
+                .find_map(|a| a.cual.connector_prefix())
+                .or_else(|| self.namespace.as_ref().map(|n| format!("file://{n}")));
+        }
+        Ok(data)
+    }
+
+    fn get_manifest(&self) -> ConnectorManifest {
+        self.manifest.to_owned()
+    }
+
+    fn plan_changes(&self, _diffs: &LocalConnectorDiffs) -> Vec<String> {
+        // File connectors are read-only, so there is never anything to apply
+        vec![]
+    }
+
+    async fn apply_changes(&self, _diffs: &LocalConnectorDiffs) -> Result<ApplyReport> {
+        bail!(
+            "file connectors are read-only; update {} instead",
+            self.path.display()
+        )
+    }
+}
+
+fn merge_privileges<K: Eq + std::hash::Hash>(
+    target: &mut HashMap<K, HashSet<String>>,
+    other: HashMap<K, HashSet<String>>,
+) {
+    for (k, privileges) in other {
+        target.entry(k).or_default().extend(privileges);
+    }
+}
+
+/// Build a manifest from the data in the file. Read capabilities reflect what the file
+/// contains, and the privileges for each asset type come from the policies that govern
+/// assets of that type.
+fn infer_manifest(data: &ConnectorData) -> ConnectorManifest {
+    let mut read = HashSet::new();
+    if !data.assets.is_empty() {
+        read.insert(ReadCapabilities::Assets);
+    }
+    if !data.groups.is_empty() {
+        read.insert(ReadCapabilities::Groups);
+    }
+    if !data.users.is_empty() {
+        read.insert(ReadCapabilities::Users);
+    }
+    if !data.policies.is_empty() || !data.default_policies.is_empty() {
+        read.insert(ReadCapabilities::Policies {
+            default_policies: !data.default_policies.is_empty(),
+        });
+    }
+    if data
+        .assets
+        .iter()
+        .any(|a| !a.derived_from.is_empty() || !a.derived_to.is_empty())
+    {
+        read.insert(ReadCapabilities::AssetLineage);
+    }
+
+    let asset_types: HashMap<String, _> = data
+        .assets
+        .iter()
+        .map(|a| (a.cual.uri(), &a.asset_type))
+        .collect();
+    let mut asset_privileges = HashMap::new();
+    for policy in &data.policies {
+        for asset in &policy.governs_assets {
+            let asset_type = asset_types.get(asset).map(|t| (*t).to_owned()).or_else(|| {
+                url::Url::parse(asset)
+                    .ok()
+                    .and_then(|_| Cual::new(asset).asset_type())
+            });
+            if let Some(asset_type) = asset_type {
+                merge_privileges(
+                    &mut asset_privileges,
+                    HashMap::from([(asset_type, policy.privileges.to_owned())]),
+                );
+            }
+        }
+    }
+    for policy in &data.default_policies {
+        merge_privileges(
+            &mut asset_privileges,
+            HashMap::from([(policy.target_type.to_owned(), policy.privileges.to_owned())]),
+        );
+    }
+
+    ConnectorManifest {
+        capabilities: ConnectorCapabilities {
+            read,
+            write: HashSet::new(),
+        },
+        asset_privileges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::connectors::AssetType;
+
+    use super::*;
+
+    const DATA: &str = r#"
+users:
+  - name: ellen
+    identifiers:
+      - Email: ellen@example.com
+groups:
+  - name: analysts
+    includes_users: [ellen]
+assets:
+  - cual:
+      uri: legacy://hr/payroll?type=report
+    name: payroll
+    asset_type: report
+policies:
+  - name: analysts_payroll
+    privileges: [view, export]
+    governs_assets: ["legacy://hr/payroll?type=report"]
+    granted_to_groups: [analysts]
+"#;
+
+    #[test]
+    fn parses_yaml_and_infers_manifest() -> Result<()> {
+        let contents = FileContents::parse(DATA, Path::new("hr.yaml"))?;
+        assert!(contents.manifest.is_none());
+        assert_eq!(contents.data.users[0].name, "ellen");
+        assert_eq!(
+            contents.data.groups[0].includes_users,
+            ["ellen".to_owned()].into()
+        );
+
+        let manifest = infer_manifest(&contents.data);
+        assert_eq!(
+            manifest.asset_privileges[&AssetType("report".to_owned())],
+            ["view".to_owned(), "export".to_owned()].into()
+        );
+        assert!(manifest
+            .capabilities
+            .read
+            .contains(&ReadCapabilities::Users));
+        assert!(!manifest
+            .capabilities
+            .read
+            .contains(&ReadCapabilities::AssetLineage));
+        assert!(manifest.capabilities.write.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn combining_files_rejects_conflicting_prefixes() -> Result<()> {
+        let mut contents =
+            FileContents::parse(r#"{"cual_prefix": "legacy://hr"}"#, Path::new("a.json"))?;
+        let other = FileContents::parse(
+            r#"{"cual_prefix": "legacy://finance"}"#,
+            Path::new("b.json"),
+        )?;
+        assert!(contents.extend(other).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_extensions_fail() {
+        assert!(FileContents::parse("", Path::new("hr.csv")).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+//! Restrictions on who can be a member of a group.
+//!
+//! Some platforms have groups that users can't be direct members of, like Snowflake's
+//! database roles. Connectors mark those groups through group metadata so that the
+//! configuration can be checked before anything is applied.
+
+use std::collections::HashMap;
+
+const NO_USER_MEMBERS_KEY: &str = "users can't be members";
+
+/// Whether users can be direct members of a group, according to its metadata
+pub fn users_can_be_members(metadata: &HashMap<String, String>) -> bool {
+    !metadata.contains_key(NO_USER_MEMBERS_KEY)
+}
+
+/// Get the metadata entry that marks a group that users can't be direct members of
+pub fn no_user_members_metadata() -> (String, String) {
+    (NO_USER_MEMBERS_KEY.to_owned(), "true".to_owned())
+}
@@ -0,0 +1,47 @@
+//! Tags written natively by connectors.
+//!
+//! Connectors that can write tags report the tags they manage on each asset through asset
+//! metadata, so that Jetty can tell which assignments are changing, including those of tags
+//! that have been removed from the configuration.
+
+use std::collections::{BTreeMap, HashMap};
+
+const TAG_KEY_PREFIX: &str = "tag: ";
+
+/// Read the managed tags applied to an asset from its metadata, with their values. Tags
+/// without a value are recorded with an empty one.
+pub fn managed_tags_from_metadata(metadata: &HashMap<String, String>) -> BTreeMap<String, String> {
+    metadata
+        .iter()
+        .filter_map(|(k, v)| {
+            k.strip_prefix(TAG_KEY_PREFIX)
+                .map(|tag| (tag.to_owned(), v.to_owned()))
+        })
+        .collect()
+}
+
+/// Get the metadata entry that records a managed tag applied to an asset
+pub fn managed_tag_metadata(tag: &str, value: &str) -> (String, String) {
+    (format!("{TAG_KEY_PREFIX}{tag}"), value.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn managed_tags_round_trip_through_metadata() {
+        let metadata = HashMap::from([
+            managed_tag_metadata("pii", "email"),
+            managed_tag_metadata("finance", ""),
+            ("owner".to_owned(), "SYSADMIN".to_owned()),
+        ]);
+        assert_eq!(
+            managed_tags_from_metadata(&metadata),
+            BTreeMap::from([
+                ("finance".to_owned(), "".to_owned()),
+                ("pii".to_owned(), "email".to_owned()),
+            ])
+        );
+    }
+}
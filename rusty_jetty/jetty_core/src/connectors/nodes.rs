@@ -122,7 +122,8 @@ impl PartialEq for EffectivePermission {
 
 type UserName = String;
 /// Container for all node data for a given connector
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ConnectorData {
     /// All groups in the connector
     pub groups: Vec<RawGroup>,
@@ -144,11 +145,63 @@ pub struct ConnectorData {
     /// `effective_permissions["user_identifier"]["asset://cual"]` would contain the effective
     /// permissions for that user,asset combination, with one EffectivePermission
     /// per privilege containing possible explanations.
+    #[serde(with = "cual_keyed_matrix")]
     pub effective_permissions: SparseMatrix<UserName, Cual, HashSet<EffectivePermission>>,
     /// The globally unique cual prefix that can be used to match cuals to a namespace
     pub cual_prefix: Option<String>,
 }
 
+/// (De)serialize the effective permissions matrix with cual URIs as keys, so it can be
+/// represented as JSON or YAML
+mod cual_keyed_matrix {
+    use std::collections::{HashMap, HashSet};
+
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{EffectivePermission, SparseMatrix, UserName};
+    use crate::cual::Cual;
+
+    type UriKeyedMatrix = SparseMatrix<UserName, String, HashSet<EffectivePermission>>;
+
+    pub(super) fn serialize<S: Serializer>(
+        matrix: &SparseMatrix<UserName, Cual, HashSet<EffectivePermission>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        matrix
+            .iter()
+            .map(|(user, assets)| {
+                (
+                    user,
+                    assets
+                        .iter()
+                        .map(|(cual, perms)| (cual.uri(), perms))
+                        .collect::<HashMap<_, _>>(),
+                )
+            })
+            .collect::<HashMap<_, _>>()
+            .serialize(serializer)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SparseMatrix<UserName, Cual, HashSet<EffectivePermission>>, D::Error> {
+        UriKeyedMatrix::deserialize(deserializer)?
+            .into_iter()
+            .map(|(user, assets)| {
+                let assets = assets
+                    .into_iter()
+                    .map(|(uri, perms)| {
+                        url::Url::parse(&uri)
+                            .map(|_| (Cual::new(&uri), perms))
+                            .map_err(|e| D::Error::custom(format!("invalid cual {uri}: {e}")))
+                    })
+                    .collect::<Result<HashMap<_, _>, _>>()?;
+                Ok((user, assets))
+            })
+            .collect()
+    }
+}
+
 impl ConnectorData {
     /// Basic constructor
     #[allow(clippy::too_many_arguments)]
@@ -177,7 +230,8 @@ impl ConnectorData {
     }
 }
 
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 /// Group data provided by connectors
 pub struct RawGroup {
     /// Group name
@@ -217,7 +271,8 @@ impl RawGroup {
 }
 
 /// User data provided by connectors
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RawUser {
     /// The name of the user. When coming from a connector, this
     /// should be the name the connector uses to refer to a person.
@@ -228,7 +283,7 @@ pub struct RawUser {
     /// cross-platform
     pub identifiers: HashSet<super::UserIdentifier>,
     /// K-V pairs of user-specific metadata. When sent to the graph
-    /// the keys should be namespaced (e.g. `snow::key : value`)
+    /// the keys are namespaced by connector (e.g. `snow::key : value`)
     pub metadata: HashMap<String, String>,
     /// IDs of the groups this user is a member of
     pub member_of: HashSet<String>,
@@ -256,7 +311,8 @@ impl RawUser {
 }
 
 /// Struct used to populate asset nodes and edges in the graph
-#[derive(Default, PartialEq, Eq, Debug)]
+#[derive(Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RawAsset {
     /// Connector Universal Asset Locator
     pub cual: Cual,
@@ -326,7 +382,8 @@ impl PartialOrd for RawAsset {
 }
 
 /// Struct used to populate asset nodes and edges in the graph
-#[derive(Default, PartialEq, Eq, Debug)]
+#[derive(Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RawAssetReference {
     /// Connector Universal Asset Locator
     pub cual: Cual,
@@ -387,7 +444,8 @@ impl PartialOrd for RawAssetReference {
 }
 
 /// Struct used to populate tag nodes and edges in the graph
-#[derive(Debug, PartialEq, Eq, Default)]
+#[derive(Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RawTag {
     /// context
     pub name: String,
@@ -410,7 +468,8 @@ pub struct RawTag {
 }
 
 /// Struct used to populate policy nodes and edges in the graph
-#[derive(Debug, Derivative, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Derivative, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RawPolicy {
     /// ID of the Policy, namespaced for the relevant context
     pub name: String,
@@ -459,7 +518,7 @@ impl RawPolicy {
 
 /// Struct used to populate default policy nodes and edges in the graph. Must be returned
 /// from the connector as a single policy that can be keyed off the asset_path and
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RawDefaultPolicy {
     /// Privileges applied as part of this policy
     pub privileges: HashSet<String>,
@@ -472,11 +531,13 @@ pub struct RawDefaultPolicy {
     /// policy grantee
     pub grantee: RawPolicyGrantee,
     /// metadata for the policy
+    #[serde(default)]
     pub metadata: HashMap<String, String>,
 }
 
 /// Grantee of a policy
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum RawPolicyGrantee {
     /// Grantee of a group
     Group(String),
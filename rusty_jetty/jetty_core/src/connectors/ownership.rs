@@ -0,0 +1,46 @@
+//! Asset ownership.
+//!
+//! On some platforms, like Snowflake, every asset has exactly one owner, and ownership
+//! can't be granted alongside other privileges. Connectors that manage ownership report
+//! the owner through asset metadata instead of as a policy.
+
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+/// The privilege that represents ownership. Connectors that manage ownership don't allow it
+/// in policies.
+pub const OWNERSHIP_PRIVILEGE: &str = "OWNERSHIP";
+const OWNER_KEY: &str = "owner";
+
+/// What happens to the existing grants on an asset when its ownership is transferred
+#[derive(
+    Serialize, Deserialize, Debug, Default, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum CurrentGrants {
+    /// Keep the existing grants. They are re-granted by the new owner.
+    #[default]
+    Copy,
+    /// Revoke all the existing grants on the asset
+    Revoke,
+}
+
+impl Display for CurrentGrants {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CurrentGrants::Copy => write!(f, "copy"),
+            CurrentGrants::Revoke => write!(f, "revoke"),
+        }
+    }
+}
+
+/// Read the connector-local name of an asset's owner from its metadata
+pub fn owner_from_metadata(metadata: &HashMap<String, String>) -> Option<String> {
+    metadata.get(OWNER_KEY).cloned()
+}
+
+/// Get the metadata entry that records an asset's owner
+pub fn owner_metadata(owner: &str) -> (String, String) {
+    (OWNER_KEY.to_owned(), owner.to_owned())
+}
@@ -0,0 +1,432 @@
+//! An adapter for connectors that run out of process. This makes it possible to write
+//! connectors in any language.
+//!
+//! A plugin is an executable named in jetty_config.yaml:
+//!
+//! ```yaml
+//! connectors:
+//!   internal_bi:
+//!     type: plugin
+//!     executable: ./plugins/jetty-internal-bi
+//!     args: ["--verbose"]
+//! ```
+//!
+//! Jetty launches the executable once for every call, writes a single JSON request to its
+//! stdin, and reads a single JSON response from its stdout. The request looks like:
+//!
+//! ```json
+//! {
+//!   "protocol_version": "1",
+//!   "method": "get_data",
+//!   "config": {"executable": "./plugins/jetty-internal-bi", "args": ["--verbose"]},
+//!   "credentials": {"api_key": "..."},
+//!   "data_dir": ".data/internal_bi"
+//! }
+//! ```
+//!
+//! `plan_changes` and `apply_changes` requests also include a `diffs` field. The response
+//! is either `{"result": ...}` or `{"error": "message"}`, where the result depends on the
+//! method:
+//!
+//! | method          | result                       |
+//! | --------------- | ---------------------------- |
+//! | `check`         | `bool`                       |
+//! | `get_manifest`  | [`ConnectorManifest`]        |
+//! | `get_data`      | [`ConnectorData`]            |
+//! | `plan_changes`  | a list of strings            |
+//! | `apply_changes` | [`ApplyReport`]              |
+//!
+//! Anything the plugin writes to stderr is logged at the debug level.
+
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::PathBuf,
+    process::{Output, Stdio},
+    thread,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    access_graph::translate::diffs::LocalConnectorDiffs,
+    jetty::{ConnectorConfig, ConnectorManifest, CredentialsMap},
+    logging::{debug, error},
+    write::apply_report::ApplyReport,
+};
+
+use super::{
+    nodes::ConnectorData, registry::ConnectorRegistry, Connector, ConnectorClient, NewConnector,
+};
+
+/// The version of the plugin protocol. Sent with every request so that plugins can reject
+/// versions they don't understand.
+pub const PLUGIN_PROTOCOL_VERSION: &str = "1";
+
+/// The connector type that plugins are registered under
+pub const PLUGIN_CONNECTOR_TYPE: &str = "plugin";
+
+/// Register the plugin adapter under the `plugin` connector type
+pub fn register(registry: &mut ConnectorRegistry) {
+    registry.register::<PluginConnector>(PLUGIN_CONNECTOR_TYPE);
+}
+
+/// The methods a plugin must support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginMethod {
+    /// Check that the plugin is set up correctly
+    Check,
+    /// Get the manifest for the connector
+    GetManifest,
+    /// Get the connector data
+    GetData,
+    /// Plan the changes for a set of diffs
+    PlanChanges,
+    /// Apply the changes for a set of diffs
+    ApplyChanges,
+}
+
+/// A request sent to a plugin on stdin
+#[derive(Debug, Serialize)]
+struct PluginRequest<'a> {
+    protocol_version: &'static str,
+    method: PluginMethod,
+    config: &'a HashMap<String, serde_json::Value>,
+    credentials: &'a CredentialsMap,
+    data_dir: &'a Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diffs: Option<&'a LocalConnectorDiffs>,
+}
+
+/// A response read from a plugin's stdout
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PluginResponse<T> {
+    Result(T),
+    Error(String),
+}
+
+/// A connector backed by an external executable
+pub struct PluginConnector {
+    executable: String,
+    args: Vec<String>,
+    config: HashMap<String, serde_json::Value>,
+    credentials: CredentialsMap,
+    data_dir: Option<PathBuf>,
+    manifest: ConnectorManifest,
+}
+
+#[async_trait]
+impl NewConnector for PluginConnector {
+    /// Read the executable from the config and fetch the plugin's manifest
+    async fn new(
+        config: &ConnectorConfig,
+        credentials: &CredentialsMap,
+        _client: Option<ConnectorClient>,
+        data_dir: Option<PathBuf>,
+    ) -> Result<Box<Self>> {
+        let mut connector = Self::from_config(config, credentials, data_dir)?;
+        connector.manifest = connector
+            .call(PluginMethod::GetManifest, None)
+            .await
+            .context(format!(
+                "getting manifest from plugin {}",
+                connector.executable
+            ))?;
+        Ok(Box::new(connector))
+    }
+}
+
+impl PluginConnector {
+    /// Build the connector from its config without contacting the plugin
+    fn from_config(
+        config: &ConnectorConfig,
+        credentials: &CredentialsMap,
+        data_dir: Option<PathBuf>,
+    ) -> Result<Self> {
+        let executable = config
+            .config
+            .get("executable")
+            .and_then(|e| e.as_str())
+            .ok_or_else(|| anyhow!("plugin connectors require an `executable` field"))?
+            .to_owned();
+        let args = match config.config.get("args") {
+            Some(args) => serde_json::from_value(args.to_owned())
+                .context("the `args` field of a plugin connector must be a list of strings")?,
+            None => vec![],
+        };
+        Ok(Self {
+            executable,
+            args,
+            config: config.config.to_owned(),
+            credentials: credentials.to_owned(),
+            data_dir,
+            manifest: Default::default(),
+        })
+    }
+
+    fn request<'a>(
+        &'a self,
+        method: PluginMethod,
+        diffs: Option<&'a LocalConnectorDiffs>,
+    ) -> PluginRequest<'a> {
+        PluginRequest {
+            protocol_version: PLUGIN_PROTOCOL_VERSION,
+            method,
+            config: &self.config,
+            credentials: &self.credentials,
+            data_dir: &self.data_dir,
+            diffs,
+        }
+    }
+
+    /// Run the plugin without blocking
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: PluginMethod,
+        diffs: Option<&LocalConnectorDiffs>,
+    ) -> Result<T> {
+        let request = serde_json::to_vec(&self.request(method, diffs))?;
+
+        let mut child = tokio::process::Command::new(&self.executable)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(format!("failed to launch plugin {}", self.executable))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("unable to write to plugin {}", self.executable))?;
+        // The request is written while the response is read. Otherwise a plugin that starts
+        // responding before it has read the whole request could fill its stdout pipe and
+        // wait on us while we wait on it.
+        let write = async move {
+            let written = stdin.write_all(&request).await;
+            // Close stdin so the plugin knows the request is complete
+            drop(stdin);
+            written
+        };
+        let (written, output) = tokio::join!(write, child.wait_with_output());
+        let output = output?;
+        self.check_written(written, &output)?;
+        self.parse_output(method, output)
+    }
+
+    /// Run the plugin, blocking until it finishes. Used by the synchronous trait methods.
+    fn call_blocking<T: DeserializeOwned>(
+        &self,
+        method: PluginMethod,
+        diffs: Option<&LocalConnectorDiffs>,
+    ) -> Result<T> {
+        let request = serde_json::to_vec(&self.request(method, diffs))?;
+
+        let mut child = std::process::Command::new(&self.executable)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context(format!("failed to launch plugin {}", self.executable))?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow!("unable to write to plugin {}", self.executable))?;
+        // Write from another thread so that the response is read at the same time (see `call`)
+        let writer = thread::spawn(move || {
+            let written = stdin.write_all(&request);
+            drop(stdin);
+            written
+        });
+
+        let output = child.wait_with_output()?;
+        let written = writer
+            .join()
+            .map_err(|_| anyhow!("writing to plugin {} panicked", self.executable))?;
+        self.check_written(written, &output)?;
+        self.parse_output(method, output)
+    }
+
+    /// Make sure the whole request was written. A plugin that fails may exit before reading
+    /// all of it, and then its exit status is the more useful error.
+    fn check_written(&self, written: io::Result<()>, output: &Output) -> Result<()> {
+        match written {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe || output.status.success() => {
+                Err(e).context(format!("failed to write to plugin {}", self.executable))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_output<T: DeserializeOwned>(&self, method: PluginMethod, output: Output) -> Result<T> {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stderr.is_empty() {
+            debug!("{} {method:?} stderr: {stderr}", self.executable);
+        }
+        if !output.status.success() {
+            bail!(
+                "plugin {} exited with {} during {method:?}: {}",
+                self.executable,
+                output.status,
+                stderr.trim()
+            );
+        }
+
+        let response: PluginResponse<T> =
+            serde_json::from_slice(&output.stdout).context(format!(
+                "unable to parse the {method:?} response from plugin {}",
+                self.executable
+            ))?;
+        match response {
+            PluginResponse::Result(result) => Ok(result),
+            PluginResponse::Error(e) => {
+                bail!("plugin {} failed during {method:?}: {e}", self.executable)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Connector for PluginConnector {
+    async fn check(&self) -> bool {
+        match self.call(PluginMethod::Check, None).await {
+            Ok(ok) => ok,
+            Err(e) => {
+                error!("{e:#}");
+                false
+            }
+        }
+    }
+
+    async fn get_data(&mut self) -> Result<ConnectorData> {
+        self.call(PluginMethod::GetData, None).await
+    }
+
+    fn get_manifest(&self) -> ConnectorManifest {
+        self.manifest.to_owned()
+    }
+
+    fn plan_changes(&self, diffs: &LocalConnectorDiffs) -> Vec<String> {
+        match self.call_blocking(PluginMethod::PlanChanges, Some(diffs)) {
+            Ok(plan) => plan,
+            Err(e) => {
+                error!("Unable to generate plan for {}: {e:#}", self.executable);
+                vec![]
+            }
+        }
+    }
+
+    async fn apply_changes(&self, diffs: &LocalConnectorDiffs) -> Result<ApplyReport> {
+        self.call(PluginMethod::ApplyChanges, Some(diffs)).await
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn shell_plugin(script: &str) -> Result<PluginConnector> {
+        PluginConnector::from_config(
+            &ConnectorConfig::new(
+                PLUGIN_CONNECTOR_TYPE.to_owned(),
+                HashMap::from([
+                    ("executable".to_owned(), "sh".into()),
+                    ("args".to_owned(), serde_json::json!(["-c", script])),
+                ]),
+            ),
+            &Default::default(),
+            None,
+        )
+    }
+
+    fn empty_diffs() -> LocalConnectorDiffs {
+        LocalConnectorDiffs {
+            groups: vec![],
+            users: vec![],
+            default_policies: vec![],
+            policies: vec![],
+            tags: vec![],
+            protections: vec![],
+            ownership: vec![],
+            user_properties: vec![],
+        }
+    }
+
+    #[test]
+    fn missing_executable_fails() {
+        let config = ConnectorConfig::new(PLUGIN_CONNECTOR_TYPE.to_owned(), HashMap::new());
+        assert!(PluginConnector::from_config(&config, &Default::default(), None).is_err());
+    }
+
+    #[test]
+    fn plugin_receives_request_on_stdin() -> Result<()> {
+        // Echo the method back as the only planned change
+        let plugin = shell_plugin(
+            r#"method=$(sed -n 's/.*"method":"\([a-z_]*\)".*/\1/p'); echo "{\"result\": [\"$method\"]}""#,
+        )?;
+        assert_eq!(plugin.plan_changes(&empty_diffs()), vec!["plan_changes"]);
+        Ok(())
+    }
+
+    #[test]
+    fn plugin_errors_are_surfaced() -> Result<()> {
+        let plugin = shell_plugin(r#"cat > /dev/null; echo '{"error": "no such group"}'"#)?;
+        let err = plugin
+            .call_blocking::<Vec<String>>(PluginMethod::PlanChanges, Some(&empty_diffs()))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "plugin sh failed during PlanChanges: no such group"
+        );
+
+        let plugin = shell_plugin("cat > /dev/null; echo 'bad config' >&2; exit 3")?;
+        let err = plugin
+            .call_blocking::<Vec<String>>(PluginMethod::PlanChanges, None)
+            .unwrap_err();
+        assert!(err.to_string().contains("bad config"));
+        Ok(())
+    }
+
+    #[test]
+    fn large_requests_and_responses_do_not_deadlock() -> Result<()> {
+        // The plugin fills its stdout pipe before it reads the request, which is bigger than
+        // the stdin pipe buffer
+        let plugin = shell_plugin(
+            r#"head -c 200000 /dev/zero | tr '\0' ' '; cat > /dev/null; echo '{"result": ["done"]}'"#,
+        )?;
+        let mut diffs = empty_diffs();
+        diffs
+            .users
+            .push(crate::access_graph::translate::diffs::users::LocalDiff {
+                user: "u".repeat(200_000),
+                group_membership: crate::access_graph::translate::diffs::users::LocalDiffDetails {
+                    add: Default::default(),
+                    remove: Default::default(),
+                },
+            });
+        assert_eq!(plugin.plan_changes(&diffs), vec!["done"]);
+        Ok(())
+    }
+
+    #[test]
+    fn connector_data_deserializes_with_defaults() -> Result<()> {
+        let data: ConnectorData = serde_json::from_value(serde_json::json!({
+            "users": [{"name": "ellen", "identifiers": [{"Email": "ellen@example.com"}]}],
+            "effective_permissions": {
+                "ellen": {"plugin://internal/reports/revenue": [
+                    {"privilege": "read", "mode": "Allow", "reasons": ["owner"]}
+                ]}
+            },
+            "cual_prefix": "plugin://internal"
+        }))?;
+        assert_eq!(data.users[0].name, "ellen");
+        assert_eq!(data.effective_permissions["ellen"].len(), 1);
+        assert!(data.groups.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Masking and row access policies that protect an asset's data, and the network and session
+//! policies that protect an account.
+//!
+//! Connectors report these through asset metadata, using the keys defined here, so that
+//! they don't need special handling when the graph is built.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+const ROW_ACCESS_POLICY_KEY: &str = "row access policy";
+const ROW_ACCESS_POLICY_COLUMNS_KEY: &str = "row access policy columns";
+const MASKING_POLICY_KEY_PREFIX: &str = "masking policy: ";
+const NETWORK_POLICY_KEY: &str = "network policy";
+const SESSION_POLICY_KEY: &str = "session policy";
+
+/// A row access policy and the columns it's evaluated on
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct RowAccessPolicy {
+    /// The fully-qualified name of the policy
+    pub policy: String,
+    /// The columns passed to the policy, in order
+    pub columns: Vec<String>,
+}
+
+/// The policies protecting a single asset
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct AssetProtections {
+    /// The row access policy on the asset, if there is one
+    pub row_access_policy: Option<RowAccessPolicy>,
+    /// The masking policies on the asset, keyed by column name
+    pub masking_policies: BTreeMap<String, String>,
+    /// The network policy for the asset, if it's an account. Users with their own network
+    /// policy aren't bound by it.
+    pub network_policy: Option<String>,
+    /// The fully-qualified name of the session policy for the asset, if it's an account
+    pub session_policy: Option<String>,
+}
+
+impl AssetProtections {
+    /// Whether the asset is unprotected
+    pub fn is_empty(&self) -> bool {
+        self == &Default::default()
+    }
+
+    /// Read the protections from asset metadata
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> Result<Self> {
+        let row_access_policy = match metadata.get(ROW_ACCESS_POLICY_KEY) {
+            Some(policy) => Some(RowAccessPolicy {
+                policy: policy.to_owned(),
+                columns: match metadata.get(ROW_ACCESS_POLICY_COLUMNS_KEY) {
+                    Some(columns) => serde_json::from_str(columns)
+                        .context("parsing the row access policy columns")?,
+                    None => vec![],
+                },
+            }),
+            None => None,
+        };
+        let masking_policies = metadata
+            .iter()
+            .filter_map(|(k, v)| {
+                k.strip_prefix(MASKING_POLICY_KEY_PREFIX)
+                    .map(|column| (column.to_owned(), v.to_owned()))
+            })
+            .collect();
+
+        Ok(AssetProtections {
+            row_access_policy,
+            masking_policies,
+            network_policy: metadata.get(NETWORK_POLICY_KEY).cloned(),
+            session_policy: metadata.get(SESSION_POLICY_KEY).cloned(),
+        })
+    }
+
+    /// Convert the protections to asset metadata
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        let mut res: HashMap<String, String> = self
+            .masking_policies
+            .iter()
+            .map(|(column, policy)| {
+                (
+                    format!("{MASKING_POLICY_KEY_PREFIX}{column}"),
+                    policy.to_owned(),
+                )
+            })
+            .collect();
+        if let Some(row_access_policy) = &self.row_access_policy {
+            res.insert(
+                ROW_ACCESS_POLICY_KEY.to_owned(),
+                row_access_policy.policy.to_owned(),
+            );
+            res.insert(
+                ROW_ACCESS_POLICY_COLUMNS_KEY.to_owned(),
+                serde_json::to_string(&row_access_policy.columns).unwrap(),
+            );
+        }
+        if let Some(network_policy) = &self.network_policy {
+            res.insert(NETWORK_POLICY_KEY.to_owned(), network_policy.to_owned());
+        }
+        if let Some(session_policy) = &self.session_policy {
+            res.insert(SESSION_POLICY_KEY.to_owned(), session_policy.to_owned());
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_round_trip_works() -> Result<()> {
+        let protections = AssetProtections {
+            row_access_policy: Some(RowAccessPolicy {
+                policy: "GOV.POLICIES.REGION_FILTER".to_owned(),
+                columns: vec!["REGION".to_owned(), "COUNTRY".to_owned()],
+            }),
+            masking_policies: BTreeMap::from([(
+                "EMAIL".to_owned(),
+                "GOV.POLICIES.MASK_EMAIL".to_owned(),
+            )]),
+            ..Default::default()
+        };
+        let mut metadata = protections.to_metadata();
+        metadata.insert("owner".to_owned(), "SYSADMIN".to_owned());
+
+        assert_eq!(AssetProtections::from_metadata(&metadata)?, protections);
+
+        let account = AssetProtections {
+            network_policy: Some("OFFICE_ONLY".to_owned()),
+            session_policy: Some("GOV.POLICIES.IDLE_TIMEOUT".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            AssetProtections::from_metadata(&account.to_metadata())?,
+            account
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,192 @@
+//! A registry of the connector types available to Jetty. Connector crates register a
+//! factory under a type name (the `type` field in jetty_config.yaml), and Jetty uses the
+//! registry to instantiate every configured connector.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    marker::PhantomData,
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+
+use crate::{
+    jetty::{ConnectorConfig, ConnectorNamespace, CredentialsMap},
+    project,
+};
+
+use super::{Connector, ConnectorClient, NewConnector};
+
+/// Creates connectors of a single type
+#[async_trait]
+pub trait ConnectorFactory: Send + Sync {
+    /// Instantiate a connector from configuration
+    async fn create(
+        &self,
+        config: &ConnectorConfig,
+        credentials: &CredentialsMap,
+        client: Option<ConnectorClient>,
+        data_dir: Option<PathBuf>,
+    ) -> Result<Box<dyn Connector>>;
+}
+
+/// A factory for any connector that implements [`NewConnector`]
+struct NewConnectorFactory<T>(PhantomData<fn() -> T>);
+
+#[async_trait]
+impl<T> ConnectorFactory for NewConnectorFactory<T>
+where
+    T: NewConnector + Connector + 'static,
+{
+    async fn create(
+        &self,
+        config: &ConnectorConfig,
+        credentials: &CredentialsMap,
+        client: Option<ConnectorClient>,
+        data_dir: Option<PathBuf>,
+    ) -> Result<Box<dyn Connector>> {
+        let connector: Box<dyn Connector> = T::new(config, credentials, client, data_dir).await?;
+        Ok(connector)
+    }
+}
+
+/// The connector types that Jetty knows how to instantiate, keyed by type name
+#[derive(Default)]
+pub struct ConnectorRegistry {
+    factories: BTreeMap<String, Box<dyn ConnectorFactory>>,
+}
+
+impl ConnectorRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Register a connector that implements [`NewConnector`] under the given type name.
+    /// Registering the same type name twice replaces the earlier factory.
+    pub fn register<T>(&mut self, connector_type: &str) -> &mut Self
+    where
+        T: NewConnector + Connector + 'static,
+    {
+        self.register_factory(connector_type, NewConnectorFactory::<T>(PhantomData))
+    }
+
+    /// Register a custom factory under the given type name. Registering the same type name
+    /// twice replaces the earlier factory.
+    pub fn register_factory<F>(&mut self, connector_type: &str, factory: F) -> &mut Self
+    where
+        F: ConnectorFactory + 'static,
+    {
+        self.factories
+            .insert(connector_type.to_owned(), Box::new(factory));
+        self
+    }
+
+    /// The registered connector type names, in alphabetical order
+    pub fn registered_types(&self) -> Vec<&str> {
+        self.factories.keys().map(|t| t.as_str()).collect()
+    }
+
+    /// Get the factory for a connector type
+    pub fn factory(&self, connector_type: &str) -> Result<&dyn ConnectorFactory> {
+        self.factories
+            .get(connector_type)
+            .map(|f| f.as_ref())
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown connector type: {connector_type}; registered connector types are: {}",
+                    self.registered_types().join(", ")
+                )
+            })
+    }
+
+    /// Instantiate every configured connector, looking up the credentials for each one by
+    /// its namespace. Each connector gets its own directory under `data_dir`.
+    pub async fn new_connectors(
+        &self,
+        credentials: &HashMap<String, CredentialsMap>,
+        configs: &HashMap<ConnectorNamespace, ConnectorConfig>,
+        client: ConnectorClient,
+        data_dir: PathBuf,
+    ) -> Result<HashMap<ConnectorNamespace, Box<dyn Connector>>> {
+        let mut connectors = HashMap::new();
+
+        for (namespace, config) in configs {
+            let factory = self
+                .factory(&config.connector_type)
+                .context(format!("unable to set up {namespace}"))?;
+            let connector_credentials = credentials
+                .get(namespace.to_string().as_str())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "unable to find a connector called {} in {}",
+                        namespace,
+                        project::connector_cfg_path().display()
+                    )
+                })?;
+            let connector = factory
+                .create(
+                    config,
+                    connector_credentials,
+                    Some(client.to_owned()),
+                    Some(data_dir.join(namespace.to_string())),
+                )
+                .await?;
+            connectors.insert(namespace.to_owned(), connector);
+        }
+
+        Ok(connectors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::bail;
+
+    use super::*;
+
+    struct UnimplementedFactory;
+
+    #[async_trait]
+    impl ConnectorFactory for UnimplementedFactory {
+        async fn create(
+            &self,
+            _config: &ConnectorConfig,
+            _credentials: &CredentialsMap,
+            _client: Option<ConnectorClient>,
+            _data_dir: Option<PathBuf>,
+        ) -> Result<Box<dyn Connector>> {
+            bail!("not implemented")
+        }
+    }
+
+    #[test]
+    fn registered_types_are_sorted() {
+        let mut registry = ConnectorRegistry::new();
+        registry
+            .register_factory("tableau", UnimplementedFactory)
+            .register_factory("dbt", UnimplementedFactory)
+            .register_factory("snowflake", UnimplementedFactory);
+
+        assert_eq!(
+            registry.registered_types(),
+            vec!["dbt", "snowflake", "tableau"]
+        );
+        assert!(registry.factory("dbt").is_ok());
+    }
+
+    #[test]
+    fn unknown_type_lists_registered_types() {
+        let mut registry = ConnectorRegistry::new();
+        registry
+            .register_factory("snowflake", UnimplementedFactory)
+            .register_factory("dbt", UnimplementedFactory);
+
+        let err = registry.factory("bigquery").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "unknown connector type: bigquery; registered connector types are: dbt, snowflake"
+        );
+    }
+}
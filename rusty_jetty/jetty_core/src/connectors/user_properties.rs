@@ -0,0 +1,307 @@
+//! User properties.
+//!
+//! Some platforms, like Snowflake, keep settings on each user, like the role and warehouse
+//! that the user's sessions start with. Connectors that manage user properties report the
+//! current values through user metadata, using the keys defined here. The network and
+//! session policies a user is bound to are reported here too, so that users without one
+//! stand out in security reviews.
+
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{access_graph::translate::user_metadata_key, jetty::ConnectorNamespace};
+
+const DEFAULT_ROLE_KEY: &str = "default role";
+const DEFAULT_WAREHOUSE_KEY: &str = "default warehouse";
+const DEFAULT_NAMESPACE_KEY: &str = "default namespace";
+const RSA_PUBLIC_KEY_FP_KEY: &str = "rsa public key fingerprint";
+const DISABLED_KEY: &str = "disabled";
+const TYPE_KEY: &str = "type";
+const NETWORK_POLICY_KEY: &str = "network policy";
+const SESSION_POLICY_KEY: &str = "session policy";
+
+/// The configured value of a network or session policy that removes the user's policy
+pub const NO_POLICY: &str = "none";
+
+/// The kind of a user
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UserType {
+    /// A person, who logs in interactively
+    Person,
+    /// A service or application, which can't log in with a password
+    Service,
+    /// A service or application that can still log in with a password
+    LegacyService,
+}
+
+impl Display for UserType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserType::Person => write!(f, "PERSON"),
+            UserType::Service => write!(f, "SERVICE"),
+            UserType::LegacyService => write!(f, "LEGACY_SERVICE"),
+        }
+    }
+}
+
+impl FromStr for UserType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_uppercase().as_str() {
+            "PERSON" => UserType::Person,
+            "SERVICE" => UserType::Service,
+            "LEGACY_SERVICE" => UserType::LegacyService,
+            _ => bail!("unknown user type: {s}"),
+        })
+    }
+}
+
+/// The properties of a user in a single connector. Properties that aren't set aren't
+/// managed by Jetty.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[serde(deny_unknown_fields)]
+pub struct UserProperties {
+    /// The role that the user's sessions start with
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "default role"
+    )]
+    pub default_role: Option<String>,
+    /// The warehouse that the user's sessions start with
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "default warehouse"
+    )]
+    pub default_warehouse: Option<String>,
+    /// The database or schema that the user's sessions start in
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "default namespace"
+    )]
+    pub default_namespace: Option<String>,
+    /// The user's RSA public key, in PEM format. Platforms report the key's fingerprint
+    /// instead, so properties read from metadata hold the fingerprint.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "rsa public key"
+    )]
+    pub rsa_public_key: Option<String>,
+    /// Whether the user is disabled
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub disabled: Option<bool>,
+    /// The kind of user
+    #[serde(skip_serializing_if = "Option::is_none", default, rename = "type")]
+    pub user_type: Option<UserType>,
+    /// The network policy restricting where the user can log in from. [`NO_POLICY`] removes
+    /// the user's network policy.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "network policy"
+    )]
+    pub network_policy: Option<String>,
+    /// The fully-qualified name of the session policy on the user's sessions.
+    /// [`NO_POLICY`] removes the user's session policy.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "session policy"
+    )]
+    pub session_policy: Option<String>,
+}
+
+impl UserProperties {
+    /// Whether no properties are set
+    pub fn is_empty(&self) -> bool {
+        self == &Default::default()
+    }
+
+    /// Convert the properties to user metadata. Connectors report the RSA public key by its
+    /// fingerprint (see [`public_key_fingerprint`]).
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        [
+            (DEFAULT_ROLE_KEY, self.default_role.to_owned()),
+            (DEFAULT_WAREHOUSE_KEY, self.default_warehouse.to_owned()),
+            (DEFAULT_NAMESPACE_KEY, self.default_namespace.to_owned()),
+            (RSA_PUBLIC_KEY_FP_KEY, self.rsa_public_key.to_owned()),
+            (DISABLED_KEY, self.disabled.map(|d| d.to_string())),
+            (TYPE_KEY, self.user_type.map(|t| t.to_string())),
+            (NETWORK_POLICY_KEY, self.network_policy.to_owned()),
+            (SESSION_POLICY_KEY, self.session_policy.to_owned()),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|v| (k.to_owned(), v)))
+        .collect()
+    }
+
+    /// Read a connector's properties from the metadata of a user in the graph
+    pub fn from_metadata(
+        metadata: &HashMap<String, String>,
+        connector: &ConnectorNamespace,
+    ) -> Result<Self> {
+        let get = |key| metadata.get(&user_metadata_key(connector, key)).cloned();
+        Ok(UserProperties {
+            default_role: get(DEFAULT_ROLE_KEY),
+            default_warehouse: get(DEFAULT_WAREHOUSE_KEY),
+            default_namespace: get(DEFAULT_NAMESPACE_KEY),
+            rsa_public_key: get(RSA_PUBLIC_KEY_FP_KEY),
+            disabled: get(DISABLED_KEY)
+                .map(|d| d.parse())
+                .transpose()
+                .context("parsing the disabled flag")?,
+            user_type: get(TYPE_KEY).map(|t| t.parse()).transpose()?,
+            network_policy: get(NETWORK_POLICY_KEY),
+            session_policy: get(SESSION_POLICY_KEY),
+        })
+    }
+
+    /// Get the configured properties that don't match the current properties, as read from
+    /// metadata
+    pub(crate) fn changes_from(&self, current: &UserProperties) -> Result<UserProperties> {
+        fn changed<T: PartialEq + Clone>(configured: &Option<T>, current: &Option<T>) -> Option<T> {
+            configured
+                .as_ref()
+                .filter(|c| Some(*c) != current.as_ref())
+                .cloned()
+        }
+
+        /// Policies configured as [`NO_POLICY`] only change when the user has one
+        fn policy_changed(configured: &Option<String>, current: &Option<String>) -> Option<String> {
+            match configured.as_deref() {
+                Some(NO_POLICY) => current.as_ref().map(|_| NO_POLICY.to_owned()),
+                _ => changed(configured, current),
+            }
+        }
+
+        let rsa_public_key = match &self.rsa_public_key {
+            Some(key) if Some(public_key_fingerprint(key)?) != current.rsa_public_key => {
+                Some(key.to_owned())
+            }
+            _ => None,
+        };
+        Ok(UserProperties {
+            default_role: changed(&self.default_role, &current.default_role),
+            default_warehouse: changed(&self.default_warehouse, &current.default_warehouse),
+            default_namespace: changed(&self.default_namespace, &current.default_namespace),
+            rsa_public_key,
+            disabled: changed(&self.disabled, &current.disabled),
+            user_type: changed(&self.user_type, &current.user_type),
+            network_policy: policy_changed(&self.network_policy, &current.network_policy),
+            session_policy: policy_changed(&self.session_policy, &current.session_policy),
+        })
+    }
+}
+
+/// Get the fingerprint of a PEM public key, formatted as `SHA256:<base64 digest>`. This is
+/// the format Snowflake reports for RSA public keys.
+pub fn public_key_fingerprint(public_key: &str) -> Result<String> {
+    let encoded = public_key
+        .lines()
+        .filter(|line| !line.starts_with("-----"))
+        .flat_map(|line| line.split_whitespace())
+        .collect::<String>();
+    let der = base64::decode(encoded).context("the public key isn't valid PEM")?;
+    Ok(format!(
+        "SHA256:{}",
+        base64::encode(Sha256::digest(der).as_slice())
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 2048-bit RSA public key, like the ones Snowflake accepts
+    const PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEAxerkd/DsAFmgwmMBnJKO
+eAHqyPymOJCkpLVo0msS03QcrRmfYSPdaqE0OghU/9F+d0xhBiy3HXUgWJYkl2Sx
+vGwN77nuM8Feat+5R3AwdHksdPrkwCKidFd4Tasz/SVBTfLZ2b9KoJD6Y05qJc09
+wMpByAUWnaw4btBH91vBA9HIIYT4uCehiyVi4R0N1YJ8N7tbRx/CUMgzkjj5pAEo
+iSSDDbGuvAw2JdpxnVH1LsvIFyVqarJ1qBZBr2/Y6/kMZnm2+/U+shhEFq2ErxH9
+9QpDCSOEAbxWY8kz+AgYHeiSJX7HCycZFD5b+DNh81PurwW9qG/3JQMRthip+81h
+iQIDAQAB
+-----END PUBLIC KEY-----
+";
+
+    #[test]
+    fn fingerprints_match_snowflake() -> Result<()> {
+        assert_eq!(
+            public_key_fingerprint(PUBLIC_KEY)?,
+            "SHA256:gnw1tyU/n4S6Xm8hbssPvYxQHdhJ3BonPF6t07kHEas="
+        );
+        assert!(public_key_fingerprint("not a key!").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn only_changed_properties_are_set() -> Result<()> {
+        let connector = ConnectorNamespace("snowflake".to_owned());
+        let current = UserProperties {
+            default_role: Some("ANALYST".to_owned()),
+            rsa_public_key: Some(public_key_fingerprint(PUBLIC_KEY)?),
+            disabled: Some(false),
+            network_policy: Some("OFFICE_ONLY".to_owned()),
+            ..Default::default()
+        };
+        let metadata = current
+            .to_metadata()
+            .into_iter()
+            .map(|(k, v)| (user_metadata_key(&connector, &k), v))
+            .collect();
+        let current = UserProperties::from_metadata(&metadata, &connector)?;
+
+        let configured = UserProperties {
+            default_role: Some("ANALYST".to_owned()),
+            default_warehouse: Some("REPORTING".to_owned()),
+            rsa_public_key: Some(PUBLIC_KEY.to_owned()),
+            disabled: Some(true),
+            user_type: Some(UserType::Service),
+            network_policy: Some("OFFICE_ONLY".to_owned()),
+            session_policy: Some("GOV.POLICIES.IDLE_TIMEOUT".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            configured.changes_from(&current)?,
+            UserProperties {
+                default_warehouse: Some("REPORTING".to_owned()),
+                disabled: Some(true),
+                user_type: Some(UserType::Service),
+                session_policy: Some("GOV.POLICIES.IDLE_TIMEOUT".to_owned()),
+                ..Default::default()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn policies_configured_as_none_are_removed() -> Result<()> {
+        let configured = UserProperties {
+            network_policy: Some(NO_POLICY.to_owned()),
+            session_policy: Some(NO_POLICY.to_owned()),
+            ..Default::default()
+        };
+        let current = UserProperties {
+            network_policy: Some("OFFICE_ONLY".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            configured.changes_from(&current)?,
+            UserProperties {
+                network_policy: Some(NO_POLICY.to_owned()),
+                ..Default::default()
+            }
+        );
+        // Users without policies are left alone
+        assert!(configured.changes_from(&Default::default())?.is_empty());
+        Ok(())
+    }
+}
@@ -114,7 +114,7 @@ impl ConnectorConfig {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 /// A struct representing the built-in characteristics of a connector.
 pub struct ConnectorManifest {
     /// The capabilities of the connector.
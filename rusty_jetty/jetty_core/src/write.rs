@@ -1,11 +1,17 @@
 //! Write user-configured groups and permissions back to the data stack.
 
+pub mod apply_report;
 pub mod assets;
 pub mod config;
 pub mod diff;
 pub mod groups;
+pub mod output;
 mod parser_common;
+pub mod saved_plan;
+mod scope;
+pub mod sql_script;
 pub(crate) mod tag_parser;
+pub mod tags;
 pub mod users;
 mod utils;
 
@@ -15,7 +21,10 @@ use std::collections::{HashMap, HashSet};
 
 use crate::{jetty::ConnectorNamespace, Jetty};
 
-use self::assets::diff::{default_policies::DefaultPolicyDiff, policies::PolicyDiff};
+use self::assets::diff::{
+    default_policies::DefaultPolicyDiff, ownership::OwnershipDiff, policies::PolicyDiff,
+    protections::ProtectionDiff,
+};
 
 /// A collection of diffs to be sent to the connectors
 pub struct GlobalDiffs {
@@ -23,24 +32,40 @@ pub struct GlobalDiffs {
     pub groups: Vec<groups::Diff>,
     /// All the user-levelgroup membership diffs
     pub users: Vec<users::CombinedUserDiff>,
+    /// All the user property changes
+    pub user_properties: Vec<users::diff::UserPropertiesDiff>,
     /// All the connector-managed default policies
     pub default_policies: Vec<DefaultPolicyDiff>,
     /// All the policies
     pub policies: Vec<PolicyDiff>,
+    /// The configured tags, for connectors that can write tags
+    pub tags: Vec<tags::TagState>,
+    /// All the masking and row access policy diffs
+    pub protections: Vec<ProtectionDiff>,
+    /// All the ownership transfers
+    pub ownership: Vec<OwnershipDiff>,
 }
 
 impl GlobalDiffs {
     /// Split diffs into a HashMap of diffs, by connector
     pub fn split_by_connector(&self) -> HashMap<ConnectorNamespace, GlobalDiffs> {
         let user_map = split_diff_vec_by_connector(&self.users);
+        let user_properties_map = split_diff_vec_by_connector(&self.user_properties);
         let group_map = split_diff_vec_by_connector(&self.groups);
         let policy_map = split_diff_vec_by_connector(&self.policies);
         let default_policy_map = split_diff_vec_by_connector(&self.default_policies);
+        let tag_map = split_diff_vec_by_connector(&self.tags);
+        let protection_map = split_diff_vec_by_connector(&self.protections);
+        let ownership_map = split_diff_vec_by_connector(&self.ownership);
 
         let mut connectors: HashSet<_> = user_map.keys().collect();
+        connectors.extend(user_properties_map.keys());
         connectors.extend(group_map.keys());
         connectors.extend(policy_map.keys());
         connectors.extend(default_policy_map.keys());
+        connectors.extend(tag_map.keys());
+        connectors.extend(protection_map.keys());
+        connectors.extend(ownership_map.keys());
 
         let mut res = HashMap::new();
         for conn in connectors {
@@ -49,8 +74,12 @@ impl GlobalDiffs {
                 GlobalDiffs {
                     groups: group_map.get(conn).cloned().unwrap_or_default(),
                     users: user_map.get(conn).cloned().unwrap_or_default(),
+                    user_properties: user_properties_map.get(conn).cloned().unwrap_or_default(),
                     policies: policy_map.get(conn).cloned().unwrap_or_default(),
                     default_policies: default_policy_map.get(conn).cloned().unwrap_or_default(),
+                    tags: tag_map.get(conn).cloned().unwrap_or_default(),
+                    protections: protection_map.get(conn).cloned().unwrap_or_default(),
+                    ownership: ownership_map.get(conn).cloned().unwrap_or_default(),
                 },
             );
         }
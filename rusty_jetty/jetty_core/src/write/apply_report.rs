@@ -0,0 +1,245 @@
+//! Structured results of `jetty apply`, reported back by each connector
+
+use std::fmt::Display;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access_graph::translate::diffs::{
+        default_policies, groups, ownership, policies, protections, tags, user_properties, users,
+    },
+    cual::Cual,
+};
+
+/// The thing an operation acts on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApplyTarget {
+    /// An asset, identified by its cual
+    Asset(Cual),
+    /// A user, identified by its connector-local name
+    User(String),
+    /// A group, identified by its connector-local name
+    Group(String),
+    /// A tag, identified by its name
+    Tag(String),
+}
+
+impl Display for ApplyTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApplyTarget::Asset(cual) => write!(f, "asset {}", cual.uri()),
+            ApplyTarget::User(user) => write!(f, "user {user}"),
+            ApplyTarget::Group(group) => write!(f, "group {group}"),
+            ApplyTarget::Tag(tag) => write!(f, "tag {tag}"),
+        }
+    }
+}
+
+/// The diff that an operation was generated from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffItem {
+    /// A group diff
+    Group(groups::LocalDiff),
+    /// A user (group membership) diff
+    User(users::LocalDiff),
+    /// A policy diff
+    Policy(policies::LocalDiff),
+    /// A default policy diff
+    DefaultPolicy(default_policies::LocalDiff),
+    /// The configured state of a tag
+    Tag(tags::LocalDiff),
+    /// A masking and row access policy diff
+    Protection(protections::LocalDiff),
+    /// An ownership transfer
+    Ownership(ownership::LocalDiff),
+    /// A change to a user's properties
+    UserProperties(user_properties::LocalDiff),
+}
+
+/// A single query or request that a connector plans to run as part of `jetty apply`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedOperation {
+    /// The user, group, or asset being changed
+    pub target: ApplyTarget,
+    /// The diff the operation was generated from
+    pub diff: DiffItem,
+    /// The rendered query or request
+    pub request: String,
+}
+
+impl PlannedOperation {
+    /// Create a new planned operation
+    pub fn new(target: ApplyTarget, diff: DiffItem, request: String) -> Self {
+        Self {
+            target,
+            diff,
+            request,
+        }
+    }
+}
+
+/// Whether an operation succeeded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum ApplyOutcome {
+    /// The operation succeeded
+    Succeeded,
+    /// The operation failed
+    Failed {
+        /// The error returned by the connector or the underlying platform
+        error: String,
+    },
+}
+
+/// An operation that was executed, along with its outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedOperation {
+    /// The operation that was run
+    #[serde(flatten)]
+    pub operation: PlannedOperation,
+    /// The outcome of the operation
+    #[serde(flatten)]
+    pub outcome: ApplyOutcome,
+}
+
+/// The result of applying changes to a single connector
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApplyReport {
+    /// Every operation that was executed, in the order it was executed
+    pub operations: Vec<AppliedOperation>,
+    /// The error that stopped the connector from applying its changes, if there was one
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<String>,
+}
+
+impl ApplyReport {
+    /// Create a report for a connector that failed without reporting its operations
+    pub fn connector_failure(error: &anyhow::Error) -> Self {
+        ApplyReport {
+            operations: vec![],
+            error: Some(format!("{error:#}")),
+        }
+    }
+
+    /// Record the result of executing an operation
+    pub fn record<T>(&mut self, operation: PlannedOperation, result: &Result<T>) {
+        let outcome = match result {
+            Ok(_) => ApplyOutcome::Succeeded,
+            Err(e) => ApplyOutcome::Failed {
+                error: format!("{e:#}"),
+            },
+        };
+        self.operations
+            .push(AppliedOperation { operation, outcome });
+    }
+
+    /// The operations that succeeded
+    pub fn successes(&self) -> Vec<&AppliedOperation> {
+        self.operations
+            .iter()
+            .filter(|o| o.outcome == ApplyOutcome::Succeeded)
+            .collect()
+    }
+
+    /// The operations that failed
+    pub fn failures(&self) -> Vec<&AppliedOperation> {
+        self.operations
+            .iter()
+            .filter(|o| o.outcome != ApplyOutcome::Succeeded)
+            .collect()
+    }
+
+    /// Whether the connector and every operation succeeded
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+            && self
+                .operations
+                .iter()
+                .all(|o| o.outcome == ApplyOutcome::Succeeded)
+    }
+}
+
+impl Display for ApplyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} successful operations", self.successes().len())?;
+        write!(f, "{} failed operations", self.failures().len())?;
+        if let Some(error) = &self.error {
+            write!(f, "\nthe connector failed: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    fn operation(request: &str) -> PlannedOperation {
+        PlannedOperation::new(
+            ApplyTarget::User("user".to_owned()),
+            DiffItem::User(users::LocalDiff {
+                user: "user".to_owned(),
+                group_membership: users::LocalDiffDetails {
+                    add: ["group".to_owned()].into(),
+                    remove: Default::default(),
+                },
+            }),
+            request.to_owned(),
+        )
+    }
+
+    #[test]
+    fn report_tracks_outcomes() {
+        let mut report = ApplyReport::default();
+        report.record(operation("GRANT ROLE \"group\" TO USER \"user\";"), &Ok(()));
+        report.record::<()>(
+            operation("GRANT ROLE \"group\" TO USER \"user\";"),
+            &Err(anyhow!("role does not exist")),
+        );
+
+        assert!(!report.is_success());
+        assert_eq!(report.successes().len(), 1);
+        assert_eq!(report.failures().len(), 1);
+        assert_eq!(
+            report.failures()[0].outcome,
+            ApplyOutcome::Failed {
+                error: "role does not exist".to_owned()
+            }
+        );
+        assert_eq!(
+            report.to_string(),
+            "1 successful operations\n1 failed operations"
+        );
+    }
+
+    #[test]
+    fn report_serializes_flat_operations() -> Result<()> {
+        let mut report = ApplyReport::default();
+        report.record::<()>(operation("query"), &Err(anyhow!("bad query")));
+        let value = serde_json::to_value(&report)?;
+        let op = &value["operations"][0];
+        assert_eq!(op["target"], serde_json::json!({"user": "user"}));
+        assert_eq!(op["request"], "query");
+        assert_eq!(op["outcome"], "failed");
+        assert_eq!(op["error"], "bad query");
+        assert!(value.get("error").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn connector_failures_are_reported() -> Result<()> {
+        let report = ApplyReport::connector_failure(&anyhow!("couldn't connect"));
+        assert!(!report.is_success());
+        assert_eq!(
+            report.to_string(),
+            "0 successful operations\n0 failed operations\nthe connector failed: couldn't connect"
+        );
+        assert_eq!(serde_json::to_value(&report)?["error"], "couldn't connect");
+        Ok(())
+    }
+}
@@ -18,10 +18,14 @@ use uuid::Uuid;
 
 use crate::{
     access_graph::{
-        AccessGraph, AssetPath, DefaultPolicyAttributes, EdgeType, JettyNode, NodeName,
-        PolicyAttributes,
+        AccessGraph, AssetAttributes, AssetPath, DefaultPolicyAttributes, EdgeType, JettyNode,
+        NodeName, PolicyAttributes,
+    },
+    connectors::{
+        ownership::{owner_from_metadata, CurrentGrants},
+        protections::{AssetProtections, RowAccessPolicy},
+        AssetType, WriteCapabilities,
     },
-    connectors::AssetType,
     jetty::ConnectorNamespace,
     logging::warn,
     project, Jetty,
@@ -29,7 +33,9 @@ use crate::{
 
 use self::diff::{
     default_policies::{diff_default_policies, DefaultPolicyDiff},
+    ownership::{diff_ownership, OwnershipDiff},
     policies::{diff_policies, PolicyDiff},
+    protections::{diff_protections, DeclaredProtections, ProtectionDiff},
 };
 
 use super::groups::{get_group_capable_connectors, get_group_to_nodename_map, GroupYaml};
@@ -37,7 +43,7 @@ use super::groups::{get_group_capable_connectors, get_group_to_nodename_map, Gro
 pub(crate) use update::{remove_group_name, remove_user_name, update_group_name, update_user_name};
 
 /// Policy state
-#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[derive(Clone, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub struct PolicyState {
     /// Included privileges
     pub privileges: HashSet<String>,
@@ -62,6 +68,66 @@ pub(crate) struct YamlAssetDoc {
         rename = "default policies"
     )]
     default_policies: BTreeSet<YamlDefaultPolicy>,
+    /// `Some(None)` when the key is set to null, to remove the asset's row access policy
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        deserialize_with = "deserialize_declared",
+        rename = "row access policy"
+    )]
+    row_access_policy: Option<Option<RowAccessPolicy>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "masking policies"
+    )]
+    masking_policies: Option<BTreeMap<String, String>>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "network policy"
+    )]
+    network_policy: Option<String>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        default,
+        rename = "session policy"
+    )]
+    session_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    owner: Option<YamlOwner>,
+}
+
+/// The declared owner of an asset
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub(crate) struct YamlOwner {
+    /// The name of the owning group, as it appears in the group configuration
+    group: String,
+    /// What happens to the existing grants on the asset when ownership is transferred
+    #[serde(default, rename = "current grants")]
+    current_grants: CurrentGrants,
+}
+
+impl YamlAssetDoc {
+    /// The masking, row access, network, and session policies declared for the asset
+    fn protections(&self) -> DeclaredProtections {
+        DeclaredProtections {
+            row_access_policy: self.row_access_policy.to_owned(),
+            masking_policies: self.masking_policies.to_owned(),
+            network_policy: self.network_policy.to_owned(),
+            session_policy: self.session_policy.to_owned(),
+        }
+    }
+}
+
+/// Deserialize a key that can be set to null, so that a null value can be told apart from a
+/// missing key
+fn deserialize_declared<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone, Ord, PartialOrd, Eq, PartialEq)]
@@ -250,6 +316,122 @@ pub fn get_policy_diffs(
     Ok(diff_policies(&config_state, &env_state))
 }
 
+/// Get the diffs for the masking, row access, network, and session policies of assets in
+/// connectors that can write them
+pub fn get_protection_diffs(jetty: &Jetty) -> Result<Vec<ProtectionDiff>> {
+    let connectors: HashSet<_> = jetty
+        .connector_manifests()
+        .into_iter()
+        .filter(|(_, m)| {
+            m.capabilities
+                .write
+                .contains(&WriteCapabilities::Protections)
+        })
+        .map(|(n, _)| n)
+        .collect();
+    let in_capable_connector = |name: &NodeName| matches!(name, NodeName::Asset { connector, .. } if connectors.contains(connector));
+
+    let mut config_state = HashMap::new();
+    for path in get_config_paths()? {
+        let path = path?;
+        let yaml = std::fs::read_to_string(&path)?;
+        let (asset, protections) =
+            parser::parse_asset_protections(&yaml, jetty).context(format!(
+                "problem with configuration file: {}",
+                path.to_string_lossy()
+            ))?;
+        if in_capable_connector(&asset) {
+            config_state.insert(asset, protections);
+        } else if !protections.is_empty() {
+            warn!(
+                "{asset} has masking, row access, network, or session policies configured, but \
+                its connector can't manage them; they will be ignored"
+            );
+        }
+    }
+
+    let ag = jetty.try_access_graph()?;
+    let mut env_state = HashMap::new();
+    for (name, &idx) in &ag.graph.nodes.assets {
+        if in_capable_connector(name) {
+            let attributes: AssetAttributes = ag[idx].to_owned().try_into()?;
+            env_state.insert(
+                name.to_owned(),
+                AssetProtections::from_metadata(&attributes.metadata)?,
+            );
+        }
+    }
+
+    Ok(diff_protections(&config_state, &env_state))
+}
+
+/// Get the ownership transfers for assets in connectors that can manage ownership
+pub fn get_ownership_diffs(
+    jetty: &Jetty,
+    validated_group_config: &BTreeSet<GroupYaml>,
+) -> Result<Vec<OwnershipDiff>> {
+    let connectors = ownership_capable_connectors(jetty);
+    let in_capable_connector = |name: &NodeName| matches!(name, NodeName::Asset { connector, .. } if connectors.contains(connector));
+
+    let group_connectors = &get_group_capable_connectors(jetty)
+        .keys()
+        .cloned()
+        .collect();
+    let config_groups = get_group_to_nodename_map(validated_group_config, group_connectors);
+
+    let mut config_state = HashMap::new();
+    for path in get_config_paths()? {
+        let path = path?;
+        let yaml = std::fs::read_to_string(&path)?;
+        let (asset, owner) =
+            parser::parse_asset_owner(&yaml, jetty, &config_groups).context(format!(
+                "problem with configuration file: {}",
+                path.to_string_lossy()
+            ))?;
+        match owner {
+            Some(owner) if in_capable_connector(&asset) => {
+                config_state.insert(asset, owner);
+            }
+            Some(_) => warn!(
+                "{asset} has an owner configured, but its connector can't manage ownership; it \
+                will be ignored"
+            ),
+            None => (),
+        }
+    }
+
+    let ag = jetty.try_access_graph()?;
+    let mut env_state = HashMap::new();
+    for (name, &idx) in &ag.graph.nodes.assets {
+        if let NodeName::Asset { connector, .. } = name {
+            if connectors.contains(connector) {
+                let attributes: AssetAttributes = ag[idx].to_owned().try_into()?;
+                if let Some(owner) = owner_from_metadata(&attributes.metadata) {
+                    env_state.insert(
+                        name.to_owned(),
+                        NodeName::Group {
+                            name: owner,
+                            origin: connector.to_owned(),
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(diff_ownership(&config_state, &env_state))
+}
+
+/// Get the connectors that manage asset ownership
+pub(crate) fn ownership_capable_connectors(jetty: &Jetty) -> HashSet<ConnectorNamespace> {
+    jetty
+        .connector_manifests()
+        .into_iter()
+        .filter(|(_, m)| m.capabilities.write.contains(&WriteCapabilities::Ownership))
+        .map(|(n, _)| n)
+        .collect()
+}
+
 /// Get the paths of all asset config files
 fn get_config_paths() -> Result<glob::Paths> {
     // collect the paths to all the config files
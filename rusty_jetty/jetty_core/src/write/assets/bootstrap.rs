@@ -13,7 +13,7 @@ use crate::{
     access_graph::{
         AssetAttributes, DefaultPolicyAttributes, EdgeType, JettyNode, NodeName, PolicyAttributes,
     },
-    connectors::AssetType,
+    connectors::{ownership::owner_from_metadata, protections::AssetProtections, AssetType},
     project,
     write::utils::clean_string_for_path,
     Jetty,
@@ -21,7 +21,7 @@ use crate::{
 
 use super::{
     generate_id_file_map, CombinedPolicyState, DefaultPolicyState, PolicyState, YamlAssetDoc,
-    YamlAssetIdentifier, YamlDefaultPolicy, YamlPolicy,
+    YamlAssetIdentifier, YamlDefaultPolicy, YamlOwner, YamlPolicy,
 };
 
 type PolicyKey = (NodeName, BTreeSet<String>, BTreeSet<(String, String)>);
@@ -357,7 +357,18 @@ impl Jetty {
     /// Generate the yaml configs for each asset, as well as the proper path for them in the file system
     pub fn generate_bootstrapped_policy_yaml(&self) -> Result<HashMap<PathBuf, String>> {
         let ag = self.try_access_graph()?;
-        self.build_bootstrapped_policy_config()?
+        let mut config = self.build_bootstrapped_policy_config()?;
+        // protected and owned assets need a config file, even if they don't have any policies
+        for &idx in ag.graph.nodes.assets.values() {
+            let attributes: AssetAttributes = ag[idx].to_owned().try_into()?;
+            if !AssetProtections::from_metadata(&attributes.metadata)?.is_empty()
+                || owner_from_metadata(&attributes.metadata).is_some()
+            {
+                config.entry(idx.into()).or_default();
+            }
+        }
+
+        config
             .into_iter()
             .map(
                 |(idx, (policies, default_policies))| -> Result<(PathBuf, String)> {
@@ -367,9 +378,9 @@ impl Jetty {
                         NodeName::Asset { .. } => Ok((
                             self.asset_index_to_file_path(idx),
                             yaml_peg::serde::to_string(&YamlAssetDoc {
-                                identifier: asset_attributes_to_yaml_identifier(&attributes),
                                 policies,
                                 default_policies,
+                                ..asset_attributes_to_yaml_doc(&attributes)?
                             })?,
                         )),
                         _ => panic!("expected an asset node"),
@@ -515,11 +526,7 @@ pub fn update_asset_files(jetty: &Jetty) -> Result<()> {
             .to_owned()
             .ok_or_else(|| anyhow!("unable to get asset by id"))?;
         let attributes: AssetAttributes = ag[idx].to_owned().try_into()?;
-        let policy_doc = YamlAssetDoc {
-            identifier: asset_attributes_to_yaml_identifier(&attributes),
-            policies: Default::default(),
-            default_policies: Default::default(),
-        };
+        let policy_doc = asset_attributes_to_yaml_doc(&attributes)?;
         let yaml = yaml_peg::serde::to_string(&policy_doc)?;
         let parent_path =
             project::assets_cfg_root_path_local().join(jetty.asset_index_to_file_path(idx.into()));
@@ -557,6 +564,30 @@ pub fn update_asset_files(jetty: &Jetty) -> Result<()> {
     Ok(())
 }
 
+/// Build a config doc, without policies, for the asset. The doc includes the asset's current
+/// masking, row access, network, and session policies and owner.
+fn asset_attributes_to_yaml_doc(attributes: &AssetAttributes) -> Result<YamlAssetDoc> {
+    let protections = AssetProtections::from_metadata(&attributes.metadata)?;
+    let identifier = asset_attributes_to_yaml_identifier(attributes);
+    let owner = owner_from_metadata(&attributes.metadata).map(|owner| YamlOwner {
+        group: NodeName::Group {
+            name: owner,
+            origin: identifier.connector.to_owned(),
+        }
+        .to_string(),
+        current_grants: Default::default(),
+    });
+    Ok(YamlAssetDoc {
+        identifier,
+        row_access_policy: protections.row_access_policy.map(Some),
+        masking_policies: Some(protections.masking_policies).filter(|m| !m.is_empty()),
+        network_policy: protections.network_policy,
+        session_policy: protections.session_policy,
+        owner,
+        ..Default::default()
+    })
+}
+
 fn asset_attributes_to_yaml_identifier(attributes: &AssetAttributes) -> YamlAssetIdentifier {
     if let NodeName::Asset {
         connector,
@@ -1,4 +1,6 @@
 //! Functions to diff assets between environments
 
 pub(crate) mod default_policies;
+pub mod ownership;
 pub mod policies;
+pub mod protections;
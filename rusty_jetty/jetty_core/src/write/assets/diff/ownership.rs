@@ -0,0 +1,123 @@
+//! Module to diff the owners of assets
+
+use std::{collections::HashMap, fmt::Display};
+
+use colored::Colorize;
+
+use crate::{
+    access_graph::NodeName, connectors::ownership::CurrentGrants, jetty::ConnectorNamespace,
+    write::SplitByConnector,
+};
+
+#[derive(Debug, Clone)]
+/// A transfer of the ownership of an asset
+pub struct OwnershipDiff {
+    /// The name of the asset being changed
+    pub(crate) asset: NodeName,
+    /// The group that should own the asset
+    pub(crate) owner: NodeName,
+    /// The group that currently owns the asset, if it's known
+    pub(crate) previous_owner: Option<NodeName>,
+    /// What happens to the existing grants on the asset
+    pub(crate) current_grants: CurrentGrants,
+    pub(crate) connector: ConnectorNamespace,
+}
+
+impl SplitByConnector for OwnershipDiff {
+    fn split_by_connector(&self) -> HashMap<ConnectorNamespace, Box<Self>> {
+        [(self.connector.to_owned(), Box::new(self.to_owned()))].into()
+    }
+}
+
+impl Display for OwnershipDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = format!("asset: {}\n  owner:\n", self.asset);
+        if let Some(previous_owner) = &self.previous_owner {
+            text += &format!("{}", format!("    - {previous_owner}\n").red());
+        }
+        text += &format!("{}", format!("    + {}\n", self.owner).green());
+        text += &format!("  {}\n", current_grants_note(self.current_grants).yellow());
+
+        write!(f, "{text}")
+    }
+}
+
+/// Explain what happens to the existing grants on an asset when its ownership is transferred
+pub(crate) fn current_grants_note(current_grants: CurrentGrants) -> &'static str {
+    match current_grants {
+        CurrentGrants::Copy => {
+            "existing grants on the asset are kept, and will be granted by the new owner"
+        }
+        CurrentGrants::Revoke => "all existing grants on the asset will be revoked",
+    }
+}
+
+/// Diff the configured owners with the owners in the environment. Only assets with a
+/// configured owner are compared, since an asset can't be left without an owner.
+pub(crate) fn diff_ownership(
+    config_state: &HashMap<NodeName, (NodeName, CurrentGrants)>,
+    env_state: &HashMap<NodeName, NodeName>,
+) -> Vec<OwnershipDiff> {
+    let mut res = config_state
+        .iter()
+        .filter(|(asset, (owner, _))| env_state.get(asset) != Some(owner))
+        .map(|(asset, (owner, current_grants))| {
+            let connector = match asset {
+                NodeName::Asset { connector, .. } => connector.to_owned(),
+                _ => panic!("expected an asset"),
+            };
+            OwnershipDiff {
+                asset: asset.to_owned(),
+                owner: owner.to_owned(),
+                previous_owner: env_state.get(asset).cloned(),
+                current_grants: current_grants.to_owned(),
+                connector,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    res.sort_by(|a, b| a.asset.cmp(&b.asset));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::access_graph::AssetPath;
+
+    use super::*;
+
+    fn asset(name: &str) -> NodeName {
+        NodeName::Asset {
+            connector: ConnectorNamespace("snowflake".to_owned()),
+            asset_type: None,
+            path: AssetPath::new(vec!["DB".to_owned(), "RAW".to_owned(), name.to_owned()]),
+        }
+    }
+
+    fn group(name: &str) -> NodeName {
+        NodeName::Group {
+            name: name.to_owned(),
+            origin: ConnectorNamespace("snowflake".to_owned()),
+        }
+    }
+
+    #[test]
+    fn only_changed_owners_are_transferred() {
+        let config = HashMap::from([
+            (asset("USERS"), (group("SYSADMIN"), CurrentGrants::Copy)),
+            (asset("ORDERS"), (group("DATA_ENG"), CurrentGrants::Revoke)),
+        ]);
+        let env = HashMap::from([
+            (asset("USERS"), group("SYSADMIN")),
+            (asset("ORDERS"), group("SYSADMIN")),
+            (asset("EVENTS"), group("SYSADMIN")),
+        ]);
+
+        let diffs = diff_ownership(&config, &env);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].asset, asset("ORDERS"));
+        assert_eq!(diffs[0].owner, group("DATA_ENG"));
+        assert_eq!(diffs[0].previous_owner, Some(group("SYSADMIN")));
+        assert_eq!(diffs[0].current_grants, CurrentGrants::Revoke);
+    }
+}
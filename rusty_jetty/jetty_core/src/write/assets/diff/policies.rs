@@ -6,6 +6,7 @@ use std::{
 };
 
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     access_graph::NodeName,
@@ -36,7 +37,7 @@ impl SplitByConnector for PolicyDiff {
 }
 
 /// Details of policy diff
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DiffDetails {
     /// Add an agent to the policy
     AddAgent {
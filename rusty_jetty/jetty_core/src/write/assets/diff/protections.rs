@@ -0,0 +1,404 @@
+//! Module to diff the masking, row access, network, and session policies on assets
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    access_graph::NodeName,
+    connectors::protections::{AssetProtections, RowAccessPolicy},
+    jetty::ConnectorNamespace,
+    write::SplitByConnector,
+};
+
+/// The policies declared for an asset in its configuration. Only the declared kinds of
+/// policies are managed, so leaving one out leaves the asset's current policies alone.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct DeclaredProtections {
+    /// The row access policy, or `Some(None)` if the asset is declared to have none
+    pub(crate) row_access_policy: Option<Option<RowAccessPolicy>>,
+    /// The masking policies, keyed by column name
+    pub(crate) masking_policies: Option<BTreeMap<String, String>>,
+    /// The account's network policy
+    pub(crate) network_policy: Option<String>,
+    /// The fully-qualified name of the account's session policy
+    pub(crate) session_policy: Option<String>,
+}
+
+impl DeclaredProtections {
+    /// Whether no policies are declared
+    pub(crate) fn is_empty(&self) -> bool {
+        self == &Default::default()
+    }
+}
+
+/// A change to a single protection. `None` means that there is no policy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectionChange<T> {
+    /// The policy being applied
+    pub add: Option<T>,
+    /// The policy being removed
+    pub remove: Option<T>,
+}
+
+#[derive(Debug, Clone)]
+/// A diff of the protections on an asset
+pub struct ProtectionDiff {
+    /// The name of the asset being changed
+    pub(crate) asset: NodeName,
+    /// The change to the row access policy, if it's changing
+    pub(crate) row_access_policy: Option<ProtectionChange<RowAccessPolicy>>,
+    /// The changing masking policies, by column
+    pub(crate) masking_policies: BTreeMap<String, ProtectionChange<String>>,
+    /// The change to the account's network policy, if it's changing
+    pub(crate) network_policy: Option<ProtectionChange<String>>,
+    /// The change to the account's session policy, if it's changing
+    pub(crate) session_policy: Option<ProtectionChange<String>>,
+    pub(crate) connector: ConnectorNamespace,
+}
+
+impl SplitByConnector for ProtectionDiff {
+    fn split_by_connector(&self) -> HashMap<ConnectorNamespace, Box<Self>> {
+        [(self.connector.to_owned(), Box::new(self.to_owned()))].into()
+    }
+}
+
+impl Display for ProtectionDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = format!("asset: {}\n", self.asset);
+
+        if let Some(change) = &self.row_access_policy {
+            text += "  row access policy:\n";
+            if let Some(remove) = &change.remove {
+                text += &format!(
+                    "{}",
+                    format!("    - {}\n", format_row_access_policy(remove)).red()
+                );
+            }
+            if let Some(add) = &change.add {
+                text += &format!(
+                    "{}",
+                    format!("    + {}\n", format_row_access_policy(add)).green()
+                );
+            }
+        }
+
+        if !self.masking_policies.is_empty() {
+            text += "  masking policies:\n";
+            for (column, change) in &self.masking_policies {
+                if let Some(remove) = &change.remove {
+                    text += &format!("{}", format!("    - {column}: {remove}\n").red());
+                }
+                if let Some(add) = &change.add {
+                    text += &format!("{}", format!("    + {column}: {add}\n").green());
+                }
+            }
+        }
+
+        for (label, change) in [
+            ("network policy", &self.network_policy),
+            ("session policy", &self.session_policy),
+        ] {
+            if let Some(change) = change {
+                text += &format!("  {label}:\n");
+                if let Some(remove) = &change.remove {
+                    text += &format!("{}", format!("    - {remove}\n").red());
+                }
+                if let Some(add) = &change.add {
+                    text += &format!("{}", format!("    + {add}\n").green());
+                }
+            }
+        }
+
+        write!(f, "{text}")
+    }
+}
+
+/// Format a row access policy like `POLICY ON (COL_A, COL_B)`
+pub(crate) fn format_row_access_policy(policy: &RowAccessPolicy) -> String {
+    format!("{} ON ({})", policy.policy, policy.columns.join(", "))
+}
+
+/// Diff the configured protections with the protections in the environment. Only the
+/// assets in the configuration, and the kinds of policies they declare, are compared.
+/// Network and session policies can't be declared empty, so they're never unset.
+pub(crate) fn diff_protections(
+    config_state: &HashMap<NodeName, DeclaredProtections>,
+    env_state: &HashMap<NodeName, AssetProtections>,
+) -> Vec<ProtectionDiff> {
+    let empty = AssetProtections::default();
+    let mut res = vec![];
+
+    for (asset, config) in config_state {
+        let env = env_state.get(asset).unwrap_or(&empty);
+
+        let row_access_policy = config
+            .row_access_policy
+            .as_ref()
+            .filter(|&c| c != &env.row_access_policy)
+            .map(|c| ProtectionChange {
+                add: c.to_owned(),
+                remove: env.row_access_policy.to_owned(),
+            });
+
+        let masking_policies: BTreeMap<_, _> = match &config.masking_policies {
+            Some(configured) => configured
+                .keys()
+                .chain(env.masking_policies.keys())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .filter_map(|column| {
+                    let add = configured.get(column);
+                    let remove = env.masking_policies.get(column);
+                    if add == remove {
+                        None
+                    } else {
+                        Some((
+                            column.to_owned(),
+                            ProtectionChange {
+                                add: add.cloned(),
+                                remove: remove.cloned(),
+                            },
+                        ))
+                    }
+                })
+                .collect(),
+            None => Default::default(),
+        };
+
+        let configured_change = |config: &Option<String>, env: &Option<String>| {
+            config
+                .as_ref()
+                .filter(|&c| Some(c) != env.as_ref())
+                .map(|c| ProtectionChange {
+                    add: Some(c.to_owned()),
+                    remove: env.to_owned(),
+                })
+        };
+        let network_policy = configured_change(&config.network_policy, &env.network_policy);
+        let session_policy = configured_change(&config.session_policy, &env.session_policy);
+
+        if row_access_policy.is_some()
+            || !masking_policies.is_empty()
+            || network_policy.is_some()
+            || session_policy.is_some()
+        {
+            let connector = match asset {
+                NodeName::Asset { connector, .. } => connector.to_owned(),
+                _ => panic!("expected an asset"),
+            };
+            res.push(ProtectionDiff {
+                asset: asset.to_owned(),
+                row_access_policy,
+                masking_policies,
+                network_policy,
+                session_policy,
+                connector,
+            });
+        }
+    }
+
+    res.sort_by(|a, b| a.asset.cmp(&b.asset));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::access_graph::AssetPath;
+
+    use super::*;
+
+    fn asset(name: &str) -> NodeName {
+        NodeName::Asset {
+            connector: ConnectorNamespace("snowflake".to_owned()),
+            asset_type: None,
+            path: AssetPath::new(vec!["DB".to_owned(), "RAW".to_owned(), name.to_owned()]),
+        }
+    }
+
+    fn masked(columns: &[(&str, &str)]) -> AssetProtections {
+        AssetProtections {
+            row_access_policy: None,
+            masking_policies: columns
+                .iter()
+                .map(|(c, p)| (c.to_string(), p.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn declare_masking(columns: &[(&str, &str)]) -> DeclaredProtections {
+        DeclaredProtections {
+            masking_policies: Some(masked(columns).masking_policies),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unchanged_protections_produce_no_diff() {
+        let config = HashMap::from([(
+            asset("USERS"),
+            declare_masking(&[("EMAIL", "GOV.P.MASK_EMAIL")]),
+        )]);
+        let env = HashMap::from([(asset("USERS"), masked(&[("EMAIL", "GOV.P.MASK_EMAIL")]))]);
+        assert!(diff_protections(&config, &env).is_empty());
+    }
+
+    #[test]
+    fn undeclared_protections_are_left_alone() {
+        let env = HashMap::from([(
+            asset("USERS"),
+            AssetProtections {
+                row_access_policy: Some(RowAccessPolicy {
+                    policy: "GOV.P.REGION_FILTER".to_owned(),
+                    columns: vec!["REGION".to_owned()],
+                }),
+                masking_policies: masked(&[("EMAIL", "GOV.P.MASK_EMAIL")]).masking_policies,
+                ..Default::default()
+            },
+        )]);
+        let config = HashMap::from([(asset("USERS"), DeclaredProtections::default())]);
+        assert!(diff_protections(&config, &env).is_empty());
+
+        // Declaring them empty removes them
+        let config = HashMap::from([(
+            asset("USERS"),
+            DeclaredProtections {
+                row_access_policy: Some(None),
+                masking_policies: Some(Default::default()),
+                ..Default::default()
+            },
+        )]);
+        let diffs = diff_protections(&config, &env);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].row_access_policy,
+            Some(ProtectionChange {
+                add: None,
+                remove: env[&asset("USERS")].row_access_policy.to_owned(),
+            })
+        );
+        assert_eq!(
+            diffs[0].masking_policies,
+            BTreeMap::from([(
+                "EMAIL".to_owned(),
+                ProtectionChange {
+                    add: None,
+                    remove: Some("GOV.P.MASK_EMAIL".to_owned())
+                }
+            )])
+        );
+    }
+
+    #[test]
+    fn masking_policy_changes_are_diffed_by_column() {
+        let config = HashMap::from([(
+            asset("USERS"),
+            declare_masking(&[("EMAIL", "GOV.P.MASK_EMAIL"), ("SSN", "GOV.P.MASK_SSN")]),
+        )]);
+        let env = HashMap::from([(
+            asset("USERS"),
+            masked(&[("EMAIL", "GOV.P.MASK_ALL"), ("PHONE", "GOV.P.MASK_PHONE")]),
+        )]);
+
+        let diffs = diff_protections(&config, &env);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].row_access_policy, None);
+        assert_eq!(
+            diffs[0].masking_policies,
+            BTreeMap::from([
+                (
+                    "EMAIL".to_owned(),
+                    ProtectionChange {
+                        add: Some("GOV.P.MASK_EMAIL".to_owned()),
+                        remove: Some("GOV.P.MASK_ALL".to_owned())
+                    }
+                ),
+                (
+                    "PHONE".to_owned(),
+                    ProtectionChange {
+                        add: None,
+                        remove: Some("GOV.P.MASK_PHONE".to_owned())
+                    }
+                ),
+                (
+                    "SSN".to_owned(),
+                    ProtectionChange {
+                        add: Some("GOV.P.MASK_SSN".to_owned()),
+                        remove: None
+                    }
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn row_access_policy_columns_are_compared() {
+        let policy = |columns: &[&str]| AssetProtections {
+            row_access_policy: Some(RowAccessPolicy {
+                policy: "GOV.P.REGION_FILTER".to_owned(),
+                columns: columns.iter().map(|c| c.to_string()).collect(),
+            }),
+            ..Default::default()
+        };
+        let config = HashMap::from([(
+            asset("ORDERS"),
+            DeclaredProtections {
+                row_access_policy: Some(policy(&["REGION"]).row_access_policy),
+                ..Default::default()
+            },
+        )]);
+        let env = HashMap::from([(asset("ORDERS"), policy(&["REGION", "COUNTRY"]))]);
+
+        let diffs = diff_protections(&config, &env);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].row_access_policy,
+            Some(ProtectionChange {
+                add: policy(&["REGION"]).row_access_policy,
+                remove: policy(&["REGION", "COUNTRY"]).row_access_policy,
+            })
+        );
+    }
+
+    #[test]
+    fn account_policies_are_only_compared_when_configured() {
+        let account = NodeName::Asset {
+            connector: ConnectorNamespace("snowflake".to_owned()),
+            asset_type: None,
+            path: AssetPath::new(vec!["ACCOUNT".to_owned()]),
+        };
+        let env = HashMap::from([(
+            account.to_owned(),
+            AssetProtections {
+                network_policy: Some("OFFICE_ONLY".to_owned()),
+                session_policy: Some("GOV.P.IDLE_TIMEOUT".to_owned()),
+                ..Default::default()
+            },
+        )]);
+        let unmanaged = HashMap::from([(account.to_owned(), DeclaredProtections::default())]);
+        assert!(diff_protections(&unmanaged, &env).is_empty());
+
+        let config = HashMap::from([(
+            account,
+            DeclaredProtections {
+                network_policy: Some("VPN_ONLY".to_owned()),
+                ..Default::default()
+            },
+        )]);
+        let diffs = diff_protections(&config, &env);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(
+            diffs[0].network_policy,
+            Some(ProtectionChange {
+                add: Some("VPN_ONLY".to_owned()),
+                remove: Some("OFFICE_ONLY".to_owned()),
+            })
+        );
+        assert_eq!(diffs[0].session_policy, None);
+    }
+}
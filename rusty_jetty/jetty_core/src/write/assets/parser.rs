@@ -9,14 +9,19 @@ use anyhow::{anyhow, bail, Context, Result};
 
 use crate::{
     access_graph::{AccessGraph, AssetAttributes, NodeName, UserAttributes},
-    connectors::AssetType,
+    connectors::{
+        ownership::{CurrentGrants, OWNERSHIP_PRIVILEGE},
+        AssetType,
+    },
     jetty::ConnectorNamespace,
+    logging::warn,
     Jetty,
 };
 
 use super::{
-    get_config_paths, CombinedPolicyState, DefaultPolicyState, PolicyState, YamlAssetDoc,
-    YamlAssetIdentifier, YamlDefaultPolicy, YamlPolicy,
+    diff::protections::DeclaredProtections, get_config_paths, CombinedPolicyState,
+    DefaultPolicyState, PolicyState, YamlAssetDoc, YamlAssetIdentifier, YamlDefaultPolicy,
+    YamlPolicy,
 };
 
 /// Parse the configuration into a policy state struct
@@ -64,6 +69,74 @@ pub(crate) fn parse_asset_config(
     ))
 }
 
+/// Parse the configuration into the asset's NodeName and the policies protecting it
+pub(crate) fn parse_asset_protections(
+    val: &str,
+    jetty: &Jetty,
+) -> Result<(NodeName, DeclaredProtections)> {
+    let ag = jetty.try_access_graph()?;
+
+    let config = simple_parse(val)?;
+
+    // make sure the asset exists
+    let asset_name = get_asset_name(
+        &config.identifier.name,
+        &config.identifier.asset_type,
+        &config.identifier.connector,
+        ag,
+    )?;
+
+    let protections = config.protections();
+    if let Some(Some(row_access_policy)) = &protections.row_access_policy {
+        if row_access_policy.columns.is_empty() {
+            bail!(
+                "the row access policy {} must be applied on at least one column",
+                row_access_policy.policy
+            );
+        }
+    }
+    if let Some(policy) = &protections.session_policy {
+        if policy.split('.').count() != 3 {
+            bail!(
+                "the session policy \"{policy}\" must be a fully-qualified name, like \
+                DATABASE.SCHEMA.POLICY"
+            );
+        }
+    }
+
+    Ok((asset_name, protections))
+}
+
+/// Parse the configuration into the asset's NodeName and its declared owner, if there is one
+#[allow(clippy::type_complexity)]
+pub(crate) fn parse_asset_owner(
+    val: &str,
+    jetty: &Jetty,
+    config_groups: &HashMap<String, HashMap<ConnectorNamespace, NodeName>>,
+) -> Result<(NodeName, Option<(NodeName, CurrentGrants)>)> {
+    let ag = jetty.try_access_graph()?;
+
+    let config = simple_parse(val)?;
+
+    // make sure the asset exists
+    let asset_name = get_asset_name(
+        &config.identifier.name,
+        &config.identifier.asset_type,
+        &config.identifier.connector,
+        ag,
+    )?;
+
+    let owner = match &config.owner {
+        Some(owner) => Some((
+            get_group_name(&owner.group, &config.identifier.connector, config_groups)?,
+            owner.current_grants,
+        )),
+        None => None,
+    };
+
+    Ok((asset_name, owner))
+}
+
 /// parse all configs into a map with the file path and YamlAssetDoc
 pub(crate) fn parse_to_file_map() -> Result<HashMap<PathBuf, YamlAssetDoc>> {
     let mut res = HashMap::new();
@@ -79,6 +152,25 @@ pub(crate) fn parse_to_file_map() -> Result<HashMap<PathBuf, YamlAssetDoc>> {
     Ok(res)
 }
 
+/// Get the config file for each configured asset. Files that can't be parsed, or that
+/// refer to assets that don't exist, are skipped.
+pub(crate) fn get_config_files_by_asset(jetty: &Jetty) -> Result<HashMap<NodeName, PathBuf>> {
+    let ag = jetty.try_access_graph()?;
+    Ok(parse_to_file_map()?
+        .into_iter()
+        .filter_map(|(path, config)| {
+            get_asset_name(
+                &config.identifier.name,
+                &config.identifier.asset_type,
+                &config.identifier.connector,
+                ag,
+            )
+            .ok()
+            .map(|name| (name, path))
+        })
+        .collect())
+}
+
 /// parse a yaml file into a YamlAssetDoc with only syntactic validation
 pub(crate) fn simple_parse(val: &str) -> Result<YamlAssetDoc> {
     let config_vec: Vec<YamlAssetDoc> = yaml_peg::serde::from_str(val)?;
@@ -97,13 +189,29 @@ fn parse_policies(
     jetty: &Jetty,
 ) -> Result<HashMap<(NodeName, NodeName), PolicyState>> {
     let ag = jetty.try_access_graph()?;
+    let manages_ownership = super::ownership_capable_connectors(jetty).contains(connector);
     let mut res_policies = HashMap::new();
     for policy in policies {
+        let mut privileges: HashSet<String> = match &policy.privileges {
+            Some(p) => {
+                // Make sure the specified privileges are allowed/exist
+                privileges_are_legal(p, asset_name, jetty, connector, None)?;
+                p.iter().cloned().collect()
+            }
+            None => Default::default(),
+        };
+        // Connectors that manage ownership transfer it with the asset's owner instead
+        if manages_ownership && privileges.remove(OWNERSHIP_PRIVILEGE) {
+            warn!(
+                "{OWNERSHIP_PRIVILEGE} in a policy on {asset_name} will be ignored; set the \
+                asset's owner instead"
+            );
+            if privileges.is_empty() {
+                continue;
+            }
+        }
         let policy_state = PolicyState {
-            privileges: match &policy.privileges {
-                Some(p) => p.iter().cloned().collect(),
-                None => Default::default(),
-            },
+            privileges,
             metadata: Default::default(),
         };
 
@@ -132,11 +240,6 @@ fn parse_policies(
                 );
             }
         };
-
-        // Make sure the specified privileges are allowed/exist
-        if let Some(privileges) = &policy.privileges {
-            privileges_are_legal(privileges, asset_name, jetty, connector, None)?;
-        }
     }
     Ok(res_policies)
 }
@@ -106,7 +106,15 @@ impl UpdateConfig for YamlAssetDoc {
             self.default_policies = new_default_policies;
         }
 
-        Ok(modified_policies || modified_default_policies)
+        let mut modified_owner = false;
+        if let Some(owner) = &mut self.owner {
+            if owner.group == old {
+                owner.group = new.to_owned();
+                modified_owner = true;
+            }
+        }
+
+        Ok(modified_policies || modified_default_policies || modified_owner)
     }
 
     fn remove_group_name(&mut self, name: &str) -> anyhow::Result<bool> {
@@ -144,7 +152,13 @@ impl UpdateConfig for YamlAssetDoc {
             self.default_policies = new_default_policies;
         }
 
-        Ok(modified_policies || modified_default_policies)
+        // Without the group, the asset's ownership is no longer managed
+        let modified_owner = matches!(&self.owner, Some(owner) if owner.group == name);
+        if modified_owner {
+            self.owner = None;
+        }
+
+        Ok(modified_policies || modified_default_policies || modified_owner)
     }
 }
 
@@ -4,7 +4,7 @@ use anyhow::Result;
 
 use crate::Jetty;
 
-use super::{assets, groups, users, GlobalDiffs};
+use super::{assets, groups, scope, tags, users, GlobalDiffs};
 
 /// Get all the diffs
 pub fn get_diffs(jetty: &mut Jetty) -> Result<GlobalDiffs> {
@@ -26,6 +26,9 @@ pub fn get_diffs(jetty: &mut Jetty) -> Result<GlobalDiffs> {
     let group_membership_diffs =
         users::get_membership_diffs(jetty, validated_user_config, validated_group_config)?;
 
+    // user property changes
+    let user_properties_diffs = users::get_properties_diffs(jetty, validated_user_config)?;
+
     // combined user diffs
     let user_diffs = users::diff::combine_diffs(&user_identity_diffs, &group_membership_diffs);
 
@@ -40,10 +43,27 @@ pub fn get_diffs(jetty: &mut Jetty) -> Result<GlobalDiffs> {
     // need to get the group configs and all available connectors
     let default_policy_diffs = assets::get_default_policy_diffs(jetty, validated_group_config)?;
 
-    Ok(GlobalDiffs {
+    // tags are sent as their configured state to the connectors that can write them
+    let tag_states = tags::get_tag_states(jetty)?;
+
+    // masking and row access policy diffs
+    let protection_diffs = assets::get_protection_diffs(jetty)?;
+
+    // ownership transfers
+    let ownership_diffs = assets::get_ownership_diffs(jetty, validated_group_config)?;
+
+    let mut diffs = GlobalDiffs {
         groups: group_diffs,
         users: user_diffs.into_iter().collect(),
+        user_properties: user_properties_diffs,
         default_policies: default_policy_diffs,
         policies: policy_diffs,
-    })
+        tags: tag_states,
+        protections: protection_diffs,
+        ownership: ownership_diffs,
+    };
+    // leave out whatever the connectors' filters exclude
+    scope::scope_diffs(jetty, &mut diffs)?;
+
+    Ok(diffs)
 }
@@ -0,0 +1,1229 @@
+//! Machine-readable output for `jetty diff` and `jetty plan`.
+//!
+//! The types in this module make up the documented JSON schema for diffs and plans. They
+//! are deliberately separate from the internal diff types so that the schema stays stable
+//! as the internals change. Any breaking change to these types must bump [`SCHEMA_VERSION`].
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{access_graph::NodeName, jetty::ConnectorNamespace};
+
+use super::{
+    assets::{
+        self,
+        diff::{
+            default_policies::{ConnectorManagementDiff, DefaultPolicyDiffDetails},
+            ownership::OwnershipDiff,
+            policies::{DiffDetails, PolicyDiff},
+            protections::{format_row_access_policy, ProtectionDiff},
+        },
+    },
+    groups, tags,
+    users::{
+        self,
+        diff::{IdentityDiffDetails, UserPropertiesDiff},
+    },
+    GlobalDiffs,
+};
+
+/// The version of the diff and plan output schema
+pub const SCHEMA_VERSION: &str = "1";
+
+/// The kind of change described by a diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Something is being created
+    Add,
+    /// Something is being removed
+    Remove,
+    /// Something that exists is being changed
+    Modify,
+}
+
+impl ChangeKind {
+    fn symbol(&self) -> &'static str {
+        match self {
+            ChangeKind::Add => "+",
+            ChangeKind::Remove => "-",
+            ChangeKind::Modify => "~",
+        }
+    }
+}
+
+/// Values being added and removed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddRemove<T> {
+    /// Values being added
+    pub add: Vec<T>,
+    /// Values being removed
+    pub remove: Vec<T>,
+}
+
+impl<T> AddRemove<T> {
+    fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// A connector-specific identity for a user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalIdentityOutput {
+    /// The connector the identity belongs to
+    pub connector: String,
+    /// The name of the user in that connector
+    pub name: String,
+}
+
+/// Changes to the identities linked to a user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdentityDiffOutput {
+    /// Whether the user is being added, removed, or modified
+    pub change: ChangeKind,
+    /// The identities being linked and unlinked
+    #[serde(flatten)]
+    pub identities: AddRemove<LocalIdentityOutput>,
+}
+
+/// A diff for a single user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserDiffOutput {
+    /// The Jetty name of the user
+    pub user: String,
+    /// Changes to the user's identities, if any
+    pub identity: Option<IdentityDiffOutput>,
+    /// Changes to the user's group membership, if any. Groups are formatted as `connector::name`
+    pub group_membership: Option<AddRemove<String>>,
+}
+
+/// A change to one property of a user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserPropertyChangeOutput {
+    /// The property, as it's named in the user configuration
+    pub property: String,
+    /// The new value. RSA public keys are given by their fingerprint.
+    pub value: String,
+    /// The current value, if there is one
+    pub previous_value: Option<String>,
+}
+
+/// Changes to the properties of a single user in a connector
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserPropertiesDiffOutput {
+    /// The Jetty name of the user
+    pub user: String,
+    /// The connector the properties belong to
+    pub connector: String,
+    /// The changing properties
+    pub changes: Vec<UserPropertyChangeOutput>,
+}
+
+/// A diff for a single group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupDiffOutput {
+    /// The name of the group, formatted as `connector::name`
+    pub group: String,
+    /// The connector the group belongs to
+    pub connector: String,
+    /// Whether the group is being added, removed, or modified
+    pub change: ChangeKind,
+    /// The groups this group is joining and leaving. Groups are formatted as `connector::name`
+    pub member_of: AddRemove<String>,
+}
+
+/// The privileges and metadata of a policy
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyStateOutput {
+    /// Privileges, sorted alphabetically
+    pub privileges: Vec<String>,
+    /// Metadata
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// A change to the policy granted to one user or group
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentPolicyDiffOutput {
+    /// The user or group name
+    pub name: String,
+    /// Whether the grant is being added, removed, or modified
+    pub change: ChangeKind,
+    /// What's being granted
+    pub add: PolicyStateOutput,
+    /// What's being revoked
+    pub remove: PolicyStateOutput,
+    /// For default policies, the new value of `connector-managed`, if it is changing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connector_managed: Option<bool>,
+}
+
+/// A diff for the policies on a single asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PolicyDiffOutput {
+    /// The asset, formatted as `connector::path (type)`
+    pub asset: String,
+    /// The connector the asset belongs to
+    pub connector: String,
+    /// Changes to policies granted to users
+    pub users: Vec<AgentPolicyDiffOutput>,
+    /// Changes to policies granted to groups
+    pub groups: Vec<AgentPolicyDiffOutput>,
+}
+
+/// A diff for a single default policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DefaultPolicyDiffOutput {
+    /// The root asset, formatted as `connector::path (type)`
+    pub asset: String,
+    /// The wildcard path below the root asset
+    pub path: String,
+    /// The type of asset the policy applies to
+    pub asset_type: String,
+    /// The connector the asset belongs to
+    pub connector: String,
+    /// Changes to default policies granted to users
+    pub users: Vec<AgentPolicyDiffOutput>,
+    /// Changes to default policies granted to groups
+    pub groups: Vec<AgentPolicyDiffOutput>,
+}
+
+/// The kind of policy protecting an asset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectionKind {
+    /// A row access policy
+    RowAccess,
+    /// A masking policy on a column
+    Masking,
+    /// An account's network policy
+    Network,
+    /// An account's session policy
+    Session,
+}
+
+/// A change to one policy protecting an asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectionChangeOutput {
+    /// The kind of policy that's changing
+    pub kind: ProtectionKind,
+    /// The column being masked, or `None` for other kinds of policies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<String>,
+    /// Whether the policy is being added, removed, or replaced
+    pub change: ChangeKind,
+    /// The policy being applied. Row access policies are formatted as `policy ON (columns)`
+    pub add: Option<String>,
+    /// The policy being removed
+    pub remove: Option<String>,
+}
+
+/// A diff for the policies protecting a single asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectionDiffOutput {
+    /// The asset, formatted as `connector::path (type)`
+    pub asset: String,
+    /// The connector the asset belongs to
+    pub connector: String,
+    /// The changing policies: the row access policy, then masking policies, then network and
+    /// session policies
+    pub changes: Vec<ProtectionChangeOutput>,
+}
+
+/// A transfer of the ownership of a single asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnershipDiffOutput {
+    /// The asset, formatted as `connector::path (type)`
+    pub asset: String,
+    /// The connector the asset belongs to
+    pub connector: String,
+    /// The group that will own the asset
+    pub owner: String,
+    /// The group that owns the asset now, if it's known
+    pub previous_owner: Option<String>,
+    /// What happens to the existing grants on the asset: `copy` or `revoke`
+    pub current_grants: String,
+}
+
+/// A change to the assignment of a tag to a single asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagChangeOutput {
+    /// The asset, formatted as `connector::path (type)`
+    pub asset: String,
+    /// Whether the tag is being applied, removed, or given a new value
+    pub change: ChangeKind,
+    /// The value the tag will have on the asset
+    pub value: Option<String>,
+    /// The value the tag has on the asset now
+    pub previous_value: Option<String>,
+}
+
+/// A diff for a tag written natively by a connector
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagDiffOutput {
+    /// The name of the tag
+    pub tag: String,
+    /// The connector the tag is written to
+    pub connector: String,
+    /// The assets whose assignment of the tag is changing
+    pub changes: Vec<TagChangeOutput>,
+}
+
+/// The output of `jetty diff --format json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffOutput {
+    /// The version of the schema
+    pub schema_version: String,
+    /// User diffs
+    pub users: Vec<UserDiffOutput>,
+    /// Group diffs
+    pub groups: Vec<GroupDiffOutput>,
+    /// Policy diffs
+    pub policies: Vec<PolicyDiffOutput>,
+    /// Default policy diffs
+    pub default_policies: Vec<DefaultPolicyDiffOutput>,
+    /// Masking and row access policy diffs. Omitted when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub protections: Vec<ProtectionDiffOutput>,
+    /// Ownership transfers. Omitted when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub ownership: Vec<OwnershipDiffOutput>,
+    /// User property changes. Omitted when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub user_properties: Vec<UserPropertiesDiffOutput>,
+    /// Changes to the tags written natively by connectors. Omitted when empty.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub tags: Vec<TagDiffOutput>,
+}
+
+/// The output of `jetty plan --format json`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanOutput {
+    /// The version of the schema
+    pub schema_version: String,
+    /// The planned queries or requests for each connector, in the order they will be run
+    pub connectors: BTreeMap<String, Vec<String>>,
+}
+
+/// The number of diffs of each kind for a single connector
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffKindCounts {
+    /// Users whose identities for the connector are changing
+    pub identity: usize,
+    /// Users whose group membership in the connector is changing
+    pub membership: usize,
+    /// Groups being added, removed, or modified
+    pub group: usize,
+    /// Assets whose policies are changing
+    pub policy: usize,
+    /// Default policies that are changing
+    pub default_policy: usize,
+    /// Assets whose masking or row access policies are changing
+    #[serde(default)]
+    pub protection: usize,
+    /// Assets whose ownership is changing
+    #[serde(default)]
+    pub ownership: usize,
+    /// Users whose properties in the connector are changing
+    #[serde(default)]
+    pub user_properties: usize,
+    /// Tags whose assignments in the connector are changing
+    #[serde(default)]
+    pub tag: usize,
+}
+
+impl DiffKindCounts {
+    /// The total number of diffs
+    pub fn total(&self) -> usize {
+        self.identity
+            + self.membership
+            + self.group
+            + self.policy
+            + self.default_policy
+            + self.protection
+            + self.ownership
+            + self.user_properties
+            + self.tag
+    }
+}
+
+/// A summary of the drift between the configuration and the environment, used by
+/// `jetty drift`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DriftSummary {
+    /// The version of the schema
+    pub schema_version: String,
+    /// Whether the environment has drifted from the configuration
+    pub drift: bool,
+    /// The number of diffs of each kind, by connector. Connectors without drift are omitted.
+    pub connectors: BTreeMap<String, DiffKindCounts>,
+}
+
+impl From<&GlobalDiffs> for DriftSummary {
+    fn from(diffs: &GlobalDiffs) -> Self {
+        let mut connectors: BTreeMap<String, DiffKindCounts> = BTreeMap::new();
+
+        for diff in &diffs.users {
+            if let Some(identity) = &diff.identity {
+                let ids = match identity {
+                    IdentityDiffDetails::Add { add } => add.iter().collect::<Vec<_>>(),
+                    IdentityDiffDetails::Remove { remove } => remove.iter().collect(),
+                    IdentityDiffDetails::Modify { add, remove } => {
+                        add.iter().chain(remove.iter()).collect()
+                    }
+                };
+                // Count each user once per connector
+                let id_connectors: BTreeSet<_> = ids.iter().map(|(c, _)| c.to_string()).collect();
+                for c in id_connectors {
+                    connectors.entry(c).or_default().identity += 1;
+                }
+            }
+            if let Some(membership) = &diff.group_membership {
+                let group_connectors: BTreeSet<_> = membership
+                    .add
+                    .iter()
+                    .chain(membership.remove.iter())
+                    .filter_map(|g| g.get_group_origin().ok())
+                    .map(|c| c.to_string())
+                    .collect();
+                for c in group_connectors {
+                    connectors.entry(c).or_default().membership += 1;
+                }
+            }
+        }
+        for diff in &diffs.groups {
+            connectors
+                .entry(diff.connector.to_string())
+                .or_default()
+                .group += 1;
+        }
+        for diff in &diffs.policies {
+            connectors
+                .entry(diff.connector.to_string())
+                .or_default()
+                .policy += 1;
+        }
+        for diff in &diffs.default_policies {
+            connectors
+                .entry(diff.connector.to_string())
+                .or_default()
+                .default_policy += 1;
+        }
+        for diff in &diffs.protections {
+            connectors
+                .entry(diff.connector.to_string())
+                .or_default()
+                .protection += 1;
+        }
+        for diff in &diffs.ownership {
+            connectors
+                .entry(diff.connector.to_string())
+                .or_default()
+                .ownership += 1;
+        }
+        for diff in &diffs.user_properties {
+            connectors
+                .entry(diff.connector.to_string())
+                .or_default()
+                .user_properties += 1;
+        }
+        for state in &diffs.tags {
+            if !state.changes().is_empty() {
+                connectors
+                    .entry(state.connector.to_string())
+                    .or_default()
+                    .tag += 1;
+            }
+        }
+
+        DriftSummary {
+            schema_version: SCHEMA_VERSION.to_owned(),
+            drift: !connectors.is_empty(),
+            connectors,
+        }
+    }
+}
+
+impl DriftSummary {
+    /// Render the summary as a markdown table
+    pub fn to_markdown(&self) -> String {
+        let mut text = "## Jetty drift\n\n".to_owned();
+        if !self.drift {
+            text += "No drift detected\n";
+            return text;
+        }
+        text += "| Connector | Identity | Membership | User properties | Group | Policy | Default policy | Protection | Ownership | Tag |\n";
+        text += "| --- | --- | --- | --- | --- | --- | --- | --- | --- | --- |\n";
+        for (connector, counts) in &self.connectors {
+            text += &format!(
+                "| {connector} | {} | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+                counts.identity,
+                counts.membership,
+                counts.user_properties,
+                counts.group,
+                counts.policy,
+                counts.default_policy,
+                counts.protection,
+                counts.ownership,
+                counts.tag
+            );
+        }
+        text
+    }
+}
+
+impl From<&GlobalDiffs> for DiffOutput {
+    fn from(diffs: &GlobalDiffs) -> Self {
+        DiffOutput {
+            schema_version: SCHEMA_VERSION.to_owned(),
+            users: diffs.users.iter().map(UserDiffOutput::from).collect(),
+            groups: diffs.groups.iter().map(GroupDiffOutput::from).collect(),
+            policies: diffs.policies.iter().map(PolicyDiffOutput::from).collect(),
+            default_policies: diffs
+                .default_policies
+                .iter()
+                .map(DefaultPolicyDiffOutput::from)
+                .collect(),
+            protections: diffs
+                .protections
+                .iter()
+                .map(ProtectionDiffOutput::from)
+                .collect(),
+            ownership: diffs
+                .ownership
+                .iter()
+                .map(OwnershipDiffOutput::from)
+                .collect(),
+            user_properties: diffs
+                .user_properties
+                .iter()
+                .map(UserPropertiesDiffOutput::from)
+                .collect(),
+            tags: diffs
+                .tags
+                .iter()
+                .filter(|state| !state.changes().is_empty())
+                .map(TagDiffOutput::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<&users::CombinedUserDiff> for UserDiffOutput {
+    fn from(diff: &users::CombinedUserDiff) -> Self {
+        let identities = |ids: &BTreeSet<(ConnectorNamespace, String)>| {
+            ids.iter()
+                .map(|(connector, name)| LocalIdentityOutput {
+                    connector: connector.to_string(),
+                    name: name.to_owned(),
+                })
+                .collect::<Vec<_>>()
+        };
+        UserDiffOutput {
+            user: diff.user.to_string(),
+            identity: diff.identity.as_ref().map(|details| match details {
+                IdentityDiffDetails::Add { add } => IdentityDiffOutput {
+                    change: ChangeKind::Add,
+                    identities: AddRemove {
+                        add: identities(add),
+                        remove: vec![],
+                    },
+                },
+                IdentityDiffDetails::Remove { remove } => IdentityDiffOutput {
+                    change: ChangeKind::Remove,
+                    identities: AddRemove {
+                        add: vec![],
+                        remove: identities(remove),
+                    },
+                },
+                IdentityDiffDetails::Modify { add, remove } => IdentityDiffOutput {
+                    change: ChangeKind::Modify,
+                    identities: AddRemove {
+                        add: identities(add),
+                        remove: identities(remove),
+                    },
+                },
+            }),
+            group_membership: diff.group_membership.as_ref().map(|m| AddRemove {
+                add: node_names(&m.add),
+                remove: node_names(&m.remove),
+            }),
+        }
+    }
+}
+
+impl From<&groups::Diff> for GroupDiffOutput {
+    fn from(diff: &groups::Diff) -> Self {
+        let (change, member_of) = match &diff.details {
+            groups::diff::DiffDetails::AddGroup { member_of } => (
+                ChangeKind::Add,
+                AddRemove {
+                    add: node_names(member_of),
+                    remove: vec![],
+                },
+            ),
+            groups::diff::DiffDetails::RemoveGroup => (ChangeKind::Remove, Default::default()),
+            groups::diff::DiffDetails::ModifyGroup {
+                add_member_of,
+                remove_member_of,
+            } => (
+                ChangeKind::Modify,
+                AddRemove {
+                    add: node_names(add_member_of),
+                    remove: node_names(remove_member_of),
+                },
+            ),
+        };
+        GroupDiffOutput {
+            group: diff.group_name.to_string(),
+            connector: diff.connector.to_string(),
+            change,
+            member_of,
+        }
+    }
+}
+
+impl From<&PolicyDiff> for PolicyDiffOutput {
+    fn from(diff: &PolicyDiff) -> Self {
+        let agents = |agents: &BTreeMap<NodeName, DiffDetails>| {
+            agents
+                .iter()
+                .map(|(name, details)| {
+                    let (change, add, remove) = match details {
+                        DiffDetails::AddAgent { add } => {
+                            (ChangeKind::Add, add.into(), Default::default())
+                        }
+                        DiffDetails::RemoveAgent { remove } => {
+                            (ChangeKind::Remove, Default::default(), remove.into())
+                        }
+                        DiffDetails::ModifyAgent { add, remove } => {
+                            (ChangeKind::Modify, add.into(), remove.into())
+                        }
+                    };
+                    AgentPolicyDiffOutput {
+                        name: name.to_string(),
+                        change,
+                        add,
+                        remove,
+                        connector_managed: None,
+                    }
+                })
+                .collect()
+        };
+        PolicyDiffOutput {
+            asset: diff.asset.to_string(),
+            connector: diff.connector.to_string(),
+            users: agents(&diff.users),
+            groups: agents(&diff.groups),
+        }
+    }
+}
+
+impl From<&assets::diff::default_policies::DefaultPolicyDiff> for DefaultPolicyDiffOutput {
+    fn from(diff: &assets::diff::default_policies::DefaultPolicyDiff) -> Self {
+        let agents = |agents: &BTreeMap<NodeName, DefaultPolicyDiffDetails>| {
+            agents
+                .iter()
+                .map(|(name, details)| {
+                    let (change, add, remove, connector_managed) = match details {
+                        DefaultPolicyDiffDetails::Add { add } => (
+                            ChangeKind::Add,
+                            policy_state_output(&add.privileges, &add.metadata),
+                            Default::default(),
+                            Some(add.connector_managed),
+                        ),
+                        DefaultPolicyDiffDetails::Remove { remove } => (
+                            ChangeKind::Remove,
+                            Default::default(),
+                            policy_state_output(&remove.privileges, &remove.metadata),
+                            None,
+                        ),
+                        DefaultPolicyDiffDetails::Modify {
+                            add,
+                            remove,
+                            connector_managed,
+                        } => (
+                            ChangeKind::Modify,
+                            policy_state_output(&add.privileges, &add.metadata),
+                            policy_state_output(&remove.privileges, &remove.metadata),
+                            match connector_managed {
+                                ConnectorManagementDiff::Changed(v) => Some(*v),
+                                ConnectorManagementDiff::Unchanged(_) => None,
+                            },
+                        ),
+                    };
+                    AgentPolicyDiffOutput {
+                        name: name.to_string(),
+                        change,
+                        add,
+                        remove,
+                        connector_managed,
+                    }
+                })
+                .collect()
+        };
+        DefaultPolicyDiffOutput {
+            asset: diff.asset.to_string(),
+            path: diff.path.to_owned(),
+            asset_type: diff.asset_type.to_string(),
+            connector: diff.connector.to_string(),
+            users: agents(&diff.users),
+            groups: agents(&diff.groups),
+        }
+    }
+}
+
+impl From<&ProtectionDiff> for ProtectionDiffOutput {
+    fn from(diff: &ProtectionDiff) -> Self {
+        let row_access_policy = diff.row_access_policy.iter().map(|change| {
+            protection_change_output(
+                ProtectionKind::RowAccess,
+                None,
+                change.add.as_ref().map(format_row_access_policy),
+                change.remove.as_ref().map(format_row_access_policy),
+            )
+        });
+        let masking_policies = diff.masking_policies.iter().map(|(column, change)| {
+            protection_change_output(
+                ProtectionKind::Masking,
+                Some(column.to_owned()),
+                change.add.to_owned(),
+                change.remove.to_owned(),
+            )
+        });
+        let account_policies = [
+            (ProtectionKind::Network, &diff.network_policy),
+            (ProtectionKind::Session, &diff.session_policy),
+        ]
+        .into_iter()
+        .filter_map(|(kind, change)| {
+            change.as_ref().map(|change| {
+                protection_change_output(
+                    kind,
+                    None,
+                    change.add.to_owned(),
+                    change.remove.to_owned(),
+                )
+            })
+        });
+        ProtectionDiffOutput {
+            asset: diff.asset.to_string(),
+            connector: diff.connector.to_string(),
+            changes: row_access_policy
+                .chain(masking_policies)
+                .chain(account_policies)
+                .collect(),
+        }
+    }
+}
+
+impl From<&OwnershipDiff> for OwnershipDiffOutput {
+    fn from(diff: &OwnershipDiff) -> Self {
+        OwnershipDiffOutput {
+            asset: diff.asset.to_string(),
+            connector: diff.connector.to_string(),
+            owner: diff.owner.to_string(),
+            previous_owner: diff.previous_owner.as_ref().map(|o| o.to_string()),
+            current_grants: diff.current_grants.to_string(),
+        }
+    }
+}
+
+impl From<&UserPropertiesDiff> for UserPropertiesDiffOutput {
+    fn from(diff: &UserPropertiesDiff) -> Self {
+        UserPropertiesDiffOutput {
+            user: diff.user.to_string(),
+            connector: diff.connector.to_string(),
+            changes: diff
+                .changes()
+                .into_iter()
+                .map(
+                    |(property, previous_value, value)| UserPropertyChangeOutput {
+                        property: property.to_owned(),
+                        value,
+                        previous_value,
+                    },
+                )
+                .collect(),
+        }
+    }
+}
+
+impl From<&tags::TagState> for TagDiffOutput {
+    fn from(state: &tags::TagState) -> Self {
+        TagDiffOutput {
+            tag: state.tag.to_string(),
+            connector: state.connector.to_string(),
+            changes: state
+                .changes()
+                .into_iter()
+                .map(|(asset, current, new)| TagChangeOutput {
+                    asset: asset.to_string(),
+                    change: match (current, &new) {
+                        (None, _) => ChangeKind::Add,
+                        (_, None) => ChangeKind::Remove,
+                        _ => ChangeKind::Modify,
+                    },
+                    value: new,
+                    previous_value: current.cloned(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn protection_change_output(
+    kind: ProtectionKind,
+    column: Option<String>,
+    add: Option<String>,
+    remove: Option<String>,
+) -> ProtectionChangeOutput {
+    let change = match (&add, &remove) {
+        (Some(_), None) => ChangeKind::Add,
+        (None, Some(_)) => ChangeKind::Remove,
+        _ => ChangeKind::Modify,
+    };
+    ProtectionChangeOutput {
+        kind,
+        column,
+        change,
+        add,
+        remove,
+    }
+}
+
+impl From<&assets::PolicyState> for PolicyStateOutput {
+    fn from(state: &assets::PolicyState) -> Self {
+        policy_state_output(&state.privileges, &state.metadata)
+    }
+}
+
+impl PlanOutput {
+    /// Create the plan output from the planned changes for each connector
+    pub fn new(plans: &HashMap<ConnectorNamespace, Vec<String>>) -> Self {
+        PlanOutput {
+            schema_version: SCHEMA_VERSION.to_owned(),
+            connectors: plans
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Render the plan as markdown
+    pub fn to_markdown(&self) -> String {
+        let mut text = "## Jetty plan\n".to_owned();
+        if self.connectors.values().all(|v| v.is_empty()) {
+            text += "\nNo changes planned\n";
+            return text;
+        }
+        for (connector, plan) in &self.connectors {
+            text += &format!("\n### {connector}\n\n");
+            if plan.is_empty() {
+                text += "No changes planned\n";
+                continue;
+            }
+            text += "```\n";
+            text += &plan.join("\n\n");
+            text += "\n```\n";
+        }
+        text
+    }
+}
+
+impl DiffOutput {
+    /// Whether there are no changes at all
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+            && self.groups.is_empty()
+            && self.policies.is_empty()
+            && self.default_policies.is_empty()
+            && self.protections.is_empty()
+            && self.ownership.is_empty()
+            && self.user_properties.is_empty()
+            && self.tags.is_empty()
+    }
+
+    /// Render the diff as markdown
+    pub fn to_markdown(&self) -> String {
+        let mut text = "## Jetty diff\n".to_owned();
+
+        text += "\n### Users\n\n";
+        if self.users.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.users {
+            let change = diff
+                .identity
+                .as_ref()
+                .map(|i| i.change)
+                .unwrap_or(ChangeKind::Modify);
+            text += &format!("- `{}` user **{}**\n", change.symbol(), diff.user);
+            if let Some(identity) = &diff.identity {
+                let ids = AddRemove {
+                    add: identity
+                        .identities
+                        .add
+                        .iter()
+                        .map(|i| format!("{}: {}", i.connector, i.name))
+                        .collect(),
+                    remove: identity
+                        .identities
+                        .remove
+                        .iter()
+                        .map(|i| format!("{}: {}", i.connector, i.name))
+                        .collect(),
+                };
+                text += &markdown_add_remove("identity", &ids, "  ");
+            }
+            if let Some(membership) = &diff.group_membership {
+                text += &markdown_add_remove("groups", membership, "  ");
+            }
+        }
+
+        text += "\n### User Properties\n\n";
+        if self.user_properties.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.user_properties {
+            text += &format!("- user **{}** in {}\n", diff.user, diff.connector);
+            for change in &diff.changes {
+                text += &markdown_add_remove(
+                    &change.property,
+                    &AddRemove {
+                        add: vec![change.value.to_owned()],
+                        remove: change.previous_value.iter().cloned().collect(),
+                    },
+                    "  ",
+                );
+            }
+        }
+
+        text += "\n### Groups\n\n";
+        if self.groups.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.groups {
+            text += &format!("- `{}` group **{}**\n", diff.change.symbol(), diff.group);
+            text += &markdown_add_remove("member of", &diff.member_of, "  ");
+        }
+
+        text += "\n### Policies\n\n";
+        if self.policies.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.policies {
+            text += &format!("- asset **{}**\n", diff.asset);
+            text += &markdown_agents(&diff.users, "user");
+            text += &markdown_agents(&diff.groups, "group");
+        }
+
+        text += "\n### Default Policies\n\n";
+        if self.default_policies.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.default_policies {
+            text += &format!(
+                "- policy **{}** (path: `{}`, asset type: `{}`)\n",
+                diff.asset, diff.path, diff.asset_type
+            );
+            text += &markdown_agents(&diff.users, "user");
+            text += &markdown_agents(&diff.groups, "group");
+        }
+
+        text += "\n### Protection Policies\n\n";
+        if self.protections.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.protections {
+            text += &format!("- asset **{}**\n", diff.asset);
+            for change in &diff.changes {
+                let label = match (change.kind, &change.column) {
+                    (ProtectionKind::Masking, Some(column)) => {
+                        format!("masking policy on `{column}`")
+                    }
+                    (ProtectionKind::Masking, None) => "masking policy".to_owned(),
+                    (ProtectionKind::RowAccess, _) => "row access policy".to_owned(),
+                    (ProtectionKind::Network, _) => "network policy".to_owned(),
+                    (ProtectionKind::Session, _) => "session policy".to_owned(),
+                };
+                text += &format!("  - `{}` {label}\n", change.change.symbol());
+                text += &markdown_add_remove(
+                    "policy",
+                    &AddRemove {
+                        add: change.add.iter().cloned().collect(),
+                        remove: change.remove.iter().cloned().collect(),
+                    },
+                    "    ",
+                );
+            }
+        }
+
+        text += "\n### Ownership\n\n";
+        if self.ownership.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.ownership {
+            text += &format!(
+                "- asset **{}** (current grants: `{}`)\n",
+                diff.asset, diff.current_grants
+            );
+            text += &markdown_add_remove(
+                "owner",
+                &AddRemove {
+                    add: vec![diff.owner.to_owned()],
+                    remove: diff.previous_owner.iter().cloned().collect(),
+                },
+                "  ",
+            );
+        }
+
+        text += "\n### Tags\n\n";
+        if self.tags.is_empty() {
+            text += "No changes found\n";
+        }
+        for diff in &self.tags {
+            text += &format!("- tag **{}** in {}\n", diff.tag, diff.connector);
+            for change in &diff.changes {
+                text += &format!(
+                    "  - `{}` asset **{}**\n",
+                    change.change.symbol(),
+                    change.asset
+                );
+                text += &markdown_add_remove(
+                    "value",
+                    &AddRemove {
+                        add: change.value.iter().cloned().collect(),
+                        remove: change.previous_value.iter().cloned().collect(),
+                    },
+                    "    ",
+                );
+            }
+        }
+
+        text
+    }
+}
+
+fn node_names(names: &BTreeSet<NodeName>) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+fn policy_state_output<'a>(
+    privileges: impl IntoIterator<Item = &'a String>,
+    metadata: &HashMap<String, String>,
+) -> PolicyStateOutput {
+    let mut privileges = privileges.into_iter().cloned().collect::<Vec<_>>();
+    privileges.sort();
+    PolicyStateOutput {
+        privileges,
+        metadata: metadata.clone().into_iter().collect(),
+    }
+}
+
+fn markdown_add_remove(label: &str, values: &AddRemove<String>, indent: &str) -> String {
+    if values.is_empty() {
+        return String::new();
+    }
+    let mut text = format!("{indent}- {label}:\n");
+    for v in &values.add {
+        text += &format!("{indent}  - `+` {v}\n");
+    }
+    for v in &values.remove {
+        text += &format!("{indent}  - `-` {v}\n");
+    }
+    text
+}
+
+fn markdown_agents(agents: &[AgentPolicyDiffOutput], agent_type: &str) -> String {
+    let mut text = String::new();
+    for agent in agents {
+        text += &format!(
+            "  - `{}` {agent_type} **{}**\n",
+            agent.change.symbol(),
+            agent.name
+        );
+        if let Some(connector_managed) = agent.connector_managed {
+            text += &format!("    - connector-managed: {connector_managed}\n");
+        }
+        text += &markdown_add_remove(
+            "privileges",
+            &AddRemove {
+                add: agent.add.privileges.to_owned(),
+                remove: agent.remove.privileges.to_owned(),
+            },
+            "    ",
+        );
+        text += &markdown_add_remove(
+            "metadata",
+            &AddRemove {
+                add: agent
+                    .add
+                    .metadata
+                    .iter()
+                    .map(|(k, v)| format!("{k}: {v}"))
+                    .collect(),
+                remove: agent
+                    .remove
+                    .metadata
+                    .iter()
+                    .map(|(k, v)| format!("{k}: {v}"))
+                    .collect(),
+            },
+            "    ",
+        );
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str) -> NodeName {
+        NodeName::Group {
+            name: name.to_owned(),
+            origin: ConnectorNamespace("snowflake".to_owned()),
+        }
+    }
+
+    fn test_diffs() -> GlobalDiffs {
+        GlobalDiffs {
+            groups: vec![groups::Diff {
+                group_name: group("ANALYST"),
+                details: groups::diff::DiffDetails::AddGroup {
+                    member_of: BTreeSet::from([group("READER")]),
+                },
+                connector: ConnectorNamespace("snowflake".to_owned()),
+            }],
+            users: vec![],
+            default_policies: vec![],
+            policies: vec![],
+            tags: vec![],
+            protections: vec![],
+            ownership: vec![],
+            user_properties: vec![],
+        }
+    }
+
+    #[test]
+    fn diff_output_serializes_with_stable_schema() -> anyhow::Result<()> {
+        let output = DiffOutput::from(&test_diffs());
+        let value = serde_json::to_value(&output)?;
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "schema_version": "1",
+                "users": [],
+                "groups": [{
+                    "group": "snowflake::ANALYST",
+                    "connector": "snowflake",
+                    "change": "add",
+                    "member_of": {"add": ["snowflake::READER"], "remove": []}
+                }],
+                "policies": [],
+                "default_policies": []
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn diff_output_renders_markdown() {
+        let markdown = DiffOutput::from(&test_diffs()).to_markdown();
+        assert!(markdown.contains("- `+` group **snowflake::ANALYST**\n"));
+        assert!(markdown.contains("  - member of:\n    - `+` snowflake::READER\n"));
+        assert!(markdown.contains("### Users\n\nNo changes found\n"));
+    }
+
+    #[test]
+    fn plan_output_renders_markdown() {
+        let plan = PlanOutput::new(&HashMap::from([(
+            ConnectorNamespace("snowflake".to_owned()),
+            vec!["CREATE ROLE \"ANALYST\";".to_owned()],
+        )]));
+        assert_eq!(
+            plan.to_markdown(),
+            "## Jetty plan\n\n### snowflake\n\n```\nCREATE ROLE \"ANALYST\";\n```\n"
+        );
+    }
+
+    #[test]
+    fn drift_summary_groups_by_connector_and_kind() {
+        let mut diffs = test_diffs();
+        diffs.groups.push(groups::Diff {
+            group_name: NodeName::Group {
+                name: "Viewers".to_owned(),
+                origin: ConnectorNamespace("tableau".to_owned()),
+            },
+            details: groups::diff::DiffDetails::RemoveGroup,
+            connector: ConnectorNamespace("tableau".to_owned()),
+        });
+        let summary = DriftSummary::from(&diffs);
+        assert!(summary.drift);
+        assert_eq!(summary.connectors["snowflake"].group, 1);
+        assert_eq!(summary.connectors["tableau"].group, 1);
+        assert_eq!(summary.connectors["tableau"].total(), 1);
+
+        let empty = DriftSummary::from(&GlobalDiffs {
+            groups: vec![],
+            users: vec![],
+            default_policies: vec![],
+            policies: vec![],
+            tags: vec![],
+            protections: vec![],
+            ownership: vec![],
+            user_properties: vec![],
+        });
+        assert!(!empty.drift);
+        assert_eq!(empty.to_markdown(), "## Jetty drift\n\nNo drift detected\n");
+    }
+
+    #[test]
+    fn tag_changes_are_included() {
+        let snowflake = ConnectorNamespace("snowflake".to_owned());
+        let table = |name: &str| NodeName::Asset {
+            connector: snowflake.to_owned(),
+            asset_type: None,
+            path: crate::access_graph::AssetPath::new(vec!["DB".to_owned(), name.to_owned()]),
+        };
+        let mut diffs = test_diffs();
+        diffs.tags = vec![
+            tags::TagState {
+                tag: NodeName::Tag("pii".to_owned()),
+                value: Some("email".to_owned()),
+                description: None,
+                applied_to: BTreeSet::from([table("USERS")]),
+                current: BTreeMap::from([(table("USERS"), "phone".to_owned())]),
+                connector: snowflake.to_owned(),
+            },
+            tags::TagState {
+                tag: NodeName::Tag("finance".to_owned()),
+                value: None,
+                description: None,
+                applied_to: BTreeSet::from([table("LEDGER")]),
+                current: BTreeMap::from([(table("LEDGER"), "".to_owned())]),
+                connector: snowflake.to_owned(),
+            },
+        ];
+
+        let output = DiffOutput::from(&diffs);
+        assert_eq!(
+            output.tags,
+            vec![TagDiffOutput {
+                tag: "pii".to_owned(),
+                connector: "snowflake".to_owned(),
+                changes: vec![TagChangeOutput {
+                    asset: "snowflake::DB/USERS".to_owned(),
+                    change: ChangeKind::Modify,
+                    value: Some("email".to_owned()),
+                    previous_value: Some("phone".to_owned()),
+                }],
+            }]
+        );
+        assert!(output
+            .to_markdown()
+            .contains("- tag **pii** in snowflake\n  - `~` asset **snowflake::DB/USERS**\n"));
+        assert_eq!(DriftSummary::from(&diffs).connectors["snowflake"].tag, 1);
+    }
+}
@@ -0,0 +1,238 @@
+//! Save plans to disk so that `jetty apply` can execute exactly what was reviewed
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use glob::glob;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    access_graph::translate::diffs::LocalConnectorDiffs, jetty::ConnectorNamespace, project, Jetty,
+};
+
+/// The version of the saved plan format. This should be bumped whenever the format changes
+/// in a way that makes older plans unreadable.
+const SAVED_PLAN_VERSION: &str = "0.0.1";
+
+/// A plan written by `jetty plan --out`. It holds the translated diffs for each connector,
+/// along with fingerprints of the state the plan was generated from.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedPlan {
+    /// The version of the plan format
+    version: String,
+    /// When the plan was created
+    created: OffsetDateTime,
+    /// The last-modified time of the access graph that the plan was generated from
+    graph_last_modified: Option<OffsetDateTime>,
+    /// A fingerprint of the configuration files that the plan was generated from
+    config_fingerprint: Uuid,
+    /// The connector-specific diffs to be applied
+    diffs: BTreeMap<ConnectorNamespace, LocalConnectorDiffs>,
+}
+
+impl SavedPlan {
+    /// Create a new plan from a set of connector-specific diffs. Uses the current state of
+    /// the access graph and configuration files to fingerprint the plan.
+    pub fn new(
+        jetty: &Jetty,
+        diffs: HashMap<ConnectorNamespace, LocalConnectorDiffs>,
+    ) -> Result<Self> {
+        Ok(SavedPlan {
+            version: SAVED_PLAN_VERSION.to_owned(),
+            created: OffsetDateTime::now_utc(),
+            graph_last_modified: jetty.try_access_graph()?.get_last_modified(),
+            config_fingerprint: config_fingerprint()?,
+            diffs: diffs.into_iter().collect(),
+        })
+    }
+
+    /// Write the plan to a file
+    pub fn write_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        if let Some(p) = path.as_ref().parent() {
+            fs::create_dir_all(p)?
+        };
+        let plan = serde_json::to_string_pretty(self).context("serializing plan")?;
+        fs::write(path, plan).context("writing plan file")
+    }
+
+    /// Read a plan from a file
+    pub fn read_from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let plan = fs::read_to_string(&path).context(format!(
+            "unable to read plan file {}",
+            path.as_ref().display()
+        ))?;
+        let plan: SavedPlan = serde_json::from_str(&plan).context(format!(
+            "unable to parse plan file {}",
+            path.as_ref().display()
+        ))?;
+        if plan.version != SAVED_PLAN_VERSION {
+            bail!(
+                "plan file was created with format version {}, but this version of Jetty expects {}; run `jetty plan --out` to generate a new plan",
+                plan.version,
+                SAVED_PLAN_VERSION
+            )
+        }
+        Ok(plan)
+    }
+
+    /// Make sure that neither the environment nor the configuration files have changed since
+    /// the plan was generated, and that every connector in the plan is still configured.
+    pub fn validate(&self, jetty: &Jetty) -> Result<()> {
+        if jetty.try_access_graph()?.get_last_modified() != self.graph_last_modified {
+            bail!(
+                "the environment has been fetched since this plan was created; run `jetty plan --out` to generate a new plan"
+            )
+        }
+        if config_fingerprint()? != self.config_fingerprint {
+            bail!(
+                "the configuration files have changed since this plan was created; run `jetty plan --out` to generate a new plan"
+            )
+        }
+        for connector in self.diffs.keys() {
+            if !jetty.has_connector(connector) {
+                bail!(
+                    "this plan includes changes for {connector}, but that connector isn't configured in {}",
+                    project::jetty_cfg_path_local().display()
+                )
+            }
+        }
+        Ok(())
+    }
+
+    /// The connector-specific diffs included in the plan
+    pub fn diffs(&self) -> &BTreeMap<ConnectorNamespace, LocalConnectorDiffs> {
+        &self.diffs
+    }
+
+    /// When the plan was created
+    pub fn created(&self) -> OffsetDateTime {
+        self.created
+    }
+}
+
+/// Generate a fingerprint of all of the configuration files in the project. Any change to
+/// the contents or the location of a config file will result in a different fingerprint.
+pub fn config_fingerprint() -> Result<Uuid> {
+    let mut paths: Vec<PathBuf> = vec![
+        project::jetty_cfg_path_local(),
+        project::groups_cfg_path_local(),
+        project::tags_cfg_path_local(),
+    ];
+    for root in [
+        project::users_cfg_root_path_local(),
+        project::assets_cfg_root_path_local(),
+    ] {
+        let config_paths = glob(format!("{}/**/*.y*ml", root.to_string_lossy()).as_str())
+            .context("trouble generating config file paths")?;
+        for path in config_paths {
+            paths.push(path?);
+        }
+    }
+    paths.sort();
+
+    let mut contents = Vec::new();
+    for path in paths {
+        if !path.exists() {
+            continue;
+        }
+        contents.extend(path.to_string_lossy().as_bytes());
+        contents.extend(fs::read(&path).context(format!("reading {}", path.display()))?);
+    }
+
+    Ok(Uuid::new_v5(&Uuid::NAMESPACE_URL, &contents))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{access_graph::AccessGraph, jetty::JettyConfig};
+
+    use super::*;
+
+    fn empty_diffs() -> LocalConnectorDiffs {
+        LocalConnectorDiffs {
+            groups: vec![],
+            users: vec![],
+            default_policies: vec![],
+            policies: vec![],
+            tags: vec![],
+            protections: vec![],
+            ownership: vec![],
+            user_properties: vec![],
+        }
+    }
+
+    fn jetty() -> Result<Jetty> {
+        let mut jetty =
+            Jetty::new_with_config(JettyConfig::new(), PathBuf::new(), HashMap::new(), false)?;
+        jetty.access_graph = Some(AccessGraph::new_dummy(&[], &[]));
+        Ok(jetty)
+    }
+
+    #[test]
+    fn plans_round_trip_through_a_file() -> Result<()> {
+        let jetty = jetty()?;
+        let plan = SavedPlan::new(
+            &jetty,
+            HashMap::from([(ConnectorNamespace("snowflake".to_owned()), empty_diffs())]),
+        )?;
+        let path = std::env::temp_dir()
+            .join(format!("jetty_saved_plan_{}", Uuid::new_v4()))
+            .join("plan.json");
+        plan.write_to_file(&path)?;
+        let loaded = SavedPlan::read_from_file(&path)?;
+        fs::remove_dir_all(path.parent().unwrap())?;
+
+        assert_eq!(loaded.created(), plan.created());
+        assert_eq!(loaded.graph_last_modified, plan.graph_last_modified);
+        assert_eq!(loaded.config_fingerprint, plan.config_fingerprint);
+        assert_eq!(
+            loaded.diffs().keys().collect::<Vec<_>>(),
+            vec![&ConnectorNamespace("snowflake".to_owned())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn plans_from_another_format_version_are_rejected() -> Result<()> {
+        let mut plan = SavedPlan::new(&jetty()?, HashMap::new())?;
+        plan.version = "0.0.0".to_owned();
+        let path = std::env::temp_dir().join(format!("jetty_saved_plan_{}.json", Uuid::new_v4()));
+        plan.write_to_file(&path)?;
+        let result = SavedPlan::read_from_file(&path);
+        fs::remove_file(&path)?;
+
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn stale_plans_are_rejected() -> Result<()> {
+        let jetty = jetty()?;
+        let plan = SavedPlan::new(&jetty, HashMap::new())?;
+        plan.validate(&jetty)?;
+
+        // The environment was fetched again
+        let mut stale = SavedPlan::new(&jetty, HashMap::new())?;
+        stale.graph_last_modified = Some(OffsetDateTime::now_utc());
+        assert!(stale.validate(&jetty).is_err());
+
+        // The configuration changed
+        let mut stale = SavedPlan::new(&jetty, HashMap::new())?;
+        stale.config_fingerprint = Uuid::new_v4();
+        assert!(stale.validate(&jetty).is_err());
+
+        // A connector in the plan was removed from the project
+        let stale = SavedPlan::new(
+            &jetty,
+            HashMap::from([(ConnectorNamespace("snowflake".to_owned()), empty_diffs())]),
+        )?;
+        assert!(stale.validate(&jetty).is_err());
+        Ok(())
+    }
+}
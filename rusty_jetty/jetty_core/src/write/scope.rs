@@ -0,0 +1,410 @@
+//! Leaving objects that connectors don't manage out of the diffs. Connectors with filters
+//! don't fetch the objects they exclude, so configured access to them would otherwise look
+//! like it's missing.
+
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+
+use crate::{
+    access_graph::NodeName, connectors::ScopedObject, jetty::ConnectorNamespace, logging::warn,
+    Jetty,
+};
+
+use super::{groups::diff::DiffDetails, GlobalDiffs};
+
+/// Drop the parts of the diffs that involve objects outside of their connector's scope
+pub(crate) fn scope_diffs(jetty: &Jetty, diffs: &mut GlobalDiffs) -> Result<()> {
+    let translator = jetty.try_access_graph()?.translator();
+    retain_in_scope(diffs, |connector, node| {
+        let connector_impl = match jetty.connectors.get(connector) {
+            Some(c) => c,
+            None => return true,
+        };
+        // Objects that can't be translated don't exist in the connector yet, so they can't
+        // have been filtered out
+        match node {
+            NodeName::Asset { .. } => translator
+                .asset_name_to_cual(node)
+                .map(|cual| connector_impl.in_scope(ScopedObject::Asset(&cual)))
+                .unwrap_or(true),
+            NodeName::Group { name, .. } => connector_impl.in_scope(ScopedObject::Group(name)),
+            NodeName::User(_) => translator
+                .try_translate_node_name_to_local(node, connector)
+                .map(|user| connector_impl.in_scope(ScopedObject::User(&user)))
+                .unwrap_or(true),
+            _ => true,
+        }
+    });
+    Ok(())
+}
+
+/// Drop the parts of the diffs that involve objects for which `in_scope` is false, and warn
+/// about them
+fn retain_in_scope(
+    diffs: &mut GlobalDiffs,
+    in_scope: impl Fn(&ConnectorNamespace, &NodeName) -> bool,
+) {
+    let mut skipped = BTreeSet::new();
+    let mut keep = |connector: &ConnectorNamespace, node: &NodeName| {
+        let included = in_scope(connector, node);
+        if !included {
+            skipped.insert(format!("{node} ({connector})"));
+        }
+        included
+    };
+
+    diffs.groups.retain(|g| keep(&g.connector, &g.group_name));
+    for group in &mut diffs.groups {
+        let connector = &group.connector;
+        match &mut group.details {
+            DiffDetails::AddGroup { member_of } => member_of.retain(|r| keep(connector, r)),
+            DiffDetails::RemoveGroup => (),
+            DiffDetails::ModifyGroup {
+                add_member_of,
+                remove_member_of,
+            } => {
+                add_member_of.retain(|r| keep(connector, r));
+                remove_member_of.retain(|r| keep(connector, r));
+            }
+        }
+    }
+    diffs.groups.retain(|g| match &g.details {
+        DiffDetails::ModifyGroup {
+            add_member_of,
+            remove_member_of,
+        } => !add_member_of.is_empty() || !remove_member_of.is_empty(),
+        _ => true,
+    });
+
+    // Memberships are written by the connector the group comes from
+    for user in &mut diffs.users {
+        if let Some(membership) = &mut user.group_membership {
+            for groups in [&mut membership.add, &mut membership.remove] {
+                groups.retain(|g| match g.get_group_origin() {
+                    Ok(connector) => keep(connector, &user.user) && keep(connector, g),
+                    Err(_) => true,
+                });
+            }
+            if membership.add.is_empty() && membership.remove.is_empty() {
+                user.group_membership = None;
+            }
+        }
+    }
+    diffs
+        .users
+        .retain(|u| u.identity.is_some() || u.group_membership.is_some());
+
+    diffs
+        .user_properties
+        .retain(|u| keep(&u.connector, &u.user));
+    for user in &mut diffs.user_properties {
+        if let Some(role) = &user.set.default_role {
+            let role = NodeName::Group {
+                name: role.to_owned(),
+                origin: user.connector.to_owned(),
+            };
+            if !keep(&user.connector, &role) {
+                user.set.default_role = None;
+            }
+        }
+    }
+    diffs.user_properties.retain(|u| !u.set.is_empty());
+
+    diffs.policies.retain(|p| keep(&p.connector, &p.asset));
+    for policy in &mut diffs.policies {
+        let connector = &policy.connector;
+        policy.users.retain(|u, _| keep(connector, u));
+        policy.groups.retain(|g, _| keep(connector, g));
+    }
+    diffs
+        .policies
+        .retain(|p| !p.users.is_empty() || !p.groups.is_empty());
+
+    diffs
+        .default_policies
+        .retain(|p| keep(&p.connector, &p.asset));
+    for policy in &mut diffs.default_policies {
+        let connector = &policy.connector;
+        policy.users.retain(|u, _| keep(connector, u));
+        policy.groups.retain(|g, _| keep(connector, g));
+    }
+    diffs
+        .default_policies
+        .retain(|p| !p.users.is_empty() || !p.groups.is_empty());
+
+    for tag in &mut diffs.tags {
+        let connector = &tag.connector;
+        tag.applied_to.retain(|a| keep(connector, a));
+        tag.current.retain(|a, _| keep(connector, a));
+    }
+
+    diffs.protections.retain(|p| keep(&p.connector, &p.asset));
+    diffs
+        .ownership
+        .retain(|o| keep(&o.connector, &o.asset) && keep(&o.connector, &o.owner));
+
+    if !skipped.is_empty() {
+        warn!(
+            "leaving out changes that involve objects excluded by the connectors' filters: {}",
+            skipped.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+
+    use crate::{
+        access_graph::AssetPath,
+        connectors::user_properties::UserProperties,
+        connectors::{ownership::CurrentGrants, AssetType},
+        write::{
+            assets::{
+                diff::{
+                    default_policies::{DefaultPolicyDiff, DefaultPolicyDiffDetails},
+                    ownership::OwnershipDiff,
+                    policies::{self, PolicyDiff},
+                },
+                DefaultPolicyState, PolicyState,
+            },
+            groups,
+            tags::TagState,
+            users::{
+                diff::{MembershipDiffDetails, UserPropertiesDiff},
+                CombinedUserDiff,
+            },
+        },
+    };
+
+    use super::*;
+
+    fn snowflake() -> ConnectorNamespace {
+        ConnectorNamespace("snowflake".to_owned())
+    }
+
+    fn asset(name: &str) -> NodeName {
+        NodeName::Asset {
+            connector: snowflake(),
+            asset_type: None,
+            path: AssetPath::new(vec!["DB".to_owned(), name.to_owned()]),
+        }
+    }
+
+    fn group(name: &str) -> NodeName {
+        NodeName::Group {
+            name: name.to_owned(),
+            origin: snowflake(),
+        }
+    }
+
+    fn user(name: &str) -> NodeName {
+        NodeName::User(name.to_owned())
+    }
+
+    fn grant() -> policies::DiffDetails {
+        policies::DiffDetails::AddAgent {
+            add: PolicyState::default(),
+        }
+    }
+
+    fn default_grant() -> DefaultPolicyDiffDetails {
+        DefaultPolicyDiffDetails::Add {
+            add: DefaultPolicyState {
+                privileges: Default::default(),
+                metadata: Default::default(),
+                connector_managed: true,
+            },
+        }
+    }
+
+    #[test]
+    fn excluded_objects_are_left_out() {
+        let mut diffs = GlobalDiffs {
+            groups: vec![
+                groups::Diff {
+                    group_name: group("ANALYST"),
+                    details: DiffDetails::AddGroup {
+                        member_of: [group("READER"), group("SCIM_ADMIN")].into(),
+                    },
+                    connector: snowflake(),
+                },
+                groups::Diff {
+                    group_name: group("SCIM_FINANCE"),
+                    details: DiffDetails::RemoveGroup,
+                    connector: snowflake(),
+                },
+            ],
+            users: vec![
+                CombinedUserDiff {
+                    user: user("ellen"),
+                    identity: None,
+                    group_membership: Some(MembershipDiffDetails {
+                        add: [group("ANALYST"), group("SCIM_FINANCE")].into(),
+                        remove: Default::default(),
+                    }),
+                },
+                CombinedUserDiff {
+                    user: user("svc_loader"),
+                    identity: None,
+                    group_membership: Some(MembershipDiffDetails {
+                        add: [group("ANALYST")].into(),
+                        remove: Default::default(),
+                    }),
+                },
+            ],
+            user_properties: vec![
+                UserPropertiesDiff {
+                    user: user("ellen"),
+                    set: UserProperties {
+                        default_role: Some("SCIM_FINANCE".to_owned()),
+                        ..Default::default()
+                    },
+                    current: Default::default(),
+                    connector: snowflake(),
+                },
+                UserPropertiesDiff {
+                    user: user("ellen"),
+                    set: UserProperties {
+                        default_role: Some("SCIM_FINANCE".to_owned()),
+                        default_warehouse: Some("REPORTING".to_owned()),
+                        ..Default::default()
+                    },
+                    current: Default::default(),
+                    connector: snowflake(),
+                },
+                UserPropertiesDiff {
+                    user: user("svc_loader"),
+                    set: UserProperties {
+                        default_warehouse: Some("LOADING".to_owned()),
+                        ..Default::default()
+                    },
+                    current: Default::default(),
+                    connector: snowflake(),
+                },
+            ],
+            policies: vec![
+                PolicyDiff {
+                    asset: asset("RAW"),
+                    users: [(user("ellen"), grant())].into(),
+                    groups: [
+                        (group("ANALYST"), grant()),
+                        (group("SCIM_FINANCE"), grant()),
+                    ]
+                    .into(),
+                    connector: snowflake(),
+                },
+                PolicyDiff {
+                    asset: asset("SCRATCH"),
+                    users: Default::default(),
+                    groups: [(group("ANALYST"), grant())].into(),
+                    connector: snowflake(),
+                },
+            ],
+            default_policies: vec![
+                DefaultPolicyDiff {
+                    asset: asset("RAW"),
+                    path: "/**".to_owned(),
+                    asset_type: AssetType("TABLE".to_owned()),
+                    users: [(user("svc_loader"), default_grant())].into(),
+                    groups: Default::default(),
+                    connector: snowflake(),
+                },
+                DefaultPolicyDiff {
+                    asset: asset("SCRATCH"),
+                    path: "/**".to_owned(),
+                    asset_type: AssetType("TABLE".to_owned()),
+                    users: Default::default(),
+                    groups: [(group("ANALYST"), default_grant())].into(),
+                    connector: snowflake(),
+                },
+            ],
+            tags: vec![TagState {
+                tag: NodeName::Tag("pii".to_owned()),
+                value: None,
+                description: None,
+                applied_to: [asset("RAW"), asset("SCRATCH")].into(),
+                current: BTreeMap::from([(asset("SCRATCH"), "".to_owned())]),
+                connector: snowflake(),
+            }],
+            protections: vec![],
+            ownership: vec![
+                OwnershipDiff {
+                    asset: asset("RAW"),
+                    owner: group("SCIM_FINANCE"),
+                    previous_owner: None,
+                    current_grants: CurrentGrants::Copy,
+                    connector: snowflake(),
+                },
+                OwnershipDiff {
+                    asset: asset("SCRATCH"),
+                    owner: group("ANALYST"),
+                    previous_owner: None,
+                    current_grants: CurrentGrants::Copy,
+                    connector: snowflake(),
+                },
+                OwnershipDiff {
+                    asset: asset("RAW"),
+                    owner: group("ANALYST"),
+                    previous_owner: None,
+                    current_grants: CurrentGrants::Copy,
+                    connector: snowflake(),
+                },
+            ],
+        };
+
+        let excluded = HashSet::from([
+            asset("SCRATCH"),
+            group("SCIM_ADMIN"),
+            group("SCIM_FINANCE"),
+            user("svc_loader"),
+        ]);
+        retain_in_scope(&mut diffs, |_, node| !excluded.contains(node));
+
+        assert_eq!(diffs.groups.len(), 1);
+        assert_eq!(diffs.groups[0].group_name, group("ANALYST"));
+        match &diffs.groups[0].details {
+            DiffDetails::AddGroup { member_of } => {
+                assert_eq!(member_of, &BTreeSet::from([group("READER")]))
+            }
+            d => panic!("unexpected group diff: {d:?}"),
+        }
+
+        assert_eq!(diffs.users.len(), 1);
+        assert_eq!(diffs.users[0].user, user("ellen"));
+        assert_eq!(
+            diffs.users[0].group_membership.as_ref().unwrap().add,
+            BTreeSet::from([group("ANALYST")])
+        );
+
+        // Excluded default roles aren't set, and neither are the properties of excluded users
+        assert_eq!(diffs.user_properties.len(), 1);
+        assert_eq!(diffs.user_properties[0].user, user("ellen"));
+        assert_eq!(
+            diffs.user_properties[0].set,
+            UserProperties {
+                default_warehouse: Some("REPORTING".to_owned()),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(diffs.policies.len(), 1);
+        assert_eq!(diffs.policies[0].asset, asset("RAW"));
+        assert_eq!(
+            diffs.policies[0].groups.keys().collect::<Vec<_>>(),
+            vec![&group("ANALYST")]
+        );
+        assert_eq!(diffs.policies[0].users.len(), 1);
+
+        // The only default policy left on RAW was for an excluded user
+        assert!(diffs.default_policies.is_empty());
+
+        assert_eq!(diffs.tags[0].applied_to, BTreeSet::from([asset("RAW")]));
+        assert!(diffs.tags[0].current.is_empty());
+
+        assert_eq!(diffs.ownership.len(), 1);
+        assert_eq!(diffs.ownership[0].asset, asset("RAW"));
+        assert_eq!(diffs.ownership[0].owner, group("ANALYST"));
+    }
+}
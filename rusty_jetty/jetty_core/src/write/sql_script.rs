@@ -0,0 +1,255 @@
+//! Export planned changes as SQL scripts for `jetty plan --sql-out`, so that they can be
+//! reviewed and run by hand. Each statement is commented with the configuration file and
+//! the diff that it comes from.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    access_graph::translate::diffs::groups,
+    cual::Cual,
+    jetty::ConnectorNamespace,
+    project,
+    write::apply_report::{DiffItem, PlannedOperation},
+    Jetty,
+};
+
+use super::{assets, users};
+
+const SCRIPT_PREFIX: &str = "batch_";
+const SCRIPT_EXTENSION: &str = "sql";
+
+/// The configuration files that planned changes come from
+#[derive(Default, Debug)]
+pub struct ConfigSources {
+    assets: HashMap<Cual, PathBuf>,
+    users: HashMap<ConnectorNamespace, HashMap<String, PathBuf>>,
+}
+
+impl ConfigSources {
+    /// Find the configuration file for every configured asset and user
+    pub fn new(jetty: &Jetty) -> Result<Self> {
+        let tr = jetty.try_access_graph()?.translator();
+        let assets = assets::parser::get_config_files_by_asset(jetty)?
+            .into_iter()
+            .filter_map(|(name, path)| tr.asset_name_to_cual(&name).ok().map(|c| (c, path)))
+            .collect();
+        Ok(Self {
+            assets,
+            users: users::get_config_files_by_local_name()?,
+        })
+    }
+
+    /// The configuration file a connector's diff comes from. Diffs that remove something
+    /// from the configuration may not have one.
+    fn source(&self, connector: &ConnectorNamespace, diff: &DiffItem) -> Option<PathBuf> {
+        match diff {
+            DiffItem::Group(_) => Some(project::groups_cfg_path_local()),
+            DiffItem::User(diff) => self.users.get(connector)?.get(&diff.user).cloned(),
+            DiffItem::Policy(diff) => self.assets.get(&diff.asset).cloned(),
+            DiffItem::DefaultPolicy(diff) => self.assets.get(&diff.asset).cloned(),
+            DiffItem::Tag(_) => Some(project::tags_cfg_path_local()),
+            DiffItem::Protection(diff) => self.assets.get(&diff.asset).cloned(),
+            DiffItem::Ownership(diff) => self.assets.get(&diff.asset).cloned(),
+            DiffItem::UserProperties(diff) => self.users.get(connector)?.get(&diff.user).cloned(),
+        }
+    }
+}
+
+/// Write a connector's planned batches to `<dir>/<connector>/batch_<n>.sql`, replacing the
+/// scripts from any earlier plan. Returns the paths of the new scripts, in the order they
+/// should be run.
+pub fn write_sql_scripts(
+    dir: &Path,
+    connector: &ConnectorNamespace,
+    batches: &[Vec<PlannedOperation>],
+    sources: &ConfigSources,
+) -> Result<Vec<PathBuf>> {
+    clear_sql_scripts(dir, connector)?;
+    let connector_dir = dir.join(connector.to_string());
+    fs::create_dir_all(&connector_dir).context(format!("creating {}", connector_dir.display()))?;
+
+    let mut paths = vec![];
+    for (i, batch) in batches.iter().enumerate() {
+        let path = connector_dir.join(format!("{SCRIPT_PREFIX}{}.{SCRIPT_EXTENSION}", i + 1));
+        let script = render_batch(connector, i + 1, batches.len(), batch, sources);
+        fs::write(&path, script).context(format!("writing {}", path.display()))?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+/// Remove the scripts that an earlier plan wrote for a connector. Stale scripts could
+/// otherwise be run by mistake, including for connectors that no longer have any changes.
+pub fn clear_sql_scripts(dir: &Path, connector: &ConnectorNamespace) -> Result<()> {
+    let connector_dir = dir.join(connector.to_string());
+    if !connector_dir.exists() {
+        return Ok(());
+    }
+    for entry in fs::read_dir(&connector_dir)? {
+        let path = entry?.path();
+        if is_script(&path) {
+            fs::remove_file(&path).context(format!("removing {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn is_script(path: &Path) -> bool {
+    let is_named_like_a_script = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(|name| name.starts_with(SCRIPT_PREFIX))
+        .unwrap_or_default();
+    is_named_like_a_script && path.extension().and_then(|e| e.to_str()) == Some(SCRIPT_EXTENSION)
+}
+
+fn render_batch(
+    connector: &ConnectorNamespace,
+    batch_number: usize,
+    batch_count: usize,
+    operations: &[PlannedOperation],
+    sources: &ConfigSources,
+) -> String {
+    let mut script = format!(
+        "-- Jetty plan for {connector}, batch {batch_number} of {batch_count}\n\
+        -- Run the batches in order. The statements in a batch can run in any order, but each \
+        batch has to finish before the next one starts.\n"
+    );
+    if operations.is_empty() {
+        script.push_str("\n-- No changes in this batch\n");
+    }
+    for operation in operations {
+        let source = match sources.source(connector, &operation.diff) {
+            Some(path) => path.display().to_string(),
+            None => "not in the configuration".to_owned(),
+        };
+        script.push_str(&format!(
+            "\n-- {source}: {}\n{}\n",
+            describe(&operation.diff),
+            operation.request
+        ));
+    }
+    script
+}
+
+/// Describe the change that a diff makes
+fn describe(diff: &DiffItem) -> String {
+    match diff {
+        DiffItem::Group(diff) => match &diff.details {
+            groups::LocalDiffDetails::AddGroup { .. } => format!("add group {}", diff.group_name),
+            groups::LocalDiffDetails::RemoveGroup => format!("remove group {}", diff.group_name),
+            groups::LocalDiffDetails::ModifyGroup { .. } => {
+                format!("change the groups that {} is a member of", diff.group_name)
+            }
+        },
+        DiffItem::User(diff) => {
+            format!("change the groups that user {} is a member of", diff.user)
+        }
+        DiffItem::Policy(diff) => format!("change the policies on {}", diff.asset.uri()),
+        DiffItem::DefaultPolicy(diff) => format!(
+            "change the default policy for {} on {}",
+            diff.path,
+            diff.asset.uri()
+        ),
+        DiffItem::Tag(diff) => format!("configure tag {}", diff.name),
+        DiffItem::Protection(diff) => {
+            format!("change the policies protecting {}", diff.asset.uri())
+        }
+        DiffItem::Ownership(diff) => format!(
+            "transfer ownership of {} to {}",
+            diff.asset.uri(),
+            diff.owner
+        ),
+        DiffItem::UserProperties(diff) => format!("set the properties of user {}", diff.user),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        access_graph::translate::diffs::{ownership, users},
+        connectors::ownership::CurrentGrants,
+        write::apply_report::ApplyTarget,
+    };
+
+    use super::*;
+
+    #[test]
+    fn statements_are_traced_to_their_config_files() {
+        let connector = ConnectorNamespace("snowflake".to_owned());
+        let asset = Cual::new("snowflake://account/DB/RAW/USERS?type=TABLE");
+        let sources = ConfigSources {
+            assets: HashMap::from([(asset.to_owned(), PathBuf::from("assets/DB/RAW/USERS.yaml"))]),
+            users: Default::default(),
+        };
+        let operations = vec![
+            PlannedOperation::new(
+                ApplyTarget::Asset(asset.to_owned()),
+                DiffItem::Ownership(ownership::LocalDiff {
+                    asset,
+                    owner: "LOADER".to_owned(),
+                    previous_owner: None,
+                    current_grants: CurrentGrants::Copy,
+                }),
+                "GRANT OWNERSHIP ON TABLE \"DB\".\"RAW\".\"USERS\" TO ROLE \"LOADER\" COPY CURRENT GRANTS;"
+                    .to_owned(),
+            ),
+            PlannedOperation::new(
+                ApplyTarget::User("ELLIOT".to_owned()),
+                DiffItem::User(users::LocalDiff {
+                    user: "ELLIOT".to_owned(),
+                    group_membership: users::LocalDiffDetails {
+                        add: Default::default(),
+                        remove: Default::default(),
+                    },
+                }),
+                "REVOKE ROLE \"ANALYST\" FROM USER \"ELLIOT\";".to_owned(),
+            ),
+        ];
+
+        let script = render_batch(&connector, 3, 3, &operations, &sources);
+        assert!(script.starts_with("-- Jetty plan for snowflake, batch 3 of 3\n"));
+        assert!(script.contains(
+            "\n-- assets/DB/RAW/USERS.yaml: transfer ownership of \
+            snowflake://account/DB/RAW/USERS?type=TABLE to LOADER\nGRANT OWNERSHIP"
+        ));
+        // The user's file was removed, so there's nothing to point to
+        assert!(script.contains(
+            "\n-- not in the configuration: change the groups that user ELLIOT is a member of\n\
+            REVOKE ROLE"
+        ));
+        assert!(is_script(Path::new("plans/snowflake/batch_2.sql")));
+        assert!(!is_script(Path::new("plans/snowflake/notes.sql")));
+    }
+
+    #[test]
+    fn earlier_scripts_are_cleared() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("jetty_sql_scripts_{}", uuid::Uuid::new_v4()));
+        let connector = ConnectorNamespace("snowflake".to_owned());
+        let connector_dir = dir.join("snowflake");
+        fs::create_dir_all(&connector_dir)?;
+        for name in ["batch_1.sql", "batch_2.sql", "notes.sql"] {
+            fs::write(connector_dir.join(name), "")?;
+        }
+
+        let paths = write_sql_scripts(&dir, &connector, &[vec![]], &Default::default())?;
+        assert_eq!(paths, vec![connector_dir.join("batch_1.sql")]);
+        assert!(!connector_dir.join("batch_2.sql").exists());
+
+        // A connector without changes is left with no scripts at all
+        clear_sql_scripts(&dir, &connector)?;
+        assert!(!connector_dir.join("batch_1.sql").exists());
+        assert!(connector_dir.join("notes.sql").exists());
+        // Connectors that never had scripts are fine too
+        clear_sql_scripts(&dir, &ConnectorNamespace("dbt".to_owned()))?;
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+//! The configured state of tags for connectors that can write tags natively.
+//!
+//! Unlike the other diffs, these don't only describe a change. The connectors that can write
+//! tags keep their own record of the tags they have written, which can be newer than the last
+//! fetch, so each of them gets the configured state of every tag and works out what needs to
+//! change. The tags they reported at the last fetch are kept alongside so that the changes can
+//! be shown in diffs.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::{
+    access_graph::{AssetAttributes, NodeName},
+    connectors::{
+        managed_tags::managed_tags_from_metadata, processed_nodes::ProcessedTag, WriteCapabilities,
+    },
+    jetty::ConnectorNamespace,
+    project, Jetty,
+};
+
+use super::{
+    tag_parser::{parse_tags, tags_to_jetty_node_helpers},
+    SplitByConnector,
+};
+
+/// The configured state of a tag in a single connector
+#[derive(Debug, Clone)]
+pub struct TagState {
+    /// The name of the tag
+    pub(crate) tag: NodeName,
+    /// The configured value of the tag
+    pub(crate) value: Option<String>,
+    /// The configured description of the tag
+    pub(crate) description: Option<String>,
+    /// The connector's assets that the tag is applied to directly. Empty for tags that have
+    /// been removed from the configuration.
+    pub(crate) applied_to: BTreeSet<NodeName>,
+    /// The connector's assets that the tag was applied to as of the last fetch, with its value
+    pub(crate) current: BTreeMap<NodeName, String>,
+    pub(crate) connector: ConnectorNamespace,
+}
+
+impl TagState {
+    /// The assets whose assignment of the tag is changing, as (asset, current value, new
+    /// value). Assets the tag is being removed from have no new value.
+    pub fn changes(&self) -> Vec<(&NodeName, Option<&String>, Option<String>)> {
+        let value = self.value.to_owned().unwrap_or_default();
+        let mut res = vec![];
+        for asset in &self.applied_to {
+            let current = self.current.get(asset);
+            if current != Some(&value) {
+                res.push((asset, current, Some(value.to_owned())));
+            }
+        }
+        for (asset, current) in &self.current {
+            if !self.applied_to.contains(asset) {
+                res.push((asset, Some(current), None));
+            }
+        }
+        res.sort();
+        res
+    }
+}
+
+impl SplitByConnector for TagState {
+    fn split_by_connector(&self) -> HashMap<ConnectorNamespace, Box<Self>> {
+        [(self.connector.to_owned(), Box::new(self.to_owned()))].into()
+    }
+}
+
+impl Display for TagState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = format!(
+            "{}{} ({})\n",
+            "~ tag: ".yellow(),
+            self.tag.to_string().yellow(),
+            self.connector
+        );
+        for (asset, current, new) in self.changes() {
+            text += &format!("  {asset}:\n");
+            if let Some(current) = current {
+                text += &format!("{}", format!("    - {current}\n").red());
+            }
+            if let Some(new) = new {
+                text += &format!("{}", format!("    + {new}\n").green());
+            }
+        }
+
+        write!(f, "{text}")
+    }
+}
+
+/// Get the configured state of every tag for each of the connectors that can write tags,
+/// including the tags they manage that are no longer configured
+pub(crate) fn get_tag_states(jetty: &Jetty) -> Result<Vec<TagState>> {
+    let tag_capable_connectors = get_tag_capable_connectors(jetty);
+    if tag_capable_connectors.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let ag = jetty.try_access_graph()?;
+    // A missing tags file means that no tags are configured, so any managed tags are removed
+    let tags_path = project::tags_cfg_path_local();
+    let tags = if tags_path.exists() {
+        let config = std::fs::read_to_string(&tags_path)
+            .context(format!("reading {}", tags_path.display()))?;
+        tags_to_jetty_node_helpers(parse_tags(&config)?, ag, &config)?
+    } else {
+        vec![]
+    };
+
+    let mut current = HashMap::new();
+    for (name, &idx) in &ag.graph.nodes.assets {
+        if let NodeName::Asset { connector, .. } = name {
+            if tag_capable_connectors.contains(connector) {
+                let attributes: AssetAttributes = ag[idx].to_owned().try_into()?;
+                for (tag, value) in managed_tags_from_metadata(&attributes.metadata) {
+                    current
+                        .entry((connector.to_owned(), tag))
+                        .or_insert_with(BTreeMap::new)
+                        .insert(name.to_owned(), value);
+                }
+            }
+        }
+    }
+
+    Ok(tag_states(&tags, current, &tag_capable_connectors))
+}
+
+/// Combine the configured tags with the tags each connector manages, keyed by connector and
+/// tag name
+fn tag_states(
+    tags: &[ProcessedTag],
+    mut current: HashMap<(ConnectorNamespace, String), BTreeMap<NodeName, String>>,
+    tag_capable_connectors: &HashSet<ConnectorNamespace>,
+) -> Vec<TagState> {
+    let mut res = vec![];
+    for tag in tags {
+        for connector in tag_capable_connectors {
+            res.push(TagState {
+                tag: tag.name.to_owned(),
+                value: tag.value.to_owned(),
+                description: tag.description.to_owned(),
+                applied_to: tag
+                    .applied_to
+                    .iter()
+                    .filter(|a| matches!(a, NodeName::Asset { connector: c, .. } if c == connector))
+                    .cloned()
+                    .collect(),
+                current: current
+                    .remove(&(connector.to_owned(), tag.name.to_string()))
+                    .unwrap_or_default(),
+                connector: connector.to_owned(),
+            });
+        }
+    }
+    // Whatever is left is no longer configured, so it's removed from all of its assets
+    for ((connector, tag), current) in current {
+        res.push(TagState {
+            tag: NodeName::Tag(tag),
+            value: None,
+            description: None,
+            applied_to: Default::default(),
+            current,
+            connector,
+        });
+    }
+    res.sort_by(|a, b| (&a.tag, &a.connector).cmp(&(&b.tag, &b.connector)));
+    res
+}
+
+/// Collect all connectors that can write tags
+fn get_tag_capable_connectors(jetty: &Jetty) -> HashSet<ConnectorNamespace> {
+    jetty
+        .connector_manifests()
+        .into_iter()
+        .filter(|(_, m)| m.capabilities.write.contains(&WriteCapabilities::Tags))
+        .map(|(n, _)| n)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::access_graph::AssetPath;
+
+    use super::*;
+
+    fn snowflake() -> ConnectorNamespace {
+        ConnectorNamespace("snowflake".to_owned())
+    }
+
+    fn table(name: &str) -> NodeName {
+        NodeName::Asset {
+            connector: snowflake(),
+            asset_type: None,
+            path: AssetPath::new(vec!["DB".to_owned(), "RAW".to_owned(), name.to_owned()]),
+        }
+    }
+
+    #[test]
+    fn removed_tags_are_unset_everywhere() {
+        let tags = vec![ProcessedTag {
+            name: NodeName::Tag("pii".to_owned()),
+            value: Some("email".to_owned()),
+            applied_to: HashSet::from([table("USERS"), table("ORDERS")]),
+            ..Default::default()
+        }];
+        let current = HashMap::from([
+            (
+                (snowflake(), "pii".to_owned()),
+                BTreeMap::from([
+                    (table("USERS"), "email".to_owned()),
+                    (table("EVENTS"), "email".to_owned()),
+                ]),
+            ),
+            (
+                (snowflake(), "finance".to_owned()),
+                BTreeMap::from([(table("LEDGER"), "".to_owned())]),
+            ),
+        ]);
+
+        let states = tag_states(&tags, current, &HashSet::from([snowflake()]));
+        assert_eq!(states.len(), 2);
+
+        assert_eq!(states[0].tag, NodeName::Tag("finance".to_owned()));
+        assert!(states[0].applied_to.is_empty());
+        assert_eq!(
+            states[0].changes(),
+            vec![(&table("LEDGER"), Some(&"".to_owned()), None)]
+        );
+
+        assert_eq!(states[1].tag, NodeName::Tag("pii".to_owned()));
+        assert_eq!(
+            states[1].changes(),
+            vec![
+                (&table("EVENTS"), Some(&"email".to_owned()), None),
+                (&table("ORDERS"), None, Some("email".to_owned())),
+            ]
+        );
+    }
+}
@@ -5,15 +5,18 @@ pub mod diff;
 pub mod parser;
 mod update;
 
-use std::collections::{BTreeSet, HashMap};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+};
 
 use anyhow::{Context, Result};
 use glob::glob;
 use serde::{Deserialize, Serialize};
 
-use crate::{jetty::ConnectorNamespace, project};
+use crate::{connectors::user_properties::UserProperties, jetty::ConnectorNamespace, project};
 
-pub use diff::{get_membership_diffs, CombinedUserDiff};
+pub use diff::{get_membership_diffs, get_properties_diffs, CombinedUserDiff};
 pub use parser::get_validated_file_config_map;
 pub(crate) use update::{remove_group_name, remove_user_name, update_group_name, update_user_name};
 
@@ -30,6 +33,10 @@ pub struct UserYaml {
         rename = "member of"
     )]
     member_of: BTreeSet<String>,
+    /// Properties to set on the user, by connector. Properties that aren't listed aren't
+    /// managed.
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    properties: HashMap<ConnectorNamespace, UserProperties>,
 }
 
 impl UpdateConfig for UserYaml {
@@ -62,6 +69,21 @@ impl UpdateConfig for UserYaml {
     }
 }
 
+/// Get the config file for each user, by connector and connector-local name
+pub(crate) fn get_config_files_by_local_name(
+) -> Result<HashMap<ConnectorNamespace, HashMap<String, PathBuf>>> {
+    let configs = parser::read_config_files(get_config_paths()?)?;
+    let mut res: HashMap<ConnectorNamespace, HashMap<String, PathBuf>> = HashMap::new();
+    for (path, config) in configs {
+        for (connector, local_name) in config.identifiers {
+            res.entry(connector)
+                .or_default()
+                .insert(local_name, path.to_owned());
+        }
+    }
+    Ok(res)
+}
+
 /// Get the paths of all asset config files
 pub(crate) fn get_config_paths() -> Result<glob::Paths> {
     // collect the paths to all the config files
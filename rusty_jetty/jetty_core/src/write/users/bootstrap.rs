@@ -74,6 +74,8 @@ fn user_yaml_from_idx(jetty: &Jetty, idx: UserIndex) -> Result<UserYaml> {
             .into_iter()
             .map(|g| g.name(jetty).unwrap().to_string())
             .collect(),
+        // user properties are only managed once they're added to the configuration
+        properties: Default::default(),
     })
 }
 
@@ -2,6 +2,7 @@
 
 mod identity;
 mod membership;
+mod properties;
 
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
@@ -11,19 +12,19 @@ use std::{
 use colored::Colorize;
 pub use identity::{get_identity_diffs, update_graph};
 pub use membership::get_membership_diffs;
+pub use properties::{get_properties_diffs, UserPropertiesDiff};
 
 use crate::{access_graph::NodeName, jetty::ConnectorNamespace, write::SplitByConnector};
 
-use self::{
-    identity::{IdentityDiff, IdentityDiffDetails},
-    membership::{MembershipDiff, MembershipDiffDetails},
-};
+pub(crate) use self::identity::IdentityDiffDetails;
+pub(crate) use self::membership::MembershipDiffDetails;
+use self::{identity::IdentityDiff, membership::MembershipDiff};
 
 /// Complete diffs for users
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct CombinedUserDiff {
     pub(crate) user: NodeName,
-    identity: Option<IdentityDiffDetails>,
+    pub(crate) identity: Option<IdentityDiffDetails>,
     pub(crate) group_membership: Option<MembershipDiffDetails>,
 }
 
@@ -0,0 +1,144 @@
+//! Diff changes to user properties
+
+use std::{collections::HashMap, fmt::Display, path::PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::{
+    access_graph::NodeName,
+    connectors::user_properties::{public_key_fingerprint, UserProperties},
+    jetty::ConnectorNamespace,
+    write::{users::UserYaml, SplitByConnector},
+    Jetty,
+};
+
+/// Changes to a user's properties in a single connector
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UserPropertiesDiff {
+    /// The user with the change
+    pub(crate) user: NodeName,
+    /// The properties to set. Only the properties that are changing are included.
+    pub(crate) set: UserProperties,
+    /// The current values of the properties. RSA public keys are given by their fingerprint.
+    pub(crate) current: UserProperties,
+    pub(crate) connector: ConnectorNamespace,
+}
+
+impl UserPropertiesDiff {
+    /// The changing properties, as (property, current value, new value). RSA public keys are
+    /// given by their fingerprint.
+    pub(crate) fn changes(&self) -> Vec<(&'static str, Option<String>, String)> {
+        let mut res = vec![];
+        let mut push = |property, current: &Option<String>, new: &Option<String>| {
+            if let Some(new) = new {
+                res.push((property, current.to_owned(), new.to_owned()));
+            }
+        };
+        push(
+            "default role",
+            &self.current.default_role,
+            &self.set.default_role,
+        );
+        push(
+            "default warehouse",
+            &self.current.default_warehouse,
+            &self.set.default_warehouse,
+        );
+        push(
+            "default namespace",
+            &self.current.default_namespace,
+            &self.set.default_namespace,
+        );
+        push(
+            "rsa public key",
+            &self.current.rsa_public_key,
+            &self
+                .set
+                .rsa_public_key
+                .as_deref()
+                .and_then(|k| public_key_fingerprint(k).ok()),
+        );
+        push(
+            "disabled",
+            &self.current.disabled.map(|d| d.to_string()),
+            &self.set.disabled.map(|d| d.to_string()),
+        );
+        push(
+            "type",
+            &self.current.user_type.map(|t| t.to_string()),
+            &self.set.user_type.map(|t| t.to_string()),
+        );
+        push(
+            "network policy",
+            &self.current.network_policy,
+            &self.set.network_policy,
+        );
+        push(
+            "session policy",
+            &self.current.session_policy,
+            &self.set.session_policy,
+        );
+        res
+    }
+}
+
+impl SplitByConnector for UserPropertiesDiff {
+    fn split_by_connector(&self) -> HashMap<ConnectorNamespace, Box<Self>> {
+        [(self.connector.to_owned(), Box::new(self.to_owned()))].into()
+    }
+}
+
+impl Display for UserPropertiesDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut text = format!(
+            "{}{} ({})\n",
+            "~ user: ".yellow(),
+            self.user.to_string().yellow(),
+            self.connector
+        );
+        for (property, current, new) in self.changes() {
+            text += &format!("  {property}:\n");
+            if let Some(current) = current {
+                text += &format!("{}", format!("    - {current}\n").red());
+            }
+            text += &format!("{}", format!("    + {new}\n").green());
+        }
+
+        write!(f, "{text}")
+    }
+}
+
+/// Get the changes to user properties. Only the properties in the configuration are
+/// compared.
+pub fn get_properties_diffs(
+    jetty: &Jetty,
+    validated_user_config: &HashMap<PathBuf, UserYaml>,
+) -> Result<Vec<UserPropertiesDiff>> {
+    let ag = jetty.try_access_graph()?;
+    let mut res = vec![];
+    for user in validated_user_config.values() {
+        let name = NodeName::User(user.name.to_owned());
+        for (connector, properties) in &user.properties {
+            let current = match ag.get_user_index_from_name(&name) {
+                Some(idx) => {
+                    UserProperties::from_metadata(&idx.get_attributes(jetty)?.metadata, connector)
+                        .context(format!("reading the {connector} properties of {name}"))?
+                }
+                None => Default::default(),
+            };
+            let set = properties.changes_from(&current)?;
+            if !set.is_empty() {
+                res.push(UserPropertiesDiff {
+                    user: name.to_owned(),
+                    set,
+                    current,
+                    connector: connector.to_owned(),
+                });
+            }
+        }
+    }
+
+    res.sort();
+    Ok(res)
+}
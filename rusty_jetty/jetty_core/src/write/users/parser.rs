@@ -13,8 +13,16 @@ use glob::Paths;
 
 use crate::{
     access_graph::NodeName,
+    connectors::{
+        group_membership::users_can_be_members,
+        user_properties::{public_key_fingerprint, NO_POLICY},
+        WriteCapabilities,
+    },
     jetty::ConnectorNamespace,
-    write::groups::{parser::get_all_group_names, GroupConfig},
+    write::groups::{
+        get_group_capable_connectors, get_group_to_nodename_map, parser::get_all_group_names,
+        GroupConfig,
+    },
     Jetty,
 };
 
@@ -54,6 +62,9 @@ fn validate_config(
     let mut allowed_local_names: HashSet<_> =
         ag.translator().get_all_local_users().into_keys().collect();
     let allowed_group_names = get_all_group_names(validated_group_config);
+    let group_connectors: HashSet<_> = get_group_capable_connectors(jetty).into_keys().collect();
+    let group_nodenames = get_group_to_nodename_map(validated_group_config, &group_connectors);
+    let user_properties_connectors = get_user_properties_capable_connectors(jetty);
     let mut errors = Vec::new();
     let mut jetty_name_map = HashMap::new();
     let mut local_id_map = HashMap::new();
@@ -95,12 +106,56 @@ fn validate_config(
             }
         }
 
+        for (connector, properties) in &config.properties {
+            if !config.identifiers.contains_key(connector) {
+                errors.push(format!(
+                    "invalid properties in {}: the user doesn't have a {connector} identifier",
+                    path.display()
+                ));
+            } else if !user_properties_connectors.contains(connector) {
+                errors.push(format!(
+                    "invalid properties in {}: {connector} can't manage user properties",
+                    path.display()
+                ));
+            }
+            if let Some(Err(e)) = properties
+                .rsa_public_key
+                .as_deref()
+                .map(public_key_fingerprint)
+            {
+                errors.push(format!(
+                    "invalid rsa public key for {connector} in {}: {e}",
+                    path.display()
+                ));
+            }
+            if let Some(policy) = &properties.session_policy {
+                if policy != NO_POLICY && policy.split('.').count() != 3 {
+                    errors.push(format!(
+                        "invalid session policy for {connector} in {}: \"{policy}\" must be a \
+                        fully-qualified name, like DATABASE.SCHEMA.POLICY, or {NO_POLICY}",
+                        path.display()
+                    ));
+                }
+            }
+        }
+
         for group in &config.member_of {
             if !allowed_group_names.contains(group) {
                 errors.push(format!(
                     "invalid group name in {}: group config doesn't specify a group called \"{group}\"", path.display()
                 ));
             }
+            // some groups, like Snowflake database roles, can't have users as members
+            for (connector, node_name) in group_nodenames.get(group).into_iter().flatten() {
+                if let Some(idx) = ag.get_group_index_from_name(node_name) {
+                    if !users_can_be_members(&idx.get_attributes(jetty)?.metadata) {
+                        errors.push(format!(
+                            "invalid group membership in {}: users can't be members of {connector} group \"{group}\"",
+                            path.display()
+                        ));
+                    }
+                }
+            }
         }
     }
 
@@ -115,6 +170,20 @@ fn validate_config(
     Ok(errors)
 }
 
+/// Get the connectors that can set user properties
+fn get_user_properties_capable_connectors(jetty: &Jetty) -> HashSet<ConnectorNamespace> {
+    jetty
+        .connector_manifests()
+        .into_iter()
+        .filter(|(_, m)| {
+            m.capabilities
+                .write
+                .contains(&WriteCapabilities::UserProperties)
+        })
+        .map(|(n, _)| n)
+        .collect()
+}
+
 /// Get a map of nodenames to local ids for each connector
 pub(crate) fn get_nodename_local_id_map(
     configs: &HashMap<PathBuf, UserYaml>,
@@ -1,34 +1,24 @@
-use std::sync::Once;
+use std::fmt::Display;
 
-use anyhow::{bail, Result};
 // Convenience import
-pub(crate) use jetty_core::cual::{Cual, Cualable};
-
-use crate::manifest::node::NamePartable;
-
-static mut CUAL_ACCOUNT_NAME: String = String::new();
-static INIT_CUAL_ACCOUNT_NAME: Once = Once::new();
-
-// Accessing a `static mut` is unsafe much of the time, but if we do so
-// in a synchronized fashion (e.g., write once or read all) then we're
-// good to go!
-//
-// This function will only set the string once, and will
-// otherwise always effectively be a no-op.
-pub(crate) fn set_cual_account_name(account_name: &str) {
-    unsafe {
-        INIT_CUAL_ACCOUNT_NAME.call_once(|| {
-            CUAL_ACCOUNT_NAME = format!("{}.snowflakecomputing.com", account_name.to_lowercase())
-        });
+pub(crate) use jetty_core::cual::Cual;
+
+/// The account-specific host used in the cuals of the Snowflake assets that dbt references.
+/// Each connector instance has its own, so connectors for different accounts can coexist
+/// in a single project.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CualAccount(String);
+
+impl CualAccount {
+    /// Create the cual account from a Snowflake account identifier
+    pub(crate) fn new(account: &str) -> Self {
+        Self(format!("{}.snowflakecomputing.com", account.to_lowercase()))
     }
 }
 
-pub(crate) fn get_cual_account_name<'a>() -> Result<&'a str> {
-    if INIT_CUAL_ACCOUNT_NAME.is_completed() {
-        // CUAL_PREFIX is set by a Once and is safe to use after initialization.
-        unsafe { Ok(&CUAL_ACCOUNT_NAME) }
-    } else {
-        bail!("cual prefix was not yet set")
+impl Display for CualAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
@@ -38,20 +28,20 @@ macro_rules! cual {
     ($db:expr) => {
         Cual::new(&format!("{}://{}", "snowflake", urlencoding::encode(&$db)))
     };
-    ($db:expr, $schema:expr) => {
+    ($account:expr, $db:expr, $schema:expr) => {
         Cual::new(&format!(
             "{}://{}/{}/{}",
             "snowflake",
-            get_cual_account_name().expect("couldn't get CUAL account name"),
+            $account,
             urlencoding::encode(&$db),
             urlencoding::encode(&$schema)
         ))
     };
-    ($db:expr, $schema:expr, $table:expr) => {
+    ($account:expr, $db:expr, $schema:expr, $table:expr) => {
         Cual::new(&format!(
             "{}://{}/{}/{}/{}",
             "snowflake",
-            get_cual_account_name().expect("couldn't get CUAL account name"),
+            $account,
             urlencoding::encode(&$db),
             urlencoding::encode(&$schema),
             urlencoding::encode(&$table)
@@ -61,19 +51,13 @@ macro_rules! cual {
 
 pub(crate) use cual;
 
-impl Cualable for dyn NamePartable {
-    fn cual(&self) -> Cual {
-        self.dbt_cual()
-    }
-}
-
 #[cfg(test)]
 mod test {
     use jetty_core::connectors::AssetType;
 
     use crate::{
         consts::TABLE,
-        manifest::node::{DbtModelNode, DbtSourceNode},
+        manifest::node::{DbtModelNode, DbtSourceNode, NamePartable},
     };
 
     use super::*;
@@ -86,13 +70,12 @@ mod test {
 
     #[test]
     fn proper_model_node_yields_cual() {
-        set_cual_account_name("account");
         let result_cual = (&DbtModelNode {
             name: "db.schema.model".to_owned(),
             materialized_as: AssetType(TABLE.to_owned()),
             ..Default::default()
         } as &dyn NamePartable)
-            .cual();
+            .dbt_cual(&CualAccount::new("account"));
 
         assert_eq!(
             result_cual,
@@ -102,13 +85,13 @@ mod test {
 
     #[test]
     fn no_quoting_config_yields_no_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"db.schema.model"#.to_owned(),
         };
 
         // No quoting
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(
             result_cual,
             Cual::new("snowflake://account.snowflakecomputing.com/DB/SCHEMA/MODEL")
@@ -117,12 +100,12 @@ mod test {
 
     #[test]
     fn db_quoting_config_results_in_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"\"db\".schema.model"#.to_owned(),
         };
         // Just db
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         dbg!(&result_cual);
         assert_eq!(
             result_cual,
@@ -132,12 +115,12 @@ mod test {
 
     #[test]
     fn schema_quoting_config_results_in_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"db.\"schema\".model"#.to_owned(),
         };
         // Just schema
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(
             result_cual,
             Cual::new("snowflake://account.snowflakecomputing.com/DB/schema/MODEL")
@@ -146,12 +129,12 @@ mod test {
 
     #[test]
     fn identifier_quoting_config_results_in_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"db.schema.\"model\""#.to_owned(),
         };
         // Just identifier
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(
             result_cual,
             Cual::new("snowflake://account.snowflakecomputing.com/DB/SCHEMA/model")
@@ -160,12 +143,12 @@ mod test {
 
     #[test]
     fn db_schema_quoting_config_results_in_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"\"db\".\"schema\".model"#.to_owned(),
         };
         // db and schema
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(
             result_cual,
             Cual::new("snowflake://account.snowflakecomputing.com/db/schema/MODEL")
@@ -174,12 +157,12 @@ mod test {
 
     #[test]
     fn db_schema_identifier_quoting_config_results_in_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"\"db\".\"schema\".\"model\""#.to_owned(),
         };
         // db and schema and identifier
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(
             result_cual,
             Cual::new("snowflake://account.snowflakecomputing.com/db/schema/model")
@@ -188,12 +171,12 @@ mod test {
 
     #[test]
     fn db_identifier_quoting_config_results_in_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"\"db\".schema.\"model\""#.to_owned(),
         };
         // db and schema and identifier
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(
             result_cual,
             Cual::new("snowflake://account.snowflakecomputing.com/db/SCHEMA/model")
@@ -202,12 +185,12 @@ mod test {
 
     #[test]
     fn schema_identifier_quoting_config_results_in_quotes() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"db.\"schema\".\"model\""#.to_owned(),
         };
         // db and schema and identifier
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(
             result_cual,
             Cual::new("snowflake://account.snowflakecomputing.com/DB/schema/model")
@@ -218,12 +201,12 @@ mod test {
     #[test]
     #[should_panic]
     fn periods_in_quotes_panics() {
-        set_cual_account_name("account");
         let source_node = DbtSourceNode {
             name: r#"db.\"schema.schema2\".\"model\""#.to_owned(),
         };
         // db and schema and identifier
-        let result_cual = (&source_node as &dyn NamePartable).cual();
+        let result_cual =
+            (&source_node as &dyn NamePartable).dbt_cual(&CualAccount::new("account"));
         assert_eq!(result_cual, Cual::new("snowflake://DB/schema/model"));
     }
 }
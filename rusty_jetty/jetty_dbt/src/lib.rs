@@ -18,15 +18,17 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use cual::set_cual_account_name;
+use cual::CualAccount;
 use jetty_core::{
     access_graph::translate::diffs::LocalConnectorDiffs,
     connectors::{
         self,
         nodes::{ConnectorData, RawAssetReference as JettyAssetReference},
+        registry::ConnectorRegistry,
         ConnectorCapabilities, NewConnector, ReadCapabilities,
     },
     jetty::{ConnectorConfig, ConnectorManifest, CredentialsMap},
+    write::apply_report::ApplyReport,
     Connector,
 };
 
@@ -39,19 +41,28 @@ use manifest::{DbtManifest, DbtProjectManifest};
 /// within dbt
 pub struct DbtConnector {
     manifest: Box<dyn DbtProjectManifest + Send + Sync>,
+    /// The account-specific part of the cuals for the Snowflake assets that dbt references
+    cual_account: CualAccount,
 }
 
 impl DbtConnector {
     /// Enhanced new method to inject a DbtManifest into the connector.
     fn new_with_manifest(
         manifest: impl DbtProjectManifest + Send + Sync + 'static,
+        cual_account: CualAccount,
     ) -> Result<Box<Self>> {
         Ok(Box::new(DbtConnector {
             manifest: Box::new(manifest),
+            cual_account,
         }))
     }
 }
 
+/// Register the dbt connector under the `dbt` connector type
+pub fn register(registry: &mut ConnectorRegistry) {
+    registry.register::<DbtConnector>("dbt");
+}
+
 #[async_trait]
 impl NewConnector for DbtConnector {
     async fn new(
@@ -66,10 +77,12 @@ impl NewConnector for DbtConnector {
         if !credentials.contains_key("snowflake_account") {
             bail!("missing `snowflake_account` dbt configuration (connectors.yaml)");
         }
-        set_cual_account_name(&credentials["snowflake_account"]);
         let manifest = DbtManifest::new(&credentials["project_dir"])
             .context("creating dbt manifest object")?;
-        Self::new_with_manifest(manifest)
+        Self::new_with_manifest(
+            manifest,
+            CualAccount::new(&credentials["snowflake_account"]),
+        )
     }
 }
 
@@ -90,19 +103,18 @@ impl Connector for DbtConnector {
         is_file && valid_json
     }
 
-    async fn get_data(&mut self) -> ConnectorData {
-        self.manifest.init(&None).unwrap();
+    async fn get_data(&mut self) -> Result<ConnectorData> {
+        self.manifest.init(&None)?;
         let all_nodes_as_assets: Vec<JettyAssetReference> = self
             .manifest
-            .get_nodes()
-            .unwrap()
+            .get_nodes()?
             .values()
-            .map(|node| node.to_jetty_asset(&self.manifest))
+            .map(|node| node.to_jetty_asset(&self.manifest, &self.cual_account))
             .collect();
-        ConnectorData {
+        Ok(ConnectorData {
             asset_references: all_nodes_as_assets,
             ..Default::default()
-        }
+        })
     }
 
     fn get_manifest(&self) -> ConnectorManifest {
@@ -119,7 +131,7 @@ impl Connector for DbtConnector {
         todo!()
     }
 
-    async fn apply_changes(&self, _: &LocalConnectorDiffs) -> Result<String> {
+    async fn apply_changes(&self, _: &LocalConnectorDiffs) -> Result<ApplyReport> {
         todo!()
     }
 }
@@ -139,7 +151,7 @@ mod tests {
     #[test]
     fn creating_connector_works() -> Result<()> {
         let manifest_mock = MockDbtProjectManifest::new();
-        DbtConnector::new_with_manifest(manifest_mock)
+        DbtConnector::new_with_manifest(manifest_mock, CualAccount::new("account"))
             .context("creating dbt manifest object in creating_connector_works")?;
         Ok(())
     }
@@ -171,7 +183,6 @@ mod tests {
 
     #[tokio::test]
     async fn get_data_returns_empty() -> Result<()> {
-        set_cual_account_name("account");
         // Create mocked manifest
         let mut manifest_mock = MockDbtProjectManifest::new();
 
@@ -182,15 +193,15 @@ mod tests {
             .returning(|| Ok(HashMap::new()));
 
         let mut connector =
-            DbtConnector::new_with_manifest(manifest_mock).context("creating connector")?;
-        let data = connector.get_data().await;
+            DbtConnector::new_with_manifest(manifest_mock, CualAccount::new("account"))
+                .context("creating connector")?;
+        let data = connector.get_data().await?;
         assert_eq!(data, ConnectorData::default());
         Ok(())
     }
 
     #[tokio::test]
     async fn get_data_returns_valid_dbt_assets() -> Result<()> {
-        set_cual_account_name("account");
         // Create mocked manifest
         let mut manifest_mock = MockDbtProjectManifest::new();
 
@@ -210,9 +221,10 @@ mod tests {
             )]))
         });
         let mut connector =
-            DbtConnector::new_with_manifest(manifest_mock).context("creating connector")?;
+            DbtConnector::new_with_manifest(manifest_mock, CualAccount::new("account"))
+                .context("creating connector")?;
 
-        let data = connector.get_data().await;
+        let data = connector.get_data().await?;
         assert_eq!(
             data,
             ConnectorData {
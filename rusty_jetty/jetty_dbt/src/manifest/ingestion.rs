@@ -5,9 +5,10 @@ use std::{
 };
 
 use anyhow::{bail, Context, Result};
-use jetty_core::cual::{Cual, Cualable};
+use jetty_core::cual::Cual;
 use serde::Deserialize;
 
+use crate::cual::CualAccount;
 use crate::manifest::{
     filtered_asset::should_filter,
     node::{DbtModelNode, DbtNode, DbtSourceNode},
@@ -248,9 +249,9 @@ impl DbtProjectManifest for DbtManifest {
         Ok(self.dependencies.get(node_name).cloned())
     }
 
-    fn cual_for_node(&self, node_name: DbtNodeName) -> Result<Cual> {
+    fn cual_for_node(&self, node_name: DbtNodeName, account: &CualAccount) -> Result<Cual> {
         if let Some(node) = self.nodes.get(&node_name) {
-            Ok((node as &dyn NamePartable).cual())
+            Ok((node as &dyn NamePartable).dbt_cual(account))
         } else {
             bail!("couldn't get node for name {}", node_name);
         }
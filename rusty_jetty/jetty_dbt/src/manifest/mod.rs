@@ -6,6 +6,8 @@ mod to_asset_type;
 use jetty_core::cual::Cual;
 use node::DbtNode;
 
+use crate::cual::CualAccount;
+
 use anyhow::{bail, Result};
 use mockall::automock;
 
@@ -24,7 +26,7 @@ pub(crate) trait DbtProjectManifest {
     /// List all nodes that depend on the given node.
     fn get_dependencies(&self, node_name: &str) -> Result<Option<HashSet<String>>>;
     /// Get the CUAL for a given node name.
-    fn cual_for_node(&self, node_name: DbtNodeName) -> Result<Cual>;
+    fn cual_for_node(&self, node_name: DbtNodeName, account: &CualAccount) -> Result<Cual>;
 }
 
 #[derive(Default)]
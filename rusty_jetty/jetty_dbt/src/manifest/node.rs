@@ -1,14 +1,14 @@
 use serde::Deserialize;
 
+use jetty_core::connectors::nodes::RawAssetReference as JettyAssetReference;
 use jetty_core::connectors::AssetType;
 use jetty_core::cual::Cual;
-use jetty_core::{connectors::nodes::RawAssetReference as JettyAssetReference, cual::Cualable};
 
 use std::collections::{HashMap, HashSet};
 
 use super::DbtProjectManifest;
 
-use crate::cual::{cual, get_cual_account_name};
+use crate::cual::{cual, CualAccount};
 
 pub(crate) trait NamePartable {
     // Get the relation name for the object.
@@ -33,12 +33,12 @@ pub(crate) trait NamePartable {
     }
 
     // Get the cual based on the parts of the relational name.
-    fn dbt_cual(&self) -> Cual {
+    fn dbt_cual(&self, account: &CualAccount) -> Cual {
         let name_parts = self.name_parts();
         match name_parts.len() {
             1 => cual!(name_parts[0]),
-            2 => cual!(name_parts[0], name_parts[1]),
-            3 => cual!(name_parts[0], name_parts[1], name_parts[2]),
+            2 => cual!(account, name_parts[0], name_parts[1]),
+            3 => cual!(account, name_parts[0], name_parts[1], name_parts[2]),
             num => panic!("{num} name parts is too many for a dbt CUAL"),
         }
     }
@@ -80,6 +80,7 @@ impl DbtNode {
     pub(crate) fn to_jetty_asset(
         &self,
         manifest: &Box<dyn DbtProjectManifest + Send + Sync>,
+        account: &CualAccount,
     ) -> JettyAssetReference {
         match self {
             Self::ModelNode(m_node) => {
@@ -89,10 +90,15 @@ impl DbtNode {
                     .unwrap_or_default();
                 let dependency_cuals = node_dependencies
                     .iter()
-                    .map(|dep_name| manifest.cual_for_node(dep_name.to_owned()).unwrap().uri())
+                    .map(|dep_name| {
+                        manifest
+                            .cual_for_node(dep_name.to_owned(), account)
+                            .unwrap()
+                            .uri()
+                    })
                     .collect();
                 JettyAssetReference::new(
-                    (m_node as &dyn NamePartable).cual(),
+                    (m_node as &dyn NamePartable).dbt_cual(account),
                     m_node.get_metadata(),
                     // No policies in dbt.
                     HashSet::new(),
@@ -115,10 +121,15 @@ impl DbtNode {
                     .unwrap_or_default();
                 let dependency_cuals = node_dependencies
                     .iter()
-                    .map(|dep_name| manifest.cual_for_node(dep_name.to_owned()).unwrap().uri())
+                    .map(|dep_name| {
+                        manifest
+                            .cual_for_node(dep_name.to_owned(), account)
+                            .unwrap()
+                            .uri()
+                    })
                     .collect();
                 JettyAssetReference::new(
-                    (s_node as &dyn NamePartable).cual(),
+                    (s_node as &dyn NamePartable).dbt_cual(account),
                     HashMap::new(),
                     // No policies in dbt.
                     HashSet::new(),
@@ -11,7 +11,7 @@ use jetty_core::{
         graph::typed_indices::{AssetIndex, UserIndex},
         EdgeType, JettyNode,
     },
-    connectors::nodes::PermissionMode,
+    connectors::{nodes::PermissionMode, protections::AssetProtections},
 };
 use serde::Serialize;
 use uuid::Uuid;
@@ -40,6 +40,7 @@ pub(super) fn router() -> Router {
         .route("/:node_id/users", get(direct_users_handler))
         .route("/:node_id/all_users", get(users_incl_downstream_handler))
         .route("/:node_id/tags", get(tags_handler))
+        .route("/:node_id/protections", get(protections_handler))
 }
 
 #[derive(Serialize)]
@@ -123,6 +124,24 @@ async fn tags_handler(
     })
 }
 
+/// Return the policies that protect the asset
+async fn protections_handler(
+    // node_id is the cual for an asset
+    Path(node_id): Path<Uuid>,
+    Extension(ag): Extension<Arc<access_graph::AccessGraph>>,
+) -> Json<AssetProtections> {
+    // convert the node_id to an AssetIndex
+    let asset_index = ag.get_asset_index_from_id(&node_id).unwrap();
+
+    let protections = match &ag[asset_index] {
+        JettyNode::Asset(attributes) => AssetProtections::from_metadata(&attributes.metadata)
+            .context("reading asset protections")
+            .unwrap(),
+        _ => panic!("expected an asset node"),
+    };
+    Json(protections)
+}
+
 /// Return users that have direct access to the asset, including their levels of privilege and privilege explanation
 async fn direct_users_handler(
     // node_id is the cual for an asset
@@ -1,7 +1,12 @@
 use std::sync::Arc;
 
+use anyhow::Context;
 use axum::{routing::get, Extension, Json, Router};
-use jetty_core::access_graph::AccessGraph;
+use jetty_core::{
+    access_graph::{AccessGraph, JettyNode},
+    connectors::user_properties::UserProperties,
+};
+use serde::Serialize;
 
 use crate::node_summaries::NodeSummary;
 
@@ -13,6 +18,7 @@ pub(super) fn router() -> Router {
         .route("/assets", get(get_assets))
         .route("/groups", get(get_groups))
         .route("/tags", get(get_tags))
+        .route("/user_policies", get(get_user_policies))
 }
 
 /// Return all nodes in the graph
@@ -85,6 +91,45 @@ async fn get_tags(Extension(ag): Extension<Arc<AccessGraph>>) -> Json<Vec<NodeSu
     Json(nodes)
 }
 
+/// The network and session policies on a user in a single connector
+#[derive(Serialize)]
+struct UserPoliciesResponse {
+    node: NodeSummary,
+    connector: String,
+    network_policy: Option<String>,
+    session_policy: Option<String>,
+}
+
+/// Return the network and session policies on every user, including the users without any,
+/// for connectors that report user properties
+async fn get_user_policies(
+    Extension(ag): Extension<Arc<AccessGraph>>,
+) -> Json<Vec<UserPoliciesResponse>> {
+    let mut res = vec![];
+    for (_, node) in ag.get_nodes() {
+        if let JettyNode::User(attributes) = node {
+            for connector in &attributes.connectors {
+                let properties = UserProperties::from_metadata(&attributes.metadata, connector)
+                    .context("reading user properties")
+                    .unwrap();
+                // Connectors that don't manage user properties don't have policies either
+                if properties.is_empty() {
+                    continue;
+                }
+                res.push(UserPoliciesResponse {
+                    node: node.to_owned().into(),
+                    connector: connector.to_string(),
+                    network_policy: properties.network_policy,
+                    session_policy: properties.session_policy,
+                });
+            }
+        }
+    }
+    res.sort_by_key(|r| (r.node.get_name(), r.connector.to_owned()));
+
+    Json(res)
+}
+
 /// Pull all the nodes out of the graph and convert them in to a format that
 /// explore can use.
 fn get_all_nodes(ag: Arc<AccessGraph>) -> Vec<NodeSummary> {
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 use anyhow::Context;
 use axum::{extract::Path, routing::get, Extension, Json, Router};
@@ -9,7 +9,10 @@ use crate::{
     SummaryWithAssociatedSummaries,
 };
 
-use jetty_core::access_graph::{self, EdgeType, JettyNode};
+use jetty_core::{
+    access_graph::{self, EdgeType, JettyNode},
+    connectors::user_properties::UserProperties,
+};
 
 /// Return a router to handle all user-related requests
 pub(super) fn router() -> Router {
@@ -21,6 +24,7 @@ pub(super) fn router() -> Router {
             "/:user_name/inherited_groups",
             get(inherited_groups_handler),
         )
+        .route("/:user_name/properties", get(properties_handler))
 }
 
 /// Return information about a user's access to assets, including privilege and explanation
@@ -158,3 +162,31 @@ async fn inherited_groups_handler(
         .collect::<Vec<_>>();
     Json(group_attributes)
 }
+
+/// Return the user's properties, including their network and session policies, by connector.
+/// Connectors that don't report any properties are left out.
+async fn properties_handler(
+    Path(node_id): Path<Uuid>,
+    Extension(ag): Extension<Arc<access_graph::AccessGraph>>,
+) -> Json<BTreeMap<String, UserProperties>> {
+    let user_index = ag
+        .get_user_index_from_id(&node_id)
+        .context("fetching user node")
+        .unwrap();
+
+    let properties = match &ag[user_index] {
+        JettyNode::User(attributes) => attributes
+            .connectors
+            .iter()
+            .map(|connector| {
+                UserProperties::from_metadata(&attributes.metadata, connector)
+                    .context("reading user properties")
+                    .map(|p| (connector.to_string(), p))
+            })
+            .filter(|p| !matches!(p, Ok((_, p)) if p.is_empty()))
+            .collect::<anyhow::Result<_>>()
+            .unwrap(),
+        _ => panic!("expected a user node"),
+    };
+    Json(properties)
+}
@@ -7,12 +7,65 @@ pub const USER_AGENT_HEADER: &str = "User-Agent";
 /// Valid asset types for Snowflake.
 ///
 /// Ignored types here:
-/// ACCOUNT, FUNCTION, WAREHOUSE: These are TODOs for a future iteration.
 /// ROLE: We don't need children groups. Those relationships will be taken care of
 /// as parent roles.
-pub const ASSET_TYPES: [&str; 4] = ["TABLE", "VIEW", "SCHEMA", "DATABASE"];
+pub const ASSET_TYPES: [&str; 13] = [
+    "ACCOUNT",
+    "TABLE",
+    "VIEW",
+    "SCHEMA",
+    "DATABASE",
+    "WAREHOUSE",
+    "FUNCTION",
+    "PROCEDURE",
+    "STAGE",
+    "STREAM",
+    "TASK",
+    "MATERIALIZED_VIEW",
+    "EXTERNAL_TABLE",
+];
 
+/// Asset types that live directly in a schema. Using any of them requires USAGE on the
+/// schema and its database.
+pub const SCHEMA_OBJECT_TYPES: [&str; 9] = [
+    "TABLE",
+    "VIEW",
+    "FUNCTION",
+    "PROCEDURE",
+    "STAGE",
+    "STREAM",
+    "TASK",
+    "MATERIALIZED_VIEW",
+    "EXTERNAL_TABLE",
+];
+
+/// The account itself, which holds account-level privileges like CREATE DATABASE
+pub const ACCOUNT: &str = "ACCOUNT";
+/// The name of the account asset in Jetty
+pub const ACCOUNT_ASSET_NAME: &str = "ACCOUNT";
 pub const DATABASE: &str = "DATABASE";
 pub const SCHEMA: &str = "SCHEMA";
 pub const VIEW: &str = "VIEW";
 pub const TABLE: &str = "TABLE";
+pub const WAREHOUSE: &str = "WAREHOUSE";
+pub const FUNCTION: &str = "FUNCTION";
+pub const PROCEDURE: &str = "PROCEDURE";
+pub const STAGE: &str = "STAGE";
+pub const STREAM: &str = "STREAM";
+pub const TASK: &str = "TASK";
+/// Multi-word object types use underscores in Jetty. See [`crate::sql_object_type`].
+pub const MATERIALIZED_VIEW: &str = "MATERIALIZED_VIEW";
+pub const EXTERNAL_TABLE: &str = "EXTERNAL_TABLE";
+
+/// The role that every user and role in an account holds implicitly.
+pub const PUBLIC_ROLE: &str = "PUBLIC";
+
+/// The asset metadata key that marks a managed-access schema
+pub const MANAGED_ACCESS_METADATA_KEY: &str = "managed_access";
+/// The account privilege that allows a role to grant privileges on any object, including
+/// the objects in managed-access schemas
+pub const MANAGE_GRANTS: &str = "MANAGE GRANTS";
+
+/// The built-in roles that administer the account. Changes to them are only applied when
+/// `allow_system_role_changes` is set in the connector config.
+pub const SYSTEM_ROLES: [&str; 3] = ["ACCOUNTADMIN", "SECURITYADMIN", "SYSADMIN"];
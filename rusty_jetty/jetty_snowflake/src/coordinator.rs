@@ -1,9 +1,9 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::sync::Mutex;
 
-use anyhow::Context;
 use futures::future::join_all;
 use futures::future::BoxFuture;
 use futures::StreamExt;
@@ -12,25 +12,41 @@ use jetty_core::connectors::nodes;
 use jetty_core::connectors::AssetType;
 use jetty_core::connectors::UserIdentifier;
 
+use jetty_core::connectors::group_membership::no_user_members_metadata;
 use jetty_core::connectors::nodes::ConnectorData;
 use jetty_core::connectors::nodes::RawPolicy;
 use jetty_core::connectors::nodes::RawPolicyGrantee;
-use jetty_core::cual::Cualable;
+use jetty_core::connectors::ownership::owner_metadata;
+use jetty_core::connectors::ownership::OWNERSHIP_PRIVILEGE;
+use jetty_core::connectors::protections::AssetProtections;
+use jetty_core::connectors::protections::RowAccessPolicy;
+use jetty_core::connectors::user_properties::UserProperties;
 use jetty_core::logging::debug;
 use jetty_core::logging::error;
 use jetty_core::print_runtime;
 
-use super::cual::{self, cual, get_cual_account_name, Cual};
+use super::cual::{account_cual, cual, Cual, CualAccount};
+use crate::consts::ACCOUNT;
+use crate::consts::ACCOUNT_ASSET_NAME;
 use crate::consts::DATABASE;
+use crate::consts::MANAGED_ACCESS_METADATA_KEY;
+use crate::consts::MANAGE_GRANTS;
 use crate::consts::SCHEMA;
-use crate::consts::TABLE;
-use crate::consts::VIEW;
+use crate::consts::WAREHOUSE;
 use crate::entry_types;
 use crate::entry_types::ObjectKind;
 use crate::entry_types::RoleName;
+use crate::write::ManagedTags;
+use crate::write::RestrictedSchemas;
+use crate::write::RoleKind;
+use crate::write::RoleKinds;
+use crate::FetchStrategy;
 use crate::FutureGrant;
 use crate::Grant;
 use crate::GrantType;
+use effective_permissions::get_effective_permissions;
+
+mod effective_permissions;
 
 /// Number of metadata request to run currently (e.g. permissions).
 /// ~20 seems to give the best performance. In some circumstances, we may want to bump this up.
@@ -43,11 +59,19 @@ pub(crate) struct Environment {
     pub(crate) databases: Vec<entry_types::Database>,
     pub(crate) schemas: Vec<entry_types::Schema>,
     pub(crate) objects: Vec<entry_types::Object>,
+    pub(crate) warehouses: Vec<entry_types::Warehouse>,
+    pub(crate) routines: Vec<entry_types::Routine>,
     pub(crate) users: Vec<entry_types::User>,
+    /// The fingerprints of users' RSA public keys, by user name
+    pub(crate) user_public_key_fingerprints: HashMap<String, String>,
     pub(crate) roles: Vec<entry_types::Role>,
+    pub(crate) database_roles: Vec<entry_types::DatabaseRole>,
     pub(crate) standard_grants: Vec<entry_types::StandardGrant>,
     pub(crate) future_grants: Vec<entry_types::FutureGrant>,
     pub(crate) role_grants: Vec<entry_types::GrantOf>,
+    pub(crate) tag_references: Vec<entry_types::TagReference>,
+    pub(crate) policy_references: Vec<entry_types::PolicyReference>,
+    pub(crate) object_dependencies: Vec<entry_types::ObjectDependency>,
 }
 
 // Now lets start filling up the environment
@@ -73,7 +97,7 @@ impl<'a> Coordinator<'a> {
         }
     }
 
-    pub(super) async fn get_data(&mut self) -> nodes::ConnectorData {
+    pub(super) async fn get_data(&mut self) -> Result<nodes::ConnectorData> {
         // // Run in one group
         // Get all databases
         // Get all the schemas
@@ -85,6 +109,16 @@ impl<'a> Coordinator<'a> {
             Box::pin(self.conn.get_schemas_future(&mut self.env.schemas)),
             Box::pin(self.conn.get_users_future(&mut self.env.users)),
             Box::pin(self.conn.get_roles_future(&mut self.env.roles)),
+            Box::pin(self.conn.get_warehouses_future(&mut self.env.warehouses)),
+            Box::pin(self.conn.get_routines_future(&mut self.env.routines)),
+            Box::pin(
+                self.conn
+                    .get_tag_references_future(&mut self.env.tag_references),
+            ),
+            Box::pin(
+                self.conn
+                    .get_policy_references_future(&mut self.env.policy_references),
+            ),
         ];
 
         let results = join_all(hold).await;
@@ -97,13 +131,29 @@ impl<'a> Coordinator<'a> {
         // try one object:
         let mut hold: Vec<BoxFuture<_>> = vec![];
 
-        // for each schema, get objects
+        // for each schema, get objects, or get them all at once from ACCOUNT_USAGE
         let objects_mutex = Arc::new(Mutex::new(&mut self.env.objects));
-        for schema in &self.env.schemas {
-            let m = Arc::clone(&objects_mutex);
-            hold.push(Box::pin(self.conn.get_objects_futures(schema, m)));
+        match self.conn.config.fetch_strategy {
+            FetchStrategy::Show => {
+                for schema in &self.env.schemas {
+                    let m = Arc::clone(&objects_mutex);
+                    hold.push(Box::pin(self.conn.get_objects_futures(schema, m)));
+                }
+            }
+            FetchStrategy::AccountUsage => {
+                hold.push(Box::pin(self.conn.get_account_usage_objects_future(
+                    &self.env.schemas,
+                    Arc::clone(&objects_mutex),
+                )))
+            }
         }
 
+        // Get the stages, streams, tasks, etc. that SHOW OBJECTS doesn't include
+        hold.push(Box::pin(
+            self.conn
+                .get_account_objects_future(Arc::clone(&objects_mutex)),
+        ));
+
         // Get all the object grants
         let grants_to_role_mutex = Arc::new(Mutex::new(&mut self.env.standard_grants));
         let grants_to_role_mutex_clone = Arc::clone(&grants_to_role_mutex);
@@ -112,13 +162,23 @@ impl<'a> Coordinator<'a> {
                 .get_privilege_grants_future(grants_to_role_mutex_clone),
         ));
 
-        // for each role, get grants of
+        // for each role, get grants of, or get them all at once from ACCOUNT_USAGE
         let target_arc = Arc::new(Mutex::new(&mut self.env.role_grants));
-        for role in &self.env.roles {
-            let m = Arc::clone(&target_arc);
-            hold.push(Box::pin(self.conn.get_grants_of_role_future(role, m)));
+        match self.conn.config.fetch_strategy {
+            FetchStrategy::Show => {
+                for role in &self.env.roles {
+                    let m = Arc::clone(&target_arc);
+                    hold.push(Box::pin(self.conn.get_grants_of_role_future(role, m)));
+                }
+            }
+            FetchStrategy::AccountUsage => hold
+                .push(Box::pin(self.conn.get_account_usage_role_grants_future(
+                    Arc::clone(&target_arc),
+                ))),
         }
 
+        // Future grants aren't in ACCOUNT_USAGE, so they're always fetched with SHOW
+
         // for each schema, get future grants
         let future_grants_arc = Arc::new(Mutex::new(&mut self.env.future_grants));
         for schema in &self.env.schemas {
@@ -136,6 +196,59 @@ impl<'a> Coordinator<'a> {
             ));
         }
 
+        // Get the lineage between objects
+        let lineage_arc = Arc::new(Mutex::new(&mut self.env.object_dependencies));
+        hold.push(Box::pin(
+            self.conn
+                .get_object_dependencies_future(Arc::clone(&lineage_arc)),
+        ));
+        if let Some(days) = self.conn.config.access_history_lineage_days {
+            hold.push(Box::pin(self.conn.get_access_history_lineage_future(
+                days,
+                Arc::clone(&lineage_arc),
+            )));
+        }
+
+        // for each user with an RSA public key, get the key's fingerprint
+        let fingerprints_arc = Arc::new(Mutex::new(&mut self.env.user_public_key_fingerprints));
+        for user in self.env.users.iter().filter(|u| u.has_rsa_public_key) {
+            let m = Arc::clone(&fingerprints_arc);
+            hold.push(Box::pin(
+                self.conn.get_user_public_key_fingerprint_future(user, m),
+            ));
+        }
+
+        // for each database, get database roles
+        let database_roles_arc = Arc::new(Mutex::new(&mut self.env.database_roles));
+        for database in &self.env.databases {
+            let m = Arc::clone(&database_roles_arc);
+            hold.push(Box::pin(self.conn.get_database_roles_future(database, m)));
+        }
+
+        let results = futures::stream::iter(hold)
+            .buffer_unordered(CONCURRENT_METADATA_FETCHES)
+            .collect::<Vec<_>>()
+            .await;
+
+        for res in results {
+            if let Err(e) = res {
+                error!("{}", e)
+            }
+        }
+
+        // Once the database roles are known, get their grants
+        let mut hold: Vec<BoxFuture<_>> = vec![];
+        for role in &self.env.database_roles {
+            hold.push(Box::pin(self.conn.get_grants_of_database_role_future(
+                role,
+                Arc::clone(&target_arc),
+            )));
+            hold.push(Box::pin(self.conn.get_grants_to_database_role_future(
+                role,
+                Arc::clone(&grants_to_role_mutex),
+            )));
+        }
+
         let results = futures::stream::iter(hold)
             .buffer_unordered(CONCURRENT_METADATA_FETCHES)
             .collect::<Vec<_>>()
@@ -147,6 +260,8 @@ impl<'a> Coordinator<'a> {
             }
         }
 
+        dedupe_objects(&mut self.env.objects);
+        self.filter_grants();
         self.role_grants = self.build_role_grants();
 
         let mut connector_data = nodes::ConnectorData {
@@ -164,20 +279,34 @@ impl<'a> Coordinator<'a> {
             ),
             effective_permissions: print_runtime!(
                 "getting jetty effective_permissions",
-                // self.get_effective_permissions()
-                Default::default()
+                get_effective_permissions(&self.env, &self.role_grants, &self.conn.cual_account)
             ),
             asset_references: print_runtime!("getting jetty asset_references", Default::default()),
-            cual_prefix: Some(
-                cual::get_cual_prefix()
-                    .context("cual account not yet set")
-                    .unwrap(),
-            ),
+            cual_prefix: Some(self.conn.cual_account.prefix()),
         };
         // Add policies to overwrite the default, when necessary.
         add_non_default_policies(&mut connector_data);
 
-        connector_data
+        Ok(connector_data)
+    }
+
+    /// Drop the grants that involve roles or users that the connector's filters exclude.
+    /// The roles and users themselves are filtered as they're fetched.
+    fn filter_grants(&mut self) {
+        let conn = self.conn;
+        self.env
+            .standard_grants
+            .retain(|g| conn.include_role(g.role_name()));
+        self.env
+            .future_grants
+            .retain(|g| conn.include_role(g.role_name()));
+        self.env.role_grants.retain(|g| {
+            conn.include_role(&g.role.0)
+                && match g.granted_to.as_str() {
+                    "USER" => conn.include_user(&g.grantee_name),
+                    _ => conn.include_role(&g.grantee_name),
+                }
+        });
     }
 
     /// Get the role grants into a nicer format
@@ -185,7 +314,8 @@ impl<'a> Coordinator<'a> {
         let mut res: HashMap<Grantee, HashSet<RoleName>> = HashMap::new();
         for grant in &self.env.role_grants {
             let key = match &grant.granted_to[..] {
-                "ROLE" => Grantee::Role(grant.grantee_name.to_owned()),
+                // Database role grantees are already namespaced by their database
+                "ROLE" | "DATABASE_ROLE" => Grantee::Role(grant.grantee_name.to_owned()),
                 "USER" => Grantee::User(grant.grantee_name.to_owned()),
                 other => {
                     debug!("skipping unexpected role type: {}", other);
@@ -250,11 +380,23 @@ impl<'a> Coordinator<'a> {
     /// Get groups from environment
     fn get_jetty_groups(&self) -> Vec<nodes::RawGroup> {
         let mut res = vec![];
-        for role in &self.env.roles {
-            let RoleName(role_name) = &role.name;
+        // Snowflake doesn't allow database roles to be granted to users
+        let database_roles = self
+            .env
+            .database_roles
+            .iter()
+            .map(|r| (r.role_name(), HashMap::from([no_user_members_metadata()])));
+        for (role_name, metadata) in self
+            .env
+            .roles
+            .iter()
+            .map(|r| (r.name.to_owned(), HashMap::new()))
+            .chain(database_roles)
+        {
+            let RoleName(role_name) = &role_name;
             res.push(nodes::RawGroup::new(
                 role_name.to_owned(),
-                HashMap::new(),
+                metadata,
                 self.get_role_grant_names(&Grantee::Role(role_name.to_owned())),
                 HashSet::new(),
                 HashSet::new(),
@@ -267,6 +409,7 @@ impl<'a> Coordinator<'a> {
     /// Get users from environment
     fn get_jetty_users(&self) -> Vec<nodes::RawUser> {
         let mut res = vec![];
+        let user_policies = self.get_user_policies();
         for user in &self.env.users {
             // only add user identifiers if they are not blank
             let mut identifiers = HashSet::new();
@@ -295,7 +438,7 @@ impl<'a> Coordinator<'a> {
             res.push(nodes::RawUser::new(
                 user.name.to_owned(),
                 identifiers,
-                HashMap::new(),
+                self.get_user_properties(user, &user_policies).to_metadata(),
                 self.get_role_grant_names(&Grantee::User(user.name.to_owned())),
                 HashSet::new(),
             ))
@@ -303,26 +446,134 @@ impl<'a> Coordinator<'a> {
         res
     }
 
+    /// Get the properties of a user that Jetty manages
+    fn get_user_properties(
+        &self,
+        user: &entry_types::User,
+        user_policies: &HashMap<(&str, &str), String>,
+    ) -> UserProperties {
+        let non_empty = |value: &String| (!value.is_empty()).then(|| value.to_owned());
+        UserProperties {
+            default_role: non_empty(&user.default_role),
+            default_warehouse: non_empty(&user.default_warehouse),
+            default_namespace: non_empty(&user.default_namespace),
+            rsa_public_key: self
+                .env
+                .user_public_key_fingerprints
+                .get(&user.name)
+                .cloned(),
+            disabled: Some(user.disabled),
+            user_type: user.user_type.parse().ok(),
+            network_policy: user_policies
+                .get(&(user.name.as_str(), entry_types::NETWORK_POLICY))
+                .cloned(),
+            session_policy: user_policies
+                .get(&(user.name.as_str(), entry_types::SESSION_POLICY))
+                .cloned(),
+        }
+    }
+
+    /// Collect the network and session policies on each user, keyed by (user, policy kind)
+    fn get_user_policies(&self) -> HashMap<(&str, &str), String> {
+        self.env
+            .policy_references
+            .iter()
+            .filter(|r| r.ref_entity_domain == entry_types::USER_DOMAIN)
+            .map(|r| {
+                (
+                    (r.ref_entity_name.as_str(), r.policy_kind.as_str()),
+                    r.policy_fqn(),
+                )
+            })
+            .collect()
+    }
+
+    /// Get the network and session policies set on the account
+    fn get_account_protections(&self) -> AssetProtections {
+        let mut res = AssetProtections::default();
+        for reference in &self.env.policy_references {
+            if reference.ref_entity_domain != entry_types::ACCOUNT_DOMAIN {
+                continue;
+            }
+            match reference.policy_kind.as_str() {
+                entry_types::NETWORK_POLICY => res.network_policy = Some(reference.policy_fqn()),
+                entry_types::SESSION_POLICY => res.session_policy = Some(reference.policy_fqn()),
+                kind => debug!("skipping unknown account policy kind {kind}"),
+            }
+        }
+        res
+    }
+
     /// get assets from environment
     fn get_jetty_assets(&self) -> Vec<nodes::RawAsset> {
         let mut res = vec![];
+        let protections = self.get_object_protections();
         for object in &self.env.objects {
-            let object_type = match object.kind {
-                ObjectKind::Table => TABLE,
-                ObjectKind::View => VIEW,
-            };
-
             res.push(nodes::RawAsset::new(
-                object.cual(),
+                object.cual(&self.conn.cual_account),
                 "".to_owned(),
-                AssetType(object_type.to_owned()),
+                AssetType(object.kind.to_string()),
+                protections
+                    .get(&(&object.database_name, &object.schema_name, &object.name))
+                    .map(|p| p.to_metadata())
+                    .unwrap_or_default(),
+                // Policies applied are handled in get_jetty_policies
+                HashSet::new(),
+                HashSet::from([cual!(
+                    self.conn.cual_account,
+                    object.database_name,
+                    object.schema_name
+                )
+                .uri()]),
+                // Handled in child_of for parents.
+                HashSet::new(),
+                // Lineage is added once all the assets are known
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+            ));
+        }
+
+        for routine in &self.env.routines {
+            res.push(nodes::RawAsset::new(
+                routine.cual(&self.conn.cual_account),
+                format!(
+                    "{}.{}.{}",
+                    routine.database_name,
+                    routine.schema_name,
+                    routine.signature()
+                ),
+                AssetType(routine.kind.to_string()),
                 HashMap::new(),
                 // Policies applied are handled in get_jetty_policies
                 HashSet::new(),
-                HashSet::from([cual!(object.database_name, object.schema_name).uri()]),
+                HashSet::from([cual!(
+                    self.conn.cual_account,
+                    routine.database_name,
+                    routine.schema_name
+                )
+                .uri()]),
                 // Handled in child_of for parents.
                 HashSet::new(),
-                // We aren't extracting lineage from Snowflake right now.
+                // Lineage is added once all the assets are known
+                HashSet::new(),
+                HashSet::new(),
+                HashSet::new(),
+            ));
+        }
+
+        for warehouse in &self.env.warehouses {
+            res.push(nodes::RawAsset::new(
+                warehouse.cual(&self.conn.cual_account),
+                warehouse.name.to_owned(),
+                AssetType(WAREHOUSE.to_owned()),
+                HashMap::new(),
+                // Policies applied are handled in get_jetty_policies
+                HashSet::new(),
+                // Warehouses aren't part of the database hierarchy
+                HashSet::new(),
+                HashSet::new(),
+                // Lineage is added once all the assets are known
                 HashSet::new(),
                 HashSet::new(),
                 HashSet::new(),
@@ -331,25 +582,44 @@ impl<'a> Coordinator<'a> {
 
         for schema in &self.env.schemas {
             res.push(nodes::RawAsset::new(
-                schema.cual(),
+                schema.cual(&self.conn.cual_account),
                 format!("{}.{}", schema.database_name, schema.name),
                 AssetType(SCHEMA.to_owned()),
-                HashMap::new(),
+                if schema.is_managed_access() {
+                    HashMap::from([(MANAGED_ACCESS_METADATA_KEY.to_owned(), "true".to_owned())])
+                } else {
+                    HashMap::new()
+                },
                 // Policies applied are handled in get_jetty_policies
                 HashSet::new(),
-                HashSet::from([cual!(schema.database_name).uri()]),
+                HashSet::from([cual!(self.conn.cual_account, schema.database_name).uri()]),
                 // Handled in child_of for parents.
                 HashSet::new(),
-                // We aren't extracting lineage from Snowflake right now.
+                // Lineage is added once all the assets are known
                 HashSet::new(),
                 HashSet::new(),
                 HashSet::new(),
             ));
         }
 
+        // Account-level grants, like CREATE DATABASE, are on the account itself
+        res.push(nodes::RawAsset::new(
+            account_cual(&self.conn.cual_account),
+            ACCOUNT_ASSET_NAME.to_owned(),
+            AssetType(ACCOUNT.to_owned()),
+            self.get_account_protections().to_metadata(),
+            // Policies applied are handled in get_jetty_policies
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+            HashSet::new(),
+        ));
+
         for db in &self.env.databases {
             res.push(nodes::RawAsset::new(
-                db.cual(),
+                db.cual(&self.conn.cual_account),
                 db.name.to_owned(),
                 AssetType(DATABASE.to_owned()),
                 HashMap::new(),
@@ -358,20 +628,240 @@ impl<'a> Coordinator<'a> {
                 HashSet::new(),
                 // Handled in child_of for parents.
                 HashSet::new(),
-                // We aren't extracting lineage from Snowflake right now.
+                // Lineage is added once all the assets are known
                 HashSet::new(),
                 HashSet::new(),
                 HashSet::new(),
             ));
         }
 
+        // Every asset but the account has exactly one owner
+        let owners = self.get_asset_owners();
+        // Lineage is only kept between assets that were fetched
+        let known_assets = res.iter().map(|a| a.cual.to_owned()).collect();
+        let mut lineage = build_lineage(
+            &self.env.object_dependencies,
+            &known_assets,
+            &self.conn.cual_account,
+        );
+        for asset in &mut res {
+            if let Some(owner) = owners.get(&asset.cual) {
+                let (key, value) = owner_metadata(owner);
+                asset.metadata.insert(key, value);
+            }
+            if let Some(derived_from) = lineage.remove(&asset.cual) {
+                asset.derived_from = derived_from;
+            }
+        }
+
         res
     }
 
-    /// get tags from environment
-    /// NOT CURRENTLY IMPLEMENTED - This is an enterprise-only feature
+    /// Get the role that owns each asset, from the OWNERSHIP grants
+    fn get_asset_owners(&self) -> HashMap<Cual, &str> {
+        self.env
+            .standard_grants
+            .iter()
+            .filter(|g| g.privilege() == OWNERSHIP_PRIVILEGE)
+            .filter_map(|g| {
+                g.cual(&self.conn.cual_account)
+                    .ok()
+                    .map(|cual| (cual, g.role_name()))
+            })
+            .collect()
+    }
+
+    /// Collect the masking and row access policies on each object, keyed by
+    /// (database, schema, name)
+    fn get_object_protections(&self) -> HashMap<(&String, &String, &String), AssetProtections> {
+        let mut res: HashMap<_, AssetProtections> = HashMap::new();
+        for reference in &self.env.policy_references {
+            if [entry_types::USER_DOMAIN, entry_types::ACCOUNT_DOMAIN]
+                .contains(&reference.ref_entity_domain.as_str())
+            {
+                // Handled in get_user_policies and get_account_protections
+                continue;
+            }
+            let protections = res
+                .entry((
+                    &reference.ref_database_name,
+                    &reference.ref_schema_name,
+                    &reference.ref_entity_name,
+                ))
+                .or_default();
+            match reference.policy_kind.as_str() {
+                entry_types::MASKING_POLICY => {
+                    protections
+                        .masking_policies
+                        .insert(reference.ref_column_name.to_owned(), reference.policy_fqn());
+                }
+                entry_types::ROW_ACCESS_POLICY => {
+                    protections.row_access_policy = Some(RowAccessPolicy {
+                        policy: reference.policy_fqn(),
+                        columns: reference.arg_columns(),
+                    });
+                }
+                kind => debug!("skipping unknown policy kind {kind}"),
+            }
+        }
+        res
+    }
+
+    /// get tags from environment. Snowflake tags are inherited by the objects in a
+    /// database or schema, so they pass through the hierarchy. The tags in the tag schema
+    /// are written from Jetty's own tags, so they're reported through asset metadata instead.
     fn get_jetty_tags(&self) -> Vec<nodes::RawTag> {
-        vec![]
+        let tag_schema = self.conn.config.tag_schema.as_deref();
+        let mut res: HashMap<String, nodes::RawTag> = HashMap::new();
+        for (reference, cual) in self.get_tagged_assets() {
+            if tag_schema.map_or(false, |schema| reference.is_in_schema(schema)) {
+                continue;
+            }
+            let name = reference.jetty_tag_name();
+            res.entry(name.to_owned())
+                .or_insert_with(|| nodes::RawTag {
+                    name,
+                    value: reference.value(),
+                    pass_through_hierarchy: true,
+                    ..Default::default()
+                })
+                .applied_to
+                .insert(cual.uri());
+        }
+        res.into_values().collect()
+    }
+
+    /// Get the tags in the tag schema that Jetty writes to, and the assets they are
+    /// applied to
+    pub(crate) fn get_managed_tags(&self, tag_schema: &str) -> ManagedTags {
+        let mut res = ManagedTags::default();
+        for (reference, cual) in self.get_tagged_assets() {
+            if reference.is_in_schema(tag_schema) {
+                res.insert(&reference.tag_name, &cual, &reference.tag_value);
+            }
+        }
+        res
+    }
+
+    /// Get the kinds of the roles in the account
+    pub(crate) fn get_role_kinds(&self) -> RoleKinds {
+        let account_roles = self.env.roles.iter().map(|r| {
+            let RoleName(name) = &r.name;
+            (name.to_owned(), RoleKind::Account)
+        });
+        let database_roles = self.env.database_roles.iter().map(|r| {
+            let RoleName(name) = r.role_name();
+            (
+                name,
+                RoleKind::Database {
+                    database: r.database_name.to_owned(),
+                    role: r.name.to_owned(),
+                },
+            )
+        });
+        RoleKinds {
+            roles: account_roles.chain(database_roles).collect(),
+            databases: self
+                .env
+                .databases
+                .iter()
+                .map(|d| d.name.to_owned())
+                .collect(),
+        }
+    }
+
+    /// Get the managed-access schemas that the role Jetty uses can't grant privileges in.
+    /// That takes owning the schema, or MANAGE GRANTS on the account, either directly or
+    /// through an inherited role.
+    pub(crate) fn get_restricted_schemas(&self) -> RestrictedSchemas {
+        let role = self.conn.rest_client.get_snowflake_role();
+
+        let mut held_roles = HashSet::new();
+        let mut queue = VecDeque::from([role.to_owned()]);
+        while let Some(current) = queue.pop_front() {
+            if held_roles.insert(current.to_owned()) {
+                queue.extend(
+                    self.role_grants
+                        .get(&Grantee::Role(current))
+                        .into_iter()
+                        .flatten()
+                        .map(|RoleName(r)| r.to_owned()),
+                );
+            }
+        }
+
+        let manages_grants = self.env.standard_grants.iter().any(|g| {
+            g.granted_on() == ACCOUNT
+                && g.privilege() == MANAGE_GRANTS
+                && held_roles.contains(g.role_name())
+        });
+        let schemas = if manages_grants {
+            Default::default()
+        } else {
+            self.env
+                .schemas
+                .iter()
+                .filter(|s| s.is_managed_access() && !held_roles.contains(&s.owner))
+                .map(|s| s.fqn())
+                .collect()
+        };
+        RestrictedSchemas { role, schemas }
+    }
+
+    /// Match tag references to the assets they are applied to. References to assets
+    /// that weren't fetched (because of the include list, for example) are skipped.
+    fn get_tagged_assets(&self) -> Vec<(&entry_types::TagReference, Cual)> {
+        let databases: HashMap<_, _> = self.env.databases.iter().map(|d| (&d.name, d)).collect();
+        let schemas: HashMap<_, _> = self
+            .env
+            .schemas
+            .iter()
+            .map(|s| ((&s.database_name, &s.name), s))
+            .collect();
+        let warehouses: HashMap<_, _> = self.env.warehouses.iter().map(|w| (&w.name, w)).collect();
+        let objects: HashMap<_, _> = self
+            .env
+            .objects
+            .iter()
+            .map(|o| ((&o.database_name, &o.schema_name, &o.name), o))
+            .collect();
+
+        let account = &self.conn.cual_account;
+        self.env
+            .tag_references
+            .iter()
+            .filter_map(|reference| {
+                let cual = match reference.domain.as_str() {
+                    DATABASE => databases
+                        .get(&reference.object_name)
+                        .map(|d| d.cual(account)),
+                    SCHEMA => schemas
+                        .get(&(&reference.object_database, &reference.object_name))
+                        .map(|s| s.cual(account)),
+                    WAREHOUSE => warehouses
+                        .get(&reference.object_name)
+                        .map(|w| w.cual(account)),
+                    // Tables, views, stages, etc. The kind of object comes from the
+                    // object itself.
+                    _ => objects
+                        .get(&(
+                            &reference.object_database,
+                            &reference.object_schema,
+                            &reference.object_name,
+                        ))
+                        .map(|o| o.cual(account)),
+                };
+                if cual.is_none() {
+                    debug!(
+                        "skipping tag {} on unknown {} {}",
+                        reference.tag_fqn(),
+                        reference.domain,
+                        reference.object_name
+                    );
+                }
+                cual.map(|c| (reference, c))
+            })
+            .collect()
     }
 
     /// get policies from environment
@@ -398,6 +888,52 @@ impl<'a> Coordinator<'a> {
     }
 }
 
+/// Get the assets each asset is derived from, as cual uris, keyed by the derived asset.
+/// Dependencies on or from assets that aren't known are skipped.
+fn build_lineage(
+    dependencies: &[entry_types::ObjectDependency],
+    known_assets: &HashSet<Cual>,
+    account: &CualAccount,
+) -> HashMap<Cual, HashSet<String>> {
+    let mut res: HashMap<Cual, HashSet<String>> = HashMap::new();
+    for dependency in dependencies {
+        let (referenced, referencing) = match (
+            dependency.referenced_cual(account),
+            dependency.referencing_cual(account),
+        ) {
+            (Ok(referenced), Ok(referencing)) => (referenced, referencing),
+            _ => {
+                debug!("skipping lineage for unsupported objects: {dependency:?}");
+                continue;
+            }
+        };
+        if referenced == referencing
+            || !known_assets.contains(&referenced)
+            || !known_assets.contains(&referencing)
+        {
+            continue;
+        }
+        res.entry(referencing).or_default().insert(referenced.uri());
+    }
+    res
+}
+
+/// `SHOW OBJECTS` can also return some of the objects that are fetched with their own
+/// queries (like materialized views), but with a less specific kind. Keep a single entry
+/// for each object, preferring the more specific kind.
+fn dedupe_objects(objects: &mut Vec<entry_types::Object>) {
+    let specific: HashSet<_> = objects
+        .iter()
+        .filter(|o| !matches!(o.kind, ObjectKind::Table | ObjectKind::View))
+        .map(|o| o.fqn())
+        .collect();
+    let mut seen = HashSet::new();
+    objects.retain(|o| {
+        let generic = matches!(o.kind, ObjectKind::Table | ObjectKind::View);
+        !(generic && specific.contains(&o.fqn())) && seen.insert((o.fqn(), o.kind))
+    });
+}
+
 /// This function adds empty privileges to all existing objects that don't have the default privileges that would be applied if there
 /// weren't a more specific policy.
 ///
@@ -512,28 +1048,92 @@ mod tests {
     use anyhow::Result;
     use jetty_core::connectors::nodes::{RawAsset, RawDefaultPolicy};
 
+    #[test]
+    fn dedupe_objects_prefers_specific_kinds() {
+        let object = |name: &str, kind| entry_types::Object {
+            name: name.to_owned(),
+            schema_name: "schema".to_owned(),
+            database_name: "db".to_owned(),
+            kind,
+        };
+        let mut objects = vec![
+            object("mv", ObjectKind::View),
+            object("mv", ObjectKind::MaterializedView),
+            object("table", ObjectKind::Table),
+            object("stage", ObjectKind::Stage),
+            object("stage", ObjectKind::Stage),
+        ];
+        dedupe_objects(&mut objects);
+        assert_eq!(
+            objects
+                .iter()
+                .map(|o| (o.name.as_str(), o.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("mv", ObjectKind::MaterializedView),
+                ("table", ObjectKind::Table),
+                ("stage", ObjectKind::Stage),
+            ]
+        );
+    }
+
+    #[test]
+    fn lineage_is_kept_between_known_assets() {
+        let account = CualAccount::new("account");
+        let dependency =
+            |referenced: &str, referencing: &str, domain: &str| entry_types::ObjectDependency {
+                referenced_database: "DB".to_owned(),
+                referenced_schema: "RAW".to_owned(),
+                referenced_object_name: referenced.to_owned(),
+                referenced_object_domain: consts::TABLE.to_owned(),
+                referencing_database: "DB".to_owned(),
+                referencing_schema: "RAW".to_owned(),
+                referencing_object_name: referencing.to_owned(),
+                referencing_object_domain: domain.to_owned(),
+            };
+        let users = cual!(account, "DB", "RAW", "USERS", consts::TABLE);
+        let users_view = cual!(account, "DB", "RAW", "USERS_VIEW", consts::VIEW);
+        let known = HashSet::from([users.to_owned(), users_view.to_owned()]);
+
+        let lineage = build_lineage(
+            &[
+                dependency("USERS", "USERS_VIEW", consts::VIEW),
+                // Objects that weren't fetched are skipped
+                dependency("USERS", "SCRATCH", consts::TABLE),
+                // So are objects Jetty doesn't model
+                dependency("USERS", "USERS_PIPE", "PIPE"),
+            ],
+            &known,
+            &account,
+        );
+        assert_eq!(
+            lineage,
+            HashMap::from([(users_view, HashSet::from([users.uri()]))])
+        );
+    }
+
     #[test]
     fn test_add_non_default_policies() -> Result<()> {
-        cual::set_cual_account_name("account");
+        let account = CualAccount::new("account");
         let assets = [
             RawAsset {
-                cual: cual!("db"),
+                cual: cual!(account, "db"),
                 name: "whatever".to_owned(),
                 asset_type: AssetType(consts::DATABASE.to_owned()),
                 ..Default::default()
             },
             RawAsset {
-                cual: cual!("db", "schema"),
+                cual: cual!(account, "db", "schema"),
                 name: "whatever schema".to_owned(),
                 asset_type: AssetType(consts::SCHEMA.to_owned()),
-                child_of: [cual!("db").to_string()].into(),
+                child_of: [cual!(account, "db").to_string()].into(),
                 ..Default::default()
             },
             RawAsset {
-                cual: cual!("db", "schema2"),
+                cual: cual!(account, "db", "schema2"),
                 name: "whatever schema2".to_owned(),
                 asset_type: AssetType(consts::SCHEMA.to_owned()),
-                child_of: [cual!("db").to_string()].into(),
+                child_of: [cual!(account, "db").to_string()].into(),
                 ..Default::default()
             },
         ]
@@ -542,7 +1142,7 @@ mod tests {
         let policies = [RawPolicy {
             name: "p1".to_owned(),
             privileges: ["read".to_owned()].into(),
-            governs_assets: [cual!("db", "schema2").to_string()].into(),
+            governs_assets: [cual!(account, "db", "schema2").to_string()].into(),
             granted_to_groups: ["group".to_owned()].into(),
             ..Default::default()
         }]
@@ -550,7 +1150,7 @@ mod tests {
 
         let default_policies = [RawDefaultPolicy {
             privileges: ["write".to_owned()].into(),
-            root_asset: cual!("db"),
+            root_asset: cual!(account, "db"),
             wildcard_path: "/*".to_owned(),
             target_type: AssetType(consts::SCHEMA.to_owned()),
             grantee: RawPolicyGrantee::Group("group".to_owned()),
@@ -0,0 +1,438 @@
+//! Effective permissions for Snowflake users.
+//!
+//! Snowflake only grants privileges to roles. A user holds the privileges of every role
+//! granted to them, directly or through other roles, as well as those of PUBLIC. A
+//! privilege on a table or view also requires USAGE on its database and schema (and a
+//! privilege on a schema requires USAGE on its database), so privileges missing that
+//! prerequisite are included with a mode of [`PermissionMode::None`].
+
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
+
+use jetty_core::{
+    connectors::nodes::{EffectivePermission, PermissionMode, SparseMatrix},
+    logging::debug,
+    permissions::matrix::Merge,
+};
+
+use super::{Environment, Grantee};
+use crate::{
+    consts::{DATABASE, PUBLIC_ROLE, SCHEMA},
+    cual::{account_cual, cual_from_snowflake_obj_name, Cual, CualAccount},
+    entry_types::{fix_privilege_name, RoleName},
+    Grant, StandardGrant,
+};
+
+/// Privileges that make a database or schema usable
+const USAGE_PRIVILEGES: [&str; 2] = ["USAGE", "OWNERSHIP"];
+
+/// Compute the effective permissions of every user in the environment. Only users and
+/// assets that exist in the environment are included.
+pub(super) fn get_effective_permissions(
+    env: &Environment,
+    role_grants: &HashMap<Grantee, HashSet<RoleName>>,
+    account: &CualAccount,
+) -> SparseMatrix<String, Cual, HashSet<EffectivePermission>> {
+    let known_assets: HashSet<Cual> = env
+        .objects
+        .iter()
+        .map(|o| o.cual(account))
+        .chain(env.schemas.iter().map(|s| s.cual(account)))
+        .chain(env.databases.iter().map(|d| d.cual(account)))
+        .chain(env.routines.iter().map(|r| r.cual(account)))
+        .chain(env.warehouses.iter().map(|w| w.cual(account)))
+        .chain([account_cual(account)])
+        .collect();
+
+    // The grants held by each role, and the databases and schemas each role can use
+    let mut grants_by_role: HashMap<&str, Vec<(Cual, &StandardGrant)>> = HashMap::new();
+    let mut usable_by_role: HashMap<&str, HashSet<Cual>> = HashMap::new();
+    for grant in &env.standard_grants {
+        let cual = match grant.cual(account) {
+            Ok(cual) => cual,
+            Err(e) => {
+                debug!("skipping grant for effective permissions: {e}");
+                continue;
+            }
+        };
+        if !known_assets.contains(&cual) {
+            continue;
+        }
+        if USAGE_PRIVILEGES.contains(&grant.privilege())
+            && [DATABASE, SCHEMA].contains(&grant.granted_on())
+        {
+            usable_by_role
+                .entry(grant.role_name())
+                .or_default()
+                .insert(cual.to_owned());
+        }
+        grants_by_role
+            .entry(grant.role_name())
+            .or_default()
+            .push((cual, grant));
+    }
+
+    // (role, root asset, asset type, privilege) for every future grant
+    let future_grants: HashSet<(&str, Cual, &str, &str)> = env
+        .future_grants
+        .iter()
+        .filter_map(|g| {
+            cual_from_snowflake_obj_name(account, g.root_asset(), g.grant_on())
+                .ok()
+                .map(|root| (g.role_name(), root, g.grant_on(), g.privilege()))
+        })
+        .collect();
+
+    let mut res = HashMap::new();
+    for user in &env.users {
+        let roles = roles_for_user(&user.name, role_grants);
+        let usable: HashSet<&Cual> = roles
+            .keys()
+            .filter_map(|r| usable_by_role.get(r.as_str()))
+            .flatten()
+            .collect();
+
+        let mut user_permissions: HashMap<Cual, HashMap<String, EffectivePermission>> =
+            HashMap::new();
+        for (role, path) in &roles {
+            for (cual, grant) in grants_by_role.get(role.as_str()).into_iter().flatten() {
+                let privilege = fix_privilege_name(grant.privilege());
+                let mut reasons = vec![format!(
+                    "{privilege} on {} was granted to role {role}, {}",
+                    grant.granted_on_name(),
+                    describe_role_path(&user.name, path)
+                )];
+
+                let containers = match grant.containers(account) {
+                    Ok(containers) => containers,
+                    Err(e) => {
+                        debug!("skipping grant for effective permissions: {e}");
+                        continue;
+                    }
+                };
+                if let Some((name, _)) = containers.iter().find(|(_, c)| {
+                    future_grants.contains(&(
+                        grant.role_name(),
+                        c.to_owned(),
+                        grant.granted_on(),
+                        grant.privilege(),
+                    ))
+                }) {
+                    reasons.push(format!(
+                        "the grant matches a future grant to role {role} on {name}"
+                    ));
+                }
+
+                let missing_usage: Vec<_> = containers
+                    .iter()
+                    .filter(|(_, c)| !usable.contains(c))
+                    .map(|(name, _)| name.as_str())
+                    .collect();
+                let mode = if missing_usage.is_empty() {
+                    PermissionMode::Allow
+                } else {
+                    reasons.push(format!(
+                        "{} doesn't have USAGE on {}, so the grant can't be used",
+                        user.name,
+                        missing_usage.join(" or ")
+                    ));
+                    PermissionMode::None
+                };
+
+                merge_permission(
+                    user_permissions.entry(cual.to_owned()).or_default(),
+                    EffectivePermission::new(privilege, mode, reasons),
+                );
+            }
+        }
+
+        if !user_permissions.is_empty() {
+            res.insert(
+                user.name.to_owned(),
+                user_permissions
+                    .into_iter()
+                    .map(|(cual, permissions)| (cual, permissions.into_values().collect()))
+                    .collect(),
+            );
+        }
+    }
+    res
+}
+
+/// Get every role a user holds, along with the chain of role grants that gives it to
+/// them. The chain starts with the role granted directly to the user and ends with the
+/// role itself. Roles reachable in more than one way keep the shortest chain.
+fn roles_for_user(
+    user: &str,
+    role_grants: &HashMap<Grantee, HashSet<RoleName>>,
+) -> HashMap<String, Vec<String>> {
+    let mut roles: HashMap<String, Vec<String>> = HashMap::new();
+    let mut queue: VecDeque<Vec<String>> = role_grants
+        .get(&Grantee::User(user.to_owned()))
+        .into_iter()
+        .flatten()
+        .map(|RoleName(r)| vec![r.to_owned()])
+        .collect();
+
+    while let Some(path) = queue.pop_front() {
+        let role = path.last().unwrap().to_owned();
+        if roles.contains_key(&role) {
+            continue;
+        }
+        for RoleName(inherited) in role_grants
+            .get(&Grantee::Role(role.clone()))
+            .into_iter()
+            .flatten()
+        {
+            if !roles.contains_key(inherited) {
+                let mut inherited_path = path.to_owned();
+                inherited_path.push(inherited.to_owned());
+                queue.push_back(inherited_path);
+            }
+        }
+        roles.insert(role, path);
+    }
+
+    // Every user holds PUBLIC, whether or not it was granted explicitly
+    roles.entry(PUBLIC_ROLE.to_owned()).or_default();
+    roles
+}
+
+/// Describe how a user came to hold a role
+fn describe_role_path(user: &str, path: &[String]) -> String {
+    match path {
+        [] => "which every user has".to_owned(),
+        [_] => format!("which was granted to {user}"),
+        [through @ .., _] => format!("which {user} has through {}", through.join(" > ")),
+    }
+}
+
+/// Add a permission to a user's permissions on an asset. Allow takes precedence over any
+/// other mode, and the reasons for permissions with the same mode are combined.
+fn merge_permission(
+    permissions: &mut HashMap<String, EffectivePermission>,
+    permission: EffectivePermission,
+) {
+    match permissions.entry(permission.privilege.to_owned()) {
+        Entry::Vacant(e) => {
+            e.insert(permission);
+        }
+        Entry::Occupied(mut e) => {
+            let existing = e.get_mut();
+            if existing.mode == permission.mode || permission.mode == PermissionMode::Allow {
+                // The privileges match, so this can't fail
+                existing.merge(permission).unwrap();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        cual::cual, entry_types, entry_types::ObjectKind, Database, FutureGrant, Object, Schema,
+    };
+
+    fn grant(role: &str, privilege: &str, granted_on: &str, name: &str) -> StandardGrant {
+        let parts: Vec<_> = name.split('.').collect();
+        let (table_catalog, table_schema, name) = match parts[..] {
+            [db] => (db, "", db),
+            [db, schema] => (db, "", schema),
+            [db, schema, table] => (db, schema, table),
+            _ => panic!("bad name: {name}"),
+        };
+        serde_json::from_value(json!({
+            "NAME": name,
+            "TABLE_CATALOG": table_catalog,
+            "TABLE_SCHEMA": table_schema,
+            "PRIVILEGE": privilege,
+            "GRANTED_ON": granted_on,
+            "GRANTEE_NAME": role,
+        }))
+        .unwrap()
+    }
+
+    fn user(name: &str) -> entry_types::User {
+        entry_types::User {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn environment(standard_grants: Vec<StandardGrant>) -> Result<Environment> {
+        Ok(Environment {
+            databases: vec![Database::new("DB".to_owned())],
+            schemas: vec![Schema::new("S".to_owned(), "DB".to_owned())],
+            objects: vec![Object {
+                name: "T".to_owned(),
+                schema_name: "S".to_owned(),
+                database_name: "DB".to_owned(),
+                kind: ObjectKind::Table,
+            }],
+            users: vec![user("ELLEN"), user("ANNA")],
+            standard_grants,
+            future_grants: vec![serde_json::from_value::<FutureGrant>(json!({
+                "name": "DB.S.<TABLE>",
+                "privilege": "SELECT",
+                "grant_on": "TABLE",
+                "grantee_name": "ANALYST",
+            }))?],
+            ..Default::default()
+        })
+    }
+
+    fn role_grants() -> HashMap<Grantee, HashSet<RoleName>> {
+        HashMap::from([
+            (
+                Grantee::User("ELLEN".to_owned()),
+                HashSet::from([RoleName("SYSADMIN".to_owned())]),
+            ),
+            (
+                Grantee::Role("SYSADMIN".to_owned()),
+                HashSet::from([RoleName("ANALYST".to_owned())]),
+            ),
+            (
+                Grantee::User("ANNA".to_owned()),
+                HashSet::from([RoleName("READER".to_owned())]),
+            ),
+        ])
+    }
+
+    fn permission<'a>(
+        matrix: &'a SparseMatrix<String, Cual, HashSet<EffectivePermission>>,
+        user: &str,
+        cual: &Cual,
+        privilege: &str,
+    ) -> Option<&'a EffectivePermission> {
+        matrix
+            .get(user)?
+            .get(cual)?
+            .iter()
+            .find(|p| p.privilege == privilege)
+    }
+
+    #[test]
+    fn inherited_grants_are_effective() -> Result<()> {
+        let account = CualAccount::new("account");
+        let env = environment(vec![
+            grant("ANALYST", "USAGE", "DATABASE", "DB"),
+            grant("ANALYST", "USAGE", "SCHEMA", "DB.S"),
+            grant("ANALYST", "SELECT", "TABLE", "DB.S.T"),
+            grant("READER", "SELECT", "TABLE", "DB.S.T"),
+            // Grants on assets that weren't fetched are ignored
+            grant("ANALYST", "SELECT", "TABLE", "DB.S.MISSING"),
+        ])?;
+
+        let matrix = get_effective_permissions(&env, &role_grants(), &account);
+        let table = cual!(account, "DB", "S", "T", "TABLE");
+
+        let ellen = permission(&matrix, "ELLEN", &table, "SELECT").unwrap();
+        assert_eq!(ellen.mode, PermissionMode::Allow);
+        assert_eq!(
+            ellen.reasons,
+            vec![
+                "SELECT on DB.S.T was granted to role ANALYST, which ELLEN has through SYSADMIN"
+                    .to_owned(),
+                "the grant matches a future grant to role ANALYST on DB.S".to_owned()
+            ]
+        );
+
+        // ANNA can't use the grant without USAGE on the database and schema
+        let anna = permission(&matrix, "ANNA", &table, "SELECT").unwrap();
+        assert_eq!(anna.mode, PermissionMode::None);
+        assert_eq!(
+            anna.reasons[1],
+            "ANNA doesn't have USAGE on DB or DB.S, so the grant can't be used"
+        );
+
+        assert_eq!(matrix["ELLEN"].len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn usage_can_come_from_any_role() -> Result<()> {
+        let account = CualAccount::new("account");
+        let env = environment(vec![
+            grant("PUBLIC", "USAGE", "DATABASE", "DB"),
+            grant("PUBLIC", "USAGE", "SCHEMA", "DB.S"),
+            grant("READER", "SELECT", "TABLE", "DB.S.T"),
+            grant("ANALYST", "SELECT", "TABLE", "DB.S.T"),
+        ])?;
+
+        let matrix = get_effective_permissions(&env, &role_grants(), &account);
+        let table = cual!(account, "DB", "S", "T", "TABLE");
+
+        let anna = permission(&matrix, "ANNA", &table, "SELECT").unwrap();
+        assert_eq!(anna.mode, PermissionMode::Allow);
+        assert_eq!(
+            permission(&matrix, "ANNA", &cual!(account, "DB"), "USAGE")
+                .unwrap()
+                .reasons,
+            vec!["USAGE on DB was granted to role PUBLIC, which every user has".to_owned()]
+        );
+        // ELLEN gets SELECT through ANALYST only
+        assert_eq!(
+            permission(&matrix, "ELLEN", &table, "SELECT")
+                .unwrap()
+                .reasons
+                .len(),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn account_grants_dont_need_usage() -> Result<()> {
+        let account = CualAccount::new("account");
+        let env = environment(vec![grant(
+            "SYSADMIN",
+            "CREATE DATABASE",
+            "ACCOUNT",
+            "XY12345",
+        )])?;
+
+        let matrix = get_effective_permissions(&env, &role_grants(), &account);
+        let ellen =
+            permission(&matrix, "ELLEN", &account_cual(&account), "CREATE DATABASE").unwrap();
+        assert_eq!(ellen.mode, PermissionMode::Allow);
+        assert_eq!(
+            ellen.reasons,
+            vec!["CREATE DATABASE on ACCOUNT was granted to role SYSADMIN, which was granted to ELLEN".to_owned()]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn allow_takes_precedence_when_merging() {
+        let mut permissions = HashMap::new();
+        merge_permission(
+            &mut permissions,
+            EffectivePermission::new(
+                "SELECT".to_owned(),
+                PermissionMode::None,
+                vec!["a".to_owned()],
+            ),
+        );
+        merge_permission(
+            &mut permissions,
+            EffectivePermission::new(
+                "SELECT".to_owned(),
+                PermissionMode::Allow,
+                vec!["b".to_owned()],
+            ),
+        );
+        merge_permission(
+            &mut permissions,
+            EffectivePermission::new(
+                "SELECT".to_owned(),
+                PermissionMode::None,
+                vec!["c".to_owned()],
+            ),
+        );
+        assert_eq!(permissions["SELECT"].mode, PermissionMode::Allow);
+        assert_eq!(permissions["SELECT"].reasons, vec!["b".to_owned()]);
+    }
+}
@@ -1,35 +1,242 @@
-use anyhow::{anyhow, Result};
-use serde::Deserialize;
+use anyhow::{anyhow, Context, Result};
+use jetty_core::jetty::CredentialsMap;
+use serde::{Deserialize, Serialize};
+
+const LOGIN_METHOD_KEY: &str = "login_method";
 
 /// Credentials for authenticating to Snowflake.
 ///
 /// The user sets these up by following Jetty documentation
 /// and pasting their keys into their connector config.
-#[derive(Deserialize, Debug, Default)]
-pub(crate) struct SnowflakeCredentials {
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
+pub struct SnowflakeCredentials {
     pub(crate) account: String,
     pub(crate) role: String,
     pub(crate) user: String,
     pub(crate) warehouse: String,
-    pub(crate) private_key: String,
-    pub(crate) public_key_fp: String,
+    #[serde(flatten)]
+    pub(crate) method: LoginMethod,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) url: Option<String>,
 }
 
+/// The ways to authenticate to Snowflake and the information each one needs
+#[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(tag = "login_method")]
+#[serde(rename_all = "snake_case")]
+pub enum LoginMethod {
+    /// Sign requests with a key pair. The public key is set on the user with
+    /// `ALTER USER ... SET RSA_PUBLIC_KEY`.
+    KeyPair {
+        /// The PKCS8 private key, in PEM format
+        private_key: String,
+        /// The SHA256 fingerprint of the public key, as Snowflake reports it
+        public_key_fp: String,
+    },
+    /// Use an OAuth access token, like one issued by an external identity provider. Access
+    /// tokens expire, so the token is usually read from a command or an environment
+    /// variable, which are read again when Snowflake rejects the token. Exactly one of the
+    /// sources has to be set.
+    Oauth {
+        /// The OAuth access token
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        oauth_token: Option<String>,
+        /// A command that prints a current access token, like a call to the identity
+        /// provider's CLI
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        oauth_token_command: Option<String>,
+        /// The environment variable that holds the access token
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        oauth_token_env: Option<String>,
+    },
+    /// Log in with the user's password
+    Password {
+        /// Snowflake password
+        password: String,
+    },
+    /// Log in through the identity provider in a web browser, the way SSO users log in to
+    /// Snowsight
+    ExternalBrowser,
+}
+
+impl Default for LoginMethod {
+    fn default() -> Self {
+        Self::KeyPair {
+            private_key: Default::default(),
+            public_key_fp: Default::default(),
+        }
+    }
+}
+
 impl SnowflakeCredentials {
-    /// Perform simple field validation to catch bad input.
+    /// Basic constructor.
+    pub fn new(
+        method: LoginMethod,
+        account: String,
+        user: String,
+        role: String,
+        warehouse: String,
+    ) -> Self {
+        Self {
+            account,
+            role,
+            user,
+            warehouse,
+            method,
+            url: None,
+        }
+    }
+
+    /// Given a SnowflakeCredentials object, return a CredentialsMap
+    pub fn to_map(&self) -> CredentialsMap {
+        let string_rep = serde_json::to_string(&self).unwrap();
+        let map: CredentialsMap = serde_json::from_str(&string_rep).unwrap();
+        map
+    }
+
+    /// Given a map of credentials, return a SnowflakeCredentials object. Credentials
+    /// without a login method use a key pair, since that was the only option at first.
+    pub(crate) fn from_map(m: &CredentialsMap) -> Result<Self> {
+        let mut m = m.to_owned();
+        m.entry(LOGIN_METHOD_KEY.to_owned())
+            .or_insert_with(|| "key_pair".to_owned());
+        let string_rep = serde_json::to_string(&m)?;
+        serde_json::from_str(&string_rep).context("Snowflake credentials are invalid")
+    }
+
+    /// Perform simple field validation to catch bad input. The error names the missing
+    /// fields without their values, since the others can be secrets.
     pub(crate) fn validate(&self) -> Result<()> {
-        if self.account.is_empty()
-            || self.role.is_empty()
-            || self.user.is_empty()
-            || self.warehouse.is_empty()
-            || self.private_key.is_empty()
-            || self.public_key_fp.is_empty()
-        {
+        let mut missing = [
+            ("account", &self.account),
+            ("role", &self.role),
+            ("user", &self.user),
+            ("warehouse", &self.warehouse),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_empty())
+        .map(|(field, _)| field.to_owned())
+        .collect::<Vec<_>>();
+        match &self.method {
+            LoginMethod::KeyPair {
+                private_key,
+                public_key_fp,
+            } => missing.extend(
+                [
+                    ("private_key", private_key),
+                    ("public_key_fp", public_key_fp),
+                ]
+                .into_iter()
+                .filter(|(_, value)| value.is_empty())
+                .map(|(field, _)| field.to_owned()),
+            ),
+            LoginMethod::Oauth {
+                oauth_token,
+                oauth_token_command,
+                oauth_token_env,
+            } => {
+                let sources = [oauth_token, oauth_token_command, oauth_token_env]
+                    .iter()
+                    .filter(|source| source.as_ref().map_or(false, |s| !s.is_empty()))
+                    .count();
+                if sources != 1 {
+                    missing.push(
+                        "exactly one of oauth_token, oauth_token_command, or oauth_token_env"
+                            .to_owned(),
+                    );
+                }
+            }
+            LoginMethod::Password { password } => {
+                if password.is_empty() {
+                    missing.push("password".to_owned());
+                }
+            }
+            LoginMethod::ExternalBrowser => (),
+        };
+        if !missing.is_empty() {
             return Err(anyhow!(
-                "Credentials are missing. Please make sure your connectors.yaml file is correct. Credentials received: {:#?}", self
+                "Credentials are missing. Please make sure your connectors.yaml file is correct. \
+                Missing: {}",
+                missing.join(", ")
             ));
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn creds_map(entries: &[(&str, &str)]) -> CredentialsMap {
+        let mut map = HashMap::from([
+            ("account".to_owned(), "my_account".to_owned()),
+            ("role".to_owned(), "role".to_owned()),
+            ("user".to_owned(), "user".to_owned()),
+            ("warehouse".to_owned(), "warehouse".to_owned()),
+        ]);
+        map.extend(entries.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+        map
+    }
+
+    #[test]
+    fn credentials_without_a_login_method_use_a_key_pair() {
+        let creds = SnowflakeCredentials::from_map(&creds_map(&[
+            ("private_key", "private_key"),
+            ("public_key_fp", "fp"),
+        ]))
+        .unwrap();
+        assert!(matches!(creds.method, LoginMethod::KeyPair { .. }));
+        creds.validate().unwrap();
+    }
+
+    #[test]
+    fn login_methods_round_trip() {
+        for map in [
+            creds_map(&[("login_method", "oauth"), ("oauth_token", "token")]),
+            creds_map(&[
+                ("login_method", "oauth"),
+                ("oauth_token_command", "idp-cli token --audience snowflake"),
+            ]),
+            creds_map(&[
+                ("login_method", "oauth"),
+                ("oauth_token_env", "SNOWFLAKE_TOKEN"),
+            ]),
+            creds_map(&[("login_method", "password"), ("password", "hunter2")]),
+            creds_map(&[("login_method", "external_browser")]),
+        ] {
+            let creds = SnowflakeCredentials::from_map(&map).unwrap();
+            creds.validate().unwrap();
+            assert_eq!(creds.to_map(), map);
+        }
+        // OAuth needs exactly one token source
+        for map in [
+            creds_map(&[("login_method", "oauth")]),
+            creds_map(&[
+                ("login_method", "oauth"),
+                ("oauth_token", "token"),
+                ("oauth_token_env", "SNOWFLAKE_TOKEN"),
+            ]),
+        ] {
+            assert!(SnowflakeCredentials::from_map(&map)
+                .unwrap()
+                .validate()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn missing_fields_are_named_without_printing_secrets() {
+        let mut map = creds_map(&[("login_method", "password"), ("password", "hunter2")]);
+        map.insert("warehouse".to_owned(), "".to_owned());
+        let error = SnowflakeCredentials::from_map(&map)
+            .unwrap()
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(error.ends_with("Missing: warehouse"));
+        assert!(!error.contains("hunter2"));
+    }
+}
@@ -1,75 +1,62 @@
-use std::sync::Once;
+use std::fmt::Display;
 
 use anyhow::{bail, Result};
 
-use jetty_core::cual::Cualable;
 // Reexport for convenience.
 pub use jetty_core::cual::Cual;
 
-use crate::{escape_snowflake_quotes, Database, Object, Schema, SnowflakeAsset};
-
-static mut CUAL_ACCOUNT_NAME: String = String::new();
-static INIT_CUAL_ACCOUNT_NAME: Once = Once::new();
-
-// Accessing a `static mut` is unsafe much of the time, but if we do so
-// in a synchronized fashion (e.g., write once or read all) then we're
-// good to go!
-//
-// This function will only set the string once, and will
-// otherwise always effectively be a no-op.
-pub(crate) fn set_cual_account_name(account_name: &str) {
-    unsafe {
-        INIT_CUAL_ACCOUNT_NAME.call_once(|| {
-            CUAL_ACCOUNT_NAME = format!("{}.snowflakecomputing.com", account_name.to_lowercase())
-        });
+use crate::{
+    consts::{ACCOUNT, ACCOUNT_ASSET_NAME},
+    entry_types::routine_signature,
+    escape_snowflake_quotes, Database, Object, Routine, Schema, SnowflakeAsset, Warehouse,
+};
+
+/// The account-specific host used in Snowflake cuals. Each connector instance has its own,
+/// so connectors for different accounts can coexist in a single project.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct CualAccount(String);
+
+impl CualAccount {
+    /// Create the cual account from a Snowflake account identifier
+    pub(crate) fn new(account: &str) -> Self {
+        Self(format!("{}.snowflakecomputing.com", account.to_lowercase()))
     }
-}
 
-pub(crate) fn get_cual_account_name<'a>() -> Result<&'a str> {
-    if INIT_CUAL_ACCOUNT_NAME.is_completed() {
-        // CUAL_PREFIX is set by a Once and is safe to use after initialization.
-        unsafe { Ok(&CUAL_ACCOUNT_NAME) }
-    } else {
-        bail!("cual prefix was not yet set")
+    /// The cual prefix for every asset in the account
+    pub(crate) fn prefix(&self) -> String {
+        format!("{}://{}", "snowflake", self.0)
     }
 }
 
-pub(crate) fn get_cual_prefix() -> Result<String> {
-    if INIT_CUAL_ACCOUNT_NAME.is_completed() {
-        // CUAL_PREFIX is set by a Once and is safe to use after initialization.
-        Ok(format!(
-            "{}://{}",
-            "snowflake",
-            get_cual_account_name().expect("couldn't get CUAL account name")
-        ))
-    } else {
-        bail!("cual prefix was not yet set")
+impl Display for CualAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
     }
 }
 
 macro_rules! cual {
-    ($db:expr) => {
+    ($account:expr, $db:expr) => {
         Cual::new(&format!(
             "{}://{}/{}?type=DATABASE",
             "snowflake",
-            get_cual_account_name().expect("couldn't get CUAL account name"),
+            $account,
             urlencoding::encode(&$db)
         ))
     };
-    ($db:expr, $schema:expr) => {
+    ($account:expr, $db:expr, $schema:expr) => {
         Cual::new(&format!(
             "{}://{}/{}/{}?type=SCHEMA",
             "snowflake",
-            get_cual_account_name().expect("couldn't get CUAL account name"),
+            $account,
             urlencoding::encode(&$db),
             urlencoding::encode(&$schema)
         ))
     };
-    ($db:expr, $schema:expr, $table:expr, $asset_type:expr) => {
+    ($account:expr, $db:expr, $schema:expr, $table:expr, $asset_type:expr) => {
         Cual::new(&format!(
             "{}://{}/{}/{}/{}?type={}",
             "snowflake",
-            get_cual_account_name().expect("couldn't get CUAL account name"),
+            $account,
             urlencoding::encode(&$db),
             urlencoding::encode(&$schema),
             urlencoding::encode(&$table),
@@ -80,18 +67,41 @@ macro_rules! cual {
 
 pub(crate) use cual;
 
-pub(crate) fn cual_from_snowflake_obj_name(name: &str, asset_type: &str) -> Result<Cual> {
-    let parts: Vec<_> = name
-        .split('.')
+/// Warehouses aren't part of the database hierarchy, so they sit directly under the account.
+pub(crate) fn warehouse_cual(account: &CualAccount, name: &str) -> Cual {
+    Cual::new(&format!(
+        "{}://{}/{}?type=WAREHOUSE",
+        "snowflake",
+        account,
+        urlencoding::encode(name)
+    ))
+}
+
+/// There's a single account asset, which holds account-level grants. Like warehouses, it
+/// isn't part of the database hierarchy.
+pub(crate) fn account_cual(account: &CualAccount) -> Cual {
+    Cual::new(&format!(
+        "{}://{}/{}?type={}",
+        "snowflake", account, ACCOUNT_ASSET_NAME, ACCOUNT
+    ))
+}
+
+pub(crate) fn cual_from_snowflake_obj_name(
+    account: &CualAccount,
+    name: &str,
+    asset_type: &str,
+) -> Result<Cual> {
+    let parts: Vec<_> = crate::split_snowflake_name(name)
+        .into_iter()
         .map(|p| crate::strip_snowflake_quotes(p.to_owned(), true))
         .collect();
 
     if let (Some(db), Some(schema), Some(obj_name)) = (parts.get(0), parts.get(1), parts.get(2)) {
-        Ok(cual!(db, schema, obj_name, asset_type))
+        Ok(cual!(account, db, schema, obj_name, asset_type))
     } else if let (Some(db), Some(schema)) = (parts.get(0), parts.get(1)) {
-        Ok(cual!(db, schema))
+        Ok(cual!(account, db, schema))
     } else if let Some(db) = parts.get(0) {
-        Ok(cual!(db))
+        Ok(cual!(account, db))
     } else {
         bail!("name {} was not fully qualified", name)
     }
@@ -99,23 +109,28 @@ pub(crate) fn cual_from_snowflake_obj_name(name: &str, asset_type: &str) -> Resu
 
 /// Given snowlake name parts, get a Cual
 pub(crate) fn cual_from_snowflake_obj_name_parts(
+    account: &CualAccount,
     name: &str,
     db_name: &str,
     schema_name: &str,
     asset_type: &str,
 ) -> Result<Cual> {
     match asset_type {
-        "DATABASE" => return Ok(cual!(escape_snowflake_quotes(name))),
-        "SCHEMA" => return Ok(cual!(escape_snowflake_quotes(db_name), escape_snowflake_quotes(name))),
-        "TABLE" | "VIEW" => return Ok(cual!(escape_snowflake_quotes(db_name), escape_snowflake_quotes(schema_name), escape_snowflake_quotes(name), asset_type)),
+        "DATABASE" => return Ok(cual!(account, escape_snowflake_quotes(name))),
+        "SCHEMA" => return Ok(cual!(account, escape_snowflake_quotes(db_name), escape_snowflake_quotes(name))),
+        "TABLE" | "VIEW" | "STAGE" | "STREAM" | "TASK" | "MATERIALIZED_VIEW" | "EXTERNAL_TABLE" => return Ok(cual!(account, escape_snowflake_quotes(db_name), escape_snowflake_quotes(schema_name), escape_snowflake_quotes(name), asset_type)),
+        "FUNCTION" | "PROCEDURE" => return Ok(cual!(account, escape_snowflake_quotes(db_name), escape_snowflake_quotes(schema_name), routine_signature(&escape_snowflake_quotes(name)), asset_type)),
+        "WAREHOUSE" => return Ok(warehouse_cual(account, &escape_snowflake_quotes(name))),
+        "ACCOUNT" => return Ok(account_cual(account)),
         _ => bail!("Unable to build cual for: db: {db_name}, schema: {schema_name:?}, name: {name}, type: {asset_type}")
     }
 }
 
-impl Cualable for Object {
+impl Object {
     /// Get the CUAL that points to this table or view.
-    fn cual(&self) -> Cual {
+    pub(crate) fn cual(&self, account: &CualAccount) -> Cual {
         cual!(
+            account,
             escape_snowflake_quotes(&self.database_name),
             escape_snowflake_quotes(&self.schema_name),
             escape_snowflake_quotes(&self.name),
@@ -124,36 +139,85 @@ impl Cualable for Object {
     }
 }
 
-impl Cualable for Schema {
+impl Schema {
     /// Get the CUAL that points to this schema.
-    fn cual(&self) -> Cual {
+    pub(crate) fn cual(&self, account: &CualAccount) -> Cual {
         cual!(
+            account,
             escape_snowflake_quotes(&self.database_name),
             escape_snowflake_quotes(&self.name)
         )
     }
 }
 
-impl Cualable for Database {
+impl Database {
     /// Get the CUAL that points to this database.
-    fn cual(&self) -> Cual {
-        cual!(escape_snowflake_quotes(&self.name))
+    pub(crate) fn cual(&self, account: &CualAccount) -> Cual {
+        cual!(account, escape_snowflake_quotes(&self.name))
+    }
+}
+
+impl Warehouse {
+    /// Get the CUAL that points to this warehouse.
+    pub(crate) fn cual(&self, account: &CualAccount) -> Cual {
+        warehouse_cual(account, &escape_snowflake_quotes(&self.name))
+    }
+}
+
+impl Routine {
+    /// Get the CUAL that points to this function or procedure.
+    pub(crate) fn cual(&self, account: &CualAccount) -> Cual {
+        cual!(
+            account,
+            escape_snowflake_quotes(&self.database_name),
+            escape_snowflake_quotes(&self.schema_name),
+            escape_snowflake_quotes(&self.signature()),
+            self.kind.to_string()
+        )
     }
 }
 
 pub(crate) fn cual_to_snowflake_asset(cual: &Cual) -> SnowflakeAsset {
     let path = cual.asset_path().components().to_owned();
     let asset_type = cual.asset_type().unwrap();
-    let fqn = path
+    let segments = path
         .into_iter()
-        .map(|segment| format!("\"{}\"", urlencoding::decode(segment.as_str()).unwrap()))
-        .collect::<Vec<_>>()
-        .join(".");
+        .map(|segment| urlencoding::decode(segment.as_str()).unwrap().into_owned())
+        .collect::<Vec<_>>();
+    let quote = |segments: &[String]| {
+        segments
+            .iter()
+            .map(|segment| format!("\"{segment}\""))
+            .collect::<Vec<_>>()
+            .join(".")
+    };
+    let fqn = quote(&segments);
     match asset_type.to_string().as_str() {
         "TABLE" => SnowflakeAsset::Table(fqn),
         "VIEW" => SnowflakeAsset::View(fqn),
         "SCHEMA" => SnowflakeAsset::Schema(fqn),
         "DATABASE" => SnowflakeAsset::Database(fqn),
+        "WAREHOUSE" => SnowflakeAsset::Warehouse(fqn),
+        "STAGE" => SnowflakeAsset::Stage(fqn),
+        "STREAM" => SnowflakeAsset::Stream(fqn),
+        "TASK" => SnowflakeAsset::Task(fqn),
+        "MATERIALIZED_VIEW" => SnowflakeAsset::MaterializedView(fqn),
+        "EXTERNAL_TABLE" => SnowflakeAsset::ExternalTable(fqn),
+        "ACCOUNT" => SnowflakeAsset::Account,
+        routine_type @ ("FUNCTION" | "PROCEDURE") => {
+            // Routines are identified by their signature. Only the name gets quoted, and the
+            // argument types follow it, like "DB"."SCHEMA"."MY_FUNC"(NUMBER, VARCHAR)
+            let (signature, parents) = segments.split_last().unwrap();
+            let (name, args) = signature
+                .split_once('(')
+                .unwrap_or((signature.as_str(), ")"));
+            let fqn = format!("{}.\"{name}\"({args}", quote(parents));
+            if routine_type == "FUNCTION" {
+                SnowflakeAsset::Function(fqn)
+            } else {
+                SnowflakeAsset::Procedure(fqn)
+            }
+        }
         _ => panic!("illegal snowflake asset type: {asset_type:?}"),
     }
 }
@@ -166,9 +230,11 @@ mod tests {
 
     #[test]
     fn test_cual_from_name() -> Result<()> {
-        set_cual_account_name("account");
-        let c =
-            cual_from_snowflake_obj_name("SNOWFLAKE_SAMPLE_DATA.TPCDS_SF10TCL.WEB_PAGE", "TABLE")?;
+        let c = cual_from_snowflake_obj_name(
+            &CualAccount::new("account"),
+            "SNOWFLAKE_SAMPLE_DATA.TPCDS_SF10TCL.WEB_PAGE",
+            "TABLE",
+        )?;
         assert_eq!(
             c.uri(),
             "snowflake://account.snowflakecomputing.com/SNOWFLAKE_SAMPLE_DATA/TPCDS_SF10TCL/WEB_PAGE?type=TABLE".to_owned()
@@ -176,16 +242,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn account_grants_map_to_the_account_asset() -> Result<()> {
+        let account = CualAccount::new("account");
+        let c = cual_from_snowflake_obj_name_parts(&account, "ACCOUNT", "", "", "ACCOUNT")?;
+        assert_eq!(
+            c.uri(),
+            "snowflake://account.snowflakecomputing.com/ACCOUNT?type=ACCOUNT".to_owned()
+        );
+        assert_eq!(cual_to_snowflake_asset(&c).grant_target(), "ACCOUNT");
+        Ok(())
+    }
+
     #[test]
     fn table_cual_constructs_properly() {
-        set_cual_account_name("account");
         let cual = Object {
             name: "my_table".to_owned(),
             schema_name: "schema".to_owned(),
             database_name: "database".to_owned(),
             kind: ObjectKind::Table,
         }
-        .cual();
+        .cual(&CualAccount::new("account"));
         assert_eq!(
             cual,
             Cual::new(
@@ -196,14 +273,13 @@ mod tests {
 
     #[test]
     fn view_cual_constructs_properly() {
-        set_cual_account_name("account");
         let cual = Object {
             name: "my_table".to_owned(),
             schema_name: "schema".to_owned(),
             database_name: "database".to_owned(),
             kind: ObjectKind::View,
         }
-        .cual();
+        .cual(&CualAccount::new("account"));
         assert_eq!(
             cual,
             Cual::new(
@@ -214,12 +290,12 @@ mod tests {
 
     #[test]
     fn schema_cual_constructs_properly() {
-        set_cual_account_name("account");
         let cual = Schema {
             name: "my_schema".to_owned(),
             database_name: "database".to_owned(),
+            ..Default::default()
         }
-        .cual();
+        .cual(&CualAccount::new("account"));
         assert_eq!(
             cual,
             Cual::new("snowflake://account.snowflakecomputing.com/database/my_schema?type=SCHEMA")
@@ -228,17 +304,31 @@ mod tests {
 
     #[test]
     fn db_cual_constructs_properly() {
-        set_cual_account_name("account");
         let cual = Database {
             name: "my_db".to_owned(),
         }
-        .cual();
+        .cual(&CualAccount::new("account"));
         assert_eq!(
             cual,
             Cual::new("snowflake://account.snowflakecomputing.com/my_db?type=DATABASE")
         )
     }
 
+    #[test]
+    fn accounts_have_distinct_cuals() {
+        let db = Database {
+            name: "my_db".to_owned(),
+        };
+        let prod = CualAccount::new("PROD");
+        let dev = CualAccount::new("dev");
+        assert_eq!(prod.prefix(), "snowflake://prod.snowflakecomputing.com");
+        assert_ne!(db.cual(&prod), db.cual(&dev));
+        assert_eq!(
+            db.cual(&dev),
+            Cual::new("snowflake://dev.snowflakecomputing.com/my_db?type=DATABASE")
+        );
+    }
+
     #[test]
     fn cual_to_snowflake_asset_works_table() -> Result<()> {
         assert_eq!(
@@ -250,6 +340,74 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn routine_cuals_round_trip() -> Result<()> {
+        let account = CualAccount::new("account");
+        let routine = Routine {
+            name: "MY_FUNC".to_owned(),
+            schema_name: "schema".to_owned(),
+            database_name: "database".to_owned(),
+            arguments: "MY_FUNC(NUMBER, VARCHAR) RETURN NUMBER".to_owned(),
+            ..Default::default()
+        };
+        // Grants describe the routine differently, but should point to the same asset
+        assert_eq!(
+            routine.cual(&account),
+            cual_from_snowflake_obj_name_parts(
+                &account,
+                "MY_FUNC(A NUMBER(38,0), B VARCHAR):NUMBER(38,0)",
+                "database",
+                "schema",
+                "FUNCTION"
+            )?
+        );
+        assert_eq!(
+            cual_to_snowflake_asset(&routine.cual(&account)),
+            SnowflakeAsset::Function(
+                "\"database\".\"schema\".\"MY_FUNC\"(NUMBER, VARCHAR)".to_owned()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cual_to_snowflake_asset_works_materialized_view() -> Result<()> {
+        let cual = Object {
+            name: "my_mv".to_owned(),
+            schema_name: "schema".to_owned(),
+            database_name: "database".to_owned(),
+            kind: ObjectKind::MaterializedView,
+        }
+        .cual(&CualAccount::new("account"));
+        assert_eq!(
+            cual,
+            cual_from_snowflake_obj_name_parts(
+                &CualAccount::new("account"),
+                "my_mv",
+                "database",
+                "schema",
+                "MATERIALIZED_VIEW"
+            )?
+        );
+        let asset = cual_to_snowflake_asset(&cual);
+        assert_eq!(asset.asset_type(), "MATERIALIZED VIEW");
+        assert_eq!(asset.fqn(), "\"database\".\"schema\".\"my_mv\"");
+        Ok(())
+    }
+
+    #[test]
+    fn cual_to_snowflake_asset_works_warehouse() {
+        let cual = Warehouse::new("COMPUTE_WH".to_owned()).cual(&CualAccount::new("account"));
+        assert_eq!(
+            cual.uri(),
+            "snowflake://account.snowflakecomputing.com/COMPUTE_WH?type=WAREHOUSE"
+        );
+        assert_eq!(
+            cual_to_snowflake_asset(&cual),
+            SnowflakeAsset::Warehouse("\"COMPUTE_WH\"".to_owned())
+        );
+    }
+
     #[test]
     fn cual_to_snowflake_asset_works_db() -> Result<()> {
         assert_eq!(
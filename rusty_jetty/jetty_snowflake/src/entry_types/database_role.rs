@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    object_type_deserialize, split_snowflake_name, strip_quotes_and_deserialize,
+    strip_snowflake_quotes,
+};
+
+use super::{RoleName, StandardGrant};
+
+/// Snowflake database role entry, from `SHOW DATABASE ROLES`.
+#[derive(Clone, Default, Serialize, Deserialize, Debug)]
+pub struct DatabaseRole {
+    /// The role name, without the database
+    #[serde(deserialize_with = "strip_quotes_and_deserialize")]
+    pub name: String,
+    /// The database the role belongs to. `SHOW DATABASE ROLES` doesn't include it, so it's
+    /// filled in after fetching.
+    #[serde(default)]
+    pub database_name: String,
+}
+
+impl DatabaseRole {
+    /// Database roles are namespaced by their database, like `DB.ROLE`
+    pub(crate) fn role_name(&self) -> RoleName {
+        RoleName(format!("{}.{}", self.database_name, self.name))
+    }
+
+    /// Qualify the name of a database role grantee with this role's database, if needed.
+    /// Database roles can only be granted to roles in the same database.
+    pub(crate) fn qualify_grantee(&self, grantee_name: &str) -> String {
+        if grantee_name.contains('.') {
+            grantee_name.to_owned()
+        } else {
+            format!("{}.{}", self.database_name, grantee_name)
+        }
+    }
+}
+
+/// A privilege granted to a database role, from `SHOW GRANTS TO DATABASE ROLE`.
+#[derive(Clone, Default, Deserialize, Debug)]
+pub struct DatabaseRoleGrant {
+    pub privilege: String,
+    #[serde(deserialize_with = "object_type_deserialize")]
+    pub granted_on: String,
+    /// The fully-qualified name of the object the privilege is granted on
+    pub name: String,
+}
+
+impl DatabaseRoleGrant {
+    /// Convert to the same form as the grants to account roles, which are split into
+    /// database, schema, and object names
+    pub(crate) fn into_standard_grant(self, role: &DatabaseRole) -> StandardGrant {
+        let parts = split_snowflake_name(&self.name);
+        let part = |i: usize| {
+            parts
+                .get(i)
+                .map(|p| strip_snowflake_quotes(p.to_string(), false))
+                .unwrap_or_default()
+        };
+        let (table_catalog, table_schema, name) = match self.granted_on.as_str() {
+            "DATABASE" => (part(0), String::new(), part(0)),
+            "SCHEMA" => (part(0), String::new(), part(1)),
+            "ACCOUNT" | "WAREHOUSE" => (String::new(), String::new(), self.name.to_owned()),
+            // Anything after the schema is the object name
+            _ => (
+                part(0),
+                part(1),
+                strip_snowflake_quotes(parts.get(2..).unwrap_or_default().join("."), false),
+            ),
+        };
+        let RoleName(grantee_name) = role.role_name();
+        StandardGrant::new(
+            name,
+            table_catalog,
+            table_schema,
+            self.privilege,
+            self.granted_on,
+            grantee_name,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn database_role_grants_are_split_like_account_role_grants() {
+        let role = DatabaseRole {
+            name: "READER".to_owned(),
+            database_name: "ANALYTICS".to_owned(),
+        };
+        let grant = DatabaseRoleGrant {
+            privilege: "SELECT".to_owned(),
+            granted_on: "TABLE".to_owned(),
+            name: "ANALYTICS.MARTS.\"Orders\"".to_owned(),
+        };
+        assert_eq!(
+            grant.into_standard_grant(&role),
+            StandardGrant::new(
+                "Orders".to_owned(),
+                "ANALYTICS".to_owned(),
+                "MARTS".to_owned(),
+                "SELECT".to_owned(),
+                "TABLE".to_owned(),
+                "ANALYTICS.READER".to_owned(),
+            )
+        );
+        assert_eq!(role.qualify_grantee("WRITER"), "ANALYTICS.WRITER");
+        assert_eq!(role.qualify_grantee("ANALYTICS.WRITER"), "ANALYTICS.WRITER");
+    }
+    #[test]
+    fn quoted_names_with_periods_are_split_on_the_periods_between_them() {
+        let role = DatabaseRole {
+            name: "READER".to_owned(),
+            database_name: "ANALYTICS".to_owned(),
+        };
+        let grant = DatabaseRoleGrant {
+            privilege: "SELECT".to_owned(),
+            granted_on: "VIEW".to_owned(),
+            name: r#"ANALYTICS."Q3.2023"."Orders.v2""#.to_owned(),
+        };
+        assert_eq!(
+            grant.into_standard_grant(&role),
+            StandardGrant::new(
+                "Orders.v2".to_owned(),
+                "ANALYTICS".to_owned(),
+                "Q3.2023".to_owned(),
+                "SELECT".to_owned(),
+                "VIEW".to_owned(),
+                "ANALYTICS.READER".to_owned(),
+            )
+        );
+    }
+}
@@ -8,7 +8,11 @@ use jetty_core::connectors::{
 
 use serde::{Deserialize, Serialize};
 
-use crate::{consts, cual::cual_from_snowflake_obj_name};
+use crate::{
+    consts,
+    cual::{cual_from_snowflake_obj_name, CualAccount},
+    object_type_deserialize,
+};
 
 /// Snowflake future grant entry.
 ///
@@ -24,18 +28,23 @@ pub struct FutureGrant {
     name: String,
     privilege: String,
     /// This would be SCHEMA, TABLE, VIEW, etc.
+    #[serde(deserialize_with = "object_type_deserialize")]
     grant_on: String,
     // The role the future grant will apply to
     grantee_name: String,
+    /// ROLE or DATABASE_ROLE
+    #[serde(default)]
+    grant_to: String,
 }
 
 impl FutureGrant {
     pub(crate) fn into_default_policy(
         self,
         all_privileges: HashSet<String>,
+        account: &CualAccount,
     ) -> nodes::RawDefaultPolicy {
         let stripped_name = self.root_asset();
-        let cual = cual_from_snowflake_obj_name(stripped_name, self.grant_on()).unwrap();
+        let cual = cual_from_snowflake_obj_name(account, stripped_name, self.grant_on()).unwrap();
 
         let wildcard_path = if self.grant_on == "SCHEMA" {
             "/*"
@@ -69,6 +78,15 @@ impl FutureGrant {
         &self.grant_on
     }
 
+    /// Database roles are namespaced by their database in Jetty, like `DB.ROLE`. Future
+    /// grants can only go to database roles in the same database.
+    pub(crate) fn qualify_database_role_grantee(mut self, database: &str) -> Self {
+        if self.grant_to == "DATABASE_ROLE" && !self.grantee_name.contains('.') {
+            self.grantee_name = format!("{database}.{}", self.grantee_name);
+        }
+        self
+    }
+
     pub(crate) fn root_asset(&self) -> &str {
         self.name.split_once('<').unwrap().0.trim_end_matches('.')
     }
@@ -96,6 +114,13 @@ fn convert_to_asset_type(grant_on: &str) -> Result<AssetType> {
         "TABLE" => AssetType(consts::TABLE.to_owned()),
         "VIEW" => AssetType(consts::VIEW.to_owned()),
         "DATABASE" => AssetType(consts::DATABASE.to_owned()),
+        "FUNCTION" => AssetType(consts::FUNCTION.to_owned()),
+        "PROCEDURE" => AssetType(consts::PROCEDURE.to_owned()),
+        "STAGE" => AssetType(consts::STAGE.to_owned()),
+        "STREAM" => AssetType(consts::STREAM.to_owned()),
+        "TASK" => AssetType(consts::TASK.to_owned()),
+        "MATERIALIZED_VIEW" => AssetType(consts::MATERIALIZED_VIEW.to_owned()),
+        "EXTERNAL_TABLE" => AssetType(consts::EXTERNAL_TABLE.to_owned()),
         o => bail!("unable to handle asset type: {o}"),
     })
 }
@@ -2,10 +2,17 @@ pub use super::future_grant::FutureGrant;
 
 use std::collections::HashSet;
 
+use anyhow::Result;
 use jetty_core::connectors::nodes;
 use serde::{Deserialize, Serialize};
 
-use crate::cual::cual_from_snowflake_obj_name_parts;
+use crate::{
+    consts,
+    cual::{cual_from_snowflake_obj_name_parts, Cual, CualAccount},
+    object_type_deserialize,
+};
+
+use super::routine_signature;
 
 #[derive(Deserialize, Serialize, Clone, PartialEq, Eq, Hash)]
 pub enum GrantType {
@@ -18,7 +25,11 @@ pub trait Grant {
     fn role_name(&self) -> &str;
     fn privilege(&self) -> &str;
     fn granted_on(&self) -> &str;
-    fn into_policy(self, all_privileges: HashSet<String>) -> nodes::RawPolicy;
+    fn into_policy(
+        self,
+        all_privileges: HashSet<String>,
+        account: &CualAccount,
+    ) -> nodes::RawPolicy;
 }
 
 /// This can be totally reworked, but just leaving it is as
@@ -47,9 +58,13 @@ impl Grant for GrantType {
         }
     }
 
-    fn into_policy(self, all_privileges: HashSet<String>) -> nodes::RawPolicy {
+    fn into_policy(
+        self,
+        all_privileges: HashSet<String>,
+        account: &CualAccount,
+    ) -> nodes::RawPolicy {
         match self {
-            GrantType::Standard(s) => s.into_policy(all_privileges),
+            GrantType::Standard(s) => s.into_policy(all_privileges, account),
         }
     }
 }
@@ -63,18 +78,86 @@ pub struct StandardGrant {
     table_catalog: String,
     table_schema: String,
     pub(crate) privilege: String,
+    #[serde(deserialize_with = "object_type_deserialize")]
     granted_on: String,
     grantee_name: String,
 }
 
+impl StandardGrant {
+    pub(crate) fn new(
+        name: String,
+        table_catalog: String,
+        table_schema: String,
+        privilege: String,
+        granted_on: String,
+        grantee_name: String,
+    ) -> Self {
+        Self {
+            name,
+            table_catalog,
+            table_schema,
+            privilege,
+            granted_on,
+            grantee_name,
+        }
+    }
+
+    /// Get the cual for the asset the grant is on
+    pub(crate) fn cual(&self, account: &CualAccount) -> Result<Cual> {
+        cual_from_snowflake_obj_name_parts(
+            account,
+            &self.name,
+            &self.table_catalog,
+            &self.table_schema,
+            &self.granted_on,
+        )
+    }
+
+    /// Get the fully-qualified names and cuals of the database and schema that contain
+    /// the granted asset. The grant can only be used with USAGE on each of them.
+    pub(crate) fn containers(&self, account: &CualAccount) -> Result<Vec<(String, Cual)>> {
+        let database = (
+            self.table_catalog.to_owned(),
+            cual_from_snowflake_obj_name_parts(account, &self.table_catalog, "", "", "DATABASE")?,
+        );
+        Ok(match self.granted_on.as_str() {
+            t if consts::SCHEMA_OBJECT_TYPES.contains(&t) => vec![
+                database,
+                (
+                    format!("{}.{}", self.table_catalog, self.table_schema),
+                    cual_from_snowflake_obj_name_parts(
+                        account,
+                        &self.table_schema,
+                        &self.table_catalog,
+                        "",
+                        "SCHEMA",
+                    )?,
+                ),
+            ],
+            "SCHEMA" => vec![database],
+            _ => vec![],
+        })
+    }
+}
+
 impl Grant for StandardGrant {
     /// self.name corresponds to the object name when this is a grant on an object.
     fn granted_on_name(&self) -> String {
         match self.granted_on.as_str() {
-            "TABLE" | "VIEW" => {
+            // Routines are named by their signature, the same as their cuals
+            consts::FUNCTION | consts::PROCEDURE => format!(
+                "{}.{}.{}",
+                self.table_catalog,
+                self.table_schema,
+                routine_signature(&self.name)
+            ),
+            t if consts::SCHEMA_OBJECT_TYPES.contains(&t) => {
                 format!("{}.{}.{}", self.table_catalog, self.table_schema, self.name)
             }
             "DATABASE" => self.table_catalog.to_string(),
+            "WAREHOUSE" => self.name.to_owned(),
+            // Every account grant is on the same asset, whatever the account is called
+            "ACCOUNT" => consts::ACCOUNT_ASSET_NAME.to_owned(),
             "SCHEMA" => format!("{}.{}", self.table_catalog, self.name),
             _ => panic!("Unknown grant type: {}", self.granted_on),
         }
@@ -93,14 +176,12 @@ impl Grant for StandardGrant {
         &self.granted_on
     }
 
-    fn into_policy(self, all_privileges: HashSet<String>) -> nodes::RawPolicy {
-        let cual = cual_from_snowflake_obj_name_parts(
-            &self.name,
-            &self.table_catalog,
-            &self.table_schema,
-            &self.granted_on,
-        )
-        .unwrap();
+    fn into_policy(
+        self,
+        all_privileges: HashSet<String>,
+        account: &CualAccount,
+    ) -> nodes::RawPolicy {
+        let cual = self.cual(account).unwrap();
 
         let all_privileges = fix_privilege_names(all_privileges);
 
@@ -121,11 +202,19 @@ impl Grant for StandardGrant {
 }
 
 /// For some reason, the query returns at least one weird, improperly formatted privilege name. Fix that
-fn fix_privilege_names(mut privileges: HashSet<String>) -> HashSet<String> {
-    if privileges.remove("REFERENCE USAGE") {
-        privileges.insert("REFERENCE_USAGE".to_owned());
-    };
+fn fix_privilege_names(privileges: HashSet<String>) -> HashSet<String> {
     privileges
+        .into_iter()
+        .map(|p| fix_privilege_name(&p))
+        .collect()
+}
+
+/// Fix a single privilege name. See [`fix_privilege_names`].
+pub(crate) fn fix_privilege_name(privilege: &str) -> String {
+    match privilege {
+        "REFERENCE USAGE" => "REFERENCE_USAGE".to_owned(),
+        p => p.to_owned(),
+    }
 }
 
 #[cfg(test)]
@@ -133,13 +222,11 @@ mod tests {
 
     use anyhow::Result;
 
-    use crate::cual::set_cual_account_name;
-
     use super::*;
 
     #[test]
     fn grant_into_policy_works() -> Result<()> {
-        set_cual_account_name("account");
+        let account = CualAccount::new("account");
         let g = StandardGrant {
             name: "db".to_owned(),
             privilege: "priv".to_owned(),
@@ -148,15 +235,16 @@ mod tests {
             table_catalog: "db".to_owned(),
             table_schema: "".to_owned(),
         };
-        let p: nodes::RawPolicy = g.into_policy(HashSet::from(["priv".to_owned()]));
+        let p: nodes::RawPolicy = g.into_policy(HashSet::from(["priv".to_owned()]), &account);
         assert_eq!(
             p,
             nodes::RawPolicy::new(
                 "snowflake.grantee_name.db".to_owned(),
                 HashSet::from(["priv".to_owned()]),
-                HashSet::from([
-                    cual_from_snowflake_obj_name_parts("db", "db", "", "DATABASE")?.uri()
-                ]),
+                HashSet::from([cual_from_snowflake_obj_name_parts(
+                    &account, "db", "db", "", "DATABASE"
+                )?
+                .uri()]),
                 HashSet::new(),
                 HashSet::from(["grantee_name".to_owned()]),
                 HashSet::new(),
@@ -169,7 +257,7 @@ mod tests {
 
     #[test]
     fn future_grant_to_policy_results_in_idempotent_name() {
-        set_cual_account_name("account");
+        let account = CualAccount::new("account");
         let g = StandardGrant {
             name: "db".to_owned(),
             privilege: "priv".to_owned(),
@@ -178,9 +266,13 @@ mod tests {
             table_catalog: "db".to_owned(),
             table_schema: "".to_owned(),
         };
-        let p: nodes::RawPolicy = g.clone().into_policy(HashSet::from(["priv".to_owned()]));
-        let p2: nodes::RawPolicy = g.clone().into_policy(HashSet::from(["priv".to_owned()]));
-        let p3: nodes::RawPolicy = g.into_policy(HashSet::from(["priv".to_owned()]));
+        let p: nodes::RawPolicy = g
+            .clone()
+            .into_policy(HashSet::from(["priv".to_owned()]), &account);
+        let p2: nodes::RawPolicy = g
+            .clone()
+            .into_policy(HashSet::from(["priv".to_owned()]), &account);
+        let p3: nodes::RawPolicy = g.into_policy(HashSet::from(["priv".to_owned()]), &account);
         assert_eq!(p.name, "snowflake.grantee_name.db");
         assert_eq!(p2.name, p.name);
         assert_eq!(p3.name, p2.name);
@@ -188,7 +280,7 @@ mod tests {
 
     #[test]
     fn future_grant_to_policy_with_extra_privileges_works() {
-        set_cual_account_name("account");
+        let account = CualAccount::new("account");
         let g = StandardGrant {
             name: "db".to_owned(),
             privilege: "priv".to_owned(),
@@ -197,8 +289,10 @@ mod tests {
             table_catalog: "db".to_owned(),
             table_schema: "".to_owned(),
         };
-        let p: nodes::RawPolicy =
-            g.into_policy(HashSet::from(["priv".to_owned(), "priv2".to_owned()]));
+        let p: nodes::RawPolicy = g.into_policy(
+            HashSet::from(["priv".to_owned(), "priv2".to_owned()]),
+            &account,
+        );
         assert_eq!(p.name, "snowflake.grantee_name.db");
         assert_eq!(
             p.privileges,
@@ -1,23 +1,39 @@
 mod asset;
 mod database;
+mod database_role;
 mod entry;
 mod future_grant;
 mod grant;
 mod grant_of;
 mod object;
+mod object_dependency;
+mod policy_reference;
 mod role;
+mod routine;
 mod schema;
+mod tag_reference;
 mod user;
 mod warehouse;
 
 pub use asset::Asset;
 pub use database::Database;
+pub use database_role::{DatabaseRole, DatabaseRoleGrant};
 pub use entry::Entry;
+pub(crate) use grant::fix_privilege_name;
 pub use grant::GrantType;
 pub use grant::{FutureGrant, Grant, StandardGrant};
 pub use grant_of::GrantOf;
 pub use object::{Object, ObjectKind};
+pub use object_dependency::ObjectDependency;
+pub use policy_reference::PolicyReference;
+pub(crate) use policy_reference::{
+    ACCOUNT_DOMAIN, MASKING_POLICY, NETWORK_POLICY, ROW_ACCESS_POLICY, SESSION_POLICY, USER_DOMAIN,
+};
 pub use role::{Role, RoleName};
+pub(crate) use routine::routine_signature;
+pub use routine::{Routine, RoutineKind};
 pub use schema::Schema;
+pub use tag_reference::TagReference;
 pub use user::User;
+pub(crate) use user::UserProperty;
 pub use warehouse::Warehouse;
@@ -1,29 +1,50 @@
 use serde::{Deserialize, Serialize};
+
+use crate::consts;
+
 /// A type of object.
-#[derive(Copy, Clone, Default, Deserialize, Serialize, Debug)]
+#[derive(Copy, Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq, Hash)]
 pub enum ObjectKind {
+    /// `ACCOUNT_USAGE.TABLES` calls tables `BASE TABLE`
     #[default]
-    #[serde(rename = "TABLE")]
+    #[serde(rename = "TABLE", alias = "BASE TABLE")]
     Table,
     #[serde(rename = "VIEW")]
     View,
+    #[serde(rename = "STAGE")]
+    Stage,
+    #[serde(rename = "STREAM")]
+    Stream,
+    #[serde(rename = "TASK")]
+    Task,
+    #[serde(rename = "MATERIALIZED_VIEW", alias = "MATERIALIZED VIEW")]
+    MaterializedView,
+    #[serde(rename = "EXTERNAL_TABLE", alias = "EXTERNAL TABLE")]
+    ExternalTable,
 }
 
 impl ToString for ObjectKind {
     fn to_string(&self) -> String {
         match self {
-            ObjectKind::Table => "TABLE".to_string(),
-            ObjectKind::View => "VIEW".to_string(),
+            ObjectKind::Table => consts::TABLE.to_string(),
+            ObjectKind::View => consts::VIEW.to_string(),
+            ObjectKind::Stage => consts::STAGE.to_string(),
+            ObjectKind::Stream => consts::STREAM.to_string(),
+            ObjectKind::Task => consts::TASK.to_string(),
+            ObjectKind::MaterializedView => consts::MATERIALIZED_VIEW.to_string(),
+            ObjectKind::ExternalTable => consts::EXTERNAL_TABLE.to_string(),
         }
     }
 }
-/// Snowflake Table entry.
+/// Snowflake schema-level object entry: a table, view, stage, stream, task, etc.
 #[derive(Clone, Default, Deserialize, Serialize, Debug)]
 pub struct Object {
     /// The Table name in Snowflake.
     pub name: String,
     pub schema_name: String,
     pub database_name: String,
+    /// `SHOW OBJECTS` includes the kind. For other queries it's set after fetching.
+    #[serde(default)]
     pub kind: ObjectKind,
 }
 
@@ -32,3 +53,32 @@ impl Object {
         format!("{}.{}.{}", self.database_name, self.schema_name, self.name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn account_usage_table_types_are_mapped_to_kinds() {
+        for (table_type, kind) in [
+            ("BASE TABLE", ObjectKind::Table),
+            ("VIEW", ObjectKind::View),
+            ("MATERIALIZED VIEW", ObjectKind::MaterializedView),
+            ("EXTERNAL TABLE", ObjectKind::ExternalTable),
+            // The kinds reported by SHOW OBJECTS
+            ("TABLE", ObjectKind::Table),
+            ("MATERIALIZED_VIEW", ObjectKind::MaterializedView),
+        ] {
+            let object: Object = serde_json::from_value(json!({
+                "name": "USERS",
+                "schema_name": "RAW",
+                "database_name": "DB",
+                "kind": table_type,
+            }))
+            .unwrap();
+            assert_eq!(object.kind, kind, "{table_type}");
+        }
+    }
+}
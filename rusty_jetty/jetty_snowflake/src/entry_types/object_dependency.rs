@@ -0,0 +1,49 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cual::{cual_from_snowflake_obj_name_parts, Cual, CualAccount},
+    object_type_deserialize,
+};
+
+/// A dependency of one object on another, from `SNOWFLAKE.ACCOUNT_USAGE.OBJECT_DEPENDENCIES`,
+/// like a view on the table it selects from. Writes recorded in `ACCESS_HISTORY` are read
+/// into the same shape, with the written object as the referencing object.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct ObjectDependency {
+    pub referenced_database: String,
+    pub referenced_schema: String,
+    pub referenced_object_name: String,
+    #[serde(deserialize_with = "object_type_deserialize")]
+    pub referenced_object_domain: String,
+    pub referencing_database: String,
+    pub referencing_schema: String,
+    pub referencing_object_name: String,
+    #[serde(deserialize_with = "object_type_deserialize")]
+    pub referencing_object_domain: String,
+}
+
+impl ObjectDependency {
+    /// The cual of the object that's depended on
+    pub(crate) fn referenced_cual(&self, account: &CualAccount) -> Result<Cual> {
+        cual_from_snowflake_obj_name_parts(
+            account,
+            &self.referenced_object_name,
+            &self.referenced_database,
+            &self.referenced_schema,
+            &self.referenced_object_domain,
+        )
+    }
+
+    /// The cual of the object that's derived from the referenced object
+    pub(crate) fn referencing_cual(&self, account: &CualAccount) -> Result<Cual> {
+        cual_from_snowflake_obj_name_parts(
+            account,
+            &self.referencing_object_name,
+            &self.referencing_database,
+            &self.referencing_schema,
+            &self.referencing_object_domain,
+        )
+    }
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+pub(crate) const MASKING_POLICY: &str = "MASKING_POLICY";
+pub(crate) const ROW_ACCESS_POLICY: &str = "ROW_ACCESS_POLICY";
+pub(crate) const NETWORK_POLICY: &str = "NETWORK_POLICY";
+pub(crate) const SESSION_POLICY: &str = "SESSION_POLICY";
+
+pub(crate) const USER_DOMAIN: &str = "USER";
+pub(crate) const ACCOUNT_DOMAIN: &str = "ACCOUNT";
+
+/// A masking, row access, network, or session policy attached directly to an object, user,
+/// or the account, from `SNOWFLAKE.ACCOUNT_USAGE.POLICY_REFERENCES`.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct PolicyReference {
+    /// Empty for network policies, which belong to the account rather than a schema
+    pub policy_db: String,
+    /// Empty for network policies
+    pub policy_schema: String,
+    pub policy_name: String,
+    /// MASKING_POLICY, ROW_ACCESS_POLICY, NETWORK_POLICY, or SESSION_POLICY
+    pub policy_kind: String,
+    /// The kind of thing the policy is attached to, like TABLE, USER, or ACCOUNT
+    #[serde(default)]
+    pub ref_entity_domain: String,
+    pub ref_database_name: String,
+    pub ref_schema_name: String,
+    pub ref_entity_name: String,
+    /// The column a masking policy is set on. Empty for row access policies.
+    #[serde(default)]
+    pub ref_column_name: String,
+    /// The columns passed to a row access policy, as a JSON array. Empty for masking
+    /// policies.
+    #[serde(default)]
+    pub ref_arg_column_names: String,
+}
+
+impl PolicyReference {
+    /// The fully-qualified name of the policy. Network policies aren't in a schema, so
+    /// they're just named.
+    pub(crate) fn policy_fqn(&self) -> String {
+        if self.policy_db.is_empty() {
+            return self.policy_name.to_owned();
+        }
+        format!(
+            "{}.{}.{}",
+            self.policy_db, self.policy_schema, self.policy_name
+        )
+    }
+
+    /// The columns passed to a row access policy
+    pub(crate) fn arg_columns(&self) -> Vec<String> {
+        serde_json::from_str(&self.ref_arg_column_names).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_access_policy_columns_are_parsed() {
+        let reference = PolicyReference {
+            policy_db: "GOVERNANCE".to_owned(),
+            policy_schema: "POLICIES".to_owned(),
+            policy_name: "REGION_FILTER".to_owned(),
+            policy_kind: ROW_ACCESS_POLICY.to_owned(),
+            ref_arg_column_names: "[ \"REGION\", \"COUNTRY\" ]".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(reference.policy_fqn(), "GOVERNANCE.POLICIES.REGION_FILTER");
+        assert_eq!(reference.arg_columns(), vec!["REGION", "COUNTRY"]);
+        assert!(PolicyReference::default().arg_columns().is_empty());
+    }
+
+    #[test]
+    fn network_policies_are_named_without_a_schema() {
+        let reference = PolicyReference {
+            policy_name: "OFFICE_ONLY".to_owned(),
+            policy_kind: NETWORK_POLICY.to_owned(),
+            ref_entity_domain: USER_DOMAIN.to_owned(),
+            ref_entity_name: "ELLIOT".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(reference.policy_fqn(), "OFFICE_ONLY");
+    }
+}
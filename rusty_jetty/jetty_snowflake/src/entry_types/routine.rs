@@ -0,0 +1,135 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consts::{FUNCTION, PROCEDURE};
+
+/// The kind of routine: a user-defined function or a stored procedure.
+#[derive(Copy, Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+pub enum RoutineKind {
+    #[default]
+    Function,
+    Procedure,
+}
+
+impl Display for RoutineKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RoutineKind::Function => write!(f, "{FUNCTION}"),
+            RoutineKind::Procedure => write!(f, "{PROCEDURE}"),
+        }
+    }
+}
+
+/// Snowflake user-defined function or stored procedure entry.
+///
+/// Routines can be overloaded, so they are identified by their signature
+/// (name and argument types) rather than their name alone.
+#[derive(Clone, Default, Deserialize, Serialize, Debug)]
+pub struct Routine {
+    /// The routine name in Snowflake.
+    pub name: String,
+    pub schema_name: String,
+    #[serde(rename = "catalog_name")]
+    pub database_name: String,
+    /// The name, argument types, and return type, like
+    /// `MY_FUNC(NUMBER, VARCHAR) RETURN NUMBER`
+    pub arguments: String,
+    /// "Y" for routines that are built into Snowflake
+    #[serde(default)]
+    pub is_builtin: String,
+    /// Not returned by Snowflake. This is set based on the query used to fetch the routine.
+    #[serde(skip)]
+    pub kind: RoutineKind,
+}
+
+impl Routine {
+    /// The fully-qualified signature, like `DB.SCHEMA.MY_FUNC(NUMBER, VARCHAR)`. This is the
+    /// name routines are filtered by.
+    pub(crate) fn fqn(&self) -> String {
+        format!(
+            "{}.{}.{}",
+            self.database_name,
+            self.schema_name,
+            self.signature()
+        )
+    }
+
+    /// The routine name and argument types, like `MY_FUNC(NUMBER, VARCHAR)`
+    pub(crate) fn signature(&self) -> String {
+        routine_signature(&self.arguments)
+    }
+
+    /// Whether the routine is built into Snowflake
+    pub(crate) fn is_builtin(&self) -> bool {
+        self.is_builtin == "Y"
+    }
+}
+
+/// Normalize a routine description into its name and argument types.
+///
+/// Snowflake describes routines in a few different ways. `SHOW FUNCTIONS` gives
+/// `MY_FUNC(NUMBER, VARCHAR) RETURN NUMBER`, while grants name them like
+/// `MY_FUNC(A NUMBER(38,0), B VARCHAR):NUMBER(38,0)`. Both become
+/// `MY_FUNC(NUMBER, VARCHAR)`.
+pub(crate) fn routine_signature(description: &str) -> String {
+    let (name, rest) = match description.split_once('(') {
+        Some(parts) => parts,
+        None => return description.trim().to_owned(),
+    };
+
+    // Split the arguments on top-level commas, stopping at the closing parenthesis
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in rest.chars() {
+        match c {
+            '(' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' if depth == 0 => break,
+            ')' => {
+                depth -= 1;
+                current.push(c);
+            }
+            ',' if depth == 0 => args.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    args.push(current);
+
+    let arg_types = args
+        .iter()
+        .filter_map(|arg| {
+            // Drop any precision, like the (38,0) in NUMBER(38,0)
+            let arg = arg.split_once('(').map(|(a, _)| a).unwrap_or(arg);
+            // Drop the argument name, if there is one
+            arg.split_whitespace().last()
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{}({arg_types})", name.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_are_normalized() {
+        assert_eq!(
+            routine_signature("MY_FUNC(NUMBER, VARCHAR) RETURN NUMBER"),
+            "MY_FUNC(NUMBER, VARCHAR)"
+        );
+        assert_eq!(
+            routine_signature("MY_FUNC(A NUMBER(38,0), B VARCHAR):NUMBER(38,0)"),
+            "MY_FUNC(NUMBER, VARCHAR)"
+        );
+        assert_eq!(
+            routine_signature("NO_ARGS() RETURN TABLE (A NUMBER)"),
+            "NO_ARGS()"
+        );
+        assert_eq!(routine_signature("NO_ARGS():VARCHAR"), "NO_ARGS()");
+    }
+}
@@ -6,6 +6,12 @@ pub struct Schema {
     /// The schema name in Snowflake.
     pub name: String,
     pub database_name: String,
+    /// The role that owns the schema
+    #[serde(default)]
+    pub owner: String,
+    /// Comma-separated schema options, like `TRANSIENT, MANAGED ACCESS`
+    #[serde(default)]
+    pub options: String,
 }
 
 impl Schema {
@@ -13,10 +19,19 @@ impl Schema {
         Self {
             name,
             database_name,
+            ..Default::default()
         }
     }
 
     pub(crate) fn fqn(&self) -> String {
         format!("{}.{}", self.database_name, self.name)
     }
+
+    /// Whether grants on the objects in the schema are managed by the schema owner rather
+    /// than by each object's owner
+    pub(crate) fn is_managed_access(&self) -> bool {
+        self.options
+            .split(',')
+            .any(|option| option.trim() == "MANAGED ACCESS")
+    }
 }
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+
+/// A tag applied directly to an object, from `SNOWFLAKE.ACCOUNT_USAGE.TAG_REFERENCES`.
+#[derive(Clone, Default, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct TagReference {
+    pub tag_database: String,
+    pub tag_schema: String,
+    pub tag_name: String,
+    /// Empty when the tag was set without a value.
+    #[serde(default)]
+    pub tag_value: String,
+    /// Empty for databases and warehouses.
+    #[serde(default)]
+    pub object_database: String,
+    /// Empty for databases, schemas, and warehouses.
+    #[serde(default)]
+    pub object_schema: String,
+    pub object_name: String,
+    /// The kind of object the tag is applied to, like DATABASE, TABLE, or COLUMN.
+    pub domain: String,
+}
+
+impl TagReference {
+    /// The fully-qualified name of the tag itself
+    pub(crate) fn tag_fqn(&self) -> String {
+        format!(
+            "{}.{}.{}",
+            self.tag_database, self.tag_schema, self.tag_name
+        )
+    }
+
+    pub(crate) fn value(&self) -> Option<String> {
+        (!self.tag_value.is_empty()).then(|| self.tag_value.to_owned())
+    }
+
+    /// The name of the tag in Jetty. Snowflake tag values are set per object, so each
+    /// value gets its own Jetty tag, like `DB.SCHEMA.PII=email`.
+    pub(crate) fn jetty_tag_name(&self) -> String {
+        match self.value() {
+            Some(value) => format!("{}={value}", self.tag_fqn()),
+            None => self.tag_fqn(),
+        }
+    }
+
+    /// Whether the tag lives in the given schema, written as `DB.SCHEMA`
+    pub(crate) fn is_in_schema(&self, schema: &str) -> bool {
+        schema == format!("{}.{}", self.tag_database, self.tag_schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_values_are_part_of_the_name() {
+        let mut reference = TagReference {
+            tag_database: "GOVERNANCE".to_owned(),
+            tag_schema: "TAGS".to_owned(),
+            tag_name: "PII".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(reference.jetty_tag_name(), "GOVERNANCE.TAGS.PII");
+        assert_eq!(reference.value(), None);
+
+        reference.tag_value = "email".to_owned();
+        assert_eq!(reference.jetty_tag_name(), "GOVERNANCE.TAGS.PII=email");
+        assert_eq!(reference.value(), Some("email".to_owned()));
+        assert!(reference.is_in_schema("GOVERNANCE.TAGS"));
+        assert!(!reference.is_in_schema("GOVERNANCE.OTHER"));
+    }
+}
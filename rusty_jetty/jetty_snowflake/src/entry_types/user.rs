@@ -12,6 +12,24 @@ pub struct User {
     pub display_name: String,
     #[serde(deserialize_with = "deserialize_bool")]
     pub disabled: bool,
+    #[serde(default)]
+    pub default_role: String,
+    #[serde(default)]
+    pub default_warehouse: String,
+    #[serde(default)]
+    pub default_namespace: String,
+    #[serde(default, deserialize_with = "deserialize_bool")]
+    pub has_rsa_public_key: bool,
+    /// PERSON, SERVICE, or LEGACY_SERVICE. Empty for users created before user types existed.
+    #[serde(default, rename = "type")]
+    pub user_type: String,
+}
+
+/// A row of `DESC USER`
+#[derive(Clone, Deserialize, Debug, Default)]
+pub(crate) struct UserProperty {
+    pub(crate) property: String,
+    pub(crate) value: String,
 }
 
 impl User {
@@ -32,10 +50,13 @@ impl User {
             login_name,
             display_name,
             disabled,
+            ..Default::default()
         }
     }
 }
 
+/// Deserialize a boolean from Snowflake's "true" or "false". Null values come back as empty
+/// strings, and are false.
 fn deserialize_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: de::Deserializer<'de>,
@@ -44,8 +65,10 @@ where
 
     match s.as_str() {
         "true" => Ok(true),
-        "false" => Ok(false),
-        _ => panic!("unknown value for disabled field"),
+        "false" | "" => Ok(false),
+        other => Err(de::Error::custom(format!(
+            "unknown value for boolean field: {other:?}"
+        ))),
     }
 }
 
@@ -88,4 +111,40 @@ mod tests {
         assert!(user.disabled);
         Ok(())
     }
+    #[test]
+    fn empty_booleans_are_false() -> Result<()> {
+        let user_json = json! {
+            {
+                "name": "name",
+                "first_name": "first",
+                "last_name": "last",
+                "email": "elliot@allsafe.com",
+                "login_name": "hax0rz",
+                "display_name": "honeypot",
+                "disabled": "false",
+                "has_rsa_public_key": "",
+            }
+        };
+
+        let user: User = serde_json::from_value(user_json)?;
+        assert!(!user.has_rsa_public_key);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_booleans_are_errors() {
+        let user_json = json! {
+            {
+                "name": "name",
+                "first_name": "first",
+                "last_name": "last",
+                "email": "elliot@allsafe.com",
+                "login_name": "hax0rz",
+                "display_name": "honeypot",
+                "disabled": "maybe",
+            }
+        };
+
+        assert!(serde_json::from_value::<User>(user_json).is_err());
+    }
 }
@@ -1,8 +1,14 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Snowflake Warehouse entry.
-#[derive(Default, Deserialize, Debug)]
+#[derive(Clone, Default, Deserialize, Serialize, Debug)]
 pub struct Warehouse {
-    /// The database name in Snowflake.
+    /// The warehouse name in Snowflake.
     pub name: String,
 }
+
+impl Warehouse {
+    pub fn new(name: String) -> Self {
+        Self { name }
+    }
+}
@@ -0,0 +1,195 @@
+//! Name patterns for the connector's include and exclude filters.
+//!
+//! A pattern is either a glob, where `*` matches any run of characters (including dots) and
+//! `?` matches a single character, or a regular expression prefixed with `regex:`.
+//! Globs match the whole name; regular expressions match anywhere in it unless they're
+//! anchored.
+
+use std::fmt::Debug;
+
+use anyhow::{Context, Result};
+use jetty_core::cual::Cual;
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::{consts, SnowflakeConnector};
+
+const REGEX_PREFIX: &str = "regex:";
+
+/// A compiled include or exclude pattern
+#[derive(Clone)]
+pub(crate) struct NamePattern {
+    source: String,
+    regex: Regex,
+}
+
+impl NamePattern {
+    pub(crate) fn new(pattern: &str) -> Result<Self> {
+        let regex = match pattern.strip_prefix(REGEX_PREFIX) {
+            Some(regex) => Regex::new(regex),
+            None => Regex::new(&glob_to_regex(pattern)),
+        }
+        .context(format!("invalid name pattern: {pattern}"))?;
+        Ok(Self {
+            source: pattern.to_owned(),
+            regex,
+        })
+    }
+
+    /// Whether the pattern is a regular expression rather than a glob
+    pub(crate) fn is_regex(pattern: &str) -> bool {
+        pattern.starts_with(REGEX_PREFIX)
+    }
+
+    pub(crate) fn is_match(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl Debug for NamePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.source)
+    }
+}
+
+impl<'de> Deserialize<'de> for NamePattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        NamePattern::new(&pattern).map_err(|e| serde::de::Error::custom(format!("{e:#}")))
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let body = glob
+        .chars()
+        .map(|c| match c {
+            '*' => ".*".to_owned(),
+            '?' => ".".to_owned(),
+            c => regex::escape(&c.to_string()),
+        })
+        .collect::<String>();
+    format!("^{body}$")
+}
+
+/// Include and exclude patterns for roles or users. Everything is included when there are
+/// no include patterns, and exclude patterns take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NameFilter {
+    #[serde(default)]
+    include: Option<Vec<NamePattern>>,
+    #[serde(default)]
+    exclude: Vec<NamePattern>,
+}
+
+impl NameFilter {
+    pub(crate) fn allows(&self, name: &str) -> bool {
+        let included = match &self.include {
+            Some(patterns) => patterns.iter().any(|p| p.is_match(name)),
+            None => true,
+        };
+        included && !self.exclude.iter().any(|p| p.is_match(name))
+    }
+}
+
+impl SnowflakeConnector {
+    /// Whether the include and exclude filters leave out any databases, schemas, or objects
+    pub(crate) fn filters_assets(&self) -> bool {
+        self.config.include.is_some() || !self.config.exclude.is_empty()
+    }
+
+    /// Whether an asset, by its dotted name, passes the include and exclude filters.
+    /// Excluding a database or schema excludes everything in it.
+    pub(crate) fn include_asset(&self, asset_name: &str) -> bool {
+        let included = match &self.config.include {
+            Some(patterns) => patterns.iter().any(|p| p.is_match(asset_name)),
+            // If there are no include paths, we include everything.
+            None => true,
+        };
+        let parts = asset_name.split('.').collect::<Vec<_>>();
+        let excluded = (1..parts.len() + 1).any(|i| {
+            let prefix = parts[0..i].join(".");
+            self.config.exclude.iter().any(|p| p.is_match(&prefix))
+        });
+        included && !excluded
+    }
+
+    /// Whether an asset passes the include and exclude filters. Like when fetching, the
+    /// filters don't apply to warehouses or the account.
+    pub(crate) fn include_cual(&self, cual: &Cual) -> bool {
+        let asset_type = cual.asset_type().map(|t| t.to_string()).unwrap_or_default();
+        if !self.filters_assets()
+            || [consts::WAREHOUSE, consts::ACCOUNT].contains(&asset_type.as_str())
+        {
+            return true;
+        }
+        // Routines are filtered by their signature, the same as when they're fetched
+        let path = cual
+            .asset_path()
+            .components()
+            .iter()
+            .map(|segment| {
+                urlencoding::decode(segment)
+                    .map(|s| s.into_owned())
+                    .unwrap_or_else(|_| segment.to_owned())
+            })
+            .collect::<Vec<_>>();
+        self.include_asset(&path.join("."))
+    }
+
+    /// Whether a role passes the role filters. Database roles are named `DB.ROLE`.
+    pub(crate) fn include_role(&self, role_name: &str) -> bool {
+        self.config.roles.allows(role_name)
+    }
+
+    /// Whether a user passes the user filters
+    pub(crate) fn include_user(&self, user_name: &str) -> bool {
+        self.config.users.allows(user_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: Option<&[&str]>, exclude: &[&str]) -> NameFilter {
+        NameFilter {
+            include: include.map(|i| i.iter().map(|p| NamePattern::new(p).unwrap()).collect()),
+            exclude: exclude
+                .iter()
+                .map(|p| NamePattern::new(p).unwrap())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn globs_match_whole_names() {
+        let pattern = NamePattern::new("*.SCRATCH_*").unwrap();
+        assert!(pattern.is_match("DB.SCRATCH_ELLEN"));
+        assert!(!pattern.is_match("DB.RAW"));
+        // Dots and other regex characters in globs are literal
+        assert!(!NamePattern::new("DB.RAW").unwrap().is_match("DBXRAW"));
+        assert!(NamePattern::new("DEV_?").unwrap().is_match("DEV_1"));
+    }
+
+    #[test]
+    fn regex_patterns_work() {
+        let pattern = NamePattern::new("regex:^SVC_[0-9]+$").unwrap();
+        assert!(pattern.is_match("SVC_12"));
+        assert!(!pattern.is_match("SVC_ETL"));
+        assert!(NamePattern::new("regex:(").is_err());
+    }
+
+    #[test]
+    fn excludes_take_precedence() {
+        let roles = filter(Some(&["ANALYST*", "regex:^SCIM_"]), &["*_OLD"]);
+        assert!(roles.allows("ANALYST"));
+        assert!(roles.allows("SCIM_FINANCE"));
+        assert!(!roles.allows("ANALYST_OLD"));
+        assert!(!roles.allows("LOADER"));
+        assert!(filter(None, &[]).allows("ANYTHING"));
+    }
+}
@@ -19,21 +19,29 @@ mod coordinator;
 mod creds;
 mod cual;
 mod entry_types;
+mod filter;
 mod rest;
 mod write;
 
-use cual::set_cual_account_name;
+pub use creds::{LoginMethod, SnowflakeCredentials};
+
+use cual::CualAccount;
+use entry_types::UserProperty;
 pub use entry_types::{
-    Asset, Database, Entry, FutureGrant, Grant, GrantOf, GrantType, Object, Role, RoleName, Schema,
-    StandardGrant, User, Warehouse,
+    Asset, Database, DatabaseRole, DatabaseRoleGrant, Entry, FutureGrant, Grant, GrantOf,
+    GrantType, Object, ObjectDependency, ObjectKind, PolicyReference, Role, RoleName, Routine,
+    RoutineKind, Schema, StandardGrant, TagReference, User, Warehouse,
 };
+use filter::{NameFilter, NamePattern};
 use futures::StreamExt;
 use jetty_core::access_graph::translate::diffs::LocalConnectorDiffs;
 use jetty_core::connectors::{
-    AssetType, ConnectorCapabilities, NewConnector, ReadCapabilities, WriteCapabilities,
+    ownership::OWNERSHIP_PRIVILEGE, registry::ConnectorRegistry, AssetType, ConnectorCapabilities,
+    NewConnector, ReadCapabilities, ScopedObject, WriteCapabilities,
 };
 use jetty_core::jetty::ConnectorManifest;
-use jetty_core::logging::{debug, error};
+use jetty_core::logging::{debug, error, warn};
+use jetty_core::write::apply_report::{ApplyReport, PlannedOperation};
 
 use rest::{SnowflakeRequestConfig, SnowflakeRestClient, SnowflakeRestConfig};
 use serde::de::value::MapDeserializer;
@@ -42,6 +50,7 @@ use std::collections::{HashMap, HashSet};
 use std::iter::zip;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 
 use jetty_core::{
     connectors,
@@ -49,7 +58,7 @@ use jetty_core::{
     jetty::{ConnectorConfig, CredentialsMap},
 };
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{bail, Context, Result};
 use async_trait::async_trait;
 use serde::{Deserialize, Deserializer};
 use serde_json::Value as JsonValue;
@@ -63,35 +72,103 @@ pub struct SnowflakeConnector {
     rest_client: SnowflakeRestClient,
     client: connectors::ConnectorClient,
     config: SnowflakeConnectorConfig,
+    /// The account-specific part of the cuals for this connector's assets
+    cual_account: CualAccount,
+    /// Where the connector keeps track of the tags it manages between runs
+    data_dir: Option<PathBuf>,
 }
 
 /// The configuration values from the jetty_config entry for the connector
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub(crate) struct SnowflakeConnectorConfig {
-    include: Option<HashSet<String>>,
+    /// The databases, schemas, and objects to fetch, as dotted names or patterns. The
+    /// databases and schemas that contain them are fetched too.
+    #[serde(default, deserialize_with = "deserialize_include")]
+    include: Option<Vec<NamePattern>>,
+    /// The databases, schemas, and objects to leave out, as dotted names or patterns.
+    /// Excluding a database or schema excludes everything in it.
+    #[serde(default)]
+    exclude: Vec<NamePattern>,
+    /// The roles to fetch and manage
+    #[serde(default)]
+    roles: NameFilter,
+    /// The users to fetch and manage
+    #[serde(default)]
+    users: NameFilter,
+    /// The schema, written as `DB.SCHEMA`, that tags from the tags configuration are
+    /// written to. Tags are only written back when this is set.
+    #[serde(default)]
+    tag_schema: Option<String>,
+    /// Whether changes to ACCOUNTADMIN, SECURITYADMIN, and SYSADMIN may be applied.
+    /// They're flagged in plans and skipped on apply unless this is set.
+    #[serde(default)]
+    allow_system_role_changes: bool,
+    /// How many days of `ACCESS_HISTORY` to read write lineage from, like tables created
+    /// with CREATE TABLE AS SELECT. Only views and other declared dependencies are read
+    /// when this isn't set.
+    #[serde(default)]
+    access_history_lineage_days: Option<u32>,
+    /// How objects and role grants are read
+    #[serde(default)]
+    fetch_strategy: FetchStrategy,
+}
+
+/// The queries the connector uses to read objects and role grants
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum FetchStrategy {
+    /// `SHOW` queries for each schema and role. These are always up to date, but there are
+    /// a lot of them in large accounts.
+    #[default]
+    Show,
+    /// A few bulk queries against the `SNOWFLAKE.ACCOUNT_USAGE` views. These are much
+    /// faster in large accounts, but the views can lag behind by up to a couple of hours.
+    AccountUsage,
 }
 
 /// Given an ConnectorConfig object, return a SnowflakeConnectorConfig object.
 /// Throws an error on unexpected fields.
 fn parse_connector_config(connector_config: &ConnectorConfig) -> Result<SnowflakeConnectorConfig> {
     let config = serde_json::to_value(connector_config.config.clone())?;
-    let mut parsed_config = serde_json::from_value(config)
+    let parsed_config: SnowflakeConnectorConfig = serde_json::from_value(config)
         .context("Failed to parse Snowflake connector configuration")?;
 
-    if let SnowflakeConnectorConfig {
-        include: Some(include_set),
-    } = parsed_config
-    {
-        parsed_config.include = Some(expand_include_set(include_set));
-    };
+    if let Some(tag_schema) = &parsed_config.tag_schema {
+        if tag_schema.split('.').count() != 2 {
+            bail!("tag_schema must be a schema name like DATABASE.SCHEMA, but got {tag_schema}");
+        }
+    }
 
     Ok(parsed_config)
 }
 
+/// Expand the include set and compile it into patterns
+fn deserialize_include<'de, D>(deserializer: D) -> Result<Option<Vec<NamePattern>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let include_set: Option<HashSet<String>> = Option::deserialize(deserializer)?;
+    include_set
+        .map(|include_set| {
+            expand_include_set(include_set)
+                .iter()
+                .map(|pattern| NamePattern::new(pattern))
+                .collect::<Result<Vec<_>>>()
+        })
+        .transpose()
+        .map_err(|e| serde::de::Error::custom(format!("{e:#}")))
+}
+
+/// Add the databases and schemas that contain each included name. Regular expressions
+/// can't be split up, so they're left as they are.
 fn expand_include_set(include_set: HashSet<String>) -> HashSet<String> {
     let mut expanded_include = HashSet::new();
     for include_name in include_set {
+        if NamePattern::is_regex(&include_name) {
+            expanded_include.insert(include_name);
+            continue;
+        }
         let name_parts = include_name.split('.').collect::<Vec<_>>();
         for i in 1..name_parts.len() + 1 {
             let prefix = name_parts[0..i].join(".").to_string();
@@ -108,6 +185,28 @@ struct SnowflakeField {
     name: String,
 }
 
+/// Render a planned query with comments that explain it, and that warn about anything
+/// that will keep it from being applied. Queries that won't be applied are commented out.
+fn render_operation(
+    operation: &PlannedOperation,
+    restricted_schemas: &write::RestrictedSchemas,
+    allow_system_role_changes: bool,
+) -> String {
+    let mut query = write::system_roles::flag_operation(operation, allow_system_role_changes);
+    if let Some(note) = write::ownership::plan_note(operation) {
+        query = format!("{note}\n{query}");
+    }
+    if let Some(warning) = restricted_schemas.warning(operation) {
+        query = format!("{warning}\n{query}");
+    }
+    query
+}
+
+/// Register the Snowflake connector under the `snowflake` connector type
+pub fn register(registry: &mut ConnectorRegistry) {
+    registry.register::<SnowflakeConnector>("snowflake");
+}
+
 #[async_trait]
 impl NewConnector for SnowflakeConnector {
     /// Validates the configs and bootstraps a Snowflake connection.
@@ -119,50 +218,20 @@ impl NewConnector for SnowflakeConnector {
         config: &ConnectorConfig,
         credentials: &CredentialsMap,
         connector_client: Option<connectors::ConnectorClient>,
-        _data_dir: Option<PathBuf>,
+        data_dir: Option<PathBuf>,
     ) -> Result<Box<Self>> {
-        let mut conn = creds::SnowflakeCredentials::default();
-        let mut required_fields: HashSet<_> = vec![
-            "account",
-            "role",
-            "user",
-            "warehouse",
-            "private_key",
-            "public_key_fp",
-            // "url" // URL not required – defaults to typical account URL.
-        ]
-        .into_iter()
-        .collect();
-
-        for (k, v) in credentials.iter() {
-            match k.as_ref() {
-                "account" => conn.account = v.to_string(),
-                "role" => conn.role = v.to_string(),
-                "user" => conn.user = v.to_string(),
-                "warehouse" => conn.warehouse = v.to_string(),
-                "private_key" => conn.private_key = v.to_string(),
-                "public_key_fp" => conn.public_key_fp = v.to_string(),
-                "url" => conn.url = Some(v.to_string()),
-                _ => (),
-            }
-
-            required_fields.remove::<str>(k);
-        }
-        set_cual_account_name(&conn.account);
-
-        if !required_fields.is_empty() {
-            Err(anyhow![
-                "Snowflake config missing required fields: {:#?}",
-                required_fields
-            ])
-        } else {
-            let client = connector_client.unwrap_or(connectors::ConnectorClient::Core);
-            Ok(Box::new(SnowflakeConnector {
-                client,
-                rest_client: SnowflakeRestClient::new(conn, SnowflakeRestConfig { retry: true })?,
-                config: parse_connector_config(config)?,
-            }))
-        }
+        let creds = creds::SnowflakeCredentials::from_map(credentials)?;
+        // Logging in waits for the first query, so creating the connector never prompts
+        let rest_client = SnowflakeRestClient::new(creds, SnowflakeRestConfig { retry: true })?;
+
+        let client = connector_client.unwrap_or(connectors::ConnectorClient::Core);
+        Ok(Box::new(SnowflakeConnector {
+            client,
+            cual_account: CualAccount::new(&rest_client.get_account()),
+            rest_client,
+            config: parse_connector_config(config)?,
+            data_dir,
+        }))
     }
 }
 
@@ -186,13 +255,46 @@ impl Connector for SnowflakeConnector {
         };
     }
 
-    async fn get_data(&mut self) -> nodes::ConnectorData {
+    async fn get_data(&mut self) -> Result<nodes::ConnectorData> {
         // Fetch Snowflake Environment
         let mut c = coordinator::Coordinator::new(self);
-        c.get_data().await
+        let mut data = c.get_data().await?;
+
+        // Keep track of the tags Jetty manages so that plans can tell what has changed.
+        // TAG_REFERENCES can take a couple of hours to show the changes Jetty has applied, so
+        // those are kept from the previous record until it catches up.
+        if let Some(tag_schema) = &self.config.tag_schema {
+            let mut managed_tags = c.get_managed_tags(tag_schema);
+            managed_tags.merge_recently_applied(self.state(), SystemTime::now());
+            managed_tags.add_to_assets(&mut data.assets);
+            if let Some(data_dir) = &self.data_dir {
+                write::write_state(data_dir, &managed_tags)?;
+            }
+        }
+        if let Some(data_dir) = &self.data_dir {
+            // Plans warn about grants that Jetty's role can't make in managed-access schemas
+            write::write_state(data_dir, &c.get_restricted_schemas())?;
+            // Plans need to know which roles are database roles
+            write::write_state(data_dir, &c.get_role_kinds())?;
+        }
+        Ok(data)
     }
 
     fn get_manifest(&self) -> ConnectorManifest {
+        let mut write = HashSet::from([
+            WriteCapabilities::Groups { nested: true },
+            WriteCapabilities::Policies {
+                default_policies: true,
+            },
+            WriteCapabilities::Protections,
+            WriteCapabilities::Ownership,
+            WriteCapabilities::UserProperties,
+        ]);
+        // Tags are only written back when there's somewhere to put them
+        if self.config.tag_schema.is_some() {
+            write.insert(WriteCapabilities::Tags);
+        }
+
         ConnectorManifest {
             capabilities: ConnectorCapabilities {
                 read: HashSet::from([
@@ -203,12 +305,7 @@ impl Connector for SnowflakeConnector {
                     },
                     ReadCapabilities::Users,
                 ]),
-                write: HashSet::from([
-                    WriteCapabilities::Groups { nested: true },
-                    WriteCapabilities::Policies {
-                        default_policies: true,
-                    },
-                ]),
+                write,
             },
             asset_privileges: [
                 (
@@ -289,24 +386,187 @@ impl Connector for SnowflakeConnector {
                     .map(|p| p.to_owned())
                     .collect(),
                 ),
+                (
+                    AssetType(consts::STAGE.to_owned()),
+                    ["OWNERSHIP", "USAGE", "READ", "WRITE"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
+                (
+                    AssetType(consts::STREAM.to_owned()),
+                    ["OWNERSHIP", "SELECT"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
+                (
+                    AssetType(consts::TASK.to_owned()),
+                    ["OWNERSHIP", "MONITOR", "OPERATE"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
+                (
+                    AssetType(consts::MATERIALIZED_VIEW.to_owned()),
+                    ["OWNERSHIP", "SELECT", "REFERENCES"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
+                (
+                    AssetType(consts::EXTERNAL_TABLE.to_owned()),
+                    ["OWNERSHIP", "SELECT", "REFERENCES"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
+                (
+                    AssetType(consts::ACCOUNT.to_owned()),
+                    [
+                        "CREATE DATABASE",
+                        "CREATE WAREHOUSE",
+                        "CREATE ROLE",
+                        "CREATE USER",
+                        "CREATE INTEGRATION",
+                        "CREATE NETWORK POLICY",
+                        "CREATE SHARE",
+                        "IMPORT SHARE",
+                        "MANAGE GRANTS",
+                        "EXECUTE TASK",
+                        "EXECUTE MANAGED TASK",
+                        "MONITOR",
+                        "MONITOR USAGE",
+                        "MONITOR EXECUTION",
+                        "MONITOR SECURITY",
+                        "APPLY MASKING POLICY",
+                        "APPLY ROW ACCESS POLICY",
+                        "APPLY TAG",
+                        "APPLY SESSION POLICY",
+                        "APPLY PASSWORD POLICY",
+                        "ATTACH POLICY",
+                        "AUDIT",
+                        "OVERRIDE SHARE RESTRICTIONS",
+                    ]
+                    .into_iter()
+                    .map(|p| p.to_owned())
+                    .collect(),
+                ),
+                (
+                    AssetType(consts::WAREHOUSE.to_owned()),
+                    ["OWNERSHIP", "USAGE", "OPERATE", "MODIFY", "MONITOR"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
+                (
+                    AssetType(consts::FUNCTION.to_owned()),
+                    ["OWNERSHIP", "USAGE"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
+                (
+                    AssetType(consts::PROCEDURE.to_owned()),
+                    ["OWNERSHIP", "USAGE"]
+                        .into_iter()
+                        .map(|p| p.to_owned())
+                        .collect(),
+                ),
             ]
             .into(),
         }
     }
     fn plan_changes(&self, diffs: &LocalConnectorDiffs) -> Vec<std::string::String> {
-        self.generate_diff_queries(diffs).flatten()
+        let prepared_queries = self.generate_diff_queries(diffs);
+        let restricted_schemas = self.state::<write::RestrictedSchemas>();
+        [
+            prepared_queries.0,
+            prepared_queries.1,
+            prepared_queries.2,
+            prepared_queries.3,
+        ]
+        .iter()
+        .flatten()
+        .map(|operation| {
+            if !write::system_roles::system_roles_touched(operation).is_empty() {
+                warn!(
+                    "planned change touches a Snowflake system role: {}",
+                    operation.request
+                );
+            }
+            if restricted_schemas.warning(operation).is_some() {
+                warn!(
+                    "planned change can't be made by {} in a managed-access schema: {}",
+                    restricted_schemas.role, operation.request
+                );
+            }
+            render_operation(
+                operation,
+                &restricted_schemas,
+                self.config.allow_system_role_changes,
+            )
+        })
+        .collect()
+    }
+
+    fn plan_batches(&self, diffs: &LocalConnectorDiffs) -> Option<Vec<Vec<PlannedOperation>>> {
+        let prepared_queries = self.generate_diff_queries(diffs);
+        let restricted_schemas = self.state::<write::RestrictedSchemas>();
+        Some(
+            [
+                prepared_queries.0,
+                prepared_queries.1,
+                prepared_queries.2,
+                prepared_queries.3,
+            ]
+            .into_iter()
+            .map(|tier| {
+                tier.into_iter()
+                    .map(|mut operation| {
+                        operation.request = render_operation(
+                            &operation,
+                            &restricted_schemas,
+                            self.config.allow_system_role_changes,
+                        );
+                        operation
+                    })
+                    .collect()
+            })
+            .collect(),
+        )
     }
 
-    async fn apply_changes(&self, diffs: &LocalConnectorDiffs) -> Result<String> {
-        let mut success_counter = 0;
-        let mut failure_counter = 0;
+    async fn apply_changes(&self, diffs: &LocalConnectorDiffs) -> Result<ApplyReport> {
+        let mut report = ApplyReport::default();
         // This is designed in such a way that each query_set may be run concurrently.
         let prepared_queries = self.generate_diff_queries(diffs);
-        for query_set in [prepared_queries.0, prepared_queries.1, prepared_queries.2] {
+        for query_set in [
+            prepared_queries.0,
+            prepared_queries.1,
+            prepared_queries.2,
+            prepared_queries.3,
+        ] {
+            // Changes to system roles are only applied when they're explicitly allowed
+            let mut allowed = vec![];
+            for operation in query_set {
+                match write::system_roles::check_operation(
+                    &operation,
+                    self.config.allow_system_role_changes,
+                ) {
+                    Ok(()) => allowed.push(operation),
+                    Err(e) => {
+                        warn!("{:#}", e);
+                        report.record(operation, &Err::<(), _>(e));
+                    }
+                }
+            }
+            let query_set = allowed;
+
             let query_set_configs = query_set
                 .iter()
                 .map(|q| SnowflakeRequestConfig {
-                    sql: q.to_owned(),
+                    sql: q.request.to_owned(),
                     use_jwt: true,
                 })
                 .collect::<Vec<_>>();
@@ -321,21 +581,32 @@ impl Connector for SnowflakeConnector {
                 .collect::<Vec<_>>()
                 .await;
 
-            for result in results {
-                match result {
-                    Err(e) => {
-                        error!("{:?}", e);
-                        failure_counter += 1;
-                    }
-                    Ok(_) => {
-                        success_counter += 1;
-                    }
+            for (operation, result) in zip(query_set, results) {
+                if let Err(e) = &result {
+                    error!("{:?}", e);
                 }
+                report.record(operation, &result);
             }
         }
-        Ok(format!(
-            "{success_counter} successful queries\n{failure_counter} failed queries"
-        ))
+
+        // Record the tag changes right away, so that plans made before the next fetch don't
+        // repeat them. The next fetch keeps them until TAG_REFERENCES catches up.
+        if let Some(data_dir) = &self.data_dir {
+            if self.config.tag_schema.is_some() {
+                let mut managed_tags: write::ManagedTags = self.state();
+                managed_tags.record_applied(&report);
+                write::write_state(data_dir, &managed_tags)?;
+            }
+        }
+        Ok(report)
+    }
+
+    fn in_scope(&self, object: ScopedObject) -> bool {
+        match object {
+            ScopedObject::Asset(cual) => self.include_cual(cual),
+            ScopedObject::Group(role) => self.include_role(role),
+            ScopedObject::User(user) => self.include_user(user),
+        }
     }
 }
 
@@ -346,7 +617,7 @@ impl SnowflakeConnector {
         target: Arc<Mutex<&mut Vec<StandardGrant>>>,
     ) -> Result<()> {
         let res = self
-            .query_to_obj::<StandardGrant>("select * from snowflake.account_usage.grants_to_roles where deleted_on is null and granted_on in ('TABLE', 'DATABASE', 'SCHEMA', 'VIEW');")
+            .query_to_obj::<StandardGrant>("select * from snowflake.account_usage.grants_to_roles where deleted_on is null and granted_to = 'ROLE' and granted_on in ('ACCOUNT', 'TABLE', 'DATABASE', 'SCHEMA', 'VIEW', 'WAREHOUSE', 'FUNCTION', 'PROCEDURE', 'STAGE', 'STREAM', 'TASK', 'MATERIALIZED VIEW', 'MATERIALIZED_VIEW', 'EXTERNAL TABLE', 'EXTERNAL_TABLE');")
             .await
             .map_err(|e| {
                 error!("failed to get privilege grants -- error: {}", &e);
@@ -377,6 +648,93 @@ impl SnowflakeConnector {
         Ok(())
     }
 
+    /// Get the database roles in a database
+    pub(crate) async fn get_database_roles_future(
+        &self,
+        database: &Database,
+        target: Arc<Mutex<&mut Vec<DatabaseRole>>>,
+    ) -> Result<()> {
+        let res = self
+            .query_to_obj::<DatabaseRole>(&format!(
+                "SHOW DATABASE ROLES IN DATABASE \"{}\"",
+                &database.name
+            ))
+            .await
+            .context(format!(
+                "failed to get database roles in database {}",
+                &database.name
+            ))?;
+
+        let mut target = target.lock().unwrap();
+        target.extend(
+            res.into_iter()
+                .map(|role| DatabaseRole {
+                    database_name: database.name.to_owned(),
+                    ..role
+                })
+                .filter(|role| self.include_role(&role.role_name().0)),
+        );
+        Ok(())
+    }
+
+    /// Get all grants of a database role, to account roles and other database roles
+    pub(crate) async fn get_grants_of_database_role_future(
+        &self,
+        role: &DatabaseRole,
+        target: Arc<Mutex<&mut Vec<GrantOf>>>,
+    ) -> Result<()> {
+        let res = self
+            .query_to_obj::<GrantOf>(&format!(
+                "SHOW GRANTS OF DATABASE ROLE \"{}\".\"{}\"",
+                &role.database_name, &role.name
+            ))
+            .await
+            .context(format!(
+                "failed to get grants of database role {}.{}",
+                &role.database_name, &role.name
+            ))?;
+
+        let mut target = target.lock().unwrap();
+        target.extend(res.into_iter().map(|grant| {
+            let grantee_name = if grant.granted_to == "DATABASE_ROLE" {
+                role.qualify_grantee(&grant.grantee_name)
+            } else {
+                grant.grantee_name
+            };
+            GrantOf::new(role.role_name(), grant.granted_to, grantee_name)
+        }));
+        Ok(())
+    }
+
+    /// Get the privileges granted to a database role. These aren't included in the
+    /// account usage grants to roles, which only names the role without its database.
+    pub(crate) async fn get_grants_to_database_role_future(
+        &self,
+        role: &DatabaseRole,
+        target: Arc<Mutex<&mut Vec<StandardGrant>>>,
+    ) -> Result<()> {
+        let res = self
+            .query_to_obj::<DatabaseRoleGrant>(&format!(
+                "SHOW GRANTS TO DATABASE ROLE \"{}\".\"{}\"",
+                &role.database_name, &role.name
+            ))
+            .await
+            .context(format!(
+                "failed to get grants to database role {}.{}",
+                &role.database_name, &role.name
+            ))?;
+
+        let mut target = target.lock().unwrap();
+        target.extend(
+            res.into_iter()
+                // Grants of other database roles show up here too; they're handled as
+                // role grants
+                .filter(|g| consts::ASSET_TYPES.contains(&g.granted_on.as_str()))
+                .map(|g| g.into_standard_grant(role)),
+        );
+        Ok(())
+    }
+
     /// Get all future grants for a schema
     pub async fn get_future_grants_of_schema_future(
         &self,
@@ -395,7 +753,10 @@ impl SnowflakeConnector {
             ))?;
 
         let mut target = target.lock().unwrap();
-        target.extend(res);
+        target.extend(
+            res.into_iter()
+                .map(|g| g.qualify_database_role_grantee(&schema.database_name)),
+        );
         Ok(())
     }
 
@@ -417,25 +778,56 @@ impl SnowflakeConnector {
             ))?;
 
         let mut target = target.lock().unwrap();
-        target.extend(res);
+        target.extend(
+            res.into_iter()
+                .map(|g| g.qualify_database_role_grantee(&database.name)),
+        );
         Ok(())
     }
 
     /// Get all users.
     pub async fn get_users_future(&self, target: &mut Vec<User>) -> Result<()> {
-        *target = self
+        let mut users = self
             .query_to_obj::<User>("SHOW USERS")
             .await
             .context("failed to get users")?;
+        users.retain(|user| self.include_user(&user.name));
+        *target = users;
+        Ok(())
+    }
+
+    /// Get the fingerprint of a user's RSA public key. SHOW USERS only says whether the
+    /// user has one.
+    pub(crate) async fn get_user_public_key_fingerprint_future(
+        &self,
+        user: &User,
+        target: Arc<Mutex<&mut HashMap<String, String>>>,
+    ) -> Result<()> {
+        let properties = self
+            .query_to_obj::<UserProperty>(&format!("DESC USER \"{}\"", &user.name))
+            .await
+            .context(format!("failed to describe user {}", &user.name))?;
+
+        let fingerprint = properties
+            .into_iter()
+            .find(|p| p.property == "RSA_PUBLIC_KEY_FP")
+            .map(|p| p.value)
+            .filter(|fp| !fp.is_empty() && fp != "null");
+        if let Some(fingerprint) = fingerprint {
+            let mut target = target.lock().unwrap();
+            target.insert(user.name.to_owned(), fingerprint);
+        }
         Ok(())
     }
 
     /// Get all roles.
     pub(crate) async fn get_roles_future(&self, target: &mut Vec<Role>) -> Result<()> {
-        *target = self
+        let mut roles = self
             .query_to_obj::<Role>("SHOW ROLES")
             .await
             .context("failed to get roles")?;
+        roles.retain(|role| self.include_role(&role.name.0));
+        *target = roles;
         Ok(())
     }
 
@@ -447,7 +839,7 @@ impl SnowflakeConnector {
             .await
             .context("failed to get databases")?;
 
-        if self.config.include.is_some() {
+        if self.filters_assets() {
             databases.retain(|db| self.include_asset(&db.name));
         }
 
@@ -462,6 +854,124 @@ impl SnowflakeConnector {
             .context("failed to get warehouses")
     }
 
+    /// Get all warehouses.
+    pub async fn get_warehouses_future(&self, target: &mut Vec<Warehouse>) -> Result<()> {
+        *target = self.get_warehouses().await?;
+        Ok(())
+    }
+
+    /// Get all user-defined functions and stored procedures.
+    pub async fn get_routines_future(&self, target: &mut Vec<Routine>) -> Result<()> {
+        let mut routines = vec![];
+        for (query, kind, description) in [
+            (
+                "SHOW USER FUNCTIONS IN ACCOUNT",
+                RoutineKind::Function,
+                "functions",
+            ),
+            (
+                "SHOW PROCEDURES IN ACCOUNT",
+                RoutineKind::Procedure,
+                "procedures",
+            ),
+        ] {
+            let res = self
+                .query_to_obj::<Routine>(query)
+                .await
+                .context(format!("failed to get {description}"))?;
+            routines.extend(
+                res.into_iter()
+                    .filter(|r| !r.is_builtin())
+                    .map(|r| Routine { kind, ..r }),
+            );
+        }
+
+        if self.filters_assets() {
+            routines.retain(|routine| self.include_asset(&routine.fqn()));
+        }
+
+        *target = routines;
+        Ok(())
+    }
+
+    /// Get the tags applied directly to objects. Column tags are skipped because Jetty
+    /// doesn't model columns.
+    pub(crate) async fn get_tag_references_future(
+        &self,
+        target: &mut Vec<TagReference>,
+    ) -> Result<()> {
+        *target = self
+            .query_to_obj::<TagReference>("select tag_database, tag_schema, tag_name, tag_value, object_database, object_schema, object_name, domain from snowflake.account_usage.tag_references where object_deleted is null and domain != 'COLUMN';")
+            .await
+            .context("failed to get tag references")?;
+        debug!("fetched {} tag references", target.len());
+        Ok(())
+    }
+
+    /// Get the masking and row access policies attached directly to objects, and the
+    /// network and session policies on users and the account. Policies that are attached
+    /// through a tag are skipped.
+    pub(crate) async fn get_policy_references_future(
+        &self,
+        target: &mut Vec<PolicyReference>,
+    ) -> Result<()> {
+        *target = self
+            .query_to_obj::<PolicyReference>("select policy_db, policy_schema, policy_name, policy_kind, ref_database_name, ref_schema_name, ref_entity_name, ref_entity_domain, ref_column_name, ref_arg_column_names from snowflake.account_usage.policy_references where policy_kind in ('MASKING_POLICY', 'ROW_ACCESS_POLICY', 'NETWORK_POLICY', 'SESSION_POLICY') and tag_name is null;")
+            .await
+            .context("failed to get policy references")?;
+        debug!("fetched {} policy references", target.len());
+        Ok(())
+    }
+
+    /// Get the dependencies between objects, like views on the tables they select from
+    pub(crate) async fn get_object_dependencies_future(
+        &self,
+        target: Arc<Mutex<&mut Vec<ObjectDependency>>>,
+    ) -> Result<()> {
+        let res = self
+            .query_to_obj::<ObjectDependency>("select referenced_database, referenced_schema, referenced_object_name, referenced_object_domain, referencing_database, referencing_schema, referencing_object_name, referencing_object_domain from snowflake.account_usage.object_dependencies;")
+            .await
+            .context("failed to get object dependencies")?;
+        debug!("fetched {} object dependencies", res.len());
+
+        let mut target = target.lock().unwrap();
+        target.extend(res);
+        Ok(())
+    }
+
+    /// Get the objects written from other objects in the last `days` days, like tables
+    /// created with CREATE TABLE AS SELECT, from the access history
+    pub(crate) async fn get_access_history_lineage_future(
+        &self,
+        days: u32,
+        target: Arc<Mutex<&mut Vec<ObjectDependency>>>,
+    ) -> Result<()> {
+        let name_part = |column: &str, part: u8| {
+            format!("split_part({column}.value:\"objectName\"::string, '.', {part})")
+        };
+        let query = format!(
+            "select distinct {} as referenced_database, {} as referenced_schema, {} as referenced_object_name, upper(src.value:\"objectDomain\"::string) as referenced_object_domain, {} as referencing_database, {} as referencing_schema, {} as referencing_object_name, upper(tgt.value:\"objectDomain\"::string) as referencing_object_domain from snowflake.account_usage.access_history, lateral flatten(input => direct_objects_accessed) src, lateral flatten(input => objects_modified) tgt where query_start_time >= dateadd(day, -{days}, current_timestamp()) and src.value:\"objectId\" != tgt.value:\"objectId\";",
+            name_part("src", 1),
+            name_part("src", 2),
+            name_part("src", 3),
+            name_part("tgt", 1),
+            name_part("tgt", 2),
+            name_part("tgt", 3),
+        );
+        let res = self
+            .query_to_obj::<ObjectDependency>(&query)
+            .await
+            .context("failed to get lineage from the access history")?;
+        debug!(
+            "fetched {} lineage edges from the access history",
+            res.len()
+        );
+
+        let mut target = target.lock().unwrap();
+        target.extend(res);
+        Ok(())
+    }
+
     /// Get all schemas.
     pub async fn get_schemas_future(&self, target: &mut Vec<Schema>) -> Result<()> {
         let mut schemas = self
@@ -469,7 +979,7 @@ impl SnowflakeConnector {
             .await
             .context("failed to get schemas")?;
 
-        if self.config.include.is_some() {
+        if self.filters_assets() {
             schemas.retain(|schema| self.include_asset(&schema.fqn()));
         }
 
@@ -493,7 +1003,37 @@ impl SnowflakeConnector {
             .await
             .context("failed to get tables")?;
 
-        if self.config.include.is_some() {
+        if self.filters_assets() {
+            res.retain(|object| self.include_asset(&object.fqn()));
+        }
+
+        let mut target = target.lock().unwrap();
+        target.extend(res);
+        Ok(())
+    }
+
+    /// Get the tables and views in every schema at once, from `ACCOUNT_USAGE.TABLES`.
+    /// This replaces a `SHOW OBJECTS` query for each schema, so only the objects in the
+    /// given schemas are kept.
+    pub(crate) async fn get_account_usage_objects_future(
+        &self,
+        schemas: &[Schema],
+        target: Arc<Mutex<&mut Vec<Object>>>,
+    ) -> Result<()> {
+        let mut res = self
+            .query_to_obj::<Object>("select table_name as \"name\", table_schema as \"schema_name\", table_catalog as \"database_name\", table_type as \"kind\" from snowflake.account_usage.tables where deleted is null and table_type in ('BASE TABLE', 'VIEW', 'MATERIALIZED VIEW', 'EXTERNAL TABLE');")
+            .await
+            .context("failed to get tables from account usage")?;
+        debug!("fetched {} tables and views from account usage", res.len());
+
+        // The view covers the whole account, including schemas that weren't fetched, like
+        // INFORMATION_SCHEMA and those in shared databases
+        let schemas = schemas.iter().map(|s| s.fqn()).collect::<HashSet<_>>();
+        res.retain(|object| {
+            schemas.contains(&format!("{}.{}", object.database_name, object.schema_name))
+        });
+
+        if self.filters_assets() {
             res.retain(|object| self.include_asset(&object.fqn()));
         }
 
@@ -502,6 +1042,59 @@ impl SnowflakeConnector {
         Ok(())
     }
 
+    /// Get the grants of every role, to roles and users, from `ACCOUNT_USAGE.GRANTS_TO_ROLES`
+    /// and `ACCOUNT_USAGE.GRANTS_TO_USERS`. This replaces a `SHOW GRANTS OF ROLE` query for
+    /// each role.
+    pub(crate) async fn get_account_usage_role_grants_future(
+        &self,
+        target: Arc<Mutex<&mut Vec<GrantOf>>>,
+    ) -> Result<()> {
+        let mut res = self
+            .query_to_obj::<GrantOf>("select name as \"role\", granted_to as \"granted_to\", grantee_name as \"grantee_name\" from snowflake.account_usage.grants_to_roles where deleted_on is null and granted_on = 'ROLE' and granted_to = 'ROLE';")
+            .await
+            .context("failed to get role grants to roles from account usage")?;
+        res.extend(
+            self.query_to_obj::<GrantOf>("select role as \"role\", granted_to as \"granted_to\", grantee_name as \"grantee_name\" from snowflake.account_usage.grants_to_users where deleted_on is null;")
+                .await
+                .context("failed to get role grants to users from account usage")?,
+        );
+        debug!("fetched {} role grants from account usage", res.len());
+
+        let mut target = target.lock().unwrap();
+        target.extend(res);
+        Ok(())
+    }
+
+    /// Get all stages, streams, tasks, materialized views, and external tables. These
+    /// aren't included in `SHOW OBJECTS`, so each is fetched for the whole account.
+    pub(crate) async fn get_account_objects_future(
+        &self,
+        target: Arc<Mutex<&mut Vec<Object>>>,
+    ) -> Result<()> {
+        let mut objects = vec![];
+        for (kind, description) in [
+            (ObjectKind::Stage, "stages"),
+            (ObjectKind::Stream, "streams"),
+            (ObjectKind::Task, "tasks"),
+            (ObjectKind::MaterializedView, "materialized views"),
+            (ObjectKind::ExternalTable, "external tables"),
+        ] {
+            let res = self
+                .query_to_obj::<Object>(&format!("SHOW {} IN ACCOUNT", description.to_uppercase()))
+                .await
+                .context(format!("failed to get {description}"))?;
+            objects.extend(res.into_iter().map(|o| Object { kind, ..o }));
+        }
+
+        if self.filters_assets() {
+            objects.retain(|object| self.include_asset(&object.fqn()));
+        }
+
+        let mut target = target.lock().unwrap();
+        target.extend(objects);
+        Ok(())
+    }
+
     /// Execute the given query and deserialize the result into the given type.
     pub async fn query_to_obj<T>(&self, query: &str) -> Result<Vec<T>>
     where
@@ -574,10 +1167,7 @@ impl SnowflakeConnector {
                         },
                         statement_handle,
                         current_partition,
-                    )?
-                    .send()
-                    .await?
-                    .json()
+                    )
                     .await?;
                 results.extend(value_to_vector(&partition_row_values, query, &fields)?);
             }
@@ -588,12 +1178,19 @@ impl SnowflakeConnector {
     }
 
     fn grants_to_policies(&self, grants: &[GrantType]) -> Vec<nodes::RawPolicy> {
-        let filter_to_include_list = self.config.include.is_some();
+        let filter_to_include_list = self.filters_assets();
         grants
             .iter()
             .filter(|g| {
                 consts::ASSET_TYPES.contains(&g.granted_on())
-                    && (!filter_to_include_list || self.include_asset(&g.granted_on_name()))
+                    // Ownership is reported through the asset's owner rather than as a policy
+                    && g.privilege() != OWNERSHIP_PRIVILEGE
+                    // Warehouses and the account aren't part of the database hierarchy,
+                    // so the include list doesn't apply to them
+                    && (!filter_to_include_list
+                        || g.granted_on() == consts::WAREHOUSE
+                        || g.granted_on() == consts::ACCOUNT
+                        || self.include_asset(&g.granted_on_name()))
             })
             // Collect roles by asset name so the role:asset ratio is 1:1.
             .fold(
@@ -623,7 +1220,7 @@ impl SnowflakeConnector {
                 // grants into one policy.
                 let privileges: HashSet<String> =
                     grants.iter().map(|g| g.privilege().to_owned()).collect();
-                Some(final_grant.into_policy(privileges))
+                Some(final_grant.into_policy(privileges, &self.cual_account))
             })
             .collect::<Vec<_>>()
     }
@@ -634,7 +1231,7 @@ impl SnowflakeConnector {
         &self,
         grants: &[FutureGrant],
     ) -> Vec<nodes::RawDefaultPolicy> {
-        let filter_to_include_list = self.config.include.is_some();
+        let filter_to_include_list = self.filters_assets();
         grants
             .iter()
             // filter down to the asset types we support
@@ -676,26 +1273,10 @@ impl SnowflakeConnector {
                 // grants into one policy.
                 let privileges: HashSet<String> =
                     grants.iter().map(|g| g.privilege().to_owned()).collect();
-                Some(final_grant.into_default_policy(privileges))
+                Some(final_grant.into_default_policy(privileges, &self.cual_account))
             })
             .collect::<Vec<_>>()
     }
-
-    fn include_asset(&self, asset_name: &str) -> bool {
-        let include_paths = match self.config.include {
-            Some(ref paths) => paths,
-            // If there are no include paths, we include everything.
-            None => return true,
-        };
-
-        include_paths.iter().any(|include_path| {
-            if include_path.ends_with('*') {
-                asset_name.starts_with(&include_path[..include_path.len() - 1])
-            } else {
-                include_path == asset_name
-            }
-        })
-    }
 }
 
 fn value_to_vector<T>(value: &JsonValue, query: &str, fields: &[String]) -> Result<Vec<T>>
@@ -753,6 +1334,27 @@ pub(crate) fn strip_snowflake_quotes(object: String, capitalize: bool) -> String
     }
 }
 
+/// Split a fully-qualified snowflake name on the periods between its parts. Quoted identifiers
+/// can contain periods, so periods inside quotes are left alone. The parts keep their quotes.
+pub(crate) fn split_snowflake_name(name: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut quoted = false;
+    for (i, c) in name.char_indices() {
+        match c {
+            // Escaped quotes ("") toggle twice, so they don't end the identifier
+            '"' => quoted = !quoted,
+            '.' if !quoted => {
+                parts.push(&name[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(&name[start..]);
+    parts
+}
+
 /// Given a snowflake identifier (e.g. a table name, but not a fqn), escape any quotes in it by converting to double quotes.
 pub(crate) fn escape_snowflake_quotes(identifier: &str) -> String {
     if identifier.contains('"') {
@@ -762,6 +1364,19 @@ pub(crate) fn escape_snowflake_quotes(identifier: &str) -> String {
     }
 }
 
+/// Quote a snowflake identifier (e.g. a role name) for use in a query, escaping any quotes in it.
+pub(crate) fn quote_snowflake_identifier(identifier: &str) -> String {
+    format!("\"{}\"", escape_snowflake_quotes(identifier))
+}
+
+/// Quote each part of a period-separated name, like `DB.SCHEMA.POLICY`, for use in a query.
+pub(crate) fn quote_snowflake_fqn(fqn: &str) -> String {
+    fqn.split('.')
+        .map(quote_snowflake_identifier)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
 /// A Snowflake Asset. Inner value is the fully-qualified snowflake name.
 #[derive(PartialEq, Debug)]
 enum SnowflakeAsset {
@@ -769,30 +1384,85 @@ enum SnowflakeAsset {
     View(String),
     Schema(String),
     Database(String),
+    Warehouse(String),
+    Stage(String),
+    Stream(String),
+    Task(String),
+    MaterializedView(String),
+    ExternalTable(String),
+    /// The fully-qualified name followed by the argument types
+    Function(String),
+    /// The fully-qualified name followed by the argument types
+    Procedure(String),
+    /// The account itself. It doesn't have a name in SQL.
+    Account,
 }
 
 impl SnowflakeAsset {
     /// Get the snowflake fully-qualified name for the asset
-    fn fqn(&self) -> &String {
+    fn fqn(&self) -> &str {
         match self {
             SnowflakeAsset::Table(fqn) => fqn,
             SnowflakeAsset::View(fqn) => fqn,
             SnowflakeAsset::Schema(fqn) => fqn,
             SnowflakeAsset::Database(fqn) => fqn,
+            SnowflakeAsset::Warehouse(fqn) => fqn,
+            SnowflakeAsset::Stage(fqn) => fqn,
+            SnowflakeAsset::Stream(fqn) => fqn,
+            SnowflakeAsset::Task(fqn) => fqn,
+            SnowflakeAsset::MaterializedView(fqn) => fqn,
+            SnowflakeAsset::ExternalTable(fqn) => fqn,
+            SnowflakeAsset::Function(fqn) => fqn,
+            SnowflakeAsset::Procedure(fqn) => fqn,
+            SnowflakeAsset::Account => "",
         }
     }
 
-    /// Get the asset type as a &str
+    /// Get the asset type as a &str, in the form used in SQL
     fn asset_type(&self) -> &str {
         match self {
             SnowflakeAsset::Table(_) => "TABLE",
             SnowflakeAsset::View(_) => "VIEW",
             SnowflakeAsset::Schema(_) => "SCHEMA",
             SnowflakeAsset::Database(_) => "DATABASE",
+            SnowflakeAsset::Warehouse(_) => "WAREHOUSE",
+            SnowflakeAsset::Stage(_) => "STAGE",
+            SnowflakeAsset::Stream(_) => "STREAM",
+            SnowflakeAsset::Task(_) => "TASK",
+            SnowflakeAsset::MaterializedView(_) => "MATERIALIZED VIEW",
+            SnowflakeAsset::ExternalTable(_) => "EXTERNAL TABLE",
+            SnowflakeAsset::Function(_) => "FUNCTION",
+            SnowflakeAsset::Procedure(_) => "PROCEDURE",
+            SnowflakeAsset::Account => "ACCOUNT",
+        }
+    }
+
+    /// Get the object a grant is on, in the form used in SQL, like `TABLE "DB"."S"."T"`
+    /// or just `ACCOUNT`
+    fn grant_target(&self) -> String {
+        match self {
+            SnowflakeAsset::Account => self.asset_type().to_owned(),
+            _ => format!("{} {}", self.asset_type(), self.fqn()),
         }
     }
 }
 
+/// Snowflake object types can have spaces (e.g. MATERIALIZED VIEW), but in Jetty they use
+/// underscores. Convert a Jetty asset type back into the form used in SQL.
+pub(crate) fn sql_object_type(asset_type: &str) -> String {
+    asset_type.replace('_', " ")
+}
+
+/// Deserialize an object type into the form used for Jetty asset types. Snowflake isn't
+/// consistent about whether multi-word object types use spaces or underscores.
+pub(crate) fn object_type_deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let buf = String::deserialize(deserializer)?;
+    Ok(buf.replace(' ', "_"))
+}
+
 pub(crate) fn strip_quotes_and_deserialize<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: Deserializer<'de>,
@@ -803,7 +1473,9 @@ where
 
 #[cfg(test)]
 mod test {
-    use crate::creds::SnowflakeCredentials;
+    use crate::creds::{LoginMethod, SnowflakeCredentials};
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     use super::*;
 
@@ -850,17 +1522,33 @@ mod test {
             role: "role".to_owned(),
             user: "user".to_owned(),
             warehouse: "warehouse".to_owned(),
-            private_key: "private_key".to_owned(),
-            public_key_fp: "fp".to_owned(),
+            method: LoginMethod::KeyPair {
+                private_key: "private_key".to_owned(),
+                public_key_fp: "fp".to_owned(),
+            },
             url: None,
         };
 
         let conn = SnowflakeConnector {
             config: SnowflakeConnectorConfig {
-                include: Some(new_set),
+                include: Some(
+                    new_set
+                        .iter()
+                        .map(|p| NamePattern::new(p))
+                        .collect::<Result<_>>()?,
+                ),
+                exclude: vec![NamePattern::new("X.SCRATCH_*")?],
+                roles: Default::default(),
+                users: Default::default(),
+                tag_schema: None,
+                allow_system_role_changes: false,
+                access_history_lineage_days: None,
+                fetch_strategy: FetchStrategy::Show,
             },
             rest_client: SnowflakeRestClient::new(creds, SnowflakeRestConfig::default()).unwrap(),
             client: connectors::ConnectorClient::Test,
+            cual_account: CualAccount::new("my_account"),
+            data_dir: None,
         };
 
         assert!(conn.include_asset("A.B.C"));
@@ -873,7 +1561,145 @@ mod test {
         assert!(!conn.include_asset("Z.G.A"));
         assert!(conn.include_asset("X"));
         assert!(conn.include_asset("X.X"));
+        assert!(!conn.include_asset("X.SCRATCH_ELLEN"));
+        assert!(!conn.include_asset("X.SCRATCH_ELLEN.TABLE"));
+
+        // Routines are filtered by their signature, whether they're being fetched or written
+        for (schema, included) in [("X", true), ("SCRATCH_ELLEN", false)] {
+            let routine = Routine {
+                name: "MY_FUNC".to_owned(),
+                schema_name: schema.to_owned(),
+                database_name: "X".to_owned(),
+                arguments: "MY_FUNC(NUMBER, VARCHAR) RETURN NUMBER".to_owned(),
+                ..Default::default()
+            };
+            assert_eq!(
+                routine.fqn(),
+                format!("X.{schema}.MY_FUNC(NUMBER, VARCHAR)")
+            );
+            assert_eq!(conn.include_asset(&routine.fqn()), included);
+            assert_eq!(
+                conn.include_cual(&routine.cual(&conn.cual_account)),
+                included
+            );
+        }
+
+        Ok(())
+    }
+
+    /// A connector that sends its queries to a mock server
+    fn mock_connector(server: &MockServer) -> SnowflakeConnector {
+        let creds = SnowflakeCredentials {
+            account: "my_account".to_owned(),
+            role: "role".to_owned(),
+            user: "user".to_owned(),
+            warehouse: "warehouse".to_owned(),
+            method: LoginMethod::KeyPair {
+                private_key: "private_key".to_owned(),
+                public_key_fp: "fp".to_owned(),
+            },
+            url: Some(format!("{}/api/v2/statements", server.uri())),
+        };
+        SnowflakeConnector {
+            config: serde_json::from_value(serde_json::json!({"fetch_strategy": "account_usage"}))
+                .unwrap(),
+            rest_client: SnowflakeRestClient::new(creds, SnowflakeRestConfig::default()).unwrap(),
+            client: connectors::ConnectorClient::Test,
+            cual_account: CualAccount::new("my_account"),
+            data_dir: None,
+        }
+    }
+
+    /// Respond to queries that contain `query` with the given columns and rows
+    async fn mock_query(server: &MockServer, query: &str, columns: &[&str], rows: JsonValue) {
+        Mock::given(method("POST"))
+            .and(path("/api/v2/statements"))
+            .and(body_string_contains(query))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "resultSetMetaData": {
+                    "rowType": columns.iter().map(|c| serde_json::json!({"name": c})).collect::<Vec<_>>(),
+                },
+                "data": rows,
+            })))
+            .mount(server)
+            .await;
+    }
 
+    #[tokio::test]
+    async fn account_usage_objects_are_limited_to_fetched_schemas() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_query(
+            &server,
+            "snowflake.account_usage.tables",
+            &["name", "schema_name", "database_name", "kind"],
+            serde_json::json!([
+                ["USERS", "RAW", "DB", "BASE TABLE"],
+                ["USERS_V", "RAW", "DB", "VIEW"],
+                ["USERS_MV", "RAW", "DB", "MATERIALIZED VIEW"],
+                ["EVENTS", "RAW", "DB", "EXTERNAL TABLE"],
+                ["TABLES", "INFORMATION_SCHEMA", "DB", "VIEW"],
+                ["ORDERS", "PUBLIC", "SHARED", "BASE TABLE"],
+            ]),
+        )
+        .await;
+
+        let mut objects = vec![];
+        mock_connector(&server)
+            .get_account_usage_objects_future(
+                &[Schema::new("RAW".to_owned(), "DB".to_owned())],
+                Arc::new(Mutex::new(&mut objects)),
+            )
+            .await?;
+
+        assert_eq!(
+            objects
+                .iter()
+                .map(|o| (o.fqn(), o.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                ("DB.RAW.USERS".to_owned(), ObjectKind::Table),
+                ("DB.RAW.USERS_V".to_owned(), ObjectKind::View),
+                ("DB.RAW.USERS_MV".to_owned(), ObjectKind::MaterializedView),
+                ("DB.RAW.EVENTS".to_owned(), ObjectKind::ExternalTable),
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn account_usage_role_grants_include_roles_and_users() -> Result<()> {
+        let server = MockServer::start().await;
+        mock_query(
+            &server,
+            "snowflake.account_usage.grants_to_roles",
+            &["role", "granted_to", "grantee_name"],
+            serde_json::json!([["READER", "ROLE", "ANALYST"]]),
+        )
+        .await;
+        mock_query(
+            &server,
+            "snowflake.account_usage.grants_to_users",
+            &["role", "granted_to", "grantee_name"],
+            serde_json::json!([["ANALYST", "USER", "ELLEN"]]),
+        )
+        .await;
+
+        let mut grants = vec![];
+        mock_connector(&server)
+            .get_account_usage_role_grants_future(Arc::new(Mutex::new(&mut grants)))
+            .await?;
+
+        assert_eq!(
+            grants
+                .iter()
+                .map(|g| (
+                    g.role.0.as_str(),
+                    g.granted_to.as_str(),
+                    g.grantee_name.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![("READER", "ROLE", "ANALYST"), ("ANALYST", "USER", "ELLEN")]
+        );
         Ok(())
     }
 
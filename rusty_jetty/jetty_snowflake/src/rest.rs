@@ -1,16 +1,27 @@
 //! Rest API interface for Snowflake
 //!
 
-use crate::{consts, creds::SnowflakeCredentials};
+use crate::{
+    consts,
+    creds::{LoginMethod, SnowflakeCredentials},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use jetty_core::logging::{debug, error};
 use jsonwebtoken::{encode, get_current_timestamp, Algorithm, EncodingKey, Header};
+use reqwest::{Response, StatusCode};
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, RequestBuilder};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use tokio::sync::Mutex;
 
-use std::{collections::HashMap, thread, time::Duration};
+use std::{collections::HashMap, time::Duration};
+
+mod oauth;
+mod session;
+
+const STATEMENTS_PATH: &str = "/api/v2/statements";
 
 /// Claims for use with the `jsonwebtoken` crate when
 /// creating a new JWT.
@@ -29,7 +40,7 @@ struct JwtClaims {
 #[derive(Default)]
 pub struct SnowflakeRequestConfig {
     pub sql: String,
-    /// Only used to bypass JWT logic in testing
+    /// Only used to bypass authentication in testing
     pub use_jwt: bool,
 }
 
@@ -38,11 +49,23 @@ pub(crate) struct SnowflakeRestConfig {
     /// Enable/disable retry logic.
     pub(crate) retry: bool,
 }
+
+/// A token that authenticates requests
+#[derive(Clone, Debug)]
+struct AuthToken {
+    token: String,
+    /// For sessions, the token that renews the session once `token` expires
+    master_token: Option<String>,
+}
+
 /// Wrapper struct for http functionality
 pub(crate) struct SnowflakeRestClient {
     /// The credentials used to authenticate into Snowflake.
     credentials: SnowflakeCredentials,
     http_client: ClientWithMiddleware,
+    /// The token for the login methods that don't sign each request. It's only fetched
+    /// when the first request needs it, so creating a client never prompts for a login.
+    token: Mutex<Option<AuthToken>>,
 }
 
 impl SnowflakeRestClient {
@@ -61,6 +84,7 @@ impl SnowflakeRestClient {
         Ok(Self {
             credentials,
             http_client: client,
+            token: Mutex::new(None),
         })
     }
     /// Execute a query, dropping the result.
@@ -70,31 +94,35 @@ impl SnowflakeRestClient {
     /// such as those that are used to update
     /// state in Snowflake.
     pub(crate) async fn execute(&self, config: &SnowflakeRequestConfig) -> Result<()> {
-        let request = self.get_request(config)?;
-        request
-            .send()
-            .await
-            .context("couldn't send request")?
+        if self.uses_session(config) {
+            self.query_over_session(&config.sql).await?;
+            return Ok(());
+        }
+        self.send(|| self.get_request(config), config)
+            .await?
             .error_for_status()?;
         Ok(())
     }
 
     pub(crate) async fn query(&self, config: &SnowflakeRequestConfig) -> Result<String> {
         debug!("starting query: {:?}", &config.sql);
+        if self.uses_session(config) {
+            let res = self.query_over_session(&config.sql).await?;
+            debug!("completed query: {:?}", &config.sql);
+            return Ok(res);
+        }
+
         #[derive(Deserialize)]
         struct AcceptedResponse {
             #[serde(rename = "statementHandle")]
             statement_handle: String,
             code: String,
         }
-        let request = self
-            .get_request(config)
-            .context(format!("failed to get request for query {:?}", &config.sql))?;
 
-        let response = request
-            .send()
+        let response = self
+            .send(|| self.get_request(config), config)
             .await
-            .context("couldn't send request")?
+            .context(format!("failed to send query {:?}", &config.sql))?
             .error_for_status()
             .map_err(|e| {
                 error!("error status for query: {} -- error: {}", &config.sql, &e);
@@ -107,14 +135,12 @@ impl SnowflakeRestClient {
             .map(|r| r.code == "333334")
             .unwrap_or(false)
         {
-            thread::sleep(Duration::from_millis(1500));
+            tokio::time::sleep(Duration::from_millis(1500)).await;
             let statement_handle = serde_json::from_str::<AcceptedResponse>(&res)?.statement_handle;
 
-            let request = self.get_status_check_request(config, statement_handle)?;
-            res = request
-                .send()
-                .await
-                .context("couldn't send request")?
+            res = self
+                .send(|| self.get_status_check_request(&statement_handle), config)
+                .await?
                 .error_for_status()?
                 .text()
                 .await
@@ -125,74 +151,184 @@ impl SnowflakeRestClient {
         Ok(res)
     }
 
+    /// Whether queries run over a session rather than through the SQL API. The SQL API only
+    /// accepts key pair JWTs and OAuth tokens.
+    fn uses_session(&self, config: &SnowflakeRequestConfig) -> bool {
+        config.use_jwt
+            && matches!(
+                self.credentials.method,
+                LoginMethod::Password { .. } | LoginMethod::ExternalBrowser
+            )
+    }
+
     /// If the URL is explicitly defined, that's used first.
     /// Otherwise, the standard account configuration
     /// is used
     fn get_url(&self) -> String {
-        self.credentials.url.to_owned().unwrap_or_else(|| {
-            format![
-                "https://{}.snowflakecomputing.com/api/v2/statements",
+        self.credentials
+            .url
+            .to_owned()
+            .unwrap_or_else(|| format!["{}{STATEMENTS_PATH}", self.get_base_url()])
+    }
+
+    /// The root of the account's URL, used for logging in
+    fn get_base_url(&self) -> String {
+        match &self.credentials.url {
+            Some(url) => url.trim_end_matches(STATEMENTS_PATH).to_owned(),
+            None => format![
+                "https://{}.snowflakecomputing.com",
                 self.credentials.account
-            ]
-        })
+            ],
+        }
     }
 
-    fn get_request(&self, config: &SnowflakeRequestConfig) -> Result<RequestBuilder> {
+    /// The account name, as used to identify the account when authenticating
+    fn account_name(&self) -> String {
+        self.credentials.account.split('.').collect::<Vec<_>>()[0].to_uppercase()
+    }
+
+    fn get_request(&self, config: &SnowflakeRequestConfig) -> RequestBuilder {
         let body = self.get_body(&config.sql);
 
-        let mut builder = self
-            .http_client
+        self.http_client
             .post(self.get_url())
             .json(&body)
             .header(consts::CONTENT_TYPE_HEADER, "application/json")
             .header(consts::ACCEPT_HEADER, "application/json")
-            .header(consts::SNOWFLAKE_AUTH_HEADER, "KEYPAIR_JWT")
-            .header(consts::USER_AGENT_HEADER, "jetty-labs");
-        if config.use_jwt {
-            let token = self.get_jwt().context("failed to get jwt")?;
-            builder = builder.header(consts::AUTH_HEADER, format!["Bearer {token}"]);
-        }
-        Ok(builder)
+            .header(consts::USER_AGENT_HEADER, "jetty-labs")
     }
 
-    fn get_status_check_request(
-        &self,
-        config: &SnowflakeRequestConfig,
-        statement_handle: String,
-    ) -> Result<RequestBuilder> {
-        let mut builder = self
-            .http_client
+    fn get_status_check_request(&self, statement_handle: &str) -> RequestBuilder {
+        self.http_client
             .get(format!("{}/{statement_handle}", self.get_url()))
             .header(consts::CONTENT_TYPE_HEADER, "application/json")
             .header(consts::ACCEPT_HEADER, "application/json")
-            .header(consts::SNOWFLAKE_AUTH_HEADER, "KEYPAIR_JWT")
-            .header(consts::USER_AGENT_HEADER, "jetty-labs");
-        if config.use_jwt {
-            let token = self.get_jwt().context("failed to get jwt")?;
-            builder = builder.header(consts::AUTH_HEADER, format!["Bearer {token}"]);
-        }
-        Ok(builder)
+            .header(consts::USER_AGENT_HEADER, "jetty-labs")
     }
 
-    pub(crate) fn get_partition(
+    /// Get one of the later partitions of a query's results
+    pub(crate) async fn get_partition(
         &self,
         config: &SnowflakeRequestConfig,
         statement_handle: &str,
         partition_number: usize,
+    ) -> Result<JsonValue> {
+        let build = || {
+            self.http_client
+                .get(format!("{}/{statement_handle}", self.get_url()))
+                .query(&[("partition", partition_number)])
+                .header(consts::CONTENT_TYPE_HEADER, "application/json")
+                .header(consts::ACCEPT_HEADER, "application/json")
+                .header(consts::USER_AGENT_HEADER, "jetty-labs")
+        };
+        Ok(self
+            .send(build, config)
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// Authenticate and send a request to the SQL API. Requests whose token is rejected,
+    /// like expired OAuth tokens, are sent once more with a new token.
+    async fn send(
+        &self,
+        build: impl Fn() -> RequestBuilder,
+        config: &SnowflakeRequestConfig,
+    ) -> Result<Response> {
+        let mut refreshed = false;
+        loop {
+            let token = match (&self.credentials.method, config.use_jwt) {
+                (LoginMethod::Oauth { .. }, true) => Some(self.token().await?),
+                _ => None,
+            };
+            let response = self
+                .authorize(build(), config, token.as_deref())?
+                .send()
+                .await
+                .context("couldn't send request")?;
+            match token {
+                Some(token) if response.status() == StatusCode::UNAUTHORIZED && !refreshed => {
+                    debug!("Snowflake rejected the access token, so getting a new one");
+                    self.expire_token(&token).await;
+                    refreshed = true;
+                }
+                _ => return Ok(response),
+            }
+        }
+    }
+
+    /// Add the authentication headers for the login method
+    fn authorize(
+        &self,
+        builder: RequestBuilder,
+        config: &SnowflakeRequestConfig,
+        token: Option<&str>,
     ) -> Result<RequestBuilder> {
-        let mut builder = self
-            .http_client
-            .get(format!("{}/{statement_handle}", self.get_url()))
-            .query(&[("partition", partition_number)])
-            .header(consts::CONTENT_TYPE_HEADER, "application/json")
-            .header(consts::ACCEPT_HEADER, "application/json")
-            .header(consts::SNOWFLAKE_AUTH_HEADER, "KEYPAIR_JWT")
-            .header(consts::USER_AGENT_HEADER, "jetty-labs");
-        if config.use_jwt {
-            let token = self.get_jwt().context("failed to get jwt")?;
-            builder = builder.header(consts::AUTH_HEADER, format!["Bearer {token}"]);
+        if !config.use_jwt {
+            return Ok(builder);
+        }
+        Ok(match &self.credentials.method {
+            LoginMethod::KeyPair { .. } => {
+                let token = self.get_jwt().context("failed to get jwt")?;
+                builder
+                    .header(consts::SNOWFLAKE_AUTH_HEADER, "KEYPAIR_JWT")
+                    .header(consts::AUTH_HEADER, format!["Bearer {token}"])
+            }
+            LoginMethod::Oauth { .. } => {
+                let token = token.context("missing the OAuth access token")?;
+                builder
+                    .header(consts::SNOWFLAKE_AUTH_HEADER, "OAUTH")
+                    .header(consts::AUTH_HEADER, format!["Bearer {token}"])
+            }
+            LoginMethod::Password { .. } | LoginMethod::ExternalBrowser => {
+                bail!("the SQL API doesn't accept session tokens")
+            }
+        })
+    }
+
+    /// Get the token for the login method, getting a new one if there isn't one yet. Only
+    /// one request gets a new token at a time, so a browser login only happens once.
+    async fn token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        if let Some(token) = token.as_ref() {
+            return Ok(token.token.to_owned());
         }
-        Ok(builder)
+        let new_token = match &self.credentials.method {
+            LoginMethod::KeyPair { .. } => bail!("key pair logins sign each request instead"),
+            LoginMethod::Oauth {
+                oauth_token,
+                oauth_token_command,
+                oauth_token_env,
+            } => AuthToken {
+                token: oauth::get_token(oauth_token, oauth_token_command, oauth_token_env)
+                    .await
+                    .context("failed to get an OAuth access token")?,
+                master_token: None,
+            },
+            LoginMethod::Password { .. } | LoginMethod::ExternalBrowser => self.log_in().await?,
+        };
+        *token = Some(new_token.to_owned());
+        Ok(new_token.token)
+    }
+
+    /// Drop a token that Snowflake rejected, so that the next request gets a new one.
+    /// Sessions are renewed if they can be. Tokens that were already replaced by another
+    /// request are left alone.
+    async fn expire_token(&self, rejected: &str) {
+        let mut token = self.token.lock().await;
+        let master_token = match token.as_ref() {
+            Some(current) if current.token == rejected => current.master_token.to_owned(),
+            _ => return,
+        };
+        *token = match master_token {
+            Some(master_token) => self
+                .renew_session(rejected, &master_token)
+                .await
+                .map_err(|e| debug!("couldn't renew the Snowflake session: {e:#}"))
+                .ok(),
+            None => None,
+        };
     }
 
     fn get_body<'a>(&'a self, sql: &'a str) -> HashMap<&str, &'a str> {
@@ -204,10 +340,17 @@ impl SnowflakeRestClient {
     }
 
     fn get_jwt(&self) -> Result<String> {
+        let (private_key, public_key_fp) = match &self.credentials.method {
+            LoginMethod::KeyPair {
+                private_key,
+                public_key_fp,
+            } => (private_key, public_key_fp),
+            _ => bail!("a JWT needs a key pair"),
+        };
         {
             let qualified_username = format![
                 "{}.{}",
-                self.credentials.account.split('.').collect::<Vec<_>>()[0].to_uppercase(),
+                self.account_name(),
                 self.credentials.user.to_uppercase()
             ];
 
@@ -215,7 +358,7 @@ impl SnowflakeRestClient {
             let claims = JwtClaims {
                 exp: (get_current_timestamp() + 3600) as usize,
                 iat: get_current_timestamp() as usize,
-                iss: format!["{qualified_username}.{}", self.credentials.public_key_fp],
+                iss: format!["{qualified_username}.{public_key_fp}"],
                 sub: qualified_username,
             };
 
@@ -223,8 +366,7 @@ impl SnowflakeRestClient {
                 &Header::new(Algorithm::RS256),
                 &claims,
                 &EncodingKey::from_rsa_pem(
-                    self.credentials
-                        .private_key
+                    private_key
                         .replace(' ', "")
                         .replace("ENDPRIVATEKEY", "END PRIVATE KEY")
                         .replace("BEGINPRIVATEKEY", "BEGIN PRIVATE KEY")
@@ -235,6 +377,11 @@ impl SnowflakeRestClient {
         }
     }
 
+    /// Get the account identifier from the credentials
+    pub(crate) fn get_account(&self) -> String {
+        self.credentials.account.to_owned()
+    }
+
     /// Get the snowflake user used for queries
     pub(crate) fn get_snowflake_role(&self) -> String {
         self.credentials.role.to_owned()
@@ -246,7 +393,7 @@ mod tests {
     use super::*;
 
     use jetty_core::logging::debug;
-    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::matchers::{body_string_contains, header, method, path};
     use wiremock::{Mock, MockGuard, MockServer, ResponseTemplate};
 
     pub struct WiremockServer {
@@ -290,8 +437,10 @@ mod tests {
             role: "role".to_owned(),
             user: "user".to_owned(),
             warehouse: "warehouse".to_owned(),
-            private_key: "private_key".to_owned(),
-            public_key_fp: "fp".to_owned(),
+            method: LoginMethod::KeyPair {
+                private_key: "private_key".to_owned(),
+                public_key_fp: "fp".to_owned(),
+            },
             url: None,
         };
         SnowflakeRestClient::new(creds, SnowflakeRestConfig::default()).unwrap();
@@ -307,8 +456,10 @@ mod tests {
             role: "role".to_owned(),
             user: "user".to_owned(),
             warehouse: "warehouse".to_owned(),
-            private_key: "private_key".to_owned(),
-            public_key_fp: "fp".to_owned(),
+            method: LoginMethod::KeyPair {
+                private_key: "private_key".to_owned(),
+                public_key_fp: "fp".to_owned(),
+            },
             url: Some(format!(
                 "{}/api/v2/statements",
                 server.server.as_ref().unwrap().uri()
@@ -335,8 +486,10 @@ mod tests {
             role: "role".to_owned(),
             user: "user".to_owned(),
             warehouse: "warehouse".to_owned(),
-            private_key: "private_key".to_owned(),
-            public_key_fp: "fp".to_owned(),
+            method: LoginMethod::KeyPair {
+                private_key: "private_key".to_owned(),
+                public_key_fp: "fp".to_owned(),
+            },
             url: Some(format!(
                 "{}/api/v2/statements",
                 server.server.as_ref().unwrap().uri()
@@ -353,6 +506,54 @@ mod tests {
         drop(guard);
     }
 
+    #[tokio::test]
+    async fn password_logins_query_over_a_session() -> Result<()> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/session/v1/login-request"))
+            .and(body_string_contains("hunter2"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"data": {"token": "session", "masterToken": "master"}, "success": true}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/queries/v1/query-request"))
+            .and(header(consts::AUTH_HEADER, r#"Snowflake Token="session""#))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"data": {"rowtype": [{"name": "N"}], "rowset": [["1"]], "queryResultFormat": "json"}, "success": true}"#,
+            ))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let creds = SnowflakeCredentials {
+            account: "my_account".to_owned(),
+            role: "role".to_owned(),
+            user: "user".to_owned(),
+            warehouse: "warehouse".to_owned(),
+            method: LoginMethod::Password {
+                password: "hunter2".to_owned(),
+            },
+            url: Some(format!("{}/api/v2/statements", server.uri())),
+        };
+        let config = SnowflakeRequestConfig {
+            sql: "select 1".to_owned(),
+            use_jwt: true,
+        };
+        let client = SnowflakeRestClient::new(creds, SnowflakeRestConfig::default())?;
+        // Creating the client doesn't log in
+        assert!(server.received_requests().await.unwrap().is_empty());
+
+        let res: JsonValue = serde_json::from_str(&client.query(&config).await?)?;
+        assert_eq!(res["resultSetMetaData"]["rowType"][0]["name"], "N");
+        assert_eq!(res["data"], serde_json::json!([["1"]]));
+        // The session is reused
+        client.execute(&config).await?;
+        Ok(())
+    }
+
     #[tokio::test]
     #[should_panic]
     async fn server_error_panics() {
@@ -372,8 +573,10 @@ mod tests {
             role: "role".to_owned(),
             user: "user".to_owned(),
             warehouse: "warehouse".to_owned(),
-            private_key: "private_key".to_owned(),
-            public_key_fp: "fp".to_owned(),
+            method: LoginMethod::KeyPair {
+                private_key: "private_key".to_owned(),
+                public_key_fp: "fp".to_owned(),
+            },
             url: Some(format!(
                 "{}/api/v2/statements",
                 server.server.as_ref().unwrap().uri()
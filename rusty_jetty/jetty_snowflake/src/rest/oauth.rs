@@ -0,0 +1,74 @@
+//! Getting OAuth access tokens from the source set in the credentials
+
+use std::env;
+
+use anyhow::{bail, Context, Result};
+use tokio::process::Command;
+
+/// Get an access token from the configured source. Tokens from a command or an environment
+/// variable are read again each time this is called, so an expired token can be replaced.
+pub(super) async fn get_token(
+    token: &Option<String>,
+    command: &Option<String>,
+    env_var: &Option<String>,
+) -> Result<String> {
+    let token = match (token, command, env_var) {
+        (Some(token), _, _) => token.to_owned(),
+        (_, Some(command), _) => run_token_command(command).await?,
+        (_, _, Some(env_var)) => {
+            env::var(env_var).context(format!("reading the {env_var} environment variable"))?
+        }
+        _ => bail!("no OAuth token source is set"),
+    };
+    let token = token.trim().to_owned();
+    if token.is_empty() {
+        bail!("the OAuth access token is empty");
+    }
+    Ok(token)
+}
+
+/// Run a command that prints an access token
+async fn run_token_command(command: &str) -> Result<String> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").arg("/C").arg(command).output().await
+    } else {
+        Command::new("sh").arg("-c").arg(command).output().await
+    }
+    .context(format!("running `{command}`"))?;
+    if !output.status.success() {
+        bail!(
+            "`{command}` failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    String::from_utf8(output.stdout).context(format!("reading the output of `{command}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tokens_are_read_from_their_source() -> Result<()> {
+        assert_eq!(
+            get_token(&Some("abc".to_owned()), &None, &None).await?,
+            "abc"
+        );
+
+        env::set_var("JETTY_TEST_SNOWFLAKE_TOKEN", "from-env\n");
+        assert_eq!(
+            get_token(&None, &None, &Some("JETTY_TEST_SNOWFLAKE_TOKEN".to_owned())).await?,
+            "from-env"
+        );
+
+        assert_eq!(
+            get_token(&None, &Some("echo from-command".to_owned()), &None).await?,
+            "from-command"
+        );
+        assert!(get_token(&None, &Some("exit 1".to_owned()), &None)
+            .await
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,416 @@
+//! Logging in to Snowflake for the login methods that need a session: passwords and
+//! external browser (SSO) authentication. These methods exchange their credentials for a
+//! session token. The SQL API doesn't accept session tokens, so queries run through the
+//! query endpoint that the Snowflake drivers use, and their results are reshaped to match
+//! the SQL API's.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Context, Result};
+use jetty_core::logging::{debug, error, info};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+use super::{AuthToken, SnowflakeRestClient};
+use crate::{consts, creds::LoginMethod};
+
+const LOGIN_PATH: &str = "/session/v1/login-request";
+const AUTHENTICATOR_PATH: &str = "/session/authenticator-request";
+const TOKEN_PATH: &str = "/session/token-request";
+const QUERY_PATH: &str = "/queries/v1/query-request";
+const EXTERNAL_BROWSER_AUTHENTICATOR: &str = "EXTERNALBROWSER";
+/// How long to wait for the user to finish logging in in the browser
+const BROWSER_LOGIN_TIMEOUT: Duration = Duration::from_secs(120);
+/// The codes for queries that are still running
+const QUERY_IN_PROGRESS_CODES: [&str; 2] = ["333333", "333334"];
+/// The code for requests made with an expired session token
+const SESSION_EXPIRED_CODE: &str = "390112";
+
+/// The envelope of Snowflake's session endpoints
+#[derive(Deserialize, Debug)]
+struct SessionResponse<T> {
+    data: Option<T>,
+    code: Option<String>,
+    message: Option<String>,
+    success: bool,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct LoginData {
+    token: String,
+    master_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct RenewData {
+    session_token: String,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct AuthenticatorData {
+    sso_url: String,
+    proof_key: String,
+}
+
+/// The results of a query run over a session
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct QueryData {
+    #[serde(default)]
+    rowtype: Vec<Value>,
+    #[serde(default)]
+    rowset: Vec<Value>,
+    /// The rest of the rows of large results, stored separately
+    #[serde(default)]
+    chunks: Vec<Chunk>,
+    /// The headers needed to download the chunks
+    #[serde(default)]
+    chunk_headers: Option<Map<String, Value>>,
+    /// The key needed to download the chunks, when there are no chunk headers
+    #[serde(default)]
+    qrmk: Option<String>,
+    #[serde(default)]
+    query_result_format: Option<String>,
+    /// Where to check on a query that's still running
+    #[serde(default)]
+    get_result_url: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Chunk {
+    url: String,
+}
+
+impl SnowflakeRestClient {
+    /// Log in and start a session
+    pub(super) async fn log_in(&self) -> Result<AuthToken> {
+        let data = match &self.credentials.method {
+            LoginMethod::Password { password } => json!({ "PASSWORD": password }),
+            LoginMethod::ExternalBrowser => {
+                let (token, proof_key) = self.get_external_browser_token().await?;
+                json!({
+                    "AUTHENTICATOR": EXTERNAL_BROWSER_AUTHENTICATOR,
+                    "TOKEN": token,
+                    "PROOF_KEY": proof_key,
+                })
+            }
+            LoginMethod::KeyPair { .. } | LoginMethod::Oauth { .. } => {
+                bail!("only password and external browser logins start a session")
+            }
+        };
+
+        let login = self
+            .post_session_request::<LoginData>(
+                LOGIN_PATH,
+                data,
+                &[
+                    ("warehouse", self.credentials.warehouse.as_str()),
+                    ("roleName", self.credentials.role.as_str()),
+                ],
+            )
+            .await
+            .context("failed to log in to Snowflake")?;
+        debug!("logged in to Snowflake as {}", self.credentials.user);
+        Ok(AuthToken {
+            token: login.token,
+            master_token: Some(login.master_token),
+        })
+    }
+
+    /// Get a new session token for a session whose token expired
+    pub(super) async fn renew_session(
+        &self,
+        session_token: &str,
+        master_token: &str,
+    ) -> Result<AuthToken> {
+        let response = self
+            .http_client
+            .post(format!("{}{TOKEN_PATH}", self.get_base_url()))
+            .query(&[("requestId", uuid::Uuid::new_v4().to_string())])
+            .json(&json!({ "oldSessionToken": session_token, "requestType": "RENEW" }))
+            .header(consts::AUTH_HEADER, session_auth_header(master_token))
+            .header(consts::CONTENT_TYPE_HEADER, "application/json")
+            .header(consts::ACCEPT_HEADER, "application/json")
+            .header(consts::USER_AGENT_HEADER, "jetty-labs")
+            .send()
+            .await
+            .context("couldn't send request")?
+            .error_for_status()?
+            .json::<SessionResponse<RenewData>>()
+            .await
+            .context("couldn't parse the response")?;
+        let renewed = response_data(response)?;
+        debug!("renewed the Snowflake session");
+        Ok(AuthToken {
+            token: renewed.session_token,
+            master_token: Some(master_token.to_owned()),
+        })
+    }
+
+    /// Run a statement over the session, logging in first if needed. The results are
+    /// returned in the shape the SQL API uses, with `resultSetMetaData.rowType` and `data`.
+    pub(super) async fn query_over_session(&self, sql: &str) -> Result<String> {
+        let mut renewed = false;
+        let data = loop {
+            let token = self.token().await?;
+            let response = self.post_query(sql, &token).await?;
+            if response.code.as_deref() == Some(SESSION_EXPIRED_CODE) && !renewed {
+                self.expire_token(&token).await;
+                renewed = true;
+                continue;
+            }
+            break self.wait_for_results(response, &token).await?;
+        };
+
+        if let Some(format) = &data.query_result_format {
+            if !format.eq_ignore_ascii_case("json") {
+                bail!("Snowflake returned results in an unsupported format: {format}");
+            }
+        }
+        let mut rows = data.rowset;
+        for chunk in &data.chunks {
+            rows.extend(self.get_chunk(chunk, &data).await?);
+        }
+        Ok(json!({
+            "resultSetMetaData": { "rowType": data.rowtype },
+            "data": rows,
+        })
+        .to_string())
+    }
+
+    async fn post_query(&self, sql: &str, token: &str) -> Result<SessionResponse<QueryData>> {
+        let submitted_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.http_client
+            .post(format!("{}{QUERY_PATH}", self.get_base_url()))
+            .query(&[("requestId", uuid::Uuid::new_v4().to_string())])
+            .json(&json!({
+                "sqlText": sql,
+                "asyncExec": false,
+                "sequenceId": 1,
+                "querySubmissionTime": submitted_at,
+            }))
+            .header(consts::AUTH_HEADER, session_auth_header(token))
+            .header(consts::CONTENT_TYPE_HEADER, "application/json")
+            .header(consts::ACCEPT_HEADER, "application/snowflake")
+            .header(consts::USER_AGENT_HEADER, "jetty-labs")
+            .send()
+            .await
+            .context("couldn't send request")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("couldn't parse the query response")
+    }
+
+    /// Wait for a query that's still running to finish
+    async fn wait_for_results(
+        &self,
+        mut response: SessionResponse<QueryData>,
+        token: &str,
+    ) -> Result<QueryData> {
+        while QUERY_IN_PROGRESS_CODES.contains(&response.code.as_deref().unwrap_or_default()) {
+            let result_url = response
+                .data
+                .as_ref()
+                .and_then(|d| d.get_result_url.to_owned())
+                .context("a running query didn't include where to get its results")?;
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            response = self
+                .http_client
+                .get(format!("{}{result_url}", self.get_base_url()))
+                .header(consts::AUTH_HEADER, session_auth_header(token))
+                .header(consts::ACCEPT_HEADER, "application/snowflake")
+                .header(consts::USER_AGENT_HEADER, "jetty-labs")
+                .send()
+                .await
+                .context("couldn't send request")?
+                .error_for_status()?
+                .json()
+                .await
+                .context("couldn't parse the query response")?;
+        }
+        response_data(response)
+    }
+
+    /// Download one of the chunks of a large result. Chunks hold rows separated by commas,
+    /// without the enclosing brackets.
+    async fn get_chunk(&self, chunk: &Chunk, data: &QueryData) -> Result<Vec<Value>> {
+        let mut request = self.http_client.get(&chunk.url);
+        match (&data.chunk_headers, &data.qrmk) {
+            (Some(headers), _) => {
+                for (name, value) in headers {
+                    request = request.header(name.as_str(), value.as_str().unwrap_or_default());
+                }
+            }
+            (None, Some(qrmk)) => {
+                request = request
+                    .header("x-amz-server-side-encryption-customer-algorithm", "AES256")
+                    .header("x-amz-server-side-encryption-customer-key", qrmk.as_str());
+            }
+            (None, None) => (),
+        }
+        let body = request
+            .send()
+            .await
+            .context("couldn't download a chunk of the results")?
+            .error_for_status()?
+            .text()
+            .await?;
+        serde_json::from_str(&format!("[{body}]")).context("couldn't parse a chunk of the results")
+    }
+
+    /// Have the user log in through their identity provider in a browser. Snowflake
+    /// redirects the browser to a local port with a token that proves the login.
+    async fn get_external_browser_token(&self) -> Result<(String, String)> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("failed to listen for the browser login redirect")?;
+        let port = listener.local_addr()?.port();
+
+        let authenticator = self
+            .post_session_request::<AuthenticatorData>(
+                AUTHENTICATOR_PATH,
+                json!({
+                    "AUTHENTICATOR": EXTERNAL_BROWSER_AUTHENTICATOR,
+                    "BROWSER_MODE_REDIRECT_PORT": port.to_string(),
+                }),
+                &[],
+            )
+            .await
+            .context("failed to start the browser login")?;
+
+        info!(
+            "Log in to Snowflake in your browser. If it doesn't open, go to {}",
+            authenticator.sso_url
+        );
+        if let Err(err) = open::that(&authenticator.sso_url) {
+            error!("An error occurred when opening the browser: {}", err);
+        }
+
+        let token = tokio::time::timeout(BROWSER_LOGIN_TIMEOUT, receive_redirect_token(&listener))
+            .await
+            .context(format!(
+                "timed out after {} seconds waiting for the browser login",
+                BROWSER_LOGIN_TIMEOUT.as_secs()
+            ))??;
+
+        Ok((token, authenticator.proof_key))
+    }
+
+    /// Send a request to one of the session endpoints, adding the account and user to the
+    /// request data
+    async fn post_session_request<T: for<'de> Deserialize<'de>>(
+        &self,
+        path: &str,
+        mut data: Value,
+        query: &[(&str, &str)],
+    ) -> Result<T> {
+        let fields = data.as_object_mut().unwrap();
+        fields.insert("ACCOUNT_NAME".to_owned(), json!(self.account_name()));
+        fields.insert("LOGIN_NAME".to_owned(), json!(self.credentials.user));
+        fields.insert("CLIENT_APP_ID".to_owned(), json!("jetty-labs"));
+
+        let mut body = Map::new();
+        body.insert("data".to_owned(), data);
+        let response = self
+            .http_client
+            .post(format!("{}{path}", self.get_base_url()))
+            .query(query)
+            .json(&body)
+            .header(consts::CONTENT_TYPE_HEADER, "application/json")
+            .header(consts::ACCEPT_HEADER, "application/json")
+            .header(consts::USER_AGENT_HEADER, "jetty-labs")
+            .send()
+            .await
+            .context("couldn't send request")?
+            .error_for_status()?
+            .json::<SessionResponse<T>>()
+            .await
+            .context("couldn't parse the response")?;
+        response_data(response)
+    }
+}
+
+/// The data from a successful response, or the reason it failed
+fn response_data<T>(response: SessionResponse<T>) -> Result<T> {
+    match (response.success, response.data) {
+        (true, Some(data)) => Ok(data),
+        _ => bail!(
+            "{}",
+            response
+                .message
+                .unwrap_or_else(|| "no reason given".to_owned())
+        ),
+    }
+}
+
+fn session_auth_header(token: &str) -> String {
+    format!(r#"Snowflake Token="{token}""#)
+}
+
+/// Wait for the browser to be redirected with the login token. Other requests, like the
+/// browser asking for a favicon, are turned away.
+async fn receive_redirect_token(listener: &TcpListener) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener
+            .accept()
+            .await
+            .context("failed to receive the browser login redirect")?;
+        let (reader, mut writer) = stream.split();
+        let mut request_line = String::new();
+        BufReader::new(reader).read_line(&mut request_line).await?;
+        match token_from_request_line(&request_line) {
+            Some(token) => {
+                writer
+                    .write_all(
+                        b"HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n\
+                        <html><body>Jetty is logged in to Snowflake. You can close this window.</body></html>",
+                    )
+                    .await?;
+                return Ok(token);
+            }
+            None => {
+                writer
+                    .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")
+                    .await?
+            }
+        }
+    }
+}
+
+/// Get the token from the first line of the redirect request, like
+/// `GET /?token=abc HTTP/1.1`
+fn token_from_request_line(request_line: &str) -> Option<String> {
+    let target = request_line.split_whitespace().nth(1)?;
+    let (_, query) = target.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "token")
+        .and_then(|(_, value)| urlencoding::decode(value).ok())
+        .map(|token| token.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirect_tokens_are_decoded() {
+        assert_eq!(
+            token_from_request_line("GET /?token=ab%2Bc%3D&confirm=true HTTP/1.1\r\n"),
+            Some("ab+c=".to_owned())
+        );
+        assert_eq!(token_from_request_line("GET /favicon.ico HTTP/1.1"), None);
+    }
+}
@@ -2,24 +2,45 @@
 
 mod default_policies;
 mod groups;
+mod managed_access;
+pub(crate) mod ownership;
 mod policies;
+mod protections;
+mod roles;
+mod state;
+pub(crate) mod system_roles;
+mod tags;
+mod user_properties;
 mod users;
 
+pub(crate) use managed_access::RestrictedSchemas;
+pub(crate) use roles::{RoleKind, RoleKinds};
+pub(crate) use state::{write_state, DataDirState};
+pub(crate) use tags::ManagedTags;
+
 // need a snowflake coordinator. The diff will be used to update the environment. Then build the role grants. Then build effective permissions.
 
 // Also need a function to take grants and generate the queries
 
 // Then need to run the queries
 
-use jetty_core::access_graph::translate::diffs::LocalConnectorDiffs;
+use jetty_core::{
+    access_graph::translate::diffs::LocalConnectorDiffs, write::apply_report::PlannedOperation,
+};
 
 use crate::SnowflakeConnector;
 
+/// Queries to run, split into tiers. All the queries in a tier may be run concurrently,
+/// but each tier must finish before the next one starts. The tiers are: getting ready to
+/// drop roles, creating and dropping roles and tags, transferring ownership of assets, and
+/// then everything else. Ownership is transferred before the grants because REVOKE CURRENT
+/// GRANTS would otherwise revoke the grants made in the same apply.
 #[derive(Default)]
 pub(crate) struct PrioritizedQueries(
-    pub(crate) Vec<String>,
-    pub(crate) Vec<String>,
-    pub(crate) Vec<String>,
+    pub(crate) Vec<PlannedOperation>,
+    pub(crate) Vec<PlannedOperation>,
+    pub(crate) Vec<PlannedOperation>,
+    pub(crate) Vec<PlannedOperation>,
 );
 
 impl PrioritizedQueries {
@@ -27,26 +48,43 @@ impl PrioritizedQueries {
         self.0.extend(other.0.clone());
         self.1.extend(other.1.clone());
         self.2.extend(other.2.clone());
+        self.3.extend(other.3.clone());
     }
     pub(crate) fn flatten(&self) -> Vec<String> {
-        [self.0.to_owned(), self.1.to_owned(), self.2.to_owned()]
+        [&self.0, &self.1, &self.2, &self.3]
             .into_iter()
             .flatten()
+            .map(|q| q.request.to_owned())
             .collect::<Vec<_>>()
     }
 }
 
 impl SnowflakeConnector {
     pub(super) fn generate_diff_queries(&self, diffs: &LocalConnectorDiffs) -> PrioritizedQueries {
-        let user_queries = users::prepare_queries(&diffs.users);
-        let group_queries = groups::prepare_queries(&diffs.groups, self);
-        let policy_queries = policies::prepare_queries(&diffs.policies);
-        let default_policy_queries = default_policies::prepare_queries(&diffs.default_policies);
+        let roles = self.state::<RoleKinds>();
+        let user_queries = users::prepare_queries(&diffs.users, &roles);
+        let user_properties_queries = user_properties::prepare_queries(&diffs.user_properties);
+        let group_queries = groups::prepare_queries(&diffs.groups, &roles, self);
+        let policy_queries = policies::prepare_queries(&diffs.policies, &roles);
+        let default_policy_queries =
+            default_policies::prepare_queries(&diffs.default_policies, &roles);
+        let tag_queries = match &self.config.tag_schema {
+            Some(tag_schema) => {
+                tags::prepare_queries(&diffs.tags, tag_schema, &self.state::<ManagedTags>())
+            }
+            None => Default::default(),
+        };
+        let protection_queries = protections::prepare_queries(&diffs.protections);
+        let ownership_queries = ownership::prepare_queries(&diffs.ownership, &roles);
 
         let mut prioritized_queries = user_queries;
+        prioritized_queries.extend(&user_properties_queries);
         prioritized_queries.extend(&group_queries);
         prioritized_queries.extend(&policy_queries);
         prioritized_queries.extend(&default_policy_queries);
+        prioritized_queries.extend(&tag_queries);
+        prioritized_queries.extend(&protection_queries);
+        prioritized_queries.extend(&ownership_queries);
         prioritized_queries
     }
 }
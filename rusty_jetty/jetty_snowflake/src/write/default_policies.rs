@@ -1,36 +1,68 @@
 //! managing the write path for policies
 
-use jetty_core::{access_graph::translate::diffs::default_policies, write::assets};
+use std::collections::HashMap;
+
+use jetty_core::{
+    access_graph::translate::diffs::default_policies,
+    write::{
+        apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+        assets,
+    },
+};
 
 use crate::SnowflakeAsset;
 
-use super::{policies::AgentType, PrioritizedQueries};
+use super::{policies::AgentType, PrioritizedQueries, RoleKinds};
 
 pub(super) fn prepare_queries(
     policy_diffs: &Vec<default_policies::LocalDiff>,
+    roles: &RoleKinds,
 ) -> PrioritizedQueries {
     let mut res = PrioritizedQueries::default();
 
     for policy in policy_diffs {
         let asset = crate::cual::cual_to_snowflake_asset(&policy.asset);
+        // Each operation carries only the change to its own agent, so that it can be
+        // checked on its own
+        let operation = |users, groups, query: String| {
+            PlannedOperation::new(
+                ApplyTarget::Asset(policy.asset.to_owned()),
+                DiffItem::DefaultPolicy(default_policies::LocalDiff {
+                    users,
+                    groups,
+                    ..policy.to_owned()
+                }),
+                query,
+            )
+        };
         for (user, details) in &policy.users {
-            res.2.extend(generate_queries_for_diff_details(
-                details,
-                &asset,
-                &policy.asset_type,
-                AgentType::User,
-                user,
-            ))
+            let agent = HashMap::from([(user.to_owned(), details.to_owned())]);
+            res.3.extend(
+                generate_queries_for_diff_details(
+                    details,
+                    &asset,
+                    &policy.asset_type,
+                    AgentType::User,
+                    user,
+                )
+                .into_iter()
+                .map(|q| operation(agent.to_owned(), Default::default(), q)),
+            )
         }
 
         for (group, details) in &policy.groups {
-            res.2.extend(generate_queries_for_diff_details(
-                details,
-                &asset,
-                &policy.asset_type,
-                AgentType::Group,
-                group,
-            ))
+            let agent = HashMap::from([(group.to_owned(), details.to_owned())]);
+            res.3.extend(
+                generate_queries_for_diff_details(
+                    details,
+                    &asset,
+                    &policy.asset_type,
+                    AgentType::Group(roles),
+                    group,
+                )
+                .into_iter()
+                .map(|q| operation(Default::default(), agent.to_owned(), q)),
+            )
         }
     }
 
@@ -44,36 +76,34 @@ fn generate_queries_for_diff_details(
     agent_type: AgentType,
     agent: &String,
 ) -> Vec<String> {
+    let asset_type = crate::sql_object_type(asset_type);
+    let grantee = agent_type.reference(agent);
     match details {
         assets::diff::policies::DiffDetails::AddAgent { add } => {
             let add = &mut add.to_owned();
             let mut res = Vec::new();
 
-            // FUTURE: How we handle ownership today may make double-applies necessary as it
-            // also affects other grants
             if add.privileges.remove("OWNERSHIP") {
+                res.push(future_ownership_grant(&asset_type, asset, &grantee))
+            }
+            if !add.privileges.is_empty() {
+                let privileges = add
+                    .privileges
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ");
                 res.push(format!(
-                    "GRANT OWNERSHIP ON FUTURE {asset_type}s in {} {} TO {agent_type} \"{agent}\" COPY CURRENT GRANTS",
+                    "GRANT {privileges} ON FUTURE {asset_type}s IN {} {} TO {grantee}",
                     asset.asset_type(),
                     asset.fqn()
-                ))
+                ));
             }
-            let privileges = add
-                .privileges
-                .iter()
-                .cloned()
-                .collect::<Vec<_>>()
-                .join(", ");
-            res.push(format!(
-                "GRANT {privileges} ON FUTURE {asset_type}s IN {} {} TO {agent_type} \"{agent}\"",
-                asset.asset_type(),
-                asset.fqn()
-            ));
             res
         }
         assets::diff::policies::DiffDetails::RemoveAgent { .. } => {
             vec![format!(
-                "REVOKE ALL ON FUTURE {asset_type}s IN {} {} FROM {agent_type} \"{agent}\"",
+                "REVOKE ALL ON FUTURE {asset_type}s IN {} {} FROM {grantee}",
                 asset.asset_type(),
                 asset.fqn()
             )]
@@ -82,14 +112,8 @@ fn generate_queries_for_diff_details(
             let add = &mut add.to_owned();
             let mut res = Vec::new();
 
-            // FUTURE: How we handle ownership today may make double-applies necessary as it
-            // also affects other grants
             if add.privileges.remove("OWNERSHIP") {
-                res.push(format!(
-                    "GRANT OWNERSHIP ON FUTURE {asset_type}s in {} {} TO {agent_type} \"{agent}\" COPY CURRENT GRANTS",
-                    asset.asset_type(),
-                    asset.fqn()
-                ))
+                res.push(future_ownership_grant(&asset_type, asset, &grantee))
             }
             if !add.privileges.is_empty() {
                 let privileges = add
@@ -99,7 +123,7 @@ fn generate_queries_for_diff_details(
                     .collect::<Vec<_>>()
                     .join(", ");
                 res.push(format!(
-                    "GRANT {privileges} ON FUTURE {asset_type}s IN {} {} TO {agent_type} \"{agent}\"",
+                    "GRANT {privileges} ON FUTURE {asset_type}s IN {} {} TO {grantee}",
                     asset.asset_type(),
                     asset.fqn()
                 ));
@@ -112,12 +136,77 @@ fn generate_queries_for_diff_details(
                     .collect::<Vec<_>>()
                     .join(", ");
                 res.push(format!(
-                "REVOKE {privileges} ON FUTURE {asset_type}s IN {} {} FROM {agent_type} \"{agent}\"",
-                asset.asset_type(),
-                asset.fqn()
-            ));
+                    "REVOKE {privileges} ON FUTURE {asset_type}s IN {} {} FROM {grantee}",
+                    asset.asset_type(),
+                    asset.fqn()
+                ));
             }
             res
         }
     }
 }
+
+/// Future ownership grants can't copy or revoke current grants: they only apply to objects
+/// created later, which don't have any grants yet.
+fn future_ownership_grant(asset_type: &str, asset: &SnowflakeAsset, grantee: &str) -> String {
+    format!(
+        "GRANT OWNERSHIP ON FUTURE {asset_type}s IN {} {} TO {grantee}",
+        asset.asset_type(),
+        asset.fqn()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use jetty_core::write::assets::PolicyState;
+
+    use super::*;
+
+    #[test]
+    fn future_ownership_is_granted_without_copying_grants() {
+        let details = assets::diff::policies::DiffDetails::AddAgent {
+            add: PolicyState {
+                privileges: ["OWNERSHIP".to_owned(), "SELECT".to_owned()].into(),
+                ..Default::default()
+            },
+        };
+
+        let queries = generate_queries_for_diff_details(
+            &details,
+            &SnowflakeAsset::Schema(r#""DB"."RAW""#.to_owned()),
+            &"TABLE".to_owned(),
+            AgentType::Group(&Default::default()),
+            &"LOADER".to_owned(),
+        );
+        assert_eq!(
+            queries,
+            vec![
+                r#"GRANT OWNERSHIP ON FUTURE TABLEs IN SCHEMA "DB"."RAW" TO ROLE "LOADER""#,
+                r#"GRANT SELECT ON FUTURE TABLEs IN SCHEMA "DB"."RAW" TO ROLE "LOADER""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn future_ownership_alone_grants_nothing_else() {
+        let details = assets::diff::policies::DiffDetails::ModifyAgent {
+            add: PolicyState {
+                privileges: ["OWNERSHIP".to_owned()].into(),
+                ..Default::default()
+            },
+            remove: Default::default(),
+        };
+
+        let queries = generate_queries_for_diff_details(
+            &details,
+            &SnowflakeAsset::Database(r#""DB""#.to_owned()),
+            &"VIEW".to_owned(),
+            AgentType::User,
+            &"ELLEN".to_owned(),
+        );
+        assert_eq!(
+            queries,
+            vec![r#"GRANT OWNERSHIP ON FUTURE VIEWs IN DATABASE "DB" TO USER "ELLEN""#]
+        );
+    }
+}
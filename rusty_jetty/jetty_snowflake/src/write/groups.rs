@@ -1,51 +1,87 @@
 //! managing the write path for groups
 
-use jetty_core::access_graph::translate::diffs::groups;
+use jetty_core::{
+    access_graph::translate::diffs::groups,
+    write::apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+};
 
-use crate::SnowflakeConnector;
+use crate::{quote_snowflake_identifier, SnowflakeConnector};
 
-use super::PrioritizedQueries;
+use super::{PrioritizedQueries, RoleKinds};
 
 pub(super) fn prepare_queries(
     group_diffs: &[groups::LocalDiff],
+    roles: &RoleKinds,
     snow: &SnowflakeConnector,
 ) -> PrioritizedQueries {
     let mut res = PrioritizedQueries::default();
     group_diffs.iter().for_each(|diff| {
+        // Each operation carries only the membership it changes, so that it can be checked
+        // on its own
+        let operation = |details: groups::LocalDiffDetails, query: String| {
+            PlannedOperation::new(
+                ApplyTarget::Group(diff.group_name.to_owned()),
+                DiffItem::Group(groups::LocalDiff {
+                    group_name: diff.group_name.to_owned(),
+                    details,
+                }),
+                query,
+            )
+        };
+        let role = roles.reference(&diff.group_name);
         match &diff.details {
             groups::LocalDiffDetails::AddGroup { member_of } => {
-                res.1.push(format!("CREATE ROLE \"{}\";", diff.group_name));
+                res.1.push(operation(
+                    groups::LocalDiffDetails::AddGroup {
+                        member_of: Default::default(),
+                    },
+                    format!("CREATE {};", role),
+                ));
                 for group in member_of {
-                    res.2.push(format!(
-                        "GRANT ROLE \"{}\" TO ROLE \"{}\";",
-                        group, diff.group_name
+                    res.3.push(operation(
+                        groups::LocalDiffDetails::AddGroup {
+                            member_of: [group.to_owned()].into(),
+                        },
+                        format!("GRANT {} TO {};", roles.reference(group), role),
                     ))
                 }
             }
             groups::LocalDiffDetails::RemoveGroup => {
                 // Drop roles. This will transfer all ownership to the Jetty role. If there are grants that are owned by the role that is dropped, those grants are dropped too.
                 // because of this, it may be necessary to run a double-apply.
-                res.0.push(format!(
-                    "GRANT OWNERSHIP ON ROLE \"{}\" TO \"{}\"; --Only the owner of a role can drop it",
-                    diff.group_name,
-                    snow.rest_client.get_snowflake_role()
+                res.0.push(operation(
+                    groups::LocalDiffDetails::RemoveGroup,
+                    format!(
+                        "GRANT OWNERSHIP ON {} TO {}; --Only the owner of a role can drop it",
+                        role,
+                        quote_snowflake_identifier(&snow.rest_client.get_snowflake_role())
+                    ),
+                ));
+                res.1.push(operation(
+                    groups::LocalDiffDetails::RemoveGroup,
+                    format!("DROP {};", role),
                 ));
-                res.1.push(format!("DROP ROLE \"{}\";", diff.group_name));
             }
             groups::LocalDiffDetails::ModifyGroup {
                 add_member_of,
                 remove_member_of,
             } => {
                 for group in add_member_of {
-                    res.2.push(format!(
-                        "GRANT ROLE \"{}\" TO ROLE \"{}\";",
-                        group, diff.group_name
+                    res.3.push(operation(
+                        groups::LocalDiffDetails::ModifyGroup {
+                            add_member_of: [group.to_owned()].into(),
+                            remove_member_of: Default::default(),
+                        },
+                        format!("GRANT {} TO {};", roles.reference(group), role),
                     ))
                 }
                 for group in remove_member_of {
-                    res.2.push(format!(
-                        "REVOKE ROLE \"{}\" FROM ROLE \"{}\";",
-                        group, diff.group_name
+                    res.3.push(operation(
+                        groups::LocalDiffDetails::ModifyGroup {
+                            add_member_of: Default::default(),
+                            remove_member_of: [group.to_owned()].into(),
+                        },
+                        format!("REVOKE {} FROM {};", roles.reference(group), role),
                     ))
                 }
             }
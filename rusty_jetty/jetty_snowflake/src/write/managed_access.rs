@@ -0,0 +1,123 @@
+//! Checking planned grants against managed-access schemas. In a managed-access schema, only
+//! the schema owner and roles with MANAGE GRANTS can grant privileges on the schema's
+//! objects, so the role Jetty uses may not be able to run some of the queries it plans.
+
+use std::collections::BTreeSet;
+
+use jetty_core::{
+    cual::Cual,
+    write::apply_report::{DiffItem, PlannedOperation},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::consts;
+
+use super::DataDirState;
+
+/// The managed-access schemas, as `DB.SCHEMA`, where the role Jetty uses can't grant
+/// privileges, as of the last fetch
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RestrictedSchemas {
+    /// The role Jetty used for the fetch
+    pub(crate) role: String,
+    pub(crate) schemas: BTreeSet<String>,
+}
+
+impl DataDirState for RestrictedSchemas {
+    const FILENAME: &'static str = "managed_access_schemas.json";
+    const DESCRIPTION: &'static str = "the managed-access Snowflake schemas";
+}
+
+impl RestrictedSchemas {
+    /// Explain why an operation will fail, if it grants or revokes privileges in a schema
+    /// where Jetty's role can't
+    pub(crate) fn warning(&self, operation: &PlannedOperation) -> Option<String> {
+        let schema = match &operation.diff {
+            // Privileges on, and ownership of, the objects in a schema
+            DiffItem::Policy(diff) => object_schema(&diff.asset),
+            DiffItem::Ownership(diff) => object_schema(&diff.asset),
+            // Future grants in a schema
+            DiffItem::DefaultPolicy(diff) => match diff.asset.asset_type() {
+                Some(t) if t.to_string() == consts::SCHEMA => Some(path_segments(&diff.asset)),
+                _ => None,
+            },
+            _ => None,
+        }?
+        .join(".");
+
+        self.schemas.contains(&schema).then(|| {
+            format!(
+                "-- WARNING: {schema} is a managed-access schema, and {} doesn't own it or hold \
+                MANAGE GRANTS, so this will fail. Run it as the schema owner, or grant \
+                MANAGE GRANTS to {}",
+                self.role, self.role
+            )
+        })
+    }
+}
+
+/// The database and schema of an object that lives in a schema
+fn object_schema(cual: &Cual) -> Option<Vec<String>> {
+    let asset_type = cual.asset_type()?.to_string();
+    consts::SCHEMA_OBJECT_TYPES
+        .contains(&asset_type.as_str())
+        .then(|| path_segments(cual).into_iter().take(2).collect())
+}
+
+fn path_segments(cual: &Cual) -> Vec<String> {
+    cual.asset_path().components().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use jetty_core::{
+        access_graph::translate::diffs::{ownership, policies},
+        connectors::ownership::CurrentGrants,
+        write::apply_report::ApplyTarget,
+    };
+
+    use super::*;
+
+    fn operation(diff: DiffItem) -> PlannedOperation {
+        PlannedOperation::new(
+            ApplyTarget::Asset(Cual::new("snowflake://account/DB")),
+            diff,
+            "GRANT ...;".to_owned(),
+        )
+    }
+
+    #[test]
+    fn grants_in_restricted_schemas_are_flagged() {
+        let restricted = RestrictedSchemas {
+            role: "JETTY".to_owned(),
+            schemas: BTreeSet::from(["DB.SECURE".to_owned()]),
+        };
+        let policy = |uri: &str| {
+            operation(DiffItem::Policy(policies::LocalDiff {
+                asset: Cual::new(uri),
+                users: Default::default(),
+                groups: Default::default(),
+            }))
+        };
+
+        assert!(restricted
+            .warning(&policy("snowflake://account/DB/SECURE/USERS?type=TABLE"))
+            .unwrap()
+            .contains("DB.SECURE is a managed-access schema"));
+        assert!(restricted
+            .warning(&operation(DiffItem::Ownership(ownership::LocalDiff {
+                asset: Cual::new("snowflake://account/DB/SECURE/USERS?type=TABLE"),
+                owner: "LOADER".to_owned(),
+                previous_owner: None,
+                current_grants: CurrentGrants::Copy,
+            })))
+            .is_some());
+        // The schema's owner grants privileges on the schema itself as usual
+        assert!(restricted
+            .warning(&policy("snowflake://account/DB/SECURE?type=SCHEMA"))
+            .is_none());
+        assert!(restricted
+            .warning(&policy("snowflake://account/DB/RAW/USERS?type=TABLE"))
+            .is_none());
+    }
+}
@@ -0,0 +1,106 @@
+//! managing the write path for asset ownership
+
+use jetty_core::{
+    access_graph::translate::diffs::ownership,
+    connectors::ownership::CurrentGrants,
+    write::apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+};
+
+use super::{PrioritizedQueries, RoleKinds};
+
+/// Generate the queries to transfer ownership. The new owner may be created in the same
+/// apply, so these run after roles are created, but before any grants are made.
+pub(super) fn prepare_queries(
+    ownership_diffs: &[ownership::LocalDiff],
+    roles: &RoleKinds,
+) -> PrioritizedQueries {
+    let mut res = PrioritizedQueries::default();
+
+    for diff in ownership_diffs {
+        let snowflake_asset = crate::cual::cual_to_snowflake_asset(&diff.asset);
+        res.2.push(PlannedOperation::new(
+            ApplyTarget::Asset(diff.asset.to_owned()),
+            DiffItem::Ownership(diff.to_owned()),
+            format!(
+                "GRANT OWNERSHIP ON {} TO {} {} CURRENT GRANTS;",
+                snowflake_asset.grant_target(),
+                roles.reference(&diff.owner),
+                current_grants_keyword(diff.current_grants)
+            ),
+        ));
+    }
+
+    res
+}
+
+fn current_grants_keyword(current_grants: CurrentGrants) -> &'static str {
+    match current_grants {
+        CurrentGrants::Copy => "COPY",
+        CurrentGrants::Revoke => "REVOKE",
+    }
+}
+
+/// Explain what an ownership transfer does to the existing grants on the asset, so that
+/// the difference between COPY and REVOKE is clear in plans
+pub(crate) fn plan_note(operation: &PlannedOperation) -> Option<String> {
+    let diff = match &operation.diff {
+        DiffItem::Ownership(diff) => diff,
+        _ => return None,
+    };
+    let fqn = crate::cual::cual_to_snowflake_asset(&diff.asset)
+        .fqn()
+        .to_owned();
+    Some(match diff.current_grants {
+        CurrentGrants::Copy => format!(
+            "-- COPY CURRENT GRANTS: the existing grants on {fqn} are kept, and {} becomes \
+            their grantor",
+            diff.owner
+        ),
+        CurrentGrants::Revoke => format!(
+            "-- REVOKE CURRENT GRANTS: every existing grant on {fqn} is revoked, including \
+            the ones in the Jetty configuration. They'll show up again in the next plan"
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use jetty_core::cual::Cual;
+
+    use crate::write::roles::RoleKind;
+
+    use super::*;
+
+    #[test]
+    fn ownership_transfers_explain_current_grants() {
+        let diff = ownership::LocalDiff {
+            asset: Cual::new("snowflake://account/DB/RAW/USERS?type=TABLE"),
+            owner: "DB.LOADER".to_owned(),
+            previous_owner: Some("SYSADMIN".to_owned()),
+            current_grants: CurrentGrants::Revoke,
+        };
+
+        let roles = RoleKinds {
+            roles: [(
+                "DB.LOADER".to_owned(),
+                RoleKind::Database {
+                    database: "DB".to_owned(),
+                    role: "LOADER".to_owned(),
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let queries = prepare_queries(&[diff], &roles);
+        assert_eq!(queries.2.len(), 1);
+        assert_eq!(
+            queries.2[0].request,
+            "GRANT OWNERSHIP ON TABLE \"DB\".\"RAW\".\"USERS\" TO DATABASE ROLE \"DB\".\"LOADER\" \
+            REVOKE CURRENT GRANTS;"
+        );
+        assert!(plan_note(&queries.2[0])
+            .unwrap()
+            .starts_with("-- REVOKE CURRENT GRANTS"));
+    }
+}
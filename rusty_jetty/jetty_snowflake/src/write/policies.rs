@@ -1,50 +1,75 @@
 //! managing the write path for policies
 
-use std::fmt::Display;
+use std::collections::HashMap;
 
-use jetty_core::{access_graph::translate::diffs::policies, write::assets};
+use jetty_core::{
+    access_graph::translate::diffs::policies,
+    write::{
+        apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+        assets,
+    },
+};
 
-use crate::SnowflakeAsset;
+use crate::{quote_snowflake_identifier, SnowflakeAsset};
 
-use super::PrioritizedQueries;
+use super::{PrioritizedQueries, RoleKinds};
 
-pub(super) fn prepare_queries(policy_diffs: &Vec<policies::LocalDiff>) -> PrioritizedQueries {
+pub(super) fn prepare_queries(
+    policy_diffs: &Vec<policies::LocalDiff>,
+    roles: &RoleKinds,
+) -> PrioritizedQueries {
     let mut res = PrioritizedQueries::default();
 
     for policy in policy_diffs {
         let asset = crate::cual::cual_to_snowflake_asset(&policy.asset);
+        // Each operation carries only the change to its own agent, so that it can be
+        // checked on its own
+        let operation = |users, groups, query: String| {
+            PlannedOperation::new(
+                ApplyTarget::Asset(policy.asset.to_owned()),
+                DiffItem::Policy(policies::LocalDiff {
+                    asset: policy.asset.to_owned(),
+                    users,
+                    groups,
+                }),
+                query,
+            )
+        };
         for (user, details) in &policy.users {
-            res.2.extend(generate_queries_for_diff_details(
-                details,
-                &asset,
-                AgentType::User,
-                user,
-            ))
+            let agent = HashMap::from([(user.to_owned(), details.to_owned())]);
+            res.3.extend(
+                generate_queries_for_diff_details(details, &asset, AgentType::User, user)
+                    .into_iter()
+                    .map(|q| operation(agent.to_owned(), Default::default(), q)),
+            )
         }
 
         for (group, details) in &policy.groups {
-            res.2.extend(generate_queries_for_diff_details(
-                details,
-                &asset,
-                AgentType::Group,
-                group,
-            ))
+            let agent = HashMap::from([(group.to_owned(), details.to_owned())]);
+            res.3.extend(
+                generate_queries_for_diff_details(details, &asset, AgentType::Group(roles), group)
+                    .into_iter()
+                    .map(|q| operation(Default::default(), agent.to_owned(), q)),
+            )
         }
     }
 
     res
 }
 
-pub(crate) enum AgentType {
+pub(crate) enum AgentType<'a> {
     User,
-    Group,
+    /// A role, with the kinds of the roles in the account
+    Group(&'a RoleKinds),
 }
 
-impl Display for AgentType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl AgentType<'_> {
+    /// Get the way the agent is referenced in SQL, like `USER "ELLEN"` or
+    /// `DATABASE ROLE "DB"."READER"`
+    pub(crate) fn reference(&self, agent: &str) -> String {
         match self {
-            AgentType::User => write!(f, "USER"),
-            AgentType::Group => write!(f, "ROLE"),
+            AgentType::User => format!("USER {}", quote_snowflake_identifier(agent)),
+            AgentType::Group(roles) => roles.reference(agent),
         }
     }
 }
@@ -55,19 +80,12 @@ pub(crate) fn generate_queries_for_diff_details(
     agent_type: AgentType,
     agent: &String,
 ) -> Vec<String> {
+    let grantee = agent_type.reference(agent);
     match details {
         assets::diff::policies::DiffDetails::AddAgent { add } => {
-            let add = &mut add.to_owned();
-            let mut res = Vec::new();
-
-            // FUTURE: How we handle ownership today may make double-applies necessary as it
-            // also affects other grants
-            if add.privileges.remove("OWNERSHIP") {
-                res.push(format!(
-                    "GRANT OWNERSHIP ON {} {} TO {agent_type} \"{agent}\" COPY CURRENT GRANTS",
-                    asset.asset_type(),
-                    asset.fqn()
-                ))
+            // Ownership is transferred through the asset's owner, not through policies
+            if add.privileges.is_empty() {
+                return vec![];
             }
             let privileges = add
                 .privileges
@@ -75,31 +93,20 @@ pub(crate) fn generate_queries_for_diff_details(
                 .cloned()
                 .collect::<Vec<_>>()
                 .join(", ");
-            res.push(format!(
-                "GRANT {privileges} ON {} {} TO {agent_type} \"{agent}\"",
-                asset.asset_type(),
-                asset.fqn()
-            ));
-            res
+            vec![format!(
+                "GRANT {privileges} ON {} TO {grantee}",
+                asset.grant_target()
+            )]
         }
         assets::diff::policies::DiffDetails::RemoveAgent { .. } => {
             vec![format!(
-                "REVOKE ALL ON {} {} FROM {agent_type} \"{agent}\"",
-                asset.asset_type(),
-                asset.fqn()
+                "REVOKE ALL ON {} FROM {grantee}",
+                asset.grant_target()
             )]
         }
         assets::diff::policies::DiffDetails::ModifyAgent { add, remove } => {
-            let add = &mut add.to_owned();
             let mut res = Vec::new();
             if !add.privileges.is_empty() {
-                if add.privileges.remove("OWNERSHIP") {
-                    res.push(format!(
-                        "GRANT OWNERSHIP ON {} {} TO {agent_type} \"{agent}\" COPY CURRENT GRANTS",
-                        asset.asset_type(),
-                        asset.fqn()
-                    ))
-                }
                 let privileges = add
                     .privileges
                     .iter()
@@ -107,9 +114,8 @@ pub(crate) fn generate_queries_for_diff_details(
                     .collect::<Vec<_>>()
                     .join(", ");
                 res.push(format!(
-                    "GRANT {privileges} ON {} {} TO {agent_type} \"{agent}\"",
-                    asset.asset_type(),
-                    asset.fqn()
+                    "GRANT {privileges} ON {} TO {grantee}",
+                    asset.grant_target()
                 ));
             }
             if !remove.privileges.is_empty() {
@@ -120,9 +126,8 @@ pub(crate) fn generate_queries_for_diff_details(
                     .collect::<Vec<_>>()
                     .join(", ");
                 res.push(format!(
-                    "REVOKE {privileges} ON {} {} FROM {agent_type} \"{agent}\"",
-                    asset.asset_type(),
-                    asset.fqn()
+                    "REVOKE {privileges} ON {} FROM {grantee}",
+                    asset.grant_target()
                 ));
             }
             res
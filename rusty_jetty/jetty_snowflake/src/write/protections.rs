@@ -0,0 +1,208 @@
+//! managing the write path for masking, row access, network, and session policies
+
+use jetty_core::{
+    access_graph::translate::diffs::protections,
+    connectors::protections::RowAccessPolicy,
+    logging::warn,
+    write::apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+};
+
+use crate::{quote_snowflake_fqn, quote_snowflake_identifier, SnowflakeAsset};
+
+use super::PrioritizedQueries;
+
+/// Generate the queries to attach and detach policies. The policies themselves must already
+/// exist, so these all run in the last tier.
+pub(super) fn prepare_queries(protection_diffs: &[protections::LocalDiff]) -> PrioritizedQueries {
+    let mut res = PrioritizedQueries::default();
+
+    for diff in protection_diffs {
+        let snowflake_asset = crate::cual::cual_to_snowflake_asset(&diff.asset);
+        let alter = format!("ALTER {}", snowflake_asset.grant_target());
+        let operation = |query: String| {
+            PlannedOperation::new(
+                ApplyTarget::Asset(diff.asset.to_owned()),
+                DiffItem::Protection(diff.to_owned()),
+                query,
+            )
+        };
+
+        if let Some(change) = &diff.row_access_policy {
+            // Dropping and adding in one statement means the asset is never unprotected
+            let actions = change
+                .remove
+                .iter()
+                .map(|p| format!("DROP ROW ACCESS POLICY {}", quote_snowflake_fqn(&p.policy)))
+                .chain(change.add.iter().map(add_row_access_policy))
+                .collect::<Vec<_>>();
+            res.3
+                .push(operation(format!("{alter} {};", actions.join(", "))));
+        }
+
+        for (column, change) in &diff.masking_policies {
+            let query = match (&change.add, &change.remove) {
+                (Some(policy), remove) => format!(
+                    "{alter} MODIFY COLUMN {} SET MASKING POLICY {}{};",
+                    quote_snowflake_identifier(column),
+                    quote_snowflake_fqn(policy),
+                    // FORCE replaces the existing policy without unsetting it first
+                    if remove.is_some() { " FORCE" } else { "" }
+                ),
+                (None, Some(_)) => {
+                    format!(
+                        "{alter} MODIFY COLUMN {} UNSET MASKING POLICY;",
+                        quote_snowflake_identifier(column)
+                    )
+                }
+                (None, None) => continue,
+            };
+            res.3.push(operation(query));
+        }
+
+        if diff.network_policy.is_none() && diff.session_policy.is_none() {
+            continue;
+        }
+        if snowflake_asset != SnowflakeAsset::Account {
+            warn!(
+                "skipping the network and session policies for {}: they can only be set on \
+                the account",
+                diff.asset.uri()
+            );
+            continue;
+        }
+        if let Some(change) = &diff.network_policy {
+            let query = match &change.add {
+                Some(policy) => format!(
+                    "{alter} SET NETWORK_POLICY = {};",
+                    quote_snowflake_identifier(policy)
+                ),
+                None => format!("{alter} UNSET NETWORK_POLICY;"),
+            };
+            res.3.push(operation(query));
+        }
+        if let Some(change) = &diff.session_policy {
+            let query = match (&change.add, &change.remove) {
+                (Some(policy), remove) => format!(
+                    "{alter} SET SESSION POLICY {}{};",
+                    quote_snowflake_fqn(policy),
+                    if remove.is_some() { " FORCE" } else { "" }
+                ),
+                (None, Some(_)) => format!("{alter} UNSET SESSION POLICY;"),
+                (None, None) => continue,
+            };
+            res.3.push(operation(query));
+        }
+    }
+
+    res
+}
+
+fn add_row_access_policy(policy: &RowAccessPolicy) -> String {
+    format!(
+        "ADD ROW ACCESS POLICY {} ON ({})",
+        quote_snowflake_fqn(&policy.policy),
+        policy
+            .columns
+            .iter()
+            .map(|c| quote_snowflake_identifier(c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use jetty_core::{cual::Cual, write::assets::diff::protections::ProtectionChange};
+
+    use super::*;
+
+    fn users_table() -> Cual {
+        Cual::new("snowflake://account/DB/RAW/USERS?type=TABLE")
+    }
+
+    #[test]
+    fn masking_policies_are_set_replaced_and_unset() {
+        let change = |add: Option<&str>, remove: Option<&str>| ProtectionChange {
+            add: add.map(|p| p.to_owned()),
+            remove: remove.map(|p| p.to_owned()),
+        };
+        let diff = protections::LocalDiff {
+            asset: users_table(),
+            row_access_policy: None,
+            masking_policies: BTreeMap::from([
+                ("EMAIL".to_owned(), change(Some("GOV.P.MASK_EMAIL"), None)),
+                ("PHONE".to_owned(), change(None, Some("GOV.P.MASK_PHONE"))),
+                (
+                    "SSN".to_owned(),
+                    change(Some("GOV.P.MASK_SSN"), Some("GOV.P.MASK_ALL")),
+                ),
+            ]),
+            network_policy: None,
+            session_policy: None,
+        };
+
+        let queries = prepare_queries(&[diff]);
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"ALTER TABLE "DB"."RAW"."USERS" MODIFY COLUMN "EMAIL" SET MASKING POLICY "GOV"."P"."MASK_EMAIL";"#,
+                r#"ALTER TABLE "DB"."RAW"."USERS" MODIFY COLUMN "PHONE" UNSET MASKING POLICY;"#,
+                r#"ALTER TABLE "DB"."RAW"."USERS" MODIFY COLUMN "SSN" SET MASKING POLICY "GOV"."P"."MASK_SSN" FORCE;"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn row_access_policies_are_swapped_in_one_statement() {
+        let policy = |name: &str| RowAccessPolicy {
+            policy: format!("GOV.P.{name}"),
+            columns: vec!["REGION".to_owned()],
+        };
+        let diff = protections::LocalDiff {
+            asset: users_table(),
+            row_access_policy: Some(ProtectionChange {
+                add: Some(policy("BY_REGION")),
+                remove: Some(policy("BY_COUNTRY")),
+            }),
+            masking_policies: Default::default(),
+            network_policy: None,
+            session_policy: None,
+        };
+
+        let queries = prepare_queries(&[diff]);
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"ALTER TABLE "DB"."RAW"."USERS" DROP ROW ACCESS POLICY "GOV"."P"."BY_COUNTRY", ADD ROW ACCESS POLICY "GOV"."P"."BY_REGION" ON ("REGION");"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn account_policies_are_set_on_the_account() {
+        let diff = protections::LocalDiff {
+            asset: Cual::new("snowflake://account.snowflakecomputing.com/ACCOUNT?type=ACCOUNT"),
+            row_access_policy: None,
+            masking_policies: Default::default(),
+            network_policy: Some(ProtectionChange {
+                add: Some("OFFICE_ONLY".to_owned()),
+                remove: None,
+            }),
+            session_policy: Some(ProtectionChange {
+                add: Some("GOV.P.IDLE_TIMEOUT".to_owned()),
+                remove: Some("GOV.P.DEFAULT_TIMEOUT".to_owned()),
+            }),
+        };
+
+        let queries = prepare_queries(&[diff]);
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"ALTER ACCOUNT SET NETWORK_POLICY = "OFFICE_ONLY";"#,
+                r#"ALTER ACCOUNT SET SESSION POLICY "GOV"."P"."IDLE_TIMEOUT" FORCE;"#,
+            ]
+        );
+    }
+}
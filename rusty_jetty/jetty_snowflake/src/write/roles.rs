@@ -0,0 +1,104 @@
+//! The kinds of the roles in the account. Database roles are named `DATABASE.ROLE` in Jetty,
+//! but account role names can contain periods too, so the kind of each role is recorded when
+//! it's fetched rather than read from its name.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::quote_snowflake_identifier;
+
+use super::DataDirState;
+
+/// The kind of a Snowflake role
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub(crate) enum RoleKind {
+    /// An account role
+    Account,
+    /// A database role, and the database it belongs to
+    Database { database: String, role: String },
+}
+
+/// The kinds of the roles in the account, keyed by their Jetty name, as of the last fetch
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct RoleKinds {
+    pub(crate) roles: BTreeMap<String, RoleKind>,
+    /// The databases in the account, so that new database roles can be recognized
+    pub(crate) databases: BTreeSet<String>,
+}
+
+impl DataDirState for RoleKinds {
+    const FILENAME: &'static str = "roles.json";
+    const DESCRIPTION: &'static str = "the kinds of the Snowflake roles";
+}
+
+impl RoleKinds {
+    /// Get the kind of a role. Roles that don't exist yet are database roles if they're
+    /// named after a database, like `DATABASE.ROLE`.
+    pub(crate) fn kind(&self, name: &str) -> RoleKind {
+        if let Some(kind) = self.roles.get(name) {
+            return kind.to_owned();
+        }
+        self.databases
+            .iter()
+            .find_map(|database| {
+                name.strip_prefix(database)
+                    .and_then(|rest| rest.strip_prefix('.'))
+                    .map(|role| RoleKind::Database {
+                        database: database.to_owned(),
+                        role: role.to_owned(),
+                    })
+            })
+            .unwrap_or(RoleKind::Account)
+    }
+
+    /// Get the way a role is referenced in SQL, like `ROLE "ANALYST"` or
+    /// `DATABASE ROLE "DB"."READER"`
+    pub(crate) fn reference(&self, name: &str) -> String {
+        match self.kind(name) {
+            RoleKind::Account => format!("ROLE {}", quote_snowflake_identifier(name)),
+            RoleKind::Database { database, role } => format!(
+                "DATABASE ROLE {}.{}",
+                quote_snowflake_identifier(&database),
+                quote_snowflake_identifier(&role)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_are_referenced_by_their_kind() {
+        let roles = RoleKinds {
+            roles: BTreeMap::from([
+                ("TEAM.ANALYST".to_owned(), RoleKind::Account),
+                (
+                    "ANALYTICS.READER".to_owned(),
+                    RoleKind::Database {
+                        database: "ANALYTICS".to_owned(),
+                        role: "READER".to_owned(),
+                    },
+                ),
+            ]),
+            databases: BTreeSet::from(["ANALYTICS".to_owned(), "TEAM".to_owned()]),
+        };
+        assert_eq!(roles.reference("ANALYST"), r#"ROLE "ANALYST""#);
+        // An account role with a period in its name
+        assert_eq!(roles.reference("TEAM.ANALYST"), r#"ROLE "TEAM.ANALYST""#);
+        assert_eq!(
+            roles.reference("ANALYTICS.READER"),
+            r#"DATABASE ROLE "ANALYTICS"."READER""#
+        );
+        // A new database role
+        assert_eq!(
+            roles.reference("ANALYTICS.WRITER"),
+            r#"DATABASE ROLE "ANALYTICS"."WRITER""#
+        );
+        // A new role that isn't named after a database
+        assert_eq!(roles.reference("OPS.ADMIN"), r#"ROLE "OPS.ADMIN""#);
+    }
+}
@@ -0,0 +1,86 @@
+//! State that the connector keeps in its data directory between runs, like the kinds of the
+//! roles it fetched. Each kind of state is kept as JSON in its own file.
+
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result};
+use jetty_core::logging::error;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::SnowflakeConnector;
+
+/// State kept in the connector's data directory
+pub(crate) trait DataDirState: Serialize + DeserializeOwned + Default {
+    /// The name of the file the state is kept in
+    const FILENAME: &'static str;
+    /// What the state holds, for error messages
+    const DESCRIPTION: &'static str;
+}
+
+/// Write state to the data directory, replacing what was there
+pub(crate) fn write_state<T: DataDirState>(data_dir: &Path, state: &T) -> Result<()> {
+    fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(T::FILENAME);
+    fs::write(&path, serde_json::to_string_pretty(state)?)
+        .context(format!("writing {}", path.display()))
+}
+
+/// Read state from the data directory. State that hasn't been written yet is empty.
+pub(crate) fn read_state<T: DataDirState>(data_dir: &Path) -> Result<T> {
+    let path = data_dir.join(T::FILENAME);
+    if !path.exists() {
+        return Ok(Default::default());
+    }
+    let contents = fs::read_to_string(&path).context(format!("reading {}", path.display()))?;
+    serde_json::from_str(&contents).context(format!("parsing {}", path.display()))
+}
+
+impl SnowflakeConnector {
+    /// Read state from the connector's data directory. It's empty if it hasn't been written,
+    /// or if it can't be read.
+    pub(crate) fn state<T: DataDirState>(&self) -> T {
+        match &self.data_dir {
+            Some(data_dir) => read_state(data_dir).unwrap_or_else(|e| {
+                error!("unable to read {}: {e:#}", T::DESCRIPTION);
+                Default::default()
+            }),
+            None => Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use serde::Deserialize;
+    use uuid::Uuid;
+
+    use super::*;
+
+    #[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+    struct Schemas {
+        schemas: BTreeSet<String>,
+    }
+
+    impl DataDirState for Schemas {
+        const FILENAME: &'static str = "schemas.json";
+        const DESCRIPTION: &'static str = "the schemas";
+    }
+
+    #[test]
+    fn state_round_trips_through_the_data_dir() -> Result<()> {
+        let data_dir =
+            std::env::temp_dir().join(format!("jetty_snowflake_state_{}", Uuid::new_v4()));
+        assert_eq!(read_state::<Schemas>(&data_dir)?, Schemas::default());
+
+        let state = Schemas {
+            schemas: ["DB.RAW".to_owned()].into(),
+        };
+        write_state(&data_dir, &state)?;
+        let read = read_state::<Schemas>(&data_dir);
+        fs::remove_dir_all(&data_dir)?;
+        assert_eq!(read?, state);
+        Ok(())
+    }
+}
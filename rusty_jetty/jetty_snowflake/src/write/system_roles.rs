@@ -0,0 +1,206 @@
+//! Guarding the built-in roles that administer the account. A mistake in the configuration
+//! of one of these roles can lock everyone out of the account, so changes to them are
+//! flagged in plans and only applied when the connector explicitly allows it.
+
+use anyhow::{bail, Result};
+use jetty_core::{
+    access_graph::translate::diffs::groups,
+    write::apply_report::{DiffItem, PlannedOperation},
+};
+
+use crate::consts::SYSTEM_ROLES;
+
+/// Get the system roles that an operation changes, grants, grants privileges to, or makes
+/// the owner or default role of something. They're read from the diff the operation was
+/// generated from rather than from its SQL, so that they're found however the statement
+/// refers to them.
+pub(crate) fn system_roles_touched(operation: &PlannedOperation) -> Vec<&'static str> {
+    let roles: Vec<&String> = match &operation.diff {
+        DiffItem::Group(diff) => {
+            let mut roles = vec![&diff.group_name];
+            match &diff.details {
+                groups::LocalDiffDetails::AddGroup { member_of } => roles.extend(member_of),
+                groups::LocalDiffDetails::RemoveGroup => (),
+                groups::LocalDiffDetails::ModifyGroup {
+                    add_member_of,
+                    remove_member_of,
+                } => {
+                    roles.extend(add_member_of);
+                    roles.extend(remove_member_of);
+                }
+            }
+            roles
+        }
+        DiffItem::User(diff) => diff
+            .group_membership
+            .add
+            .iter()
+            .chain(&diff.group_membership.remove)
+            .collect(),
+        DiffItem::Policy(diff) => diff.groups.keys().collect(),
+        DiffItem::DefaultPolicy(diff) => diff.groups.keys().collect(),
+        DiffItem::Ownership(diff) => [Some(&diff.owner), diff.previous_owner.as_ref()]
+            .into_iter()
+            .flatten()
+            .collect(),
+        DiffItem::UserProperties(diff) => diff.set.default_role.iter().collect(),
+        DiffItem::Tag(_) | DiffItem::Protection(_) => vec![],
+    };
+    SYSTEM_ROLES
+        .into_iter()
+        .filter(|system_role| roles.iter().any(|role| role == system_role))
+        .collect()
+}
+
+/// Render an operation for a plan, with a warning if it touches a system role. Operations
+/// that won't be applied because system role changes aren't allowed are commented out, so
+/// that they aren't run from an exported script either.
+pub(crate) fn flag_operation(operation: &PlannedOperation, allowed: bool) -> String {
+    let roles = system_roles_touched(operation);
+    if roles.is_empty() {
+        operation.request.to_owned()
+    } else if allowed {
+        format!(
+            "-- WARNING: this changes the system role {}\n{}",
+            roles.join(", "),
+            operation.request
+        )
+    } else {
+        format!(
+            "-- SKIPPED: this changes the system role {}, and allow_system_role_changes isn't \
+            set in the Snowflake connector configuration\n{}",
+            roles.join(", "),
+            operation
+                .request
+                .lines()
+                .map(|line| format!("-- {line}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+}
+
+/// Make sure that an operation can be applied. Operations that touch system roles are
+/// rejected unless `allow_system_role_changes` is set.
+pub(crate) fn check_operation(operation: &PlannedOperation, allowed: bool) -> Result<()> {
+    let roles = system_roles_touched(operation);
+    if !allowed && !roles.is_empty() {
+        bail!(
+            "not applied because it changes the system role {}. Set \
+            allow_system_role_changes to true in the Snowflake connector configuration to \
+            apply it",
+            roles.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use jetty_core::{
+        access_graph::translate::diffs::{ownership, user_properties, users},
+        connectors::{ownership::CurrentGrants, user_properties::UserProperties},
+        cual::Cual,
+        write::apply_report::ApplyTarget,
+    };
+
+    use crate::write::RoleKinds;
+
+    use super::*;
+
+    fn membership(role: &str) -> PlannedOperation {
+        PlannedOperation::new(
+            ApplyTarget::User("ELLEN".to_owned()),
+            DiffItem::User(users::LocalDiff {
+                user: "ELLEN".to_owned(),
+                group_membership: users::LocalDiffDetails {
+                    add: [role.to_owned()].into(),
+                    remove: Default::default(),
+                },
+            }),
+            format!("GRANT ROLE \"{role}\" TO USER \"ELLEN\";"),
+        )
+    }
+
+    #[test]
+    fn system_role_changes_are_flagged_and_blocked() {
+        let op = membership("ACCOUNTADMIN");
+        assert_eq!(system_roles_touched(&op), vec!["ACCOUNTADMIN"]);
+        assert_eq!(
+            flag_operation(&op, true),
+            "-- WARNING: this changes the system role ACCOUNTADMIN\nGRANT ROLE \"ACCOUNTADMIN\" TO USER \"ELLEN\";"
+        );
+        assert_eq!(
+            flag_operation(&op, false),
+            "-- SKIPPED: this changes the system role ACCOUNTADMIN, and allow_system_role_changes \
+            isn't set in the Snowflake connector configuration\n\
+            -- GRANT ROLE \"ACCOUNTADMIN\" TO USER \"ELLEN\";"
+        );
+        assert!(check_operation(&op, false).is_err());
+        assert!(check_operation(&op, true).is_ok());
+    }
+
+    #[test]
+    fn other_roles_are_not_flagged() {
+        // Role names that only contain a system role's name aren't system roles
+        let op = membership("SYSADMIN_LITE");
+        assert!(system_roles_touched(&op).is_empty());
+        assert_eq!(flag_operation(&op, false), op.request);
+        assert!(check_operation(&op, false).is_ok());
+    }
+
+    #[test]
+    fn system_roles_are_found_however_the_statement_names_them() {
+        let default_role = PlannedOperation::new(
+            ApplyTarget::User("ELLEN".to_owned()),
+            DiffItem::UserProperties(user_properties::LocalDiff {
+                user: "ELLEN".to_owned(),
+                set: UserProperties {
+                    default_role: Some("ACCOUNTADMIN".to_owned()),
+                    ..Default::default()
+                },
+                current: Default::default(),
+            }),
+            "ALTER USER \"ELLEN\" SET DEFAULT_ROLE = \"ACCOUNTADMIN\";".to_owned(),
+        );
+        assert_eq!(system_roles_touched(&default_role), vec!["ACCOUNTADMIN"]);
+        assert!(check_operation(&default_role, false).is_err());
+
+        let asset = Cual::new("snowflake://account/DB/RAW/USERS?type=TABLE");
+        let ownership = PlannedOperation::new(
+            ApplyTarget::Asset(asset.to_owned()),
+            DiffItem::Ownership(ownership::LocalDiff {
+                asset,
+                owner: "LOADER".to_owned(),
+                previous_owner: Some("SYSADMIN".to_owned()),
+                current_grants: CurrentGrants::Copy,
+            }),
+            "GRANT OWNERSHIP ON TABLE \"DB\".\"RAW\".\"USERS\" TO ROLE LOADER COPY CURRENT GRANTS;"
+                .to_owned(),
+        );
+        assert_eq!(system_roles_touched(&ownership), vec!["SYSADMIN"]);
+    }
+
+    #[test]
+    fn only_the_statements_that_touch_system_roles_are_blocked() {
+        let diffs = [users::LocalDiff {
+            user: "ELLEN".to_owned(),
+            group_membership: users::LocalDiffDetails {
+                add: ["ACCOUNTADMIN".to_owned(), "READER".to_owned()].into(),
+                remove: Default::default(),
+            },
+        }];
+        let queries = super::super::users::prepare_queries(&diffs, &RoleKinds::default());
+        let blocked = queries
+            .3
+            .iter()
+            .filter(|op| check_operation(op, false).is_err())
+            .map(|op| op.request.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(queries.3.len(), 2);
+        assert_eq!(
+            blocked,
+            vec!["GRANT ROLE \"ACCOUNTADMIN\" TO USER \"ELLEN\";"]
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! managing the write path for tags
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use jetty_core::{
+    access_graph::translate::diffs::tags,
+    connectors::{managed_tags::managed_tag_metadata, nodes::RawAsset},
+    cual::Cual,
+    write::apply_report::{ApplyReport, ApplyTarget, DiffItem, PlannedOperation},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{quote_snowflake_fqn, quote_snowflake_identifier};
+
+use super::{DataDirState, PrioritizedQueries};
+
+/// How long tag changes can take to show up in TAG_REFERENCES. Snowflake documents a latency
+/// of up to two hours, so this leaves some margin.
+const TAG_REFERENCES_LATENCY: Duration = Duration::from_secs(3 * 60 * 60);
+
+/// The tags in the tag schema, keyed by tag name, with the cual uris of the assets they
+/// are applied to and their values. This is what the configured tags are compared with.
+#[derive(Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ManagedTags {
+    tags: BTreeMap<String, BTreeMap<String, String>>,
+    /// The tag changes Jetty has applied that TAG_REFERENCES might not show yet
+    #[serde(default)]
+    recently_applied: Vec<AppliedTagChange>,
+}
+
+/// A tag change made by Jetty
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AppliedTagChange {
+    tag: String,
+    /// The cual uri of the asset
+    asset: String,
+    /// The value the tag was set to, or `None` if it was unset
+    value: Option<String>,
+    /// When the change was applied, in seconds since the Unix epoch
+    applied_at: u64,
+}
+
+impl DataDirState for ManagedTags {
+    const FILENAME: &'static str = "managed_tags.json";
+    const DESCRIPTION: &'static str = "the tags Jetty manages in Snowflake";
+}
+
+impl ManagedTags {
+    pub(crate) fn insert(&mut self, tag: &str, asset: &Cual, value: &str) {
+        self.tags
+            .entry(tag.to_owned())
+            .or_default()
+            .insert(asset.uri(), value.to_owned());
+    }
+
+    fn get(&self, tag: &str) -> Option<&BTreeMap<String, String>> {
+        self.tags.get(tag)
+    }
+
+    /// Update the tags with the tag operations that succeeded
+    pub(crate) fn record_applied(&mut self, report: &ApplyReport) {
+        let now = seconds_since_epoch(SystemTime::now());
+        for applied in report.successes() {
+            if let (ApplyTarget::Asset(asset), DiffItem::Tag(tag)) =
+                (&applied.operation.target, &applied.operation.diff)
+            {
+                self.apply(AppliedTagChange {
+                    tag: tag.name.to_owned(),
+                    asset: asset.uri(),
+                    value: tag.applied_to.contains(asset).then(|| tag_value(tag)),
+                    applied_at: now,
+                });
+            }
+        }
+    }
+
+    /// Bring freshly fetched tags up to date with the changes from the previous record that
+    /// TAG_REFERENCES might not show yet. Older changes are dropped, since they have caught up.
+    pub(crate) fn merge_recently_applied(&mut self, previous: ManagedTags, now: SystemTime) {
+        let cutoff = seconds_since_epoch(now).saturating_sub(TAG_REFERENCES_LATENCY.as_secs());
+        for change in previous.recently_applied {
+            if change.applied_at >= cutoff {
+                self.apply(change);
+            }
+        }
+    }
+
+    fn apply(&mut self, change: AppliedTagChange) {
+        match &change.value {
+            Some(value) => {
+                self.tags
+                    .entry(change.tag.to_owned())
+                    .or_default()
+                    .insert(change.asset.to_owned(), value.to_owned());
+            }
+            None => {
+                if let Some(assets) = self.tags.get_mut(&change.tag) {
+                    assets.remove(&change.asset);
+                    if assets.is_empty() {
+                        self.tags.remove(&change.tag);
+                    }
+                }
+            }
+        }
+        self.recently_applied.push(change);
+    }
+
+    /// Report the tags on each asset through its metadata
+    pub(crate) fn add_to_assets(&self, assets: &mut [RawAsset]) {
+        let mut by_asset: HashMap<&String, Vec<(&String, &String)>> = HashMap::new();
+        for (tag, tagged_assets) in &self.tags {
+            for (asset, value) in tagged_assets {
+                by_asset.entry(asset).or_default().push((tag, value));
+            }
+        }
+        for asset in assets {
+            if let Some(tags) = by_asset.get(&asset.cual.uri()) {
+                asset.metadata.extend(
+                    tags.iter()
+                        .map(|(tag, value)| managed_tag_metadata(tag, value)),
+                );
+            }
+        }
+    }
+}
+
+fn seconds_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub(super) fn prepare_queries(
+    tag_states: &[tags::LocalDiff],
+    tag_schema: &str,
+    managed_tags: &ManagedTags,
+) -> PrioritizedQueries {
+    let mut res = PrioritizedQueries::default();
+    let empty = Default::default();
+
+    for tag in tag_states {
+        let operation = |target: ApplyTarget, query: String| {
+            PlannedOperation::new(target, DiffItem::Tag(tag.to_owned()), query)
+        };
+        let tag_fqn = format!(
+            "{}.{}",
+            quote_snowflake_fqn(tag_schema),
+            quote_snowflake_identifier(&tag.name)
+        );
+        let value = tag_value(tag);
+        let current = managed_tags.get(&tag.name);
+
+        if current.is_none() && !tag.applied_to.is_empty() {
+            let comment = tag
+                .description
+                .as_ref()
+                .map(|d| format!(" COMMENT = '{}'", escape_string(d)))
+                .unwrap_or_default();
+            res.1.push(operation(
+                ApplyTarget::Tag(tag.name.to_owned()),
+                format!("CREATE TAG IF NOT EXISTS {tag_fqn}{comment};"),
+            ));
+        }
+
+        let current = current.unwrap_or(&empty);
+        for asset in &tag.applied_to {
+            if current.get(&asset.uri()) != Some(&value) {
+                let snowflake_asset = crate::cual::cual_to_snowflake_asset(asset);
+                res.3.push(operation(
+                    ApplyTarget::Asset(asset.to_owned()),
+                    format!(
+                        "ALTER {} SET TAG {tag_fqn} = '{}';",
+                        snowflake_asset.grant_target(),
+                        escape_string(&value)
+                    ),
+                ));
+            }
+        }
+
+        let configured: HashSet<_> = tag.applied_to.iter().map(|a| a.uri()).collect();
+        for uri in current.keys() {
+            if !configured.contains(uri) {
+                let asset = Cual::new(uri);
+                let snowflake_asset = crate::cual::cual_to_snowflake_asset(&asset);
+                res.3.push(operation(
+                    ApplyTarget::Asset(asset),
+                    format!(
+                        "ALTER {} UNSET TAG {tag_fqn};",
+                        snowflake_asset.grant_target(),
+                    ),
+                ));
+            }
+        }
+    }
+
+    // Tags can be missing from the configured states when they were applied after the last
+    // fetch. They are no longer configured, so they're removed from all of their assets.
+    let configured: HashSet<_> = tag_states.iter().map(|t| t.name.as_str()).collect();
+    for (name, assets) in &managed_tags.tags {
+        if configured.contains(name.as_str()) {
+            continue;
+        }
+        let tag = tags::LocalDiff {
+            name: name.to_owned(),
+            value: None,
+            description: None,
+            applied_to: Default::default(),
+        };
+        let tag_fqn = format!(
+            "{}.{}",
+            quote_snowflake_fqn(tag_schema),
+            quote_snowflake_identifier(name)
+        );
+        for uri in assets.keys() {
+            let asset = Cual::new(uri);
+            let snowflake_asset = crate::cual::cual_to_snowflake_asset(&asset);
+            res.3.push(PlannedOperation::new(
+                ApplyTarget::Asset(asset),
+                DiffItem::Tag(tag.to_owned()),
+                format!(
+                    "ALTER {} UNSET TAG {tag_fqn};",
+                    snowflake_asset.grant_target()
+                ),
+            ));
+        }
+    }
+
+    res
+}
+
+/// Snowflake tags always have a value, so tags without one are set to an empty string
+fn tag_value(tag: &tags::LocalDiff) -> String {
+    tag.value.to_owned().unwrap_or_default()
+}
+
+fn escape_string(value: &str) -> String {
+    value.replace('\'', "''")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    fn table(name: &str) -> Cual {
+        Cual::new(&format!("snowflake://account/DB/RAW/{name}?type=TABLE"))
+    }
+
+    #[test]
+    fn only_changed_assignments_are_planned() {
+        let tag = tags::LocalDiff {
+            name: "pii".to_owned(),
+            value: Some("email".to_owned()),
+            description: None,
+            applied_to: BTreeSet::from([table("USERS"), table("ORDERS")]),
+        };
+        let mut managed_tags = ManagedTags::default();
+        managed_tags.insert("pii", &table("USERS"), "email");
+        managed_tags.insert("pii", &table("EVENTS"), "email");
+
+        let queries = prepare_queries(&[tag], "GOVERNANCE.TAGS", &managed_tags);
+        assert!(queries.1.is_empty());
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"ALTER TABLE "DB"."RAW"."ORDERS" SET TAG "GOVERNANCE"."TAGS"."pii" = 'email';"#,
+                r#"ALTER TABLE "DB"."RAW"."EVENTS" UNSET TAG "GOVERNANCE"."TAGS"."pii";"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn tags_that_are_not_configured_are_unset() {
+        let mut managed_tags = ManagedTags::default();
+        managed_tags.insert("finance", &table("LEDGER"), "");
+
+        let queries = prepare_queries(&[], "GOVERNANCE.TAGS", &managed_tags);
+        assert_eq!(
+            queries.flatten(),
+            vec![r#"ALTER TABLE "DB"."RAW"."LEDGER" UNSET TAG "GOVERNANCE"."TAGS"."finance";"#]
+        );
+    }
+
+    #[test]
+    fn recent_changes_survive_a_fetch() {
+        let now = SystemTime::now();
+        let applied_at = |age: Duration| seconds_since_epoch(now - age);
+        let previous = ManagedTags {
+            tags: Default::default(),
+            recently_applied: vec![
+                AppliedTagChange {
+                    tag: "pii".to_owned(),
+                    asset: table("USERS").uri(),
+                    value: Some("email".to_owned()),
+                    applied_at: applied_at(Duration::from_secs(60)),
+                },
+                AppliedTagChange {
+                    tag: "pii".to_owned(),
+                    asset: table("EVENTS").uri(),
+                    value: None,
+                    applied_at: applied_at(Duration::from_secs(60)),
+                },
+                AppliedTagChange {
+                    tag: "pii".to_owned(),
+                    asset: table("ORDERS").uri(),
+                    value: None,
+                    applied_at: applied_at(TAG_REFERENCES_LATENCY * 2),
+                },
+            ],
+        };
+        // TAG_REFERENCES hasn't caught up with the recent changes yet
+        let mut fetched = ManagedTags::default();
+        fetched.insert("pii", &table("EVENTS"), "email");
+        fetched.insert("pii", &table("ORDERS"), "email");
+
+        fetched.merge_recently_applied(previous, now);
+        assert_eq!(
+            fetched.get("pii"),
+            Some(&BTreeMap::from([
+                (table("ORDERS").uri(), "email".to_owned()),
+                (table("USERS").uri(), "email".to_owned()),
+            ]))
+        );
+        // The old change is dropped, so TAG_REFERENCES is trusted for it from now on
+        assert_eq!(fetched.recently_applied.len(), 2);
+    }
+
+    #[test]
+    fn new_tags_are_created() {
+        let tag = tags::LocalDiff {
+            name: "finance".to_owned(),
+            value: None,
+            description: Some("Owned by the finance team's analysts".to_owned()),
+            applied_to: BTreeSet::from([table("LEDGER")]),
+        };
+
+        let queries = prepare_queries(&[tag], "GOVERNANCE.TAGS", &ManagedTags::default());
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"CREATE TAG IF NOT EXISTS "GOVERNANCE"."TAGS"."finance" COMMENT = 'Owned by the finance team''s analysts';"#,
+                r#"ALTER TABLE "DB"."RAW"."LEDGER" SET TAG "GOVERNANCE"."TAGS"."finance" = '';"#,
+            ]
+        );
+    }
+}
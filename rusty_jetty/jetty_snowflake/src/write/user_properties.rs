@@ -0,0 +1,219 @@
+//! managing the write path for user properties
+
+use jetty_core::{
+    access_graph::translate::diffs::user_properties,
+    connectors::user_properties::NO_POLICY,
+    write::apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+};
+
+use crate::{quote_snowflake_fqn, quote_snowflake_identifier};
+
+use super::PrioritizedQueries;
+
+/// Generate the queries to set user properties. A user's default role may be created in
+/// the same apply, so these run in the last tier. Session policies, and policies that are
+/// being removed, are set with their own statements.
+pub(super) fn prepare_queries(
+    user_properties_diffs: &[user_properties::LocalDiff],
+) -> PrioritizedQueries {
+    let mut res = PrioritizedQueries::default();
+
+    for diff in user_properties_diffs {
+        let set = &diff.set;
+        let mut assignments = vec![];
+        if let Some(role) = &set.default_role {
+            assignments.push(format!(
+                "DEFAULT_ROLE = {}",
+                quote_snowflake_identifier(role)
+            ));
+        }
+        if let Some(warehouse) = &set.default_warehouse {
+            assignments.push(format!(
+                "DEFAULT_WAREHOUSE = {}",
+                quote_snowflake_identifier(warehouse)
+            ));
+        }
+        if let Some(namespace) = &set.default_namespace {
+            assignments.push(format!(
+                "DEFAULT_NAMESPACE = {}",
+                quote_snowflake_fqn(namespace)
+            ));
+        }
+        if let Some(key) = &set.rsa_public_key {
+            // Snowflake takes the key without the PEM header and footer
+            let key = key
+                .lines()
+                .filter(|line| !line.starts_with("-----"))
+                .map(str::trim)
+                .collect::<String>();
+            assignments.push(format!("RSA_PUBLIC_KEY = '{key}'"));
+        }
+        if let Some(disabled) = set.disabled {
+            assignments.push(format!(
+                "DISABLED = {}",
+                disabled.to_string().to_uppercase()
+            ));
+        }
+        if let Some(user_type) = set.user_type {
+            assignments.push(format!("TYPE = {user_type}"));
+        }
+        match set.network_policy.as_deref() {
+            Some(NO_POLICY) | None => (),
+            Some(network_policy) => assignments.push(format!(
+                "NETWORK_POLICY = {}",
+                quote_snowflake_identifier(network_policy)
+            )),
+        }
+
+        let operation = |query: String| {
+            PlannedOperation::new(
+                ApplyTarget::User(diff.user.to_owned()),
+                DiffItem::UserProperties(diff.to_owned()),
+                query,
+            )
+        };
+        let user = quote_snowflake_identifier(&diff.user);
+        if !assignments.is_empty() {
+            res.3.push(operation(format!(
+                "ALTER USER {} SET {};",
+                user,
+                assignments.join(" ")
+            )));
+        }
+        if set.network_policy.as_deref() == Some(NO_POLICY) {
+            res.3.push(operation(format!(
+                "ALTER USER {} UNSET NETWORK_POLICY;",
+                user
+            )));
+        }
+        if set.session_policy.as_deref() == Some(NO_POLICY) {
+            res.3.push(operation(format!(
+                "ALTER USER {} UNSET SESSION POLICY;",
+                user
+            )));
+        } else if let Some(session_policy) = &set.session_policy {
+            res.3.push(operation(format!(
+                "ALTER USER {} SET SESSION POLICY {}{};",
+                user,
+                quote_snowflake_fqn(session_policy),
+                // FORCE replaces the existing policy without unsetting it first
+                if diff.current.session_policy.is_some() {
+                    " FORCE"
+                } else {
+                    ""
+                }
+            )));
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use jetty_core::connectors::user_properties::{UserProperties, UserType};
+
+    use super::*;
+
+    #[test]
+    fn changed_properties_are_set_together() {
+        let diff = user_properties::LocalDiff {
+            user: "ETL_SERVICE".to_owned(),
+            set: UserProperties {
+                default_warehouse: Some("LOADING".to_owned()),
+                default_namespace: Some("RAW.PUBLIC".to_owned()),
+                rsa_public_key: Some(
+                    "-----BEGIN PUBLIC KEY-----\nMIIBIjAN\nBgkqhkiG\n-----END PUBLIC KEY-----\n"
+                        .to_owned(),
+                ),
+                disabled: Some(false),
+                user_type: Some(UserType::Service),
+                ..Default::default()
+            },
+            current: Default::default(),
+        };
+
+        let queries = prepare_queries(&[diff]);
+        assert_eq!(queries.3.len(), 1);
+        assert_eq!(
+            queries.3[0].request,
+            "ALTER USER \"ETL_SERVICE\" SET DEFAULT_WAREHOUSE = \"LOADING\" \
+            DEFAULT_NAMESPACE = \"RAW\".\"PUBLIC\" RSA_PUBLIC_KEY = 'MIIBIjANBgkqhkiG' \
+            DISABLED = FALSE TYPE = SERVICE;"
+        );
+    }
+
+    #[test]
+    fn policies_are_set_on_users() {
+        let diff = user_properties::LocalDiff {
+            user: "ELLIOT".to_owned(),
+            set: UserProperties {
+                network_policy: Some("OFFICE_ONLY".to_owned()),
+                session_policy: Some("GOV.POLICIES.IDLE_TIMEOUT".to_owned()),
+                ..Default::default()
+            },
+            current: UserProperties {
+                session_policy: Some("GOV.POLICIES.DEFAULT_TIMEOUT".to_owned()),
+                ..Default::default()
+            },
+        };
+
+        let queries = prepare_queries(&[diff]);
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"ALTER USER "ELLIOT" SET NETWORK_POLICY = "OFFICE_ONLY";"#,
+                r#"ALTER USER "ELLIOT" SET SESSION POLICY "GOV"."POLICIES"."IDLE_TIMEOUT" FORCE;"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn policies_are_removed_from_users() {
+        let diff = user_properties::LocalDiff {
+            user: "ELLIOT".to_owned(),
+            set: UserProperties {
+                default_warehouse: Some("REPORTING".to_owned()),
+                network_policy: Some(NO_POLICY.to_owned()),
+                session_policy: Some(NO_POLICY.to_owned()),
+                ..Default::default()
+            },
+            current: UserProperties {
+                network_policy: Some("OFFICE_ONLY".to_owned()),
+                session_policy: Some("GOV.POLICIES.DEFAULT_TIMEOUT".to_owned()),
+                ..Default::default()
+            },
+        };
+
+        let queries = prepare_queries(&[diff]);
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"ALTER USER "ELLIOT" SET DEFAULT_WAREHOUSE = "REPORTING";"#,
+                r#"ALTER USER "ELLIOT" UNSET NETWORK_POLICY;"#,
+                r#"ALTER USER "ELLIOT" UNSET SESSION POLICY;"#,
+            ]
+        );
+    }
+    #[test]
+    fn quotes_in_names_are_escaped() {
+        let diff = user_properties::LocalDiff {
+            user: r#"O"BRIEN"#.to_owned(),
+            set: UserProperties {
+                default_role: Some(r#"ANALYST "EU""#.to_owned()),
+                session_policy: Some(r#"GOV.POLICIES.SHORT"TIMEOUT"#.to_owned()),
+                ..Default::default()
+            },
+            current: Default::default(),
+        };
+
+        let queries = prepare_queries(&[diff]);
+        assert_eq!(
+            queries.flatten(),
+            vec![
+                r#"ALTER USER "O""BRIEN" SET DEFAULT_ROLE = "ANALYST ""EU""";"#,
+                r#"ALTER USER "O""BRIEN" SET SESSION POLICY "GOV"."POLICIES"."SHORT""TIMEOUT";"#,
+            ]
+        );
+    }
+}
@@ -1,25 +1,55 @@
 //! managing the write path for users
 
-use jetty_core::access_graph::translate::diffs::users;
+use jetty_core::{
+    access_graph::translate::diffs::users,
+    write::apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+};
 
-use super::PrioritizedQueries;
+use crate::quote_snowflake_identifier;
 
-pub(super) fn prepare_queries(user_diffs: &[users::LocalDiff]) -> PrioritizedQueries {
+use super::{PrioritizedQueries, RoleKinds};
+
+pub(super) fn prepare_queries(
+    user_diffs: &[users::LocalDiff],
+    roles: &RoleKinds,
+) -> PrioritizedQueries {
     let mut res = PrioritizedQueries::default();
 
     user_diffs.iter().for_each(|diff| {
-        res.2.extend(
-            diff.group_membership
-                .add
-                .iter()
-                .map(|g| format!("GRANT ROLE \"{}\" TO USER \"{}\";", g, &diff.user)),
-        );
-        res.2.extend(
-            diff.group_membership
-                .remove
-                .iter()
-                .map(|g| format!("REVOKE ROLE \"{}\" FROM USER \"{}\";", g, &diff.user)),
-        );
+        // Each operation carries only the membership it changes, so that it can be checked
+        // on its own
+        let operation = |add, remove, query: String| {
+            PlannedOperation::new(
+                ApplyTarget::User(diff.user.to_owned()),
+                DiffItem::User(users::LocalDiff {
+                    user: diff.user.to_owned(),
+                    group_membership: users::LocalDiffDetails { add, remove },
+                }),
+                query,
+            )
+        };
+        res.3.extend(diff.group_membership.add.iter().map(|g| {
+            operation(
+                [g.to_owned()].into(),
+                Default::default(),
+                format!(
+                    "GRANT {} TO USER {};",
+                    roles.reference(g),
+                    quote_snowflake_identifier(&diff.user)
+                ),
+            )
+        }));
+        res.3.extend(diff.group_membership.remove.iter().map(|g| {
+            operation(
+                Default::default(),
+                [g.to_owned()].into(),
+                format!(
+                    "REVOKE {} FROM USER {};",
+                    roles.reference(g),
+                    quote_snowflake_identifier(&diff.user)
+                ),
+            )
+        }));
     });
     res
 }
@@ -286,7 +286,7 @@ async fn input_produces_correct_results() {
     let mut harness = construct_connector_from(&input).await;
 
     // Query the Snowflake connector
-    let data: nodes::ConnectorData = harness.connector.get_data().await;
+    let data: nodes::ConnectorData = harness.connector.get_data().await.unwrap();
     debug!("data: {:#?}", data);
 
     // Do some assertion on the resulting data.
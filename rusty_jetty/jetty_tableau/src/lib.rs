@@ -24,12 +24,13 @@ use serde_json::json;
 use jetty_core::{
     access_graph::translate::diffs::LocalConnectorDiffs,
     connectors::{
-        nodes as jetty_nodes, nodes::ConnectorData, AssetType, ConnectorCapabilities,
-        ConnectorClient, NewConnector, ReadCapabilities, WriteCapabilities,
+        nodes as jetty_nodes, nodes::ConnectorData, registry::ConnectorRegistry, AssetType,
+        ConnectorCapabilities, ConnectorClient, NewConnector, ReadCapabilities, WriteCapabilities,
     },
     cual::Cual,
     jetty::{ConnectorConfig, ConnectorManifest, CredentialsMap},
     logging::error,
+    write::apply_report::ApplyReport,
     Connector,
 };
 
@@ -204,6 +205,11 @@ impl TableauConnector {
     }
 }
 
+/// Register the Tableau connector under the `tableau` connector type
+pub fn register(registry: &mut ConnectorRegistry) {
+    registry.register::<TableauConnector>("tableau");
+}
+
 #[async_trait]
 impl NewConnector for TableauConnector {
     /// Validates the configs and bootstraps a Tableau connection.
@@ -234,15 +240,15 @@ impl Connector for TableauConnector {
         todo!()
     }
 
-    async fn get_data(&mut self) -> ConnectorData {
-        self.setup().await.unwrap();
+    async fn get_data(&mut self) -> Result<ConnectorData> {
+        self.setup().await?;
         let (groups, users, assets, tags, policies, default_policies) = self.env_to_jetty_all();
 
         // Actually, don't get effective permissions for now. It's too slow.
         // let effective_permissions = self.get_effective_permissions();
 
         let effective_permissions = Default::default();
-        ConnectorData {
+        Ok(ConnectorData {
             groups,
             users,
             assets,
@@ -253,11 +259,10 @@ impl Connector for TableauConnector {
             effective_permissions,
             cual_prefix: Some(
                 get_cual_prefix()
-                    .context("tableau cual prefix not yet set")
-                    .unwrap()
+                    .context("tableau cual prefix not yet set")?
                     .to_owned(),
             ),
-        }
+        })
     }
 
     fn get_manifest(&self) -> ConnectorManifest {
@@ -345,33 +350,29 @@ impl Connector for TableauConnector {
         }
     }
 
-    async fn apply_changes(&self, diffs: &LocalConnectorDiffs) -> Result<String> {
-        let mut success_counter = 0;
-        let mut failure_counter = 0;
+    async fn apply_changes(&self, diffs: &LocalConnectorDiffs) -> Result<ApplyReport> {
+        let mut report = ApplyReport::default();
         // This is designed in such a way that each query_set may be run concurrently.
         let futures = self.generate_plan_futures(diffs)?;
 
         for request_set in [futures.0, futures.1, futures.2] {
-            let results = futures::stream::iter(request_set)
+            let (operations, request_futures): (Vec<_>, Vec<_>) = request_set
+                .into_iter()
+                .map(|f| (f.operation, f.future))
+                .unzip();
+            let results = futures::stream::iter(request_futures)
                 .buffered(coordinator::CONCURRENT_METADATA_FETCHES)
                 .collect::<Vec<_>>()
                 .await;
 
-            for result in results {
-                match result {
-                    Err(e) => {
-                        error!("{:?}", e);
-                        failure_counter += 1;
-                    }
-                    Ok(_) => {
-                        success_counter += 1;
-                    }
+            for (operation, result) in operations.into_iter().zip(results) {
+                if let Err(e) = &result {
+                    error!("{:?}", e);
                 }
+                report.record(operation, &result);
             }
         }
-        Ok(format!(
-            "{success_counter} successful requests\n{failure_counter} failed requests"
-        ))
+        Ok(report)
     }
 }
 
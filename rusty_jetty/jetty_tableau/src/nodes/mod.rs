@@ -298,12 +298,13 @@ struct IdField {
     id: String,
 }
 
+#[derive(Clone)]
 pub(crate) struct IndividualPermission {
     pub(crate) capability: String,
     pub(crate) mode: TableauPermissionMode,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) enum TableauPermissionMode {
     Allow,
     Deny,
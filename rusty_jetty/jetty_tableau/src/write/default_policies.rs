@@ -7,10 +7,14 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 
-use futures::future::BoxFuture;
 use jetty_core::{
-    access_graph::translate::diffs::default_policies, cual::Cual, logging::info,
-    write::assets::PolicyState,
+    access_graph::translate::diffs::default_policies,
+    cual::Cual,
+    logging::info,
+    write::{
+        apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+        assets::PolicyState,
+    },
 };
 use reqwest::Request;
 use serde_json::json;
@@ -20,7 +24,7 @@ use crate::{
     TableauConnector,
 };
 
-use super::{SequencedFutures, SequencedPlans};
+use super::{request_to_string, PlannedFuture, RenderedFuture, SequencedFutures, SequencedPlans};
 
 impl TableauConnector {
     /// Generate sequenced plan for changes (as part of `jetty plan` execution path)
@@ -252,6 +256,14 @@ impl TableauConnector {
 
             let mut set_tableau_content_permissions: Option<String> = None;
 
+            let operation = |request: String| {
+                PlannedOperation::new(
+                    ApplyTarget::Asset(diff.asset.to_owned()),
+                    DiffItem::DefaultPolicy(diff.to_owned()),
+                    request,
+                )
+            };
+
             for (user_id, details) in &diff.users {
                 match details {
                     jetty_core::write::assets::diff::policies::DiffDetails::AddAgent { add } => {
@@ -283,7 +295,9 @@ impl TableauConnector {
                             group_map.clone(),
                         )?
                         .into_iter()
-                        .for_each(|f| futures.1.push(f)),
+                        .for_each(|(request, f)| {
+                            futures.1.push(PlannedFuture::new(operation(request), f))
+                        }),
 
                     jetty_core::write::assets::diff::policies::DiffDetails::ModifyAgent {
                         add,
@@ -313,7 +327,9 @@ impl TableauConnector {
                             group_map.clone(),
                         )?
                         .into_iter()
-                        .for_each(|f| futures.1.push(f));
+                        .for_each(|(request, f)| {
+                            futures.1.push(PlannedFuture::new(operation(request), f))
+                        });
                     }
                 }
             }
@@ -347,7 +363,9 @@ impl TableauConnector {
                             group_map.clone(),
                         )?
                         .into_iter()
-                        .for_each(|f| futures.1.push(f)),
+                        .for_each(|(request, f)| {
+                            futures.1.push(PlannedFuture::new(operation(request), f))
+                        }),
                     jetty_core::write::assets::diff::policies::DiffDetails::ModifyAgent {
                         add,
                         remove,
@@ -378,30 +396,39 @@ impl TableauConnector {
                                 group_map.clone(),
                             )?
                             .into_iter()
-                            .for_each(|f| futures.1.push(f));
+                            .for_each(|(request, f)| {
+                                futures.1.push(PlannedFuture::new(operation(request), f))
+                            });
                         }
                     }
                 }
             }
             if !user_adds.is_empty() || !group_adds.is_empty() {
-                futures.1.push(Box::pin(
-                    self.execute_add_default_policy_with_deferred_lookup(
+                let request = self.build_add_default_policy_request(
+                    asset_reference,
+                    user_adds.clone(),
+                    self.planned_group_ids(&group_adds),
+                    &asset_type,
+                )?;
+                futures.1.push(PlannedFuture::new(
+                    operation(request_to_string(&request)),
+                    Box::pin(self.execute_add_default_policy_with_deferred_lookup(
                         asset_reference,
                         user_adds,
                         group_adds,
                         asset_type.to_owned(),
                         group_map.clone(),
-                    ),
+                    )),
                 ));
             }
 
             if let Some(content_permissions) = set_tableau_content_permissions {
-                futures.1.push(Box::pin(self.execute_to_unit_result(
-                    self.generate_content_permissions_request(
-                        asset_reference,
-                        &content_permissions,
-                    )?,
-                )));
+                let request = self
+                    .generate_content_permissions_request(asset_reference, &content_permissions)?;
+                futures.1.push(PlannedFuture::new(
+                    operation(request_to_string(&request)),
+                    Box::pin(self.execute_to_unit_result(request)),
+                ));
             };
         }
         Ok(futures)
@@ -552,7 +579,7 @@ impl TableauConnector {
         grantee_type: &'a str,
         applied_to_asset_type: &TableauAssetType,
         group_map: Arc<Mutex<HashMap<String, String>>>,
-    ) -> Result<Vec<BoxFuture<Result<()>>>> {
+    ) -> Result<Vec<RenderedFuture>> {
         // If it's a project, just generate the same request for a non-default policy
         if applied_to_asset_type == &TableauAssetType::Project {
             return self.build_delete_policy_request_futures(
@@ -564,17 +591,30 @@ impl TableauConnector {
             );
         }
 
-        let mut res: Vec<BoxFuture<Result<()>>> = Vec::new();
+        let mut res: Vec<RenderedFuture> = Vec::new();
+        let grantee_id = if grantee_type == "group" {
+            self.planned_group_id(user_id_or_group_name)
+        } else {
+            user_id_or_group_name.to_owned()
+        };
         for privilege in &state.privileges {
-            res.push(Box::pin(
-                self.build_and_execute_delete_default_policy_request(
+            let request = self.generate_delete_default_privilege_request(
+                privilege,
+                asset,
+                &grantee_id,
+                grantee_type,
+                applied_to_asset_type,
+            )?;
+            res.push((
+                request_to_string(&request),
+                Box::pin(self.build_and_execute_delete_default_policy_request(
                     privilege.to_owned(),
                     asset.to_owned(),
                     user_id_or_group_name.to_owned(),
                     grantee_type.to_owned(),
                     applied_to_asset_type.to_owned(),
                     Arc::clone(&group_map),
-                ),
+                )),
             ));
         }
 
@@ -7,12 +7,15 @@ use std::{
 
 use anyhow::{anyhow, Context, Result};
 
-use jetty_core::access_graph::translate::diffs::groups;
+use jetty_core::{
+    access_graph::translate::diffs::groups,
+    write::apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+};
 use serde_json::json;
 
 use crate::{rest, TableauConnector};
 
-use super::{SequencedFutures, SequencedPlans};
+use super::{request_to_string, PlannedFuture, SequencedFutures, SequencedPlans};
 
 impl TableauConnector {
     /// prepare the plans for group changes (for jetty plan)
@@ -72,6 +75,13 @@ impl TableauConnector {
         let mut futures = SequencedFutures::default();
 
         for diff in group_diffs {
+            let operation = |request: reqwest::Request| {
+                PlannedOperation::new(
+                    ApplyTarget::Group(diff.group_name.to_owned()),
+                    DiffItem::Group(diff.to_owned()),
+                    request_to_string(&request),
+                )
+            };
             match &diff.details {
                 groups::LocalDiffDetails::AddGroup { member_of } => {
                     if !member_of.is_empty() {
@@ -79,17 +89,26 @@ impl TableauConnector {
                     }
 
                     // Request to create the group
-                    futures.0.push(Box::pin(
-                        self.create_group_and_add_to_env(&diff.group_name, Arc::clone(&group_map)),
+                    futures.0.push(PlannedFuture::new(
+                        operation(self.build_add_group_request(&diff.group_name)?),
+                        Box::pin(
+                            self.create_group_and_add_to_env(
+                                &diff.group_name,
+                                Arc::clone(&group_map),
+                            ),
+                        ),
                     ));
                 }
                 groups::LocalDiffDetails::RemoveGroup => {
-                    futures
-                        .0
-                        .push(Box::pin(self.execute_delete_group_with_deferred_lookup(
+                    futures.0.push(PlannedFuture::new(
+                        operation(self.build_delete_group_request(
+                            &self.planned_group_id(&diff.group_name),
+                        )?),
+                        Box::pin(self.execute_delete_group_with_deferred_lookup(
                             &diff.group_name,
                             Arc::clone(&group_map),
-                        )));
+                        )),
+                    ));
                 }
                 groups::LocalDiffDetails::ModifyGroup {
                     add_member_of,
@@ -8,7 +8,9 @@ use std::{
 use anyhow::{anyhow, bail, Result};
 
 use futures::future::BoxFuture;
-use jetty_core::access_graph::translate::diffs::LocalConnectorDiffs;
+use jetty_core::{
+    access_graph::translate::diffs::LocalConnectorDiffs, write::apply_report::PlannedOperation,
+};
 use reqwest::Request;
 
 use crate::TableauConnector;
@@ -45,13 +47,28 @@ impl SequencedPlans {
     }
 }
 
+/// A future to be executed as part of `jetty apply`, along with the operation it performs
+pub(crate) struct PlannedFuture<'a> {
+    pub(crate) operation: PlannedOperation,
+    pub(crate) future: BoxFuture<'a, Result<()>>,
+}
+
+impl<'a> PlannedFuture<'a> {
+    pub(crate) fn new(operation: PlannedOperation, future: BoxFuture<'a, Result<()>>) -> Self {
+        Self { operation, future }
+    }
+}
+
+/// A future along with the rendered request it will execute
+pub(crate) type RenderedFuture<'a> = (String, BoxFuture<'a, Result<()>>);
+
 /// Struct containing a sequenced futures. The sequence is important to get the order
 /// of operations right (don't add users to groups until they're created, for example)
 #[derive(Default)]
 pub(crate) struct SequencedFutures<'a>(
-    pub(crate) Vec<BoxFuture<'a, Result<()>>>,
-    pub(crate) Vec<BoxFuture<'a, Result<()>>>,
-    pub(crate) Vec<BoxFuture<'a, Result<()>>>,
+    pub(crate) Vec<PlannedFuture<'a>>,
+    pub(crate) Vec<PlannedFuture<'a>>,
+    pub(crate) Vec<PlannedFuture<'a>>,
 );
 
 impl<'a> SequencedFutures<'a> {
@@ -88,7 +105,9 @@ impl TableauConnector {
         let group_map: HashMap<String, String> = self
             .coordinator
             .env
-            .groups.values().map(|g| (g.name.to_owned(), g.id.to_owned()))
+            .groups
+            .values()
+            .map(|g| (g.name.to_owned(), g.id.to_owned()))
             .collect();
 
         let group_map_mutex = Arc::new(Mutex::new(group_map));
@@ -134,6 +153,23 @@ impl TableauConnector {
         Ok(plans)
     }
 
+    /// Get the id of a group to display in a rendered request. Groups that will be created
+    /// as part of the apply don't have an id yet, so a placeholder is used instead.
+    fn planned_group_id(&self, group_name: &String) -> String {
+        self.coordinator
+            .env
+            .get_group_id_by_name(group_name)
+            .unwrap_or(format!("group_id_for_new_group_{group_name}"))
+    }
+
+    /// Swap the group names in a map for the ids to display in a rendered request
+    fn planned_group_ids<T: Clone>(&self, groups: &HashMap<String, T>) -> HashMap<String, T> {
+        groups
+            .iter()
+            .map(|(name, v)| (self.planned_group_id(name), v.to_owned()))
+            .collect()
+    }
+
     /// Function to execute a request and return a unit response
     async fn execute_to_unit_result(&self, request: Request) -> Result<()> {
         let res = self.coordinator.rest_client.execute(request).await?;
@@ -7,8 +7,13 @@ use std::{
 
 use anyhow::{Context, Result};
 
-use futures::future::BoxFuture;
-use jetty_core::{access_graph::translate::diffs::policies, write::assets::PolicyState};
+use jetty_core::{
+    access_graph::translate::diffs::policies,
+    write::{
+        apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+        assets::PolicyState,
+    },
+};
 use reqwest::Request;
 
 use crate::{
@@ -16,7 +21,7 @@ use crate::{
     TableauConnector,
 };
 
-use super::{SequencedFutures, SequencedPlans};
+use super::{request_to_string, PlannedFuture, RenderedFuture, SequencedFutures, SequencedPlans};
 
 impl TableauConnector {
     /// generate the plan for required changes.
@@ -143,6 +148,13 @@ impl TableauConnector {
 
         for diff in policy_diffs {
             let asset_reference = self.coordinator.env.cual_id_map.get(&diff.asset).unwrap();
+            let operation = |request: String| {
+                PlannedOperation::new(
+                    ApplyTarget::Asset(diff.asset.to_owned()),
+                    DiffItem::Policy(diff.to_owned()),
+                    request,
+                )
+            };
 
             let mut user_adds = HashMap::new();
             let mut group_adds = HashMap::new();
@@ -169,7 +181,9 @@ impl TableauConnector {
                             group_map.clone(),
                         )?
                         .into_iter()
-                        .for_each(|f| futures.2.push(f)),
+                        .for_each(|(request, f)| {
+                            futures.2.push(PlannedFuture::new(operation(request), f))
+                        }),
                     jetty_core::write::assets::diff::policies::DiffDetails::ModifyAgent {
                         add,
                         remove,
@@ -189,7 +203,9 @@ impl TableauConnector {
                             group_map.clone(),
                         )?
                         .into_iter()
-                        .for_each(|f| futures.2.push(f));
+                        .for_each(|(request, f)| {
+                            futures.2.push(PlannedFuture::new(operation(request), f))
+                        });
                     }
                 }
             }
@@ -215,7 +231,9 @@ impl TableauConnector {
                             group_map.clone(),
                         )?
                         .into_iter()
-                        .for_each(|f| futures.2.push(f)),
+                        .for_each(|(request, f)| {
+                            futures.2.push(PlannedFuture::new(operation(request), f))
+                        }),
 
                     jetty_core::write::assets::diff::policies::DiffDetails::ModifyAgent {
                         add,
@@ -239,7 +257,9 @@ impl TableauConnector {
                                 group_map.clone(),
                             )?
                             .into_iter()
-                            .for_each(|f| futures.2.push(f));
+                            .for_each(|(request, f)| {
+                                futures.2.push(PlannedFuture::new(operation(request), f))
+                            });
                         }
                     }
                 }
@@ -312,16 +332,30 @@ impl TableauConnector {
         user_id_or_group_name: &'a String,
         grantee_type: &'a str,
         group_map: Arc<Mutex<HashMap<String, String>>>,
-    ) -> Result<Vec<BoxFuture<Result<()>>>> {
-        let mut res: Vec<BoxFuture<Result<()>>> = Vec::new();
+    ) -> Result<Vec<RenderedFuture>> {
+        let mut res: Vec<RenderedFuture> = Vec::new();
+        let grantee_id = if grantee_type == "group" {
+            self.planned_group_id(user_id_or_group_name)
+        } else {
+            user_id_or_group_name.to_owned()
+        };
         for privilege in &state.privileges {
-            res.push(Box::pin(self.build_and_execute_delete_policy_request(
-                privilege.to_owned(),
-                asset.to_owned(),
-                user_id_or_group_name.to_owned(),
-                grantee_type.to_owned(),
-                Arc::clone(&group_map),
-            )));
+            let request = self.generate_delete_privilege_request(
+                asset,
+                &grantee_id,
+                grantee_type,
+                privilege,
+            )?;
+            res.push((
+                request_to_string(&request),
+                Box::pin(self.build_and_execute_delete_policy_request(
+                    privilege.to_owned(),
+                    asset.to_owned(),
+                    user_id_or_group_name.to_owned(),
+                    grantee_type.to_owned(),
+                    Arc::clone(&group_map),
+                )),
+            ));
         }
 
         Ok(res)
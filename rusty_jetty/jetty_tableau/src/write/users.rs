@@ -7,12 +7,15 @@ use std::{
 
 use anyhow::{Context, Result};
 
-use jetty_core::access_graph::translate::diffs::users;
+use jetty_core::{
+    access_graph::translate::diffs::users,
+    write::apply_report::{ApplyTarget, DiffItem, PlannedOperation},
+};
 use serde_json::json;
 
 use crate::TableauConnector;
 
-use super::{SequencedFutures, SequencedPlans};
+use super::{request_to_string, PlannedFuture, SequencedFutures, SequencedPlans};
 
 impl TableauConnector {
     /// plan requests for `jetty plan`
@@ -25,22 +28,14 @@ impl TableauConnector {
         for diff in user_diffs {
             for group in &diff.group_membership.add {
                 // get the group_id
-                let group_id = self
-                    .coordinator
-                    .env
-                    .get_group_id_by_name(group)
-                    .unwrap_or(format!("group_id_for_new_group_{group}"));
+                let group_id = self.planned_group_id(group);
                 plans
                     .1
                     .push(self.build_add_user_request(&group_id, &diff.user)?);
             }
             for group in &diff.group_membership.remove {
                 // get the group_id
-                let group_id = self
-                    .coordinator
-                    .env
-                    .get_group_id_by_name(group)
-                    .unwrap_or(format!("group_id_for_new_group_{group}"));
+                let group_id = self.planned_group_id(group);
                 plans
                     .1
                     .push(self.build_remove_user_request(&group_id, &diff.user)?);
@@ -58,23 +53,36 @@ impl TableauConnector {
         let mut futures = SequencedFutures::default();
 
         for diff in user_diffs {
+            let operation = |request: reqwest::Request| {
+                PlannedOperation::new(
+                    ApplyTarget::User(diff.user.to_owned()),
+                    DiffItem::User(diff.to_owned()),
+                    request_to_string(&request),
+                )
+            };
             for group in &diff.group_membership.add {
-                futures
-                    .1
-                    .push(Box::pin(self.execute_add_user_with_deferred_lookup(
+                futures.1.push(PlannedFuture::new(
+                    operation(
+                        self.build_add_user_request(&self.planned_group_id(group), &diff.user)?,
+                    ),
+                    Box::pin(self.execute_add_user_with_deferred_lookup(
                         group,
                         &diff.user,
                         Arc::clone(&group_map),
-                    )));
+                    )),
+                ));
             }
             for group in &diff.group_membership.remove {
-                futures
-                    .1
-                    .push(Box::pin(self.execute_remove_user_with_deferred_lookup(
+                futures.1.push(PlannedFuture::new(
+                    operation(
+                        self.build_remove_user_request(&self.planned_group_id(group), &diff.user)?,
+                    ),
+                    Box::pin(self.execute_remove_user_with_deferred_lookup(
                         group,
                         &diff.user,
                         Arc::clone(&group_map),
-                    )));
+                    )),
+                ));
             }
         }
 
@@ -35,6 +35,6 @@ async fn test_fetch_data_works() -> Result<()> {
 
     info!("getting tableau data");
     tab.setup().await?;
-    tab.get_data().await;
+    tab.get_data().await?;
     Ok(())
 }